@@ -0,0 +1,105 @@
+//! Throughput benchmarks for the [NdjsonEngine]. Run with `cargo bench`. Each benchmark feeds the
+//! same NDJSON-document in chunks of a fixed size to a fresh engine and reports the throughput.
+//! For comparison, the same document is also parsed by a naive loop which reads every line with
+//! [BufRead::read_until] and parses it with [serde_json::from_slice].
+
+use std::hint::black_box;
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+use ndjson_stream::engine::NdjsonEngine;
+use serde::Deserialize;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+const TARGET_SIZE: usize = 16 * 1024 * 1024;
+const MIN_DURATION: Duration = Duration::from_secs(2);
+const WARM_UP_ITERATIONS: u32 = 5;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Record {
+    id: u64,
+    name: String,
+    payload: String
+}
+
+fn generate_document(record_size: usize) -> Vec<u8> {
+    let mut document = Vec::with_capacity(TARGET_SIZE + record_size);
+    let mut id = 0u64;
+
+    while document.len() < TARGET_SIZE {
+        let prefix = format!("{{\"id\":{},\"name\":\"record {}\",\"payload\":\"", id, id);
+        let padding = record_size.saturating_sub(prefix.len() + 3);
+
+        document.extend_from_slice(prefix.as_bytes());
+        document.resize(document.len() + padding, b'x');
+        document.extend_from_slice(b"\"}\n");
+        id += 1;
+    }
+
+    document
+}
+
+fn run(document: &[u8]) -> usize {
+    let mut engine = NdjsonEngine::<Record>::new();
+    let mut count = 0;
+
+    for chunk in document.chunks(CHUNK_SIZE) {
+        engine.input(chunk);
+
+        while let Some(record) = engine.pop() {
+            black_box(record.is_ok());
+            count += 1;
+        }
+    }
+
+    count
+}
+
+fn run_read_until(document: &[u8]) -> usize {
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, document);
+    let mut line = Vec::new();
+    let mut count = 0;
+
+    while reader.read_until(b'\n', &mut line).unwrap() > 0 {
+        let record = serde_json::from_slice::<Record>(&line);
+
+        black_box(record.is_ok());
+        count += 1;
+        line.clear();
+    }
+
+    count
+}
+
+fn bench(name: &str, document: &[u8], run: fn(&[u8]) -> usize) {
+    for _ in 0..WARM_UP_ITERATIONS {
+        black_box(run(black_box(document)));
+    }
+
+    let start = Instant::now();
+    let mut iterations = 0u32;
+
+    while start.elapsed() < MIN_DURATION {
+        black_box(run(black_box(document)));
+        iterations += 1;
+    }
+
+    let elapsed = start.elapsed();
+    let bytes = document.len() as f64 * iterations as f64;
+    let throughput = bytes / elapsed.as_secs_f64() / (1024.0 * 1024.0);
+
+    println!("{:<32} {:>10.1} MiB/s ({} iterations)", name, throughput, iterations);
+}
+
+fn bench_record_size(name: &str, record_size: usize) {
+    let document = generate_document(record_size);
+
+    bench(&format!("{} (engine)", name), &document, run);
+    bench(&format!("{} (read_until)", name), &document, run_read_until);
+}
+
+fn main() {
+    bench_record_size("100B records", 100);
+    bench_record_size("10KB records", 10 * 1024);
+}
@@ -0,0 +1,319 @@
+//! This module contains the [JsonArraySplitter], which splits the elements of top-level JSON arrays
+//! arriving in chunks, so they can be parsed like NDJSON-lines. See
+//! [NdjsonConfig::with_json_array_mode](crate::config::NdjsonConfig::with_json_array_mode).
+
+use alloc::vec::Vec;
+
+use crate::as_bytes::AsBytes;
+use crate::splitter::SplitLine;
+
+/// Removes leading and trailing whitespace from the given bytes.
+///
+/// # Returns
+///
+/// The number of removed leading bytes and the remaining bytes.
+fn trim_whitespace(mut bytes: &[u8]) -> (usize, &[u8]) {
+    let mut leading = 0;
+
+    while let [first, rest @ ..] = bytes {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+
+        leading += 1;
+        bytes = rest;
+    }
+
+    while let [rest @ .., last] = bytes {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+
+        bytes = rest;
+    }
+
+    (leading, bytes)
+}
+
+/// Splits the elements of top-level JSON arrays which are given in arbitrary chunks. Elements are
+/// delimited by commas and the closing bracket at nesting depth zero, while strings are skipped,
+/// such that delimiters within them are not considered. Elements are not validated. Blank
+/// elements, as in `[]` or `[1,]`, are not reported. Several arrays may follow each other, and
+/// data outside of arrays which is not whitespace is reported as a single element once the next
+/// array starts or the input ends, such that it results in a parse error.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct JsonArraySplitter {
+    in_array: bool,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+
+    /// The part of the current element received in previous chunks. If the element exceeds the
+    /// maximum length, only its first bytes are retained.
+    pending: Vec<u8>,
+    pending_length: usize,
+    collecting: bool,
+    max_length: Option<usize>,
+    prefix_len: usize,
+    consumed: u64,
+    element_offset: u64
+}
+
+impl JsonArraySplitter {
+
+    pub(crate) fn set_max_element_length(&mut self, max_length: usize, prefix_len: usize) {
+        self.max_length = Some(max_length);
+        self.prefix_len = prefix_len;
+    }
+
+    fn start_element(&mut self, offset: u64) {
+        self.collecting = true;
+        self.element_offset = offset;
+    }
+
+    /// Reports the current element, consisting of the pending bytes and the given `segment` of the
+    /// current chunk, if it is not blank, and resets the state of the element.
+    fn emit(&mut self, segment: &[u8], f: &mut impl FnMut(SplitLine<'_>, u64)) {
+        let length = self.pending_length + segment.len();
+        let offset = self.element_offset;
+
+        if self.max_length.is_some_and(|max_length| length > max_length) {
+            let missing_prefix = self.prefix_len.saturating_sub(self.pending.len());
+            let segment_prefix = &segment[..missing_prefix.min(segment.len())];
+
+            self.pending.extend_from_slice(segment_prefix);
+            self.pending.truncate(self.prefix_len);
+            f(SplitLine::Oversized { prefix: &self.pending, length }, offset);
+        }
+        else {
+            let element = if self.pending.is_empty() {
+                segment
+            }
+            else {
+                self.pending.extend_from_slice(segment);
+                &self.pending
+            };
+            let (leading, element) = trim_whitespace(element);
+
+            if !element.is_empty() {
+                f(SplitLine::Complete(element), offset + leading as u64);
+            }
+        }
+
+        self.pending.clear();
+        self.pending_length = 0;
+        self.collecting = false;
+    }
+
+    fn store_pending(&mut self, segment: &[u8]) {
+        self.pending.extend_from_slice(segment);
+        self.pending_length += segment.len();
+
+        if self.max_length.is_some_and(|max_length| self.pending_length > max_length) {
+            self.pending.truncate(self.prefix_len);
+        }
+    }
+
+    /// Splits the given chunk, calling `f` with every completed element and its offset in the
+    /// entire input. Incomplete elements are stored until they are completed by subsequent chunks.
+    pub(crate) fn split_elements(&mut self, data: impl AsBytes,
+            mut f: impl FnMut(SplitLine<'_>, u64)) {
+        let data = data.as_bytes();
+        let mut segment_start = 0;
+
+        for (index, &byte) in data.iter().enumerate() {
+            let offset = self.consumed + index as u64;
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                }
+                else if byte == b'\\' {
+                    self.escaped = true;
+                }
+                else if byte == b'"' {
+                    self.in_string = false;
+                }
+
+                continue;
+            }
+
+            match byte {
+                b'[' if !self.in_array && self.depth == 0 => {
+                    if self.collecting {
+                        self.emit(&data[segment_start..index], &mut f);
+                    }
+
+                    self.in_array = true;
+                    segment_start = index + 1;
+                },
+                b',' | b']' if self.in_array && self.depth == 0 => {
+                    self.emit(&data[segment_start..index], &mut f);
+                    self.in_array = byte == b',';
+                    segment_start = index + 1;
+                },
+                _ if !self.collecting && byte.is_ascii_whitespace() => segment_start = index + 1,
+                _ => {
+                    if !self.collecting {
+                        self.start_element(offset);
+                        segment_start = index;
+                    }
+
+                    match byte {
+                        b'"' => self.in_string = true,
+                        b'[' | b'{' => self.depth += 1,
+                        b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                        _ => { }
+                    }
+                }
+            }
+        }
+
+        if self.collecting {
+            self.store_pending(&data[segment_start..]);
+        }
+
+        self.consumed += data.len() as u64;
+    }
+
+    /// Gets the retained bytes of the element which is still incomplete, which are only the first
+    /// bytes of oversized elements.
+    pub(crate) fn pending(&self) -> &[u8] {
+        if self.collecting {
+            &self.pending
+        }
+        else {
+            &[]
+        }
+    }
+
+    /// Gets the length of the element which is still incomplete, including the bytes of oversized
+    /// elements which were not retained.
+    pub(crate) fn pending_len(&self) -> usize {
+        if self.collecting {
+            self.pending_length
+        }
+        else {
+            0
+        }
+    }
+
+    /// Reports the element which is still incomplete, if any, to `f` and resets the state of this
+    /// splitter. This is the case for arrays which are not terminated and data following the last
+    /// array.
+    pub(crate) fn finalize_elements(&mut self, mut f: impl FnMut(SplitLine<'_>, u64)) {
+        if self.collecting {
+            self.emit(&[], &mut f);
+        }
+
+        self.in_array = false;
+        self.depth = 0;
+        self.in_string = false;
+        self.escaped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn split(splitter: &mut JsonArraySplitter, chunks: &[&str]) -> Vec<(String, u64)> {
+        let mut elements = Vec::new();
+
+        for chunk in chunks {
+            splitter.split_elements(chunk, |element, offset|
+                elements.push(describe(element, offset)));
+        }
+
+        splitter.finalize_elements(|element, offset| elements.push(describe(element, offset)));
+        elements
+    }
+
+    fn describe(element: SplitLine<'_>, offset: u64) -> (String, u64) {
+        match element {
+            SplitLine::Complete(bytes) => (String::from_utf8(bytes.to_vec()).unwrap(), offset),
+            SplitLine::Oversized { prefix, length } =>
+                (format!("{}.. ({length})", String::from_utf8(prefix.to_vec()).unwrap()), offset)
+        }
+    }
+
+    fn element(element: &str, offset: u64) -> (String, u64) {
+        (element.to_owned(), offset)
+    }
+
+    #[test]
+    fn elements_of_single_chunk_are_split() {
+        let elements = split(&mut JsonArraySplitter::default(), &["[1, \"a\" ,{\"b\":[2,3]}]"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([
+            element("1", 1),
+            element("\"a\"", 4),
+            element("{\"b\":[2,3]}", 9)
+        ]);
+    }
+
+    #[test]
+    fn elements_spanning_chunks_are_joined() {
+        let elements = split(&mut JsonArraySplitter::default(), &["[{\"a\":", "1},", "2", "]"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([
+            element("{\"a\":1}", 1),
+            element("2", 9)
+        ]);
+    }
+
+    #[test]
+    fn delimiters_in_strings_are_ignored() {
+        let elements =
+            split(&mut JsonArraySplitter::default(), &["[\"a,]\\\"", "[\", \"\\\\\"]"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([
+            element("\"a,]\\\"[\"", 1),
+            element("\"\\\\\"", 11)
+        ]);
+    }
+
+    #[test]
+    fn blank_elements_are_not_reported() {
+        let elements = split(&mut JsonArraySplitter::default(), &["[ ]\n[1, ]"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([element("1", 5)]);
+    }
+
+    #[test]
+    fn data_outside_of_arrays_is_reported() {
+        let elements = split(&mut JsonArraySplitter::default(), &["x [1] y"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([
+            element("x", 0),
+            element("1", 3),
+            element("y", 6)
+        ]);
+    }
+
+    #[test]
+    fn unterminated_array_reports_incomplete_element() {
+        let elements = split(&mut JsonArraySplitter::default(), &["[1,{\"a\":"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([
+            element("1", 1),
+            element("{\"a\":", 3)
+        ]);
+    }
+
+    #[test]
+    fn oversized_elements_retain_prefix() {
+        let mut splitter = JsonArraySplitter::default();
+        splitter.set_max_element_length(4, 2);
+
+        let elements = split(&mut splitter, &["[\"abc", "def\",1]"]);
+
+        assert_that!(elements).contains_exactly_in_given_order([
+            element("\"a.. (8)", 1),
+            element("1", 10)
+        ]);
+    }
+}
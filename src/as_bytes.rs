@@ -1,17 +1,49 @@
-//! This module defines the [AsBytes] with baseline implementations.
+//! This module defines the [AsBytes] with baseline implementations, as well as [AsByteChunks] for
+//! non-contiguous buffers.
+//!
+//! Further implementations for byte containers of other crates are enabled by crate features of the
+//! same name: `bytes` for `Bytes` and `BytesMut`, `bstr` for `BStr` and `BString`, `smallvec` for
+//! `SmallVec<[u8; N]>`, and `memmap2` for `Mmap` and `MmapMut`. The bodies of `hyper` are made of
+//! the same `Bytes` type, which `hyper::body::Bytes` re-exports, so they are covered by the `bytes`
+//! feature.
 
-use std::borrow::Cow;
-use std::rc::Rc;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+#[cfg(feature = "bstr")]
+use bstr::{BStr, BString};
 
 #[cfg(feature = "bytes")]
 use bytes::{Bytes, BytesMut};
 
+#[cfg(feature = "memmap2")]
+use memmap2::{Mmap, MmapMut};
+
+#[cfg(feature = "smallvec")]
+use smallvec::{Array, SmallVec};
+
+#[cfg(feature = "std")]
+use std::io::IoSlice;
+
 /// A trait for types which represent a contiguous block of bytes, such as `&[u8]` or `Vec<u8>`.
 pub trait AsBytes {
 
     /// Gets a slice of the entire block of bytes contained in this instance.
     fn as_bytes(&self) -> &[u8];
+
+    /// Gets a reference-counted handle to the entire block of bytes contained in this instance, if
+    /// it is stored in a shared [Bytes] buffer. This allows parsers to retain parts of the block,
+    /// such as an incomplete line at its end, without copying them. By default, `None` is returned.
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "bytes")))]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        None
+    }
 }
 
 impl AsBytes for [u8] {
@@ -50,6 +82,10 @@ impl AsBytes for Bytes {
     fn as_bytes(&self) -> &[u8] {
         self.as_ref()
     }
+
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        Some(self.clone())
+    }
 }
 
 #[cfg(feature = "bytes")]
@@ -60,40 +96,199 @@ impl AsBytes for BytesMut {
     }
 }
 
+#[cfg(feature = "bstr")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "bstr")))]
+impl AsBytes for BStr {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "bstr")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "bstr")))]
+impl AsBytes for BString {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "smallvec")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "smallvec")))]
+impl<A: Array<Item = u8>> AsBytes for SmallVec<A> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "memmap2")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "memmap2")))]
+impl AsBytes for Mmap {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "memmap2")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "memmap2")))]
+impl AsBytes for MmapMut {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
 impl<T: AsBytes + ?Sized> AsBytes for &T {
     fn as_bytes(&self) -> &[u8] {
         T::as_bytes(self)
     }
+
+    #[cfg(feature = "bytes")]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        T::to_shared_bytes(self)
+    }
 }
 
 impl<T: AsBytes + ?Sized> AsBytes for &mut T {
     fn as_bytes(&self) -> &[u8] {
         T::as_bytes(self)
     }
+
+    #[cfg(feature = "bytes")]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        T::to_shared_bytes(self)
+    }
 }
 
 impl<T: AsBytes + ?Sized> AsBytes for Box<T> {
     fn as_bytes(&self) -> &[u8] {
         self.as_ref().as_bytes()
     }
+
+    #[cfg(feature = "bytes")]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        self.as_ref().to_shared_bytes()
+    }
 }
 
-impl<'cow, T: AsBytes + Clone + ?Sized> AsBytes for Cow<'cow, T> {
+impl<'cow, T: AsBytes + Clone> AsBytes for Cow<'cow, T> {
     fn as_bytes(&self) -> &[u8] {
         self.as_ref().as_bytes()
     }
+
+    #[cfg(feature = "bytes")]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        self.as_ref().to_shared_bytes()
+    }
 }
 
 impl<T: AsBytes + ?Sized> AsBytes for Rc<T> {
     fn as_bytes(&self) -> &[u8] {
         self.as_ref().as_bytes()
     }
+
+    #[cfg(feature = "bytes")]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        self.as_ref().to_shared_bytes()
+    }
 }
 
 impl<T: AsBytes + ?Sized> AsBytes for Arc<T> {
     fn as_bytes(&self) -> &[u8] {
         self.as_ref().as_bytes()
     }
+
+    #[cfg(feature = "bytes")]
+    fn to_shared_bytes(&self) -> Option<Bytes> {
+        self.as_ref().to_shared_bytes()
+    }
+}
+
+/// A trait for types which represent a sequence of bytes which is not necessarily contiguous, such
+/// as a [VecDeque] used as a ring buffer or the [IoSlice]s of vectored I/O. Such inputs can be
+/// given to [NdjsonEngine::input_chunks](crate::engine::NdjsonEngine::input_chunks) without first
+/// copying them into a contiguous buffer. Every type implementing [AsBytes] is a single chunk.
+pub trait AsByteChunks {
+
+    /// Calls `f` with every contiguous chunk of bytes contained in this instance, in order.
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8]));
+}
+
+impl<T: AsBytes + ?Sized> AsByteChunks for T {
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8])) {
+        f(self.as_bytes())
+    }
+}
+
+impl AsByteChunks for VecDeque<u8> {
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8])) {
+        let (front, back) = self.as_slices();
+
+        f(front);
+
+        if !back.is_empty() {
+            f(back);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+impl AsByteChunks for [IoSlice<'_>] {
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8])) {
+        for slice in self {
+            f(slice);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+impl AsByteChunks for Vec<IoSlice<'_>> {
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8])) {
+        self.as_slice().for_each_chunk(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn collect_chunks(chunks: &(impl AsByteChunks + ?Sized)) -> Vec<Vec<u8>> {
+        let mut collected = Vec::new();
+
+        chunks.for_each_chunk(&mut |chunk| collected.push(chunk.to_vec()));
+        collected
+    }
+
+    #[test]
+    fn contiguous_bytes_are_single_chunk() {
+        assert_that!(collect_chunks("abc")).contains_exactly_in_given_order([b"abc".to_vec()]);
+    }
+
+    #[test]
+    fn wrapped_vec_deque_yields_both_halves() {
+        let mut deque = VecDeque::with_capacity(4);
+
+        deque.extend(b"xxab");
+        deque.drain(..2);
+        deque.extend(b"cd");
+
+        assert_that!(collect_chunks(&deque).concat()).contains_exactly_in_given_order(b"abcd");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_slices_are_yielded_in_order() {
+        let slices = [IoSlice::new(b"ab"), IoSlice::new(b""), IoSlice::new(b"c")];
+
+        assert_that!(collect_chunks(&slices[..])).contains_exactly_in_given_order([
+            b"ab".to_vec(),
+            Vec::new(),
+            b"c".to_vec()
+        ]);
+    }
 }
 
 #[cfg(all(test, feature = "bytes"))]
@@ -117,4 +312,82 @@ mod bytes_tests {
 
         assert_that!(bytes_mut.as_bytes()).contains_exactly_in_given_order([3, 2, 1]);
     }
+
+    #[test]
+    fn bytes_are_shared() {
+        let bytes = Bytes::from(&[1, 2, 3][..]);
+        let shared = Arc::new(&bytes).to_shared_bytes();
+
+        assert_that!(shared).to_value().is_equal_to(bytes);
+    }
+
+    #[test]
+    fn other_types_are_not_shared() {
+        assert_that!(BytesMut::from(&[1][..]).to_shared_bytes()).is_none();
+        assert_that!(vec![1u8].to_shared_bytes()).is_none();
+    }
+}
+
+#[cfg(all(test, feature = "bstr"))]
+mod bstr_tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn bstr_and_bstring_work() {
+        let bstring = BString::from("{\"a\":1}\n");
+
+        assert_that!(bstring.as_bytes()).contains_exactly_in_given_order(b"{\"a\":1}\n");
+        assert_that!(BStr::new("1\n").as_bytes()).contains_exactly_in_given_order(b"1\n");
+    }
+
+    #[cfg(feature = "iter")]
+    #[test]
+    fn bstrings_can_be_parsed() {
+        let records = crate::from_iter::<u32, _>([BString::from("1\n2"), BString::from("\n")])
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(records).contains_exactly_in_given_order([1, 2]);
+    }
+}
+
+#[cfg(all(test, feature = "smallvec"))]
+mod smallvec_tests {
+
+    use kernal::prelude::*;
+    use smallvec::smallvec;
+
+    use super::*;
+
+    #[test]
+    fn inline_and_spilled_small_vecs_work() {
+        let inline: SmallVec<[u8; 4]> = smallvec![1, 2];
+        let spilled: SmallVec<[u8; 2]> = smallvec![1, 2, 3];
+
+        assert_that!(inline.as_bytes()).contains_exactly_in_given_order([1, 2]);
+        assert_that!(spilled.as_bytes()).contains_exactly_in_given_order([1, 2, 3]);
+    }
+}
+
+#[cfg(all(test, feature = "memmap2"))]
+mod memmap2_tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn memory_maps_work() {
+        let mut mmap_mut = MmapMut::map_anon(3).unwrap();
+
+        mmap_mut.copy_from_slice(b"1\n2");
+        assert_that!(mmap_mut.as_bytes()).contains_exactly_in_given_order(b"1\n2");
+
+        let mmap = mmap_mut.make_read_only().unwrap();
+
+        assert_that!(mmap.as_bytes()).contains_exactly_in_given_order(b"1\n2");
+    }
 }
@@ -0,0 +1,359 @@
+//! This module defines the [BatchSink], which collects records, such as the output of an
+//! [NdjsonStream](crate::driver::NdjsonStream), into batches and forwards these to an inner
+//! [Sink]. This is intended for bulk-insert pipelines, where a database writer accepts entire
+//! batches of records.
+
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "tokio")]
+use std::future::Future;
+
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+
+use futures::{ready, Sink};
+use pin_project_lite::pin_project;
+
+#[cfg(feature = "tokio")]
+use tokio::time::{self, Instant, Sleep};
+
+/// Keeps track of the time since the first record of the current batch was added. Without the
+/// `tokio` feature, there is no timer and batches are only bounded by their size.
+#[derive(Default)]
+struct BatchTimer {
+    #[cfg(feature = "tokio")]
+    max_delay: Option<Duration>,
+    #[cfg(feature = "tokio")]
+    sleep: Option<Pin<Box<Sleep>>>,
+    #[cfg(feature = "tokio")]
+    is_running: bool
+}
+
+impl BatchTimer {
+
+    fn start(&mut self) {
+        #[cfg(feature = "tokio")]
+        if let Some(max_delay) = self.max_delay {
+            let deadline = Instant::now() + max_delay;
+
+            match &mut self.sleep {
+                Some(sleep) => sleep.as_mut().reset(deadline),
+                None => self.sleep = Some(Box::pin(time::sleep_until(deadline)))
+            }
+
+            self.is_running = true;
+        }
+    }
+
+    fn stop(&mut self) {
+        #[cfg(feature = "tokio")]
+        {
+            self.is_running = false;
+        }
+    }
+
+    /// Polls whether the maximum delay of the current batch has elapsed, which is never the case
+    /// if there is no maximum delay.
+    fn poll_elapsed(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        #[cfg(feature = "tokio")]
+        if let (true, Some(sleep)) = (self.is_running, &mut self.sleep) {
+            return sleep.as_mut().poll(cx);
+        }
+
+        let _ = cx;
+        Poll::Pending
+    }
+
+    /// Polls whether the current batch may be forwarded by a flush, i.e. whether there is no
+    /// maximum delay or it has elapsed.
+    fn poll_flushable(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        #[cfg(feature = "tokio")]
+        if self.is_running {
+            return self.poll_elapsed(cx);
+        }
+
+        let _ = cx;
+        Poll::Ready(())
+    }
+}
+
+pin_project! {
+    /// A [Sink] of records which collects them into batches of type `Vec<T>` and forwards these to
+    /// an inner [Sink]. A batch is forwarded as soon as it contains the configured maximum number
+    /// of records. With the `tokio` feature, a maximum delay can be configured via
+    /// [BatchSink::with_max_delay], which bounds the time records wait in an incomplete batch.
+    ///
+    /// Flushing this sink forwards the incomplete batch and flushes the inner sink. If a maximum
+    /// delay is configured, flushing first waits until the delay of the current batch has elapsed,
+    /// so more records can be added in the meantime. This is the case when using
+    /// [StreamExt::forward](futures::StreamExt::forward), which flushes the sink whenever the input
+    /// stream is pending. As a consequence, batches are only cut short if no further records arrive
+    /// within the delay. Closing this sink forwards the incomplete batch immediately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    ///
+    /// use ndjson_stream::batch::BatchSink;
+    ///
+    /// futures::executor::block_on(async {
+    ///     let data = futures::stream::iter(vec!["1\n2\n3\n", "4\n5\n"]);
+    ///     let mut sink = BatchSink::new(Vec::new(), 2);
+    ///
+    ///     ndjson_stream::from_stream::<u32, _>(data)
+    ///         .map(Result::unwrap)
+    ///         .map(Ok)
+    ///         .forward(&mut sink)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(sink.into_inner(), vec![vec![1, 2], vec![3, 4], vec![5]]);
+    /// });
+    /// ```
+    pub struct BatchSink<Si, T> {
+        #[pin]
+        inner: Si,
+        batch: Vec<T>,
+        ready_batch: Option<Vec<T>>,
+        max_size: usize,
+        timer: BatchTimer
+    }
+}
+
+impl<Si, T> BatchSink<Si, T> {
+
+    /// Creates a new batch sink which forwards batches of at most `max_size` records to the given
+    /// `inner` sink. A maximum size of `0` is treated as `1`.
+    pub fn new(inner: Si, max_size: usize) -> BatchSink<Si, T> {
+        let max_size = max_size.max(1);
+
+        BatchSink {
+            inner,
+            batch: Vec::with_capacity(max_size),
+            ready_batch: None,
+            max_size,
+            timer: BatchTimer::default()
+        }
+    }
+
+    /// Creates a new batch sink from this sink which waits at most `max_delay` after the first
+    /// record of a batch has been added before forwarding an incomplete batch when flushed. By
+    /// default, flushing forwards an incomplete batch immediately. See [BatchSink] for more
+    /// details.
+    ///
+    /// # Panics
+    ///
+    /// Using the returned sink panics outside a Tokio runtime with the time driver enabled.
+    ///
+    /// # Returns
+    ///
+    /// A new batch sink with all the same values as this one, except the maximum delay.
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn with_max_delay(self, max_delay: Duration) -> BatchSink<Si, T> {
+        BatchSink {
+            timer: BatchTimer {
+                max_delay: Some(max_delay),
+                ..BatchTimer::default()
+            },
+            ..self
+        }
+    }
+
+    /// Gets a reference to the inner sink.
+    pub fn get_ref(&self) -> &Si {
+        &self.inner
+    }
+
+    /// Gets the records of the current batch, which have not been forwarded yet.
+    pub fn pending_records(&self) -> &[T] {
+        &self.batch
+    }
+
+    /// Unwraps this sink, returning the inner sink. Records which have not been forwarded yet are
+    /// dropped.
+    pub fn into_inner(self) -> Si {
+        self.inner
+    }
+}
+
+fn poll_forward_ready_batch<Si, T>(mut inner: Pin<&mut Si>, ready_batch: &mut Option<Vec<T>>,
+        cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>>
+where
+    Si: Sink<Vec<T>>
+{
+    if ready_batch.is_some() {
+        ready!(inner.as_mut().poll_ready(cx))?;
+
+        if let Some(batch) = ready_batch.take() {
+            inner.start_send(batch)?;
+        }
+    }
+
+    Poll::Ready(Ok(()))
+}
+
+impl<Si, T> Sink<T> for BatchSink<Si, T>
+where
+    Si: Sink<Vec<T>>
+{
+    type Error = Si::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let mut this = self.project();
+
+        ready!(poll_forward_ready_batch(this.inner.as_mut(), this.ready_batch, cx))?;
+
+        if this.timer.poll_elapsed(cx).is_ready() {
+            this.timer.stop();
+            *this.ready_batch = Some(mem::replace(this.batch, Vec::with_capacity(*this.max_size)));
+
+            return poll_forward_ready_batch(this.inner, this.ready_batch, cx);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, record: T) -> Result<(), Si::Error> {
+        let this = self.project();
+
+        if this.batch.is_empty() {
+            this.timer.start();
+        }
+
+        this.batch.push(record);
+
+        if this.batch.len() >= *this.max_size {
+            this.timer.stop();
+            *this.ready_batch = Some(mem::replace(this.batch, Vec::with_capacity(*this.max_size)));
+        }
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let mut this = self.project();
+
+        ready!(poll_forward_ready_batch(this.inner.as_mut(), this.ready_batch, cx))?;
+
+        if !this.batch.is_empty() {
+            ready!(this.timer.poll_flushable(cx));
+            this.timer.stop();
+            *this.ready_batch = Some(mem::replace(this.batch, Vec::with_capacity(*this.max_size)));
+            ready!(poll_forward_ready_batch(this.inner.as_mut(), this.ready_batch, cx))?;
+        }
+
+        this.inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let mut this = self.project();
+
+        ready!(poll_forward_ready_batch(this.inner.as_mut(), this.ready_batch, cx))?;
+
+        if !this.batch.is_empty() {
+            this.timer.stop();
+            *this.ready_batch = Some(mem::take(this.batch));
+            ready!(poll_forward_ready_batch(this.inner.as_mut(), this.ready_batch, cx))?;
+        }
+
+        this.inner.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use futures::{SinkExt, StreamExt};
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn full_batches_are_forwarded_before_flush() {
+        tokio_test::block_on(async {
+            let mut sink = BatchSink::new(Vec::new(), 2);
+
+            for record in 1..=5 {
+                sink.feed(record).await.unwrap();
+            }
+
+            assert_that!(sink.get_ref().as_slice())
+                .contains_exactly_in_given_order([vec![1, 2], vec![3, 4]]);
+            assert_that!(sink.pending_records()).contains_exactly_in_given_order([5]);
+
+            sink.close().await.unwrap();
+
+            assert_that!(sink.into_inner())
+                .contains_exactly_in_given_order([vec![1, 2], vec![3, 4], vec![5]]);
+        });
+    }
+
+    #[test]
+    fn flush_forwards_incomplete_batch_without_max_delay() {
+        tokio_test::block_on(async {
+            let mut sink = BatchSink::new(Vec::new(), 0);
+
+            futures::stream::iter(vec![Ok(1), Ok(2)]).forward(&mut sink).await.unwrap();
+
+            assert_that!(sink.into_inner()).contains_exactly_in_given_order([vec![1], vec![2]]);
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn flush_waits_for_max_delay() {
+        use tokio::runtime::Builder;
+
+        const MAX_DELAY: Duration = Duration::from_secs(10);
+
+        let runtime = Builder::new_current_thread().enable_time().start_paused(true).build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let mut sink = BatchSink::new(Vec::new(), 3).with_max_delay(MAX_DELAY);
+            let started = Instant::now();
+
+            sink.feed(1).await.unwrap();
+            sink.feed(2).await.unwrap();
+            sink.flush().await.unwrap();
+
+            assert_that!(started.elapsed()).is_equal_to(MAX_DELAY);
+            assert_that!(sink.get_ref().as_slice()).contains_exactly_in_given_order([vec![1, 2]]);
+
+            time::advance(MAX_DELAY * 2).await;
+            sink.feed(3).await.unwrap();
+            sink.close().await.unwrap();
+
+            assert_that!(started.elapsed()).is_equal_to(MAX_DELAY * 3);
+            assert_that!(sink.into_inner())
+                .contains_exactly_in_given_order([vec![1, 2], vec![3]]);
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn elapsed_batches_are_forwarded_when_ready() {
+        use tokio::runtime::Builder;
+
+        const MAX_DELAY: Duration = Duration::from_secs(10);
+
+        let runtime = Builder::new_current_thread().enable_time().start_paused(true).build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let mut sink = BatchSink::new(Vec::new(), 3).with_max_delay(MAX_DELAY);
+
+            sink.feed(1).await.unwrap();
+            time::advance(MAX_DELAY).await;
+            sink.feed(2).await.unwrap();
+
+            assert_that!(sink.get_ref().as_slice()).contains_exactly_in_given_order([vec![1]]);
+            assert_that!(sink.pending_records()).contains_exactly_in_given_order([2]);
+        });
+    }
+}
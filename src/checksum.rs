@@ -0,0 +1,411 @@
+//! This module offers integrity verification of NDJSON-data via CRC-32 checksums, to detect
+//! corruption such as bit rot in archived files. Checksums can be verified per line via the
+//! [Crc32Verifier] middleware, which expects every line to end with a suffix of the form
+//! `|crc32:xxxxxxxx`, or for an entire file via a [ChecksumReader], which compares the data read
+//! from a file with the checksum stored in a sidecar file.
+//!
+//! Failed verifications are reported as [IntegrityError]s, which are distinct from JSON errors: the
+//! [Crc32Verifier] reports them via [NdjsonError::Integrity], the [ChecksumReader] as
+//! [io::Error]s of kind [ErrorKind::InvalidData] holding the [IntegrityError].
+//!
+//! The checksum is the common CRC-32 (IEEE 802.3), as computed for example by zlib, gzip, or PNG.
+//! Suffixes for written lines can be created via [crc32_suffix].
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::checksum::{self, Crc32Verifier};
+//! use ndjson_stream::config::NdjsonConfig;
+//! use ndjson_stream::engine::NdjsonEngine;
+//! use ndjson_stream::error::NdjsonError;
+//!
+//! let config = NdjsonConfig::default().with_line_middleware(Crc32Verifier::new());
+//! let mut engine = NdjsonEngine::<u32>::with_config(config);
+//! let line = format!("42{}", checksum::crc32_suffix(b"42"));
+//!
+//! engine.input(format!("{line}\n43|crc32:00000000\n"));
+//!
+//! assert_eq!(engine.pop().unwrap().unwrap(), 42);
+//! assert!(matches!(engine.pop(), Some(Err(NdjsonError::Integrity(_)))));
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "std")]
+use std::fs::{self, File};
+#[cfg(feature = "std")]
+use std::io::{self, ErrorKind, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::error::{NdjsonError, NdjsonResult};
+use crate::middleware::LineMiddleware;
+
+/// The marker which separates a line from its checksum, as expected by a [Crc32Verifier].
+pub const CRC32_MARKER: &[u8] = b"|crc32:";
+
+const CRC32_POLYNOMIAL: u32 = 0xedb88320;
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = if value & 1 == 1 { value >> 1 ^ CRC32_POLYNOMIAL } else { value >> 1 };
+            bit += 1;
+        }
+
+        table[index] = value;
+        index += 1;
+    }
+
+    table
+}
+
+/// An incremental computation of a CRC-32 checksum, for data which is not available at once. For
+/// contiguous data, [crc32] can be used instead.
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32 {
+    state: u32
+}
+
+impl Crc32 {
+
+    /// Creates a new computation which has not yet seen any data.
+    pub fn new() -> Crc32 {
+        Crc32 {
+            state: u32::MAX
+        }
+    }
+
+    /// Adds the given `data` to the checksum.
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            let index = (self.state ^ byte as u32) & 0xff;
+
+            self.state = CRC32_TABLE[index as usize] ^ self.state >> 8;
+        }
+    }
+
+    /// Gets the checksum of all data added so far.
+    pub fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Crc32 {
+        Crc32::new()
+    }
+}
+
+/// Computes the CRC-32 checksum of the given `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc32 = Crc32::new();
+    crc32.update(data);
+    crc32.finish()
+}
+
+/// Creates the suffix which a [Crc32Verifier] expects at the end of the given `line`, i.e.
+/// [CRC32_MARKER] followed by the checksum of the line as 8 lowercase hexadecimal digits. The line
+/// is given without its line ending.
+pub fn crc32_suffix(line: &[u8]) -> String {
+    format!("{}{:08x}", String::from_utf8_lossy(CRC32_MARKER), crc32(line))
+}
+
+/// The errors which can occur when verifying the integrity of NDJSON-data.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IntegrityError {
+
+    /// The data has no checksum, i.e. a line does not contain [CRC32_MARKER] or a sidecar file is
+    /// empty.
+    MissingChecksum,
+
+    /// The checksum is not a hexadecimal number of at most 8 digits.
+    MalformedChecksum,
+
+    /// The checksum of the data differs from the stored one, i.e. the data or the checksum is
+    /// corrupted.
+    ChecksumMismatch {
+
+        /// The checksum stored together with the data.
+        expected: u32,
+
+        /// The checksum computed from the data.
+        actual: u32
+    }
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::MissingChecksum => write!(f, "missing checksum"),
+            IntegrityError::MalformedChecksum => write!(f, "malformed checksum"),
+            IntegrityError::ChecksumMismatch { expected, actual } =>
+                write!(f, "checksum mismatch: expected crc32 {expected:08x}, but computed \
+                    {actual:08x}")
+        }
+    }
+}
+
+impl Error for IntegrityError { }
+
+fn parse_checksum(digits: &[u8]) -> Result<u32, IntegrityError> {
+    let digits = digits.trim_ascii();
+
+    if digits.is_empty() || digits.len() > 8 {
+        return Err(IntegrityError::MalformedChecksum);
+    }
+
+    core::str::from_utf8(digits).ok()
+        .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .ok_or(IntegrityError::MalformedChecksum)
+}
+
+/// A [LineMiddleware] which verifies that every line ends with a CRC-32 checksum of the preceding
+/// bytes, separated by [CRC32_MARKER], for example `{"a":1}|crc32:561bacaf`. The checksum is given
+/// as at most 8 hexadecimal digits in any case and may be followed by whitespace. The suffix is
+/// stripped before the line is deserialized. Lines without a valid checksum result in an
+/// [NdjsonError::Integrity].
+///
+/// Lines which are ignored before the middleware is applied, such as empty lines and comments, are
+/// not verified.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crc32Verifier;
+
+impl Crc32Verifier {
+
+    /// Creates a new verifier.
+    pub fn new() -> Crc32Verifier {
+        Crc32Verifier
+    }
+
+    fn verify<'line>(&self, line: &'line [u8]) -> Result<&'line [u8], IntegrityError> {
+        let marker_index = line.windows(CRC32_MARKER.len())
+            .rposition(|window| window == CRC32_MARKER)
+            .ok_or(IntegrityError::MissingChecksum)?;
+        let (record, suffix) = line.split_at(marker_index);
+        let expected = parse_checksum(&suffix[CRC32_MARKER.len()..])?;
+        let actual = crc32(record);
+
+        if expected == actual {
+            Ok(record)
+        }
+        else {
+            Err(IntegrityError::ChecksumMismatch { expected, actual })
+        }
+    }
+}
+
+impl LineMiddleware for Crc32Verifier {
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>> {
+        self.verify(line)
+            .map(Cow::Borrowed)
+            .map_err(NdjsonError::Integrity)
+    }
+}
+
+/// A [Read] wrapper which computes the CRC-32 checksum of all data read from the wrapped reader
+/// and compares it with an expected checksum once the reader is exhausted. If they differ, the
+/// final read fails with an [io::Error] of kind [ErrorKind::InvalidData] holding an
+/// [IntegrityError::ChecksumMismatch], which drivers report as an input error after all records
+/// have been returned.
+///
+/// Note that records are returned before the checksum of the entire data is verified, so consumers
+/// which must not act on corrupted data have to wait for the end of the input.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::checksum::{self, ChecksumReader};
+/// use ndjson_stream::fallible::FallibleNdjsonError;
+/// use ndjson_stream::source::{ChunkSource, ReaderSource};
+///
+/// let data = "1\n2\n";
+/// let reader = ChecksumReader::new(data.as_bytes(), checksum::crc32(b"1\n3\n"));
+/// let mut ndjson_iter =
+///     ndjson_stream::from_fallible_iter::<u32, _>(ReaderSource::new(reader).into_chunks());
+///
+/// assert_eq!(ndjson_iter.next().unwrap().unwrap(), 1);
+/// assert_eq!(ndjson_iter.next().unwrap().unwrap(), 2);
+/// assert!(matches!(ndjson_iter.next(), Some(Err(FallibleNdjsonError::InputError(_)))));
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+#[derive(Debug)]
+pub struct ChecksumReader<R> {
+    reader: R,
+    crc32: Crc32,
+    expected: u32
+}
+
+#[cfg(feature = "std")]
+impl<R> ChecksumReader<R> {
+
+    /// Creates a new reader which verifies that the data read from the given `reader` has the
+    /// given `expected` CRC-32 checksum.
+    pub fn new(reader: R, expected: u32) -> ChecksumReader<R> {
+        ChecksumReader {
+            reader,
+            crc32: Crc32::new(),
+            expected
+        }
+    }
+
+    /// Gets the checksum which the data is expected to have.
+    pub fn expected(&self) -> u32 {
+        self.expected
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl ChecksumReader<File> {
+
+    /// Opens the file at the given `path` and creates a new reader which verifies it against the
+    /// checksum stored in the sidecar file at `sidecar_path`. The sidecar file contains the
+    /// checksum as at most 8 hexadecimal digits, optionally preceded by `crc32:`, followed by
+    /// optional whitespace and arbitrary further text, such as the name of the checked file.
+    ///
+    /// # Errors
+    ///
+    /// If either file cannot be opened or read, or with [ErrorKind::InvalidData] holding an
+    /// [IntegrityError] if the sidecar file does not contain a valid checksum.
+    pub fn open_with_sidecar(path: impl AsRef<Path>, sidecar_path: impl AsRef<Path>)
+            -> io::Result<ChecksumReader<File>> {
+        let sidecar = fs::read(sidecar_path)?;
+        let expected = parse_sidecar(&sidecar)
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+        Ok(ChecksumReader::new(File::open(path)?, expected))
+    }
+}
+
+#[cfg(feature = "std")]
+fn parse_sidecar(sidecar: &[u8]) -> Result<u32, IntegrityError> {
+    let checksum = sidecar.split(u8::is_ascii_whitespace)
+        .find(|token| !token.is_empty())
+        .ok_or(IntegrityError::MissingChecksum)?;
+    let checksum = checksum.strip_prefix(&CRC32_MARKER[1..]).unwrap_or(checksum);
+
+    parse_checksum(checksum)
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read(buf)?;
+
+        self.crc32.update(&buf[..len]);
+
+        if len == 0 && !buf.is_empty() && self.crc32.finish() != self.expected {
+            let error = IntegrityError::ChecksumMismatch {
+                expected: self.expected,
+                actual: self.crc32.finish()
+            };
+
+            return Err(io::Error::new(ErrorKind::InvalidData, error));
+        }
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn crc32_matches_reference_values() {
+        assert_that!(crc32(b"")).is_equal_to(0);
+        assert_that!(crc32(b"123456789")).is_equal_to(0xcbf43926);
+        assert_that!(crc32(b"The quick brown fox jumps over the lazy dog")).is_equal_to(0x414fa339);
+    }
+
+    #[test]
+    fn incremental_crc32_equals_contiguous_crc32() {
+        let mut incremental = Crc32::new();
+        incremental.update(b"1234");
+        incremental.update(b"");
+        incremental.update(b"56789");
+
+        assert_that!(incremental.finish()).is_equal_to(0xcbf43926);
+    }
+
+    #[test]
+    fn valid_suffix_is_stripped() {
+        let line = format!("{{\"a\":\"|crc32:\"}}{}", crc32_suffix(b"{\"a\":\"|crc32:\"}"));
+        let upper_case = format!("[1]{} \r", crc32_suffix(b"[1]").to_uppercase()
+            .replace("|CRC32:", "|crc32:"));
+
+        assert_that!(Crc32Verifier::new().verify(line.as_bytes()))
+            .contains_value(b"{\"a\":\"|crc32:\"}".as_slice());
+        assert_that!(Crc32Verifier::new().verify(upper_case.as_bytes()))
+            .contains_value(b"[1]".as_slice());
+    }
+
+    #[test]
+    fn invalid_suffixes_are_reported() {
+        let verifier = Crc32Verifier::new();
+
+        assert_that!(verifier.verify(b"[1]")).contains_error(IntegrityError::MissingChecksum);
+        assert_that!(verifier.verify(b"[1]|crc32:"))
+            .contains_error(IntegrityError::MalformedChecksum);
+        assert_that!(verifier.verify(b"[1]|crc32:+1"))
+            .contains_error(IntegrityError::MalformedChecksum);
+        assert_that!(verifier.verify(b"[1]|crc32:123456789"))
+            .contains_error(IntegrityError::MalformedChecksum);
+        assert_that!(verifier.verify(b"[2]|crc32:00000000")).contains_error(
+            IntegrityError::ChecksumMismatch { expected: 0, actual: crc32(b"[2]") });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sidecar_checksum_is_parsed() {
+        assert_that!(parse_sidecar(b"cbf43926  data.ndjson\n")).contains_value(0xcbf43926);
+        assert_that!(parse_sidecar(b"\ncrc32:CBF43926")).contains_value(0xcbf43926);
+        assert_that!(parse_sidecar(b" \n")).contains_error(IntegrityError::MissingChecksum);
+        assert_that!(parse_sidecar(b"sha256:00")).contains_error(IntegrityError::MalformedChecksum);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn checksum_reader_fails_at_end_of_corrupted_data() {
+        let mut valid = ChecksumReader::new(b"123456789".as_slice(), 0xcbf43926);
+        let mut corrupted = ChecksumReader::new(b"123456780".as_slice(), 0xcbf43926);
+        let mut valid_data = Vec::new();
+        let mut corrupted_data = Vec::new();
+
+        assert_that!(valid.read_to_end(&mut valid_data).is_ok()).is_true();
+        assert_that!(valid_data).contains_exactly_in_given_order(b"123456789".iter().copied());
+
+        let error = corrupted.read_to_end(&mut corrupted_data).unwrap_err();
+        let integrity_error = error.get_ref()
+            .and_then(|error| error.downcast_ref::<IntegrityError>());
+
+        assert_that!(error.kind()).is_equal_to(ErrorKind::InvalidData);
+        assert_that!(integrity_error.copied()).contains(IntegrityError::ChecksumMismatch {
+            expected: 0xcbf43926,
+            actual: crc32(b"123456780")
+        });
+    }
+}
@@ -0,0 +1,209 @@
+//! This module offers [compare_sorted], which compares two sequences of NDJSON-records, such as the
+//! output of two [NdjsonIter](crate::driver::iter::NdjsonIter)s, and reports the records which are
+//! missing, extra, or changed. This is useful for validating data migrations, where the result of
+//! the migration should match a reference export.
+
+use core::cmp::Ordering;
+use core::iter::{Fuse, FusedIterator};
+
+/// A difference between two sequences of records, as reported by [CompareSorted]. The first
+/// sequence given to [compare_sorted] is considered the reference, i.e. the left side, and the
+/// second one is compared against it, i.e. the right side.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Difference<T> {
+
+    /// A record of the left side for which no record with the same key exists on the right side.
+    Missing(T),
+
+    /// A record of the right side for which no record with the same key exists on the left side.
+    Extra(T),
+
+    /// Two records with the same key which are not equal.
+    Changed {
+
+        /// The record of the left side.
+        left: T,
+
+        /// The record of the right side.
+        right: T
+    }
+}
+
+/// An [Iterator] over the [Difference]s between two sequences of records which are sorted by the
+/// same key. Created by [compare_sorted].
+pub struct CompareSorted<L, R, T, F> {
+    left: Fuse<L>,
+    right: Fuse<R>,
+    left_head: Option<T>,
+    right_head: Option<T>,
+    key: F
+}
+
+/// Compares the records of the given `left` and `right` sequences, which must both be sorted in
+/// ascending order of the key extracted by the given `key` function. Records of both sides with
+/// the same key are compared for equality. The returned iterator yields the [Difference]s in order
+/// of their keys, while errors of either side, such as parse errors, are forwarded as they are
+/// encountered.
+///
+/// Since both sequences are processed in lockstep, at most one record per side is held in memory
+/// at any time, independent of the length of the input. If a sequence is not sorted, spurious
+/// differences are reported. Keys are assumed to be unique within a sequence, otherwise records are
+/// matched up in order of appearance.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::compare::{self, Difference};
+/// use ndjson_stream::engine;
+///
+/// let reference = engine::parse_all::<(u32, String), _>(["[1,\"a\"]\n[2,\"b\"]\n[3,\"c\"]\n"]);
+/// let migrated = engine::parse_all::<(u32, String), _>(["[1,\"a\"]\n[3,\"C\"]\n[4,\"d\"]\n"]);
+/// let differences = compare::compare_sorted(reference, migrated, |&(id, _)| id)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(differences, vec![
+///     Difference::Missing((2, "b".to_owned())),
+///     Difference::Changed { left: (3, "c".to_owned()), right: (3, "C".to_owned()) },
+///     Difference::Extra((4, "d".to_owned()))
+/// ]);
+/// ```
+pub fn compare_sorted<T, E, K, L, R, F>(left: L, right: R, key: F)
+    -> CompareSorted<L::IntoIter, R::IntoIter, T, F>
+where
+    L: IntoIterator<Item = Result<T, E>>,
+    R: IntoIterator<Item = Result<T, E>>,
+    F: FnMut(&T) -> K,
+    K: Ord
+{
+    CompareSorted {
+        left: left.into_iter().fuse(),
+        right: right.into_iter().fuse(),
+        left_head: None,
+        right_head: None,
+        key
+    }
+}
+
+fn fill_head<T, E>(head: &mut Option<T>, records: &mut impl Iterator<Item = Result<T, E>>)
+    -> Result<(), E> {
+    if head.is_none() {
+        *head = records.next().transpose()?;
+    }
+
+    Ok(())
+}
+
+impl<T, E, K, L, R, F> Iterator for CompareSorted<L, R, T, F>
+where
+    L: Iterator<Item = Result<T, E>>,
+    R: Iterator<Item = Result<T, E>>,
+    T: PartialEq,
+    F: FnMut(&T) -> K,
+    K: Ord
+{
+    type Item = Result<Difference<T>, E>;
+
+    fn next(&mut self) -> Option<Result<Difference<T>, E>> {
+        loop {
+            if let Err(error) = fill_head(&mut self.left_head, &mut self.left) {
+                return Some(Err(error));
+            }
+
+            if let Err(error) = fill_head(&mut self.right_head, &mut self.right) {
+                return Some(Err(error));
+            }
+
+            let ordering = match (&self.left_head, &self.right_head) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(left), Some(right)) => (self.key)(left).cmp(&(self.key)(right))
+            };
+
+            match ordering {
+                Ordering::Less => return self.left_head.take().map(Difference::Missing).map(Ok),
+                Ordering::Greater => return self.right_head.take().map(Difference::Extra).map(Ok),
+                Ordering::Equal => {
+                    let left = self.left_head.take()?;
+                    let right = self.right_head.take()?;
+
+                    if left != right {
+                        return Some(Ok(Difference::Changed { left, right }));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T, E, K, L, R, F> FusedIterator for CompareSorted<L, R, T, F>
+where
+    L: Iterator<Item = Result<T, E>>,
+    R: Iterator<Item = Result<T, E>>,
+    T: PartialEq,
+    F: FnMut(&T) -> K,
+    K: Ord
+{ }
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    fn record(key: u64, value: u64) -> Result<TestStruct, &'static str> {
+        Ok(TestStruct { key, value })
+    }
+
+    fn compare(left: Vec<Result<TestStruct, &'static str>>,
+            right: Vec<Result<TestStruct, &'static str>>)
+            -> Vec<Result<Difference<TestStruct>, &'static str>> {
+        compare_sorted(left, right, |record| record.key).collect()
+    }
+
+    #[test]
+    fn equal_sequences_have_no_differences() {
+        let left = vec![record(1, 2), record(3, 4)];
+        let right = vec![record(1, 2), record(3, 4)];
+
+        assert_that!(compare(left, right)).is_empty();
+    }
+
+    #[test]
+    fn differences_are_reported_in_key_order() {
+        let left = vec![record(1, 1), record(2, 2), record(4, 4), record(6, 6)];
+        let right = vec![record(2, 2), record(3, 3), record(4, 5)];
+
+        assert_that!(compare(left, right)).contains_exactly_in_given_order([
+            Ok(Difference::Missing(TestStruct { key: 1, value: 1 })),
+            Ok(Difference::Extra(TestStruct { key: 3, value: 3 })),
+            Ok(Difference::Changed {
+                left: TestStruct { key: 4, value: 4 },
+                right: TestStruct { key: 4, value: 5 }
+            }),
+            Ok(Difference::Missing(TestStruct { key: 6, value: 6 }))
+        ]);
+    }
+
+    #[test]
+    fn errors_are_forwarded_without_losing_records() {
+        let left = vec![record(1, 1), Err("left"), record(2, 2)];
+        let right = vec![Err("right"), record(2, 3)];
+
+        assert_that!(compare(left, right)).contains_exactly_in_given_order([
+            Err("right"),
+            Ok(Difference::Missing(TestStruct { key: 1, value: 1 })),
+            Err("left"),
+            Ok(Difference::Changed {
+                left: TestStruct { key: 2, value: 2 },
+                right: TestStruct { key: 2, value: 3 }
+            })
+        ]);
+    }
+}
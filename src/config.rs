@@ -1,6 +1,23 @@
 //! This module defines the configuration options which a NDJSON-parser can be provided. The entry
 //! point is the [NdjsonConfig] struct. Child data types are also defined in this module.
 
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+use serde_json::Value;
+
+use crate::error::SchemaViolation;
+use crate::middleware::{LineMiddleware, SharedMiddleware};
+use crate::redact;
+
 /// Controls how the parser deals with lines that contain no JSON values.
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 pub enum EmptyLineHandling {
@@ -20,6 +37,481 @@ pub enum EmptyLineHandling {
     IgnoreBlank
 }
 
+/// Controls which character sequences the parser considers to separate lines.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LineEnding {
+
+    /// Lines are separated by `\n` characters. A `\r` character preceding the `\n` remains part
+    /// of the line, which is compatible with `\r\n`-style linebreaks since JSON considers `\r` to
+    /// be whitespace.
+    #[default]
+    Lf,
+
+    /// Lines are separated by `\r\n` sequences, which are not part of the line. A `\n` character
+    /// which is not preceded by a `\r` character does not end a line.
+    CrLf,
+
+    /// Lines are separated by `\r` characters, as used by old Mac systems. A `\n` character does
+    /// not end a line.
+    Cr,
+
+    /// Lines are separated by any of `\n`, `\r\n`, or `\r`. A `\r\n` sequence is considered a
+    /// single line ending.
+    Any
+}
+
+/// Controls how the parser deals with data that follows a valid JSON value on the same line, such
+/// as in the line `{"a":1} trailing`. Whitespace after the JSON value is never considered trailing
+/// data.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum TrailingDataHandling {
+
+    /// Raise the error produced by [serde_json] for lines with trailing data. This is the behavior
+    /// of [serde_json::from_slice].
+    #[default]
+    Error,
+
+    /// Ignore any data after the first JSON value on a line and output that value as if the
+    /// trailing data were not present.
+    Ignore,
+
+    /// Raise an error for lines with trailing data, whose message includes the trailing data. This
+    /// can help in finding out what a sloppy producer appends to its records.
+    IncludeInError,
+
+    /// Treat trailing data as further JSON values and output every value on a line as a separate
+    /// record, for producers which put several whitespace-separated values onto one line, such as
+    /// `{"a":1} {"a":2}`. If a value cannot be parsed, a single error is raised for the rest of the
+    /// line, while the values before it are still output. This mode applies only to records parsed
+    /// by an [NdjsonEngine](crate::engine::NdjsonEngine), while all other parsers, such as the
+    /// parsing of headers, treat it like [TrailingDataHandling::Error]. It cannot be combined with
+    /// salvaging records via [NdjsonConfig::with_salvage].
+    MultipleValues
+}
+
+/// Controls whether records which cannot be parsed are reported as errors or skipped. This applies
+/// to all errors of records, i.e. invalid JSON, lines exceeding the maximum line length, rejected
+/// duplicate keys, and errors of a [LineMiddleware].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum InvalidLineHandling {
+
+    /// Output an error for every record which cannot be parsed.
+    #[default]
+    Report,
+
+    /// Silently skip records which cannot be parsed. Errors are only constructed as far as
+    /// necessary to detect them, so no data is copied into them and their messages are not
+    /// formatted. For example, [TrailingDataHandling::IncludeInError] is treated like
+    /// [TrailingDataHandling::Error], since the message including the trailing data is never
+    /// read. Skipped records are not counted in [Progress::records_emitted], but in
+    /// [Progress::invalid_records_skipped].
+    Skip
+}
+
+/// A naming convention for the keys of JSON objects, to which all keys of a line can be converted
+/// before it is parsed. See [NdjsonConfig::with_key_case].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum KeyCase {
+
+    /// Lowercase words separated by underscores, such as `user_id`. Uppercase ASCII letters start a
+    /// new word if they follow a lowercase letter or digit, or if they are followed by a lowercase
+    /// letter after another uppercase letter. Hence, `userId`, `UserId`, and `userID` are all
+    /// converted to `user_id`, while `HTTPServer` is converted to `http_server`.
+    Snake,
+
+    /// Words separated by capitalization, such as `userId`. Every underscore is removed and the
+    /// following character is converted to uppercase. Leading and trailing underscores are kept,
+    /// so `user_id` is converted to `userId`, while `_private` remains unchanged. Other letters are
+    /// not changed, so `UserId` remains unchanged as well.
+    Camel
+}
+
+type PreprocessFn = dyn Fn(&[u8]) -> Cow<'_, [u8]> + Send + Sync;
+
+/// A hook which transforms the raw bytes of every line before they are parsed, for example to
+/// strip a vendor-specific prefix. It is stored behind an [Arc], so cloning it is cheap. See
+/// [NdjsonConfig::with_line_preprocessor].
+#[derive(Clone)]
+pub struct LinePreprocessor {
+    preprocess: Arc<PreprocessFn>
+}
+
+impl LinePreprocessor {
+
+    /// Creates a new line preprocessor from the given function. It receives the line without the
+    /// line ending and returns the bytes which are to be parsed instead, borrowing from the line if
+    /// no modification is necessary. For lines exceeding the length configured in
+    /// [NdjsonConfig::with_max_line_length], it receives only the prefix which is included in the
+    /// error, which may end anywhere within the line.
+    pub fn new<F>(preprocess: F) -> LinePreprocessor
+    where
+        F: Fn(&[u8]) -> Cow<'_, [u8]> + Send + Sync + 'static
+    {
+        LinePreprocessor {
+            preprocess: Arc::new(preprocess)
+        }
+    }
+
+    /// Creates a new line preprocessor which masks the values of the given top-level `keys` by the
+    /// string `"[REDACTED]"`. See [LinePreprocessor::redacting_keys_with_mask] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::LinePreprocessor;
+    ///
+    /// let preprocessor = LinePreprocessor::redacting_keys(["password"]);
+    ///
+    /// assert_eq!(preprocessor.preprocess(br#"{"user":"name","password":"secret"}"#).as_ref(),
+    ///     br#"{"user":"name","password":"[REDACTED]"}"#);
+    /// ```
+    pub fn redacting_keys<I>(keys: I) -> LinePreprocessor
+    where
+        I: IntoIterator,
+        I::Item: Into<String>
+    {
+        LinePreprocessor::redacting_keys_with_mask(keys, &Value::from("[REDACTED]"))
+    }
+
+    /// Creates a new line preprocessor which replaces the values of the given top-level `keys` by
+    /// `mask`. This operates on the raw line bytes, so secrets never reach the parser, and thus
+    /// neither any error report nor
+    /// [NdjsonEvent::ParseError](crate::event::NdjsonEvent::ParseError). The line is scanned
+    /// without being validated. If it is malformed, all members before the first malformation are
+    /// redacted. If the malformation is within the value of a redacted key, the rest of the line is
+    /// masked. Keys are compared to the raw key strings, i.e. keys containing escape sequences are
+    /// not matched. Nested objects are not inspected.
+    ///
+    /// The preprocessor is also applied to the first bytes of lines exceeding the length configured
+    /// in [NdjsonConfig::with_max_line_length], which are included in
+    /// [NdjsonError::LineTooLong](crate::error::NdjsonError::LineTooLong). If such a prefix ends
+    /// within the value of a redacted key, that value is masked as well.
+    pub fn redacting_keys_with_mask<I>(keys: I, mask: &Value) -> LinePreprocessor
+    where
+        I: IntoIterator,
+        I::Item: Into<String>
+    {
+        let keys = keys.into_iter()
+            .map(|key| key.into().into_bytes())
+            .collect::<BTreeSet<Vec<u8>>>();
+        let mask = mask.to_string().into_bytes();
+
+        LinePreprocessor::new(move |line| redact::redact_top_level_keys(line, &keys, &mask))
+    }
+
+    /// Applies this preprocessor to the given line.
+    ///
+    /// # Returns
+    ///
+    /// The bytes which are to be parsed instead of the given line.
+    pub fn preprocess<'line>(&self, line: &'line [u8]) -> Cow<'line, [u8]> {
+        (self.preprocess)(line)
+    }
+}
+
+impl Debug for LinePreprocessor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("LinePreprocessor { .. }")
+    }
+}
+
+type SchemaValidateFn = dyn Fn(&Value) -> Result<(), SchemaViolation> + Send + Sync;
+
+/// A hook which checks the JSON value of every line against a schema before it is deserialized
+/// into the output type, for example by delegating to a JSON Schema implementation. It is stored
+/// behind an [Arc], so cloning it is cheap. See [NdjsonConfig::with_schema_validator].
+#[derive(Clone)]
+pub struct SchemaValidator {
+    validate: Arc<SchemaValidateFn>
+}
+
+impl SchemaValidator {
+
+    /// Creates a new schema validator from the given function. It receives the parsed JSON value of
+    /// a line and returns a [SchemaViolation] describing the first violation, if any.
+    pub fn new<F>(validate: F) -> SchemaValidator
+    where
+        F: Fn(&Value) -> Result<(), SchemaViolation> + Send + Sync + 'static
+    {
+        SchemaValidator {
+            validate: Arc::new(validate)
+        }
+    }
+
+    /// Checks the given value against the schema of this validator.
+    ///
+    /// # Errors
+    ///
+    /// A [SchemaViolation] if the value does not conform to the schema.
+    pub fn validate(&self, value: &Value) -> Result<(), SchemaViolation> {
+        (self.validate)(value)
+    }
+}
+
+impl Debug for SchemaValidator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("SchemaValidator { .. }")
+    }
+}
+
+type SentinelFn = dyn Fn(&[u8]) -> bool + Send + Sync;
+
+/// A predicate which recognizes a line that marks the end of the data, such as a bare `null` or an
+/// object like `{"type":"end"}` sent by some producers. It is stored behind an [Arc], so cloning it
+/// is cheap. See [NdjsonConfig::with_end_sentinel].
+#[derive(Clone)]
+pub struct EndSentinel {
+    is_sentinel: Arc<SentinelFn>
+}
+
+impl EndSentinel {
+
+    /// Creates a new end sentinel from the given predicate. It receives every line without the
+    /// line ending and returns `true` if the line marks the end of the data.
+    pub fn new<F>(is_sentinel: F) -> EndSentinel
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static
+    {
+        EndSentinel {
+            is_sentinel: Arc::new(is_sentinel)
+        }
+    }
+
+    /// Creates a new end sentinel which recognizes lines consisting of the JSON value `null`,
+    /// optionally surrounded by whitespace.
+    pub fn null() -> EndSentinel {
+        EndSentinel::new(|line| line.trim_ascii() == b"null")
+    }
+
+    /// Creates a new end sentinel which recognizes lines containing a JSON value equal to the given
+    /// `value`, regardless of formatting and the order of object members. Note that this parses
+    /// every line as a [Value] in addition to the regular parsing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::EndSentinel;
+    /// use serde_json::json;
+    ///
+    /// let sentinel = EndSentinel::value(json!({ "type": "end" }));
+    ///
+    /// assert!(sentinel.is_sentinel(br#"{ "type" : "end" }"#));
+    /// assert!(!sentinel.is_sentinel(br#"{"type":"data"}"#));
+    /// ```
+    pub fn value(value: Value) -> EndSentinel {
+        EndSentinel::new(move |line| serde_json::from_slice::<Value>(line)
+            .is_ok_and(|line_value| line_value == value))
+    }
+
+    /// Checks whether the given line marks the end of the data.
+    pub fn is_sentinel(&self, line: &[u8]) -> bool {
+        (self.is_sentinel)(line)
+    }
+}
+
+impl Debug for EndSentinel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("EndSentinel { .. }")
+    }
+}
+
+/// The progress of an NDJSON-parser, as reported to a [ProgressCallback].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Progress {
+
+    /// The number of bytes given to the parser so far, including incomplete lines.
+    pub bytes_consumed: u64,
+
+    /// The number of records emitted by the parser so far, including lines which could not be
+    /// parsed. Lines which are ignored or skipped are not counted.
+    pub records_emitted: u64,
+
+    /// The number of empty or blank lines which were ignored according to the configured
+    /// [EmptyLineHandling] so far. See also [NdjsonConfig::with_ignored_line_callback].
+    pub lines_ignored: u64,
+
+    /// The number of records which could not be parsed and were skipped according to
+    /// [InvalidLineHandling::Skip] so far.
+    pub invalid_records_skipped: u64
+}
+
+/// Controls how often a [ProgressCallback] is invoked.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProgressInterval {
+
+    /// Report progress whenever the given number of further records has been emitted. An interval
+    /// of `0` is treated as `1`.
+    Records(u64),
+
+    /// Report progress whenever a chunk of data completes the given number of further bytes. Since
+    /// progress is only reported after entire chunks, multiples of the interval may be skipped for
+    /// large chunks. An interval of `0` is treated as `1`.
+    Bytes(u64)
+}
+
+type ProgressFn = dyn Fn(Progress) + Send + Sync;
+
+/// A hook which is invoked with the current [Progress] of a parser in a configured
+/// [ProgressInterval], for example to drive a progress bar during a long-running import. It is
+/// stored behind an [Arc], so cloning it is cheap. See [NdjsonConfig::with_progress_callback].
+#[derive(Clone)]
+pub struct ProgressCallback {
+    report: Arc<ProgressFn>
+}
+
+impl ProgressCallback {
+
+    /// Creates a new progress callback from the given function.
+    pub fn new<F>(report: F) -> ProgressCallback
+    where
+        F: Fn(Progress) + Send + Sync + 'static
+    {
+        ProgressCallback {
+            report: Arc::new(report)
+        }
+    }
+
+    /// Invokes this callback with the given progress.
+    pub fn report(&self, progress: Progress) {
+        (self.report)(progress)
+    }
+}
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback { .. }")
+    }
+}
+
+type IgnoredLineFn = dyn Fn(&[u8], u64) + Send + Sync;
+
+/// A hook which is invoked with every line that is ignored according to the configured
+/// [EmptyLineHandling], together with its one-based line number. It is stored behind an [Arc], so
+/// cloning it is cheap. See [NdjsonConfig::with_ignored_line_callback].
+#[derive(Clone)]
+pub struct IgnoredLineCallback {
+    on_ignored_line: Arc<IgnoredLineFn>
+}
+
+impl IgnoredLineCallback {
+
+    /// Creates a new ignored-line callback from the given function, which receives the ignored
+    /// line and its line number.
+    pub fn new<F>(on_ignored_line: F) -> IgnoredLineCallback
+    where
+        F: Fn(&[u8], u64) + Send + Sync + 'static
+    {
+        IgnoredLineCallback {
+            on_ignored_line: Arc::new(on_ignored_line)
+        }
+    }
+
+    /// Invokes this callback with the given line and line number.
+    pub fn report(&self, line: &[u8], line_number: u64) {
+        (self.on_ignored_line)(line, line_number)
+    }
+}
+
+impl Debug for IgnoredLineCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("IgnoredLineCallback { .. }")
+    }
+}
+
+type LargeRecordFn = dyn Fn(usize, u64) + Send + Sync;
+
+/// A hook which is invoked with the length and one-based line number of every line which exceeds
+/// a configured threshold, for example to log unusually large records for capacity planning or
+/// abuse detection. It is stored behind an [Arc], so cloning it is cheap. See
+/// [NdjsonConfig::with_large_record_callback].
+#[derive(Clone)]
+pub struct LargeRecordCallback {
+    report: Arc<LargeRecordFn>
+}
+
+impl LargeRecordCallback {
+
+    /// Creates a new large-record callback from the given function, which receives the length of
+    /// the large line in bytes and its line number.
+    pub fn new<F>(report: F) -> LargeRecordCallback
+    where
+        F: Fn(usize, u64) + Send + Sync + 'static
+    {
+        LargeRecordCallback {
+            report: Arc::new(report)
+        }
+    }
+
+    /// Invokes this callback with the given line length and line number.
+    pub fn report(&self, length: usize, line_number: u64) {
+        (self.report)(length, line_number)
+    }
+}
+
+impl Debug for LargeRecordCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("LargeRecordCallback { .. }")
+    }
+}
+
+/// Measurements taken while deserializing a single record, as reported to a
+/// [ParseMetricsCallback].
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParseMetrics {
+
+    /// The length of the deserialized line in bytes, after preprocessing and without the line
+    /// ending.
+    pub line_length: usize,
+
+    /// The time it took to deserialize the line, including checks such as the rejection of
+    /// duplicate keys.
+    pub duration: Duration,
+
+    /// Whether deserialization failed, i.e. a parse error was emitted instead of a record.
+    pub is_error: bool
+}
+
+#[cfg(feature = "std")]
+type ParseMetricsFn = dyn Fn(ParseMetrics) + Send + Sync;
+
+/// A hook which is invoked with the [ParseMetrics] of every deserialized record, for example to
+/// record a histogram of decode latency by record size. It is stored behind an [Arc], so cloning
+/// it is cheap. See [NdjsonConfig::with_parse_metrics_callback].
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+#[derive(Clone)]
+pub struct ParseMetricsCallback {
+    report: Arc<ParseMetricsFn>
+}
+
+#[cfg(feature = "std")]
+impl ParseMetricsCallback {
+
+    /// Creates a new parse-metrics callback from the given function.
+    pub fn new<F>(report: F) -> ParseMetricsCallback
+    where
+        F: Fn(ParseMetrics) + Send + Sync + 'static
+    {
+        ParseMetricsCallback {
+            report: Arc::new(report)
+        }
+    }
+
+    /// Invokes this callback with the given metrics.
+    pub fn report(&self, metrics: ParseMetrics) {
+        (self.report)(metrics)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Debug for ParseMetricsCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("ParseMetricsCallback { .. }")
+    }
+}
+
 /// Configuration for the NDJSON-parser which controls the behavior in various situations.
 ///
 /// By default, the parser will attempt to parse every line, i.e. every segment between `\n`
@@ -35,12 +527,39 @@ pub enum EmptyLineHandling {
 ///     .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
 ///     .with_parse_rest(true);
 /// ```
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+///
+/// Besides simple flags, a config can hold owned data such as a comment prefix or a
+/// [LinePreprocessor]. All of these are reference-counted, so configs remain cheap to clone.
+#[derive(Clone, Debug, Default)]
 pub struct NdjsonConfig {
     pub(crate) empty_line_handling: EmptyLineHandling,
-    pub(crate) parse_rest: bool
+    pub(crate) parse_rest: bool,
+    pub(crate) trailing_data_handling: TrailingDataHandling,
+    pub(crate) invalid_line_handling: InvalidLineHandling,
+    pub(crate) reject_duplicate_keys: bool,
+    pub(crate) schema_validator: Option<SchemaValidator>,
+    pub(crate) salvage: bool,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) comment_prefix: Option<Arc<[u8]>>,
+    pub(crate) line_preprocessor: Option<LinePreprocessor>,
+    pub(crate) line_middleware: Option<SharedMiddleware>,
+    pub(crate) key_case: Option<KeyCase>,
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) oversized_line_prefix_len: Option<usize>,
+    pub(crate) progress_reporting: Option<(ProgressInterval, ProgressCallback)>,
+    pub(crate) ignored_line_callback: Option<IgnoredLineCallback>,
+    pub(crate) large_record_reporting: Option<(usize, LargeRecordCallback)>,
+    #[cfg(feature = "std")]
+    pub(crate) parse_metrics_callback: Option<ParseMetricsCallback>,
+    pub(crate) max_records: Option<u64>,
+    pub(crate) end_sentinel: Option<EndSentinel>,
+    pub(crate) json_array_mode: bool
 }
 
+/// The number of bytes of an oversized line included in its error by default. See
+/// [NdjsonConfig::with_oversized_line_prefix_len].
+pub const DEFAULT_OVERSIZED_LINE_PREFIX_LEN: usize = 64;
+
 impl NdjsonConfig {
 
     /// Creates a new config from this config which has a different handling for lines that contain
@@ -72,4 +591,893 @@ impl NdjsonConfig {
             ..self
         }
     }
+
+    /// Creates a new config from this config which has a different handling for data following a
+    /// valid JSON value on the same line. See [TrailingDataHandling] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the trailing-data-handling.
+    pub fn with_trailing_data_handling(self, trailing_data_handling: TrailingDataHandling)
+            -> NdjsonConfig {
+        NdjsonConfig {
+            trailing_data_handling,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has a different handling for records that
+    /// cannot be parsed. See [InvalidLineHandling] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::{InvalidLineHandling, NdjsonConfig};
+    ///
+    /// let config = NdjsonConfig::default().with_invalid_line_handling(InvalidLineHandling::Skip);
+    /// let records = ndjson_stream::from_iter_with_config::<u32, _>(vec!["1\nx\n3\n"], config)
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(records, vec![1, 3]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the invalid-line-handling.
+    pub fn with_invalid_line_handling(self, invalid_line_handling: InvalidLineHandling)
+            -> NdjsonConfig {
+        NdjsonConfig {
+            invalid_line_handling,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has the given configuration on whether to
+    /// reject lines containing an object with duplicate keys, such as `{"a":1,"a":2}`. If
+    /// `reject_duplicate_keys` is set to `true`, such lines result in an
+    /// [NdjsonError::DuplicateKey](crate::error::NdjsonError::DuplicateKey) containing the
+    /// duplicated key instead of a record. Otherwise, the behavior of the output type's
+    /// [Deserialize](serde::Deserialize) implementation applies, which often ignores all but one
+    /// of the values. By default, this is set to `false`.
+    ///
+    /// Note that enabling this check requires an additional pass over every line.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the reject-duplicate-keys-flag.
+    pub fn with_reject_duplicate_keys(self, reject_duplicate_keys: bool) -> NdjsonConfig {
+        NdjsonConfig {
+            reject_duplicate_keys,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which checks every line against the schema of the
+    /// given [SchemaValidator] before deserializing it into the output type. Lines which do not
+    /// conform to the schema result in an
+    /// [NdjsonError::SchemaViolation](crate::error::NdjsonError::SchemaViolation) containing the
+    /// [SchemaViolation] reported by the validator instead of a record. Lines which are not valid
+    /// JSON are not given to the validator, but reported as usual. By default, no schema is
+    /// checked.
+    ///
+    /// Note that checking the schema requires parsing every line into a [Value] in an additional
+    /// pass.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::{NdjsonConfig, SchemaValidator};
+    /// use ndjson_stream::error::{NdjsonError, SchemaViolation};
+    ///
+    /// let validator = SchemaValidator::new(|value| match value.get("id") {
+    ///     Some(id) if id.is_u64() => Ok(()),
+    ///     Some(_) => Err(SchemaViolation::new("/id", "expected unsigned integer")),
+    ///     None => Err(SchemaViolation::new("", "missing required property `id`"))
+    /// });
+    /// let config = NdjsonConfig::default().with_schema_validator(validator);
+    /// let data = "{\"id\":1}\n{\"id\":-1}\n";
+    /// let mut records =
+    ///     ndjson_stream::from_iter_with_config::<serde_json::Value, _>([data], config);
+    ///
+    /// assert!(records.next().unwrap().is_ok());
+    ///
+    /// let Some(Err(NdjsonError::SchemaViolation(violation))) = records.next()
+    /// else {
+    ///     panic!("expected schema violation");
+    /// };
+    ///
+    /// assert_eq!(violation.pointer, "/id");
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the schema validator.
+    pub fn with_schema_validator(self, schema_validator: SchemaValidator) -> NdjsonConfig {
+        NdjsonConfig {
+            schema_validator: Some(schema_validator),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has the given configuration on whether to
+    /// salvage records from corrupted lines. If `salvage` is set to `true`, a line which cannot be
+    /// parsed is scanned for the next plausible record start, i.e. a `{"` sequence, and the rest
+    /// of the line from there is parsed again, which is repeated until parsing succeeds or no
+    /// further record start is found. This recovers records which follow a torn write on the same
+    /// line, such as the second record in `{"id":1,"na{"id":2}`, which would otherwise be lost
+    /// together with the corrupted one. The corrupted part is still reported as a parse error,
+    /// followed by the salvaged record. By default, this is set to `false`.
+    ///
+    /// Since record starts are only guessed, a nested object of a corrupted record may be salvaged
+    /// if it happens to match the record type. Failed lines are parsed repeatedly, so this should
+    /// not be enabled for input with many invalid lines. Salvaging is not supported together with
+    /// [TrailingDataHandling::MultipleValues], so this combination is rejected by
+    /// [NdjsonConfig::validate].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let config = ndjson_stream::config::NdjsonConfig::default().with_salvage(true);
+    /// let data = "{\"id\":1,\"na{\"id\":2}\n{\"id\":3}\n";
+    /// let mut ndjson_iter =
+    ///     ndjson_stream::from_iter_with_config::<serde_json::Value, _>([data], config);
+    ///
+    /// assert!(ndjson_iter.next().unwrap().is_err());
+    /// assert_eq!(ndjson_iter.next().unwrap().unwrap()["id"], 2);
+    /// assert_eq!(ndjson_iter.next().unwrap().unwrap()["id"], 3);
+    /// assert!(ndjson_iter.next().is_none());
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the salvage-flag.
+    pub fn with_salvage(self, salvage: bool) -> NdjsonConfig {
+        NdjsonConfig {
+            salvage,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has a different set of character sequences
+    /// considered to separate lines. See [LineEnding] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the line ending.
+    pub fn with_line_ending(self, line_ending: LineEnding) -> NdjsonConfig {
+        NdjsonConfig {
+            line_ending,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which ignores all lines starting with the given
+    /// `comment_prefix`, such as `#` or `//`. Comment lines are ignored just like empty lines, i.e.
+    /// they do not count as records. An empty prefix disables comment detection again. By default,
+    /// no lines are considered comments.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the comment prefix.
+    pub fn with_comment_prefix(self, comment_prefix: impl AsRef<[u8]>) -> NdjsonConfig {
+        let comment_prefix = comment_prefix.as_ref();
+
+        NdjsonConfig {
+            comment_prefix: (!comment_prefix.is_empty()).then(|| comment_prefix.into()),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which applies the given [LinePreprocessor] to every
+    /// line before it is checked for emptiness and parsed. Comment detection configured by
+    /// [NdjsonConfig::with_comment_prefix] operates on the original line. By default, lines are
+    /// parsed as they are.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the line preprocessor.
+    pub fn with_line_preprocessor(self, line_preprocessor: LinePreprocessor) -> NdjsonConfig {
+        NdjsonConfig {
+            line_preprocessor: Some(line_preprocessor),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which applies the given [LineMiddleware] to every
+    /// line after it has been checked for emptiness and before it is deserialized, such as a
+    /// [Base64Decoder](crate::middleware::Base64Decoder). If the middleware fails, its error is
+    /// reported instead of a record. If a middleware was configured before, the given one is
+    /// applied to its result, see [LineMiddleware::then]. By default, no middleware is applied.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the line middleware.
+    pub fn with_line_middleware<M>(self, line_middleware: M) -> NdjsonConfig
+    where
+        M: LineMiddleware + 'static
+    {
+        let line_middleware: Arc<dyn LineMiddleware> = match self.line_middleware {
+            Some(SharedMiddleware(previous)) => Arc::new(previous.then(line_middleware)),
+            None => Arc::new(line_middleware)
+        };
+
+        NdjsonConfig {
+            line_middleware: Some(SharedMiddleware(line_middleware)),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which converts the keys of all JSON objects of every
+    /// line, including nested ones, to the given [KeyCase] before the line is deserialized. This
+    /// allows one record type to ingest data from producers with different naming conventions
+    /// without duplicating `serde` attributes. The conversion is applied after the
+    /// [LineMiddleware] and also affects headers. Keys containing escape sequences and non-ASCII
+    /// characters are not converted. By default, keys are kept as they are.
+    ///
+    /// Note that converting keys requires an additional pass over every line, and a copy of lines
+    /// in which a key is changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::{KeyCase, NdjsonConfig};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct User {
+    ///     user_id: u64
+    /// }
+    ///
+    /// let config = NdjsonConfig::default().with_key_case(KeyCase::Snake);
+    /// let data = "{\"userId\":1}\n{\"user_id\":2}\n";
+    /// let records = ndjson_stream::from_iter_with_config::<User, _>([data], config)
+    ///     .map(Result::unwrap)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(records, vec![User { user_id: 1 }, User { user_id: 2 }]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the key case.
+    pub fn with_key_case(self, key_case: KeyCase) -> NdjsonConfig {
+        NdjsonConfig {
+            key_case: Some(key_case),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which limits the length of lines to
+    /// `max_line_length` bytes, excluding the line ending. A longer line results in an
+    /// [NdjsonError::LineTooLong](crate::error::NdjsonError::LineTooLong) instead of a record. Only
+    /// the first bytes of such a line are buffered, so a producer which never emits a line ending
+    /// cannot exhaust the memory of the parser. By default, the length of lines is not limited.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the maximum line length.
+    pub fn with_max_line_length(self, max_line_length: usize) -> NdjsonConfig {
+        NdjsonConfig {
+            max_line_length: Some(max_line_length),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which includes the first `prefix_len` bytes of lines
+    /// exceeding the length configured in [NdjsonConfig::with_max_line_length] in their error, so
+    /// the offending producer can be identified. The prefix is never longer than the maximum line
+    /// length before it is given to the [LinePreprocessor], if any. By default,
+    /// [DEFAULT_OVERSIZED_LINE_PREFIX_LEN] bytes are included.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the oversized-line-prefix-length.
+    pub fn with_oversized_line_prefix_len(self, prefix_len: usize) -> NdjsonConfig {
+        NdjsonConfig {
+            oversized_line_prefix_len: Some(prefix_len),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which invokes the given [ProgressCallback] with the
+    /// current [Progress] of the parser in the given [ProgressInterval]. The callback is invoked
+    /// synchronously while parsing, so it should return quickly. By default, no progress is
+    /// reported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use ndjson_stream::config::{NdjsonConfig, ProgressCallback, ProgressInterval};
+    ///
+    /// let records = Arc::new(AtomicU64::new(0));
+    /// let records_in_callback = Arc::clone(&records);
+    /// let callback = ProgressCallback::new(move |progress| {
+    ///     records_in_callback.store(progress.records_emitted, Ordering::Relaxed);
+    /// });
+    /// let config = NdjsonConfig::default()
+    ///     .with_progress_callback(ProgressInterval::Records(2), callback);
+    ///
+    /// let ndjson_iter = ndjson_stream::from_iter_with_config::<u32, _>(["1\n2\n3\n"], config);
+    ///
+    /// assert_eq!(ndjson_iter.count(), 3);
+    /// assert_eq!(records.load(Ordering::Relaxed), 2);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the progress callback and its
+    /// interval.
+    pub fn with_progress_callback(self, interval: ProgressInterval, callback: ProgressCallback)
+            -> NdjsonConfig {
+        NdjsonConfig {
+            progress_reporting: Some((interval, callback)),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which invokes the given `callback` with every line
+    /// that is ignored according to the configured [EmptyLineHandling], together with its one-based
+    /// line number. Line numbers count all lines given to the parser, including comments and lines
+    /// skipped otherwise. The line is given after preprocessing and without its line ending. The
+    /// number of ignored lines is also available as [Progress::lines_ignored]. Engines which emit
+    /// events report empty and blank lines as events instead, so the callback is not invoked for
+    /// them. By default, no callback is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use ndjson_stream::config::{EmptyLineHandling, IgnoredLineCallback, NdjsonConfig};
+    ///
+    /// let line_numbers = Arc::new(Mutex::new(Vec::new()));
+    /// let line_numbers_in_callback = Arc::clone(&line_numbers);
+    /// let callback = IgnoredLineCallback::new(move |_, line_number| {
+    ///     line_numbers_in_callback.lock().unwrap().push(line_number);
+    /// });
+    /// let config = NdjsonConfig::default()
+    ///     .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+    ///     .with_ignored_line_callback(callback);
+    ///
+    /// let ndjson_iter = ndjson_stream::from_iter_with_config::<u32, _>(["1\n\n2\n  \n"], config);
+    ///
+    /// assert_eq!(ndjson_iter.count(), 2);
+    /// assert_eq!(*line_numbers.lock().unwrap(), vec![2, 4]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the ignored-line callback.
+    pub fn with_ignored_line_callback(self, callback: IgnoredLineCallback) -> NdjsonConfig {
+        NdjsonConfig {
+            ignored_line_callback: Some(callback),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which invokes the given `callback` with the length
+    /// and line number of every line that is longer than `threshold` bytes, without the line
+    /// ending. In contrast to [NdjsonConfig::with_max_line_length], large lines are still parsed,
+    /// so this can be used to flag outliers before deciding on a hard limit. Oversized lines are
+    /// reported with their full length as well. Regardless of this setting, the distribution of
+    /// line lengths is tracked by the engine, see
+    /// [NdjsonEngine::record_size_histogram](crate::engine::NdjsonEngine::record_size_histogram).
+    /// By default, no callback is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use ndjson_stream::config::{LargeRecordCallback, NdjsonConfig};
+    ///
+    /// let large_records = Arc::new(Mutex::new(Vec::new()));
+    /// let large_records_in_callback = Arc::clone(&large_records);
+    /// let callback = LargeRecordCallback::new(move |length, line_number| {
+    ///     large_records_in_callback.lock().unwrap().push((length, line_number));
+    /// });
+    /// let config = NdjsonConfig::default().with_large_record_callback(3, callback);
+    ///
+    /// let ndjson_iter = ndjson_stream::from_iter_with_config::<u32, _>(["1\n2345\n6\n"], config);
+    ///
+    /// assert_eq!(ndjson_iter.count(), 3);
+    /// assert_eq!(*large_records.lock().unwrap(), vec![(4, 2)]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the large-record threshold and
+    /// callback.
+    pub fn with_large_record_callback(self, threshold: usize, callback: LargeRecordCallback)
+            -> NdjsonConfig {
+        NdjsonConfig {
+            large_record_reporting: Some((threshold, callback)),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which invokes the given `callback` with the
+    /// [ParseMetrics] of every line that is deserialized, i.e. every emitted record except for
+    /// oversized lines, which are rejected without being deserialized. The callback is invoked on
+    /// the thread that parses the line, directly after deserialization, so it should be cheap.
+    /// Deserialization is only timed if a callback is set, which is not the case by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use ndjson_stream::config::{NdjsonConfig, ParseMetricsCallback};
+    ///
+    /// let line_lengths = Arc::new(Mutex::new(Vec::new()));
+    /// let line_lengths_in_callback = Arc::clone(&line_lengths);
+    /// let callback = ParseMetricsCallback::new(move |metrics| {
+    ///     line_lengths_in_callback.lock().unwrap().push(metrics.line_length);
+    /// });
+    /// let config = NdjsonConfig::default().with_parse_metrics_callback(callback);
+    ///
+    /// let ndjson_iter = ndjson_stream::from_iter_with_config::<u32, _>(["1\n234\n"], config);
+    ///
+    /// assert_eq!(ndjson_iter.count(), 2);
+    /// assert_eq!(*line_lengths.lock().unwrap(), vec![1, 3]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the parse-metrics callback.
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+    pub fn with_parse_metrics_callback(self, callback: ParseMetricsCallback) -> NdjsonConfig {
+        NdjsonConfig {
+            parse_metrics_callback: Some(callback),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which stops emitting records once `max_records`
+    /// records, including parse errors, have been emitted. All subsequent lines are discarded
+    /// without being parsed and drivers end as soon as the limit is reached, without reading any
+    /// further input. This is useful for sampling the head of large feeds. If `max_records` is
+    /// `None`, the number of records is not limited, which is the default.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the maximum number of records.
+    pub fn with_max_records(self, max_records: Option<u64>) -> NdjsonConfig {
+        NdjsonConfig {
+            max_records,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which ends the data at the first line recognized by
+    /// the given [EndSentinel]. The sentinel line itself is not emitted and all subsequent lines
+    /// are discarded without being parsed. Drivers end as soon as the sentinel is seen, without
+    /// reading any further input. The sentinel is checked after empty lines are ignored and a
+    /// [LineMiddleware] is applied. By default, there is no end
+    /// sentinel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::{EndSentinel, NdjsonConfig};
+    ///
+    /// let config = NdjsonConfig::default().with_end_sentinel(EndSentinel::null());
+    /// let records = ndjson_stream::from_iter_with_config::<u32, _>(["1\n2\nnull\n3\n"], config)
+    ///     .map(Result::unwrap)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(records, vec![1, 2]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the end sentinel.
+    pub fn with_end_sentinel(self, end_sentinel: EndSentinel) -> NdjsonConfig {
+        NdjsonConfig {
+            end_sentinel: Some(end_sentinel),
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which parses the elements of top-level JSON arrays,
+    /// such as `[{"a":1},{"a":2}]`, instead of NDJSON-lines, as returned by many APIs which are
+    /// almost NDJSON. Elements are emitted like NDJSON-records as soon as they are complete, so
+    /// arrays can be parsed incrementally. Several arrays may follow each other, and blank
+    /// elements, as in `[]` or `[1,]`, are ignored. Data outside of arrays which is not whitespace
+    /// results in a parse error. In this mode, the [LineEnding] and [EmptyLineHandling] are not
+    /// used, so non-default values of either are rejected by [NdjsonConfig::validate], while all
+    /// other options apply to elements as they would to lines. An unterminated array is treated
+    /// like the rest after the last line ending. By default, this is set to `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::NdjsonConfig;
+    ///
+    /// let config = NdjsonConfig::default().with_json_array_mode(true);
+    /// let mut ndjson_iter =
+    ///     ndjson_stream::from_iter_with_config::<u32, _>(["[1, 2", "3,4", "5]"], config);
+    ///
+    /// assert!(matches!(ndjson_iter.next(), Some(Ok(1))));
+    /// assert!(matches!(ndjson_iter.next(), Some(Ok(23))));
+    /// assert!(matches!(ndjson_iter.next(), Some(Ok(45))));
+    /// assert!(ndjson_iter.next().is_none());
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the JSON-array-mode-flag.
+    pub fn with_json_array_mode(self, json_array_mode: bool) -> NdjsonConfig {
+        NdjsonConfig {
+            json_array_mode,
+            ..self
+        }
+    }
+
+    /// Creates a new [NdjsonConfigBuilder] starting from the default config, which validates the
+    /// combination of options once it is built.
+    pub fn builder() -> NdjsonConfigBuilder {
+        NdjsonConfigBuilder::new()
+    }
+
+    /// Checks whether the options of this config are compatible with each other.
+    ///
+    /// # Errors
+    ///
+    /// A [ConfigError] describing the first problem that was found.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_line_length == Some(0) {
+            return Err(ConfigError::ZeroMaxLineLength);
+        }
+
+        if self.oversized_line_prefix_len.is_some() && self.max_line_length.is_none() {
+            return Err(ConfigError::OversizedLinePrefixWithoutMaxLineLength);
+        }
+
+        if let Some((ProgressInterval::Records(0) | ProgressInterval::Bytes(0), _)) =
+                &self.progress_reporting {
+            return Err(ConfigError::ZeroProgressInterval);
+        }
+
+        if self.json_array_mode && self.line_ending != LineEnding::default() {
+            return Err(ConfigError::LineEndingInJsonArrayMode);
+        }
+
+        if self.json_array_mode && self.empty_line_handling != EmptyLineHandling::default() {
+            return Err(ConfigError::EmptyLineHandlingInJsonArrayMode);
+        }
+
+        if self.salvage && self.trailing_data_handling == TrailingDataHandling::MultipleValues {
+            return Err(ConfigError::SalvageWithMultipleValues);
+        }
+
+        Ok(())
+    }
+}
+
+/// The errors which can occur when building an [NdjsonConfig] via
+/// [NdjsonConfigBuilder::try_build], each describing a combination of options which would not
+/// behave as intended.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigError {
+
+    /// An empty comment prefix was given, which would disable comments rather than treating every
+    /// line as a comment.
+    EmptyCommentPrefix,
+
+    /// A maximum line length of `0` was given, which would reject every non-empty line.
+    ZeroMaxLineLength,
+
+    /// The length of the prefix included in errors for oversized lines was set, but no maximum
+    /// line length was configured, so no line is ever considered oversized.
+    OversizedLinePrefixWithoutMaxLineLength,
+
+    /// A progress interval of `0` was given, which would otherwise be treated as `1`.
+    ZeroProgressInterval,
+
+    /// A non-default [LineEnding] was given together with the JSON array mode, in which elements
+    /// are not separated by line endings, so the line ending would be ignored.
+    LineEndingInJsonArrayMode,
+
+    /// A non-default [EmptyLineHandling] was given together with the JSON array mode, in which
+    /// blank elements are always ignored, so the empty-line-handling would be ignored.
+    EmptyLineHandlingInJsonArrayMode,
+
+    /// Salvaging records was enabled together with [TrailingDataHandling::MultipleValues], which
+    /// reports the rest of a line after an invalid value as a single error without salvaging it.
+    SalvageWithMultipleValues
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::EmptyCommentPrefix => write!(f, "comment prefix must not be empty"),
+            ConfigError::ZeroMaxLineLength => write!(f, "maximum line length must not be 0"),
+            ConfigError::OversizedLinePrefixWithoutMaxLineLength =>
+                write!(f, "oversized line prefix length requires a maximum line length"),
+            ConfigError::ZeroProgressInterval => write!(f, "progress interval must not be 0"),
+            ConfigError::LineEndingInJsonArrayMode =>
+                write!(f, "line ending cannot be configured in JSON array mode"),
+            ConfigError::EmptyLineHandlingInJsonArrayMode =>
+                write!(f, "empty-line-handling cannot be configured in JSON array mode"),
+            ConfigError::SalvageWithMultipleValues =>
+                write!(f, "salvaging records is not supported with multiple values per line")
+        }
+    }
+}
+
+impl Error for ConfigError { }
+
+/// A builder for [NdjsonConfig]s, which validates the combination of all options in
+/// [NdjsonConfigBuilder::try_build] instead of silently adjusting them. The options are the same
+/// as those offered by the `with_*` methods of [NdjsonConfig].
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::config::{ConfigError, NdjsonConfig};
+///
+/// let result = NdjsonConfig::builder()
+///     .with_oversized_line_prefix_len(16)
+///     .try_build();
+///
+/// assert_eq!(result.unwrap_err(), ConfigError::OversizedLinePrefixWithoutMaxLineLength);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct NdjsonConfigBuilder {
+    config: NdjsonConfig,
+    empty_comment_prefix: bool
+}
+
+impl NdjsonConfigBuilder {
+
+    /// Creates a new builder starting from the default config.
+    pub fn new() -> NdjsonConfigBuilder {
+        NdjsonConfigBuilder::default()
+    }
+
+    fn map_config(self, map: impl FnOnce(NdjsonConfig) -> NdjsonConfig) -> NdjsonConfigBuilder {
+        NdjsonConfigBuilder {
+            config: map(self.config),
+            ..self
+        }
+    }
+
+    /// Sets the empty-line-handling. See [NdjsonConfig::with_empty_line_handling].
+    pub fn with_empty_line_handling(self, empty_line_handling: EmptyLineHandling)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_empty_line_handling(empty_line_handling))
+    }
+
+    /// Sets the parse-rest-flag. See [NdjsonConfig::with_parse_rest].
+    pub fn with_parse_rest(self, parse_rest: bool) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_parse_rest(parse_rest))
+    }
+
+    /// Sets the trailing-data-handling. See [NdjsonConfig::with_trailing_data_handling].
+    pub fn with_trailing_data_handling(self, trailing_data_handling: TrailingDataHandling)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_trailing_data_handling(trailing_data_handling))
+    }
+
+    /// Sets the invalid-line-handling. See [NdjsonConfig::with_invalid_line_handling].
+    pub fn with_invalid_line_handling(self, invalid_line_handling: InvalidLineHandling)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_invalid_line_handling(invalid_line_handling))
+    }
+
+    /// Sets whether duplicate keys are rejected. See [NdjsonConfig::with_reject_duplicate_keys].
+    pub fn with_reject_duplicate_keys(self, reject_duplicate_keys: bool) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_reject_duplicate_keys(reject_duplicate_keys))
+    }
+
+    /// Sets the schema validator. See [NdjsonConfig::with_schema_validator].
+    pub fn with_schema_validator(self, schema_validator: SchemaValidator) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_schema_validator(schema_validator))
+    }
+
+    /// Sets whether records are salvaged from corrupted lines. See [NdjsonConfig::with_salvage].
+    pub fn with_salvage(self, salvage: bool) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_salvage(salvage))
+    }
+
+    /// Sets the accepted line endings. See [NdjsonConfig::with_line_ending].
+    pub fn with_line_ending(self, line_ending: LineEnding) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_line_ending(line_ending))
+    }
+
+    /// Sets the comment prefix. See [NdjsonConfig::with_comment_prefix]. In contrast to the
+    /// config, an empty prefix results in [ConfigError::EmptyCommentPrefix].
+    pub fn with_comment_prefix(self, comment_prefix: impl AsRef<[u8]>) -> NdjsonConfigBuilder {
+        let comment_prefix = comment_prefix.as_ref();
+
+        NdjsonConfigBuilder {
+            config: self.config.with_comment_prefix(comment_prefix),
+            empty_comment_prefix: comment_prefix.is_empty()
+        }
+    }
+
+    /// Sets the line preprocessor. See [NdjsonConfig::with_line_preprocessor].
+    pub fn with_line_preprocessor(self, line_preprocessor: LinePreprocessor)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_line_preprocessor(line_preprocessor))
+    }
+
+    /// Adds a line middleware. See [NdjsonConfig::with_line_middleware].
+    pub fn with_line_middleware<M>(self, line_middleware: M) -> NdjsonConfigBuilder
+    where
+        M: LineMiddleware + 'static
+    {
+        self.map_config(|config| config.with_line_middleware(line_middleware))
+    }
+
+    /// Sets the key case. See [NdjsonConfig::with_key_case].
+    pub fn with_key_case(self, key_case: KeyCase) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_key_case(key_case))
+    }
+
+    /// Sets the maximum line length. See [NdjsonConfig::with_max_line_length].
+    pub fn with_max_line_length(self, max_line_length: usize) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_max_line_length(max_line_length))
+    }
+
+    /// Sets the length of the prefix included in errors for oversized lines. See
+    /// [NdjsonConfig::with_oversized_line_prefix_len].
+    pub fn with_oversized_line_prefix_len(self, prefix_len: usize) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_oversized_line_prefix_len(prefix_len))
+    }
+
+    /// Sets the progress callback. See [NdjsonConfig::with_progress_callback].
+    pub fn with_progress_callback(self, interval: ProgressInterval, callback: ProgressCallback)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_progress_callback(interval, callback))
+    }
+
+    /// Sets the ignored-line callback. See [NdjsonConfig::with_ignored_line_callback].
+    pub fn with_ignored_line_callback(self, callback: IgnoredLineCallback)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_ignored_line_callback(callback))
+    }
+
+    /// Sets the large-record threshold and callback. See
+    /// [NdjsonConfig::with_large_record_callback].
+    pub fn with_large_record_callback(self, threshold: usize, callback: LargeRecordCallback)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_large_record_callback(threshold, callback))
+    }
+
+    /// Sets the parse-metrics callback. See [NdjsonConfig::with_parse_metrics_callback].
+    #[cfg(feature = "std")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+    pub fn with_parse_metrics_callback(self, callback: ParseMetricsCallback)
+            -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_parse_metrics_callback(callback))
+    }
+
+    /// Sets the maximum number of records. See [NdjsonConfig::with_max_records].
+    pub fn with_max_records(self, max_records: Option<u64>) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_max_records(max_records))
+    }
+
+    /// Sets the end sentinel. See [NdjsonConfig::with_end_sentinel].
+    pub fn with_end_sentinel(self, end_sentinel: EndSentinel) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_end_sentinel(end_sentinel))
+    }
+
+    /// Sets the JSON-array-mode-flag. See [NdjsonConfig::with_json_array_mode].
+    pub fn with_json_array_mode(self, json_array_mode: bool) -> NdjsonConfigBuilder {
+        self.map_config(|config| config.with_json_array_mode(json_array_mode))
+    }
+
+    /// Validates the combination of all options and builds the config.
+    ///
+    /// # Errors
+    ///
+    /// A [ConfigError] describing the first problem that was found. See also
+    /// [NdjsonConfig::validate].
+    pub fn try_build(self) -> Result<NdjsonConfig, ConfigError> {
+        if self.empty_comment_prefix {
+            return Err(ConfigError::EmptyCommentPrefix);
+        }
+
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn valid_builder_builds_config() {
+        let config = NdjsonConfig::builder()
+            .with_parse_rest(true)
+            .with_comment_prefix("#")
+            .with_max_line_length(16)
+            .with_oversized_line_prefix_len(4)
+            .try_build()
+            .unwrap();
+
+        assert_that!(config.parse_rest).is_true();
+        assert_that!(config.comment_prefix.as_deref()).contains(b"#".as_slice());
+        assert_that!(config.oversized_line_prefix_len).contains(4);
+    }
+
+    #[test]
+    fn empty_comment_prefix_is_rejected() {
+        let result = NdjsonConfig::builder().with_comment_prefix("").try_build();
+
+        assert_that!(result).contains_error(ConfigError::EmptyCommentPrefix);
+    }
+
+    #[test]
+    fn later_non_empty_comment_prefix_is_accepted() {
+        let result = NdjsonConfig::builder()
+            .with_comment_prefix("")
+            .with_comment_prefix("//")
+            .try_build();
+
+        assert_that!(result).is_ok();
+    }
+
+    #[test]
+    fn zero_max_line_length_is_rejected() {
+        let result = NdjsonConfig::builder().with_max_line_length(0).try_build();
+
+        assert_that!(result).contains_error(ConfigError::ZeroMaxLineLength);
+    }
+
+    #[test]
+    fn zero_progress_interval_is_rejected() {
+        let callback = ProgressCallback::new(|_| { });
+        let result = NdjsonConfig::builder()
+            .with_progress_callback(ProgressInterval::Bytes(0), callback)
+            .try_build();
+
+        assert_that!(result).contains_error(ConfigError::ZeroProgressInterval);
+    }
+
+    #[test]
+    fn line_ending_in_json_array_mode_is_rejected() {
+        let result = NdjsonConfig::builder()
+            .with_json_array_mode(true)
+            .with_line_ending(LineEnding::CrLf)
+            .try_build();
+
+        assert_that!(result).contains_error(ConfigError::LineEndingInJsonArrayMode);
+    }
+
+    #[test]
+    fn empty_line_handling_in_json_array_mode_is_rejected() {
+        let result = NdjsonConfig::builder()
+            .with_json_array_mode(true)
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .try_build();
+
+        assert_that!(result).contains_error(ConfigError::EmptyLineHandlingInJsonArrayMode);
+    }
+
+    #[test]
+    fn salvage_with_multiple_values_is_rejected() {
+        let result = NdjsonConfig::builder()
+            .with_trailing_data_handling(TrailingDataHandling::MultipleValues)
+            .with_salvage(true)
+            .try_build();
+
+        assert_that!(result).contains_error(ConfigError::SalvageWithMultipleValues);
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_that!(NdjsonConfig::default().validate()).is_ok();
+    }
 }
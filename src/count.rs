@@ -0,0 +1,167 @@
+//! This module offers [count_records], which determines the number of records in NDJSON-data
+//! without deserializing them, for quick cardinality checks on large inputs.
+
+use serde::de::IgnoredAny;
+
+use crate::as_bytes::AsBytes;
+use crate::config::NdjsonConfig;
+use crate::engine::NdjsonEngine;
+use crate::error::NdjsonError;
+
+/// The numbers of different kinds of lines found by [count_records] or [count_records_checked].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RecordCounts {
+
+    /// The total number of lines, including blank and comment lines.
+    pub lines: u64,
+
+    /// The number of lines which would be parsed as records, including invalid ones.
+    pub records: u64,
+
+    /// The number of empty or blank lines which were ignored according to the configured
+    /// [EmptyLineHandling](crate::config::EmptyLineHandling).
+    pub blank_lines: u64,
+
+    /// The number of lines which were ignored since they start with the configured
+    /// [comment prefix](NdjsonConfig::with_comment_prefix).
+    pub comment_lines: u64,
+
+    /// The number of records which are not valid JSON or exceed the maximum line length. Syntax is
+    /// only checked by [count_records_checked], so for [count_records], this only counts oversized
+    /// lines.
+    pub invalid_records: u64
+}
+
+fn count<I>(data_blocks: I, mut engine: NdjsonEngine<IgnoredAny>) -> RecordCounts
+where
+    I: IntoIterator,
+    I::Item: AsBytes
+{
+    let mut invalid_records = 0;
+
+    for data_block in data_blocks {
+        engine.input(data_block);
+        invalid_records += engine.drain().filter(Result::is_err).count() as u64;
+
+        if engine.is_finished() {
+            break;
+        }
+    }
+
+    engine.finalize();
+    invalid_records += engine.drain().filter(Result::is_err).count() as u64;
+
+    let progress = engine.progress();
+    let lines = engine.line_count();
+
+    RecordCounts {
+        lines,
+        records: progress.records_emitted + progress.invalid_records_skipped,
+        blank_lines: progress.lines_ignored,
+        comment_lines: lines - progress.records_emitted - progress.invalid_records_skipped -
+            progress.lines_ignored,
+        invalid_records: invalid_records + progress.invalid_records_skipped
+    }
+}
+
+/// Counts the lines and records of the given NDJSON-data blocks, i.e. types implementing
+/// [AsBytes], without deserializing any record. Lines are split and classified according to the
+/// given [NdjsonConfig] exactly like the parser would, i.e. the configured line ending, empty line
+/// handling, comment prefix, maximum line length, and whether the rest is parsed are respected.
+///
+/// The syntax of records is not checked, so this is only limited by the speed of the line
+/// splitter. Use [count_records_checked] to also count invalid records.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+///
+/// let config = NdjsonConfig::default()
+///     .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+///     .with_parse_rest(true);
+/// let counts = ndjson_stream::count_records(vec!["{\"a\":1}\n  \n{\"a\"", ":2}\nx"], config);
+///
+/// assert_eq!(counts.lines, 4);
+/// assert_eq!(counts.records, 3);
+/// assert_eq!(counts.blank_lines, 1);
+/// ```
+pub fn count_records<I>(data_blocks: I, config: NdjsonConfig) -> RecordCounts
+where
+    I: IntoIterator,
+    I::Item: AsBytes
+{
+    let engine = NdjsonEngine::with_config(config)
+        .with_deserializer(|_| Ok::<_, NdjsonError>(IgnoredAny));
+
+    count(data_blocks, engine)
+}
+
+/// Counts the lines and records of the given NDJSON-data blocks, i.e. types implementing
+/// [AsBytes], just like [count_records], but additionally checks the syntax of every record to
+/// count invalid ones in [RecordCounts::invalid_records]. Records are validated without building
+/// any values, but this is still considerably slower than [count_records].
+pub fn count_records_checked<I>(data_blocks: I, config: NdjsonConfig) -> RecordCounts
+where
+    I: IntoIterator,
+    I::Item: AsBytes
+{
+    count(data_blocks, NdjsonEngine::with_config(config))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::config::EmptyLineHandling;
+
+    use super::*;
+
+    #[test]
+    fn lines_are_classified() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_comment_prefix("#");
+        let data_blocks = vec!["1\n\n# comment\n", "  \nx\n", "2"];
+
+        assert_that!(count_records(data_blocks, config)).is_equal_to(RecordCounts {
+            lines: 5,
+            records: 3,
+            blank_lines: 1,
+            comment_lines: 1,
+            invalid_records: 0
+        });
+    }
+
+    #[test]
+    fn checked_counting_counts_invalid_records() {
+        let config = NdjsonConfig::default().with_max_line_length(10);
+        let data_blocks = vec!["1\n{\"a\":", "2}\nx\n[1,2,3,4,5,6]\n"];
+
+        assert_that!(count_records_checked(data_blocks.clone(), config.clone()))
+            .is_equal_to(RecordCounts {
+                lines: 4,
+                records: 4,
+                blank_lines: 0,
+                comment_lines: 0,
+                invalid_records: 2
+            });
+        assert_that!(count_records(data_blocks, config).invalid_records).is_equal_to(1);
+    }
+
+    #[test]
+    fn rest_is_counted_if_parsed() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+
+        assert_that!(count_records(vec!["1\n2"], config).records).is_equal_to(2);
+    }
+
+    #[test]
+    fn empty_input_has_no_lines() {
+        let data_blocks: Vec<&str> = Vec::new();
+
+        assert_that!(count_records(data_blocks, NdjsonConfig::default()))
+            .is_equal_to(RecordCounts::default());
+    }
+}
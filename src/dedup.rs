@@ -0,0 +1,239 @@
+//! This module offers [dedup_by_key], which drops duplicate records from a sequence of
+//! NDJSON-records, such as the output of an [NdjsonIter](crate::driver::NdjsonIter) or
+//! [NdjsonStream](crate::driver::NdjsonStream). This is useful for sources with
+//! at-least-once delivery, where the same record may be delivered several times in short
+//! succession.
+
+use alloc::collections::{BTreeSet, VecDeque};
+
+use core::iter::FusedIterator;
+
+#[cfg(feature = "stream")]
+use core::pin::Pin;
+
+#[cfg(feature = "stream")]
+use core::task::{Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures::Stream;
+
+/// Statistics of a [DedupByKey] adaptor, i.e. how many records it forwarded and how many
+/// duplicates it dropped so far.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DedupStats {
+
+    /// The number of records which were forwarded, excluding errors.
+    pub records_forwarded: u64,
+
+    /// The number of records which were dropped since a record with the same key was forwarded
+    /// within the window.
+    pub duplicates_dropped: u64
+}
+
+/// An adaptor over an [Iterator] or [Stream] of [Result]s of records, which drops every record
+/// whose key equals the key of one of the most recently forwarded records. Errors are forwarded
+/// unchanged. Created by [dedup_by_key].
+pub struct DedupByKey<I, K, F> {
+    inner: I,
+    key: F,
+    window_size: usize,
+    window: VecDeque<K>,
+    window_keys: BTreeSet<K>,
+    stats: DedupStats
+}
+
+/// Drops every record of the given `records` whose key, as extracted by the given `key` function,
+/// equals the key of one of the last `window_size` forwarded records. A window size of `0` is
+/// treated as `1`. Errors of the wrapped sequence, such as parse errors, are forwarded unchanged
+/// and do not count towards the window. Dropped duplicates do not move their key within the
+/// window.
+/// The returned adaptor is an [Iterator] if `records` is one or a [Stream] if `records` is one.
+///
+/// Since only the keys of the window are held in memory, duplicates which are further apart than
+/// the window size are not detected. The number of dropped duplicates is available via
+/// [DedupByKey::stats].
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::dedup;
+///
+/// let data = "[1,\"a\"]\n[2,\"b\"]\n[1,\"a\"]\n[3,\"c\"]\n[1,\"a\"]\n";
+/// let records = ndjson_stream::from_iter::<(u32, String), _>([data]);
+/// let mut deduplicated = dedup::dedup_by_key(records, 2, |&(id, _): &(u32, String)| id);
+/// let ids = deduplicated.by_ref()
+///     .map(|record| record.unwrap().0)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(ids, vec![1, 2, 3, 1]);
+/// assert_eq!(deduplicated.stats().duplicates_dropped, 1);
+/// ```
+pub fn dedup_by_key<I, K, F>(records: I, window_size: usize, key: F) -> DedupByKey<I, K, F> {
+    DedupByKey {
+        inner: records,
+        key,
+        window_size: window_size.max(1),
+        window: VecDeque::new(),
+        window_keys: BTreeSet::new(),
+        stats: DedupStats::default()
+    }
+}
+
+impl<I, K, F> DedupByKey<I, K, F> {
+
+    /// Gets the [DedupStats] of this adaptor.
+    pub fn stats(&self) -> DedupStats {
+        self.stats
+    }
+
+    /// Gets a reference to the wrapped iterator or stream.
+    pub fn get_ref(&self) -> &I {
+        &self.inner
+    }
+
+    /// Unwraps this adaptor, returning the wrapped iterator or stream.
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    /// Checks whether the given record is to be forwarded and updates the window accordingly.
+    fn is_new<T>(&mut self, record: &T) -> bool
+    where
+        F: FnMut(&T) -> K,
+        K: Clone + Ord
+    {
+        let key = (self.key)(record);
+
+        if self.window_keys.contains(&key) {
+            self.stats.duplicates_dropped += 1;
+            return false;
+        }
+
+        if self.window.len() >= self.window_size {
+            if let Some(oldest_key) = self.window.pop_front() {
+                self.window_keys.remove(&oldest_key);
+            }
+        }
+
+        self.window.push_back(key.clone());
+        self.window_keys.insert(key);
+        self.stats.records_forwarded += 1;
+        true
+    }
+}
+
+impl<I, K, F, T, E> Iterator for DedupByKey<I, K, F>
+where
+    I: Iterator<Item = Result<T, E>>,
+    F: FnMut(&T) -> K,
+    K: Clone + Ord
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Result<T, E>> {
+        loop {
+            match self.inner.next()? {
+                Ok(record) if !self.is_new(&record) => continue,
+                result => return Some(result)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<I, K, F, T, E> FusedIterator for DedupByKey<I, K, F>
+where
+    I: FusedIterator<Item = Result<T, E>>,
+    F: FnMut(&T) -> K,
+    K: Clone + Ord
+{ }
+
+// The wrapped stream is never pinned structurally, so it is only polled if it is Unpin itself.
+impl<I, K, F> Unpin for DedupByKey<I, K, F>
+where
+    I: Unpin
+{ }
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+impl<S, K, F, T, E> Stream for DedupByKey<S, K, F>
+where
+    S: Stream<Item = Result<T, E>> + Unpin,
+    F: FnMut(&T) -> K,
+    K: Clone + Ord
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T, E>>> {
+        let this = self.get_mut();
+
+        loop {
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(record))) if !this.is_new(&record) => continue,
+                poll => return poll
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    fn record(key: u64, value: u64) -> Result<TestStruct, &'static str> {
+        Ok(TestStruct { key, value })
+    }
+
+    #[test]
+    fn duplicates_within_window_are_dropped() {
+        let records = vec![record(1, 1), record(2, 2), record(1, 3), record(3, 3), record(1, 4)];
+        let mut deduplicated =
+            dedup_by_key(records.into_iter(), 2, |record: &TestStruct| record.key);
+
+        assert_that!(deduplicated.by_ref().collect::<Vec<_>>()).contains_exactly_in_given_order([
+            record(1, 1),
+            record(2, 2),
+            record(3, 3),
+            record(1, 4)
+        ]);
+        assert_that!(deduplicated.stats()).is_equal_to(DedupStats {
+            records_forwarded: 4,
+            duplicates_dropped: 1
+        });
+    }
+
+    #[test]
+    fn errors_are_forwarded_without_counting() {
+        let records = vec![record(1, 1), Err("a"), Err("a"), record(1, 2), record(2, 2)];
+        let mut deduplicated =
+            dedup_by_key(records.into_iter(), 0, |record: &TestStruct| record.key);
+
+        assert_that!(deduplicated.by_ref().collect::<Vec<_>>())
+            .contains_exactly_in_given_order([record(1, 1), Err("a"), Err("a"), record(2, 2)]);
+        assert_that!(deduplicated.stats().duplicates_dropped).is_equal_to(1);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn streams_are_deduplicated() {
+        use futures::stream::{self, StreamExt};
+
+        let records = stream::iter(vec![record(1, 1), record(1, 2), record(2, 3)]);
+        let deduplicated = dedup_by_key(records, 10, |record: &TestStruct| record.key);
+
+        tokio_test::block_on(async {
+            assert_that!(deduplicated.collect::<Vec<_>>().await)
+                .contains_exactly_in_given_order([record(1, 1), record(2, 3)]);
+        });
+    }
+}
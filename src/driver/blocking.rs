@@ -0,0 +1,143 @@
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use tokio::runtime::Handle;
+
+use crate::driver::stream::{FallibleNdjsonStream, NdjsonStream};
+
+/// An [Iterator] which wraps an NDJSON-stream and blocks the current thread on a Tokio runtime
+/// until the next item of the stream is available. This allows synchronous code to consume
+/// asynchronous NDJSON-sources, such as HTTP response bodies, without being rewritten. See
+/// [NdjsonStream::into_blocking_iter] and [FallibleNdjsonStream::into_blocking_iter].
+///
+/// The wrapped stream is polled via [Handle::block_on]. Hence, the IO and timer drivers of a
+/// current-thread runtime are not driven by this iterator. Streams which depend on these, such as
+/// network sources, require a multi-thread runtime.
+///
+/// # Panics
+///
+/// [Iterator::next] panics if called from within an asynchronous execution context, such as a task
+/// of a Tokio runtime.
+pub struct BlockingNdjsonIter<S> {
+    stream: Pin<Box<S>>,
+    handle: Handle
+}
+
+impl<S> BlockingNdjsonIter<S> {
+
+    /// Creates a new blocking iterator over the items of the given `stream`, which is polled on the
+    /// runtime of the given `handle`.
+    pub fn new(stream: S, handle: Handle) -> BlockingNdjsonIter<S> {
+        BlockingNdjsonIter {
+            stream: Box::pin(stream),
+            handle
+        }
+    }
+
+    /// Gets a reference to the handle of the runtime on which the wrapped stream is polled.
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+impl<S> Iterator for BlockingNdjsonIter<S>
+where
+    S: Stream
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        self.handle.block_on(self.stream.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<T, S> NdjsonStream<T, S> {
+
+    /// Converts this stream into a [BlockingNdjsonIter], which blocks the current thread on the
+    /// runtime of the given `handle` until the next record is available.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::stream;
+    /// use tokio::runtime::Builder;
+    ///
+    /// let runtime = Builder::new_current_thread().build().unwrap();
+    /// let data_blocks = stream::iter(vec!["1\n2", "\n3\n"]);
+    /// let records = ndjson_stream::from_stream::<u32, _>(data_blocks)
+    ///     .into_blocking_iter(runtime.handle().clone())
+    ///     .map(Result::unwrap)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(records, vec![1, 2, 3]);
+    /// ```
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn into_blocking_iter(self, handle: Handle) -> BlockingNdjsonIter<NdjsonStream<T, S>> {
+        BlockingNdjsonIter::new(self, handle)
+    }
+}
+
+impl<T, S> FallibleNdjsonStream<T, S> {
+
+    /// Converts this stream into a [BlockingNdjsonIter], which blocks the current thread on the
+    /// runtime of the given `handle` until the next record or input error is available.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn into_blocking_iter(self, handle: Handle)
+            -> BlockingNdjsonIter<FallibleNdjsonStream<T, S>> {
+        BlockingNdjsonIter::new(self, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use futures::channel::mpsc;
+    use kernal::prelude::*;
+    use tokio::runtime::{Builder, Runtime};
+
+    use crate::test_util::{FallibleNdjsonResultAssertions, TestStruct};
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    #[test]
+    fn records_arriving_from_other_thread_are_awaited() {
+        let runtime = runtime();
+        let (sender, receiver) = mpsc::unbounded();
+        let producer = thread::spawn(move || {
+            for data_block in ["{\"key\":1,", "\"value\":2}\n", "{\"key\":3,\"value\":4}\n"] {
+                sender.unbounded_send(data_block).unwrap();
+            }
+        });
+        let records = crate::from_stream::<TestStruct, _>(receiver)
+            .into_blocking_iter(runtime.handle().clone())
+            .collect::<Vec<_>>();
+
+        producer.join().unwrap();
+
+        assert_that!(records).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn fallible_stream_forwards_input_errors() {
+        let runtime = runtime();
+        let data_blocks = futures::stream::iter(vec![Ok("{\"key\":1,\"value\":2}\n"), Err("test")]);
+        let mut records = crate::from_fallible_stream::<TestStruct, _>(data_blocks)
+            .into_blocking_iter(runtime.handle().clone());
+
+        assert_that!(records.next()).to_value()
+            .contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(records.next()).to_value().is_input_error("test");
+        assert_that!(records.next()).is_none();
+    }
+}
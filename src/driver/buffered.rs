@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use pin_project_lite::pin_project;
+use serde::de::DeserializeOwned;
+use tokio::task::{self, JoinHandle};
+
+use crate::as_bytes::AsBytes;
+use crate::config::NdjsonConfig;
+use crate::engine::{self, NdjsonEngine};
+use crate::error::{NdjsonError, NdjsonResult};
+
+/// A record of a [BufferedParseStream] in the order of the input, which is either still being
+/// deserialized or was rejected before deserialization, such as an oversized line.
+enum PendingRecord<T> {
+    Parsing(JoinHandle<NdjsonResult<T>>),
+    Ready(Option<NdjsonResult<T>>)
+}
+
+pin_project! {
+    /// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
+    /// implementation over parsed NDJSON-records according to [Deserialize](serde::Deserialize),
+    /// just like [NdjsonStream](crate::driver::NdjsonStream). However, up to a configured number of
+    /// complete lines are deserialized concurrently on Tokio's blocking thread pool, so a single
+    /// record which is expensive to deserialize does not stall the entire stream. Records are still
+    /// yielded in the order of the input. See [from_stream_buffered] and
+    /// [from_stream_buffered_with_config] for more details.
+    ///
+    /// Splitting, preprocessing, and all other options of the [NdjsonConfig] are applied on the
+    /// polling task, while only the deserialization of lines is moved to other threads.
+    pub struct BufferedParseStream<T, S> {
+        engine: NdjsonEngine<Vec<u8>>,
+        config: Arc<NdjsonConfig>,
+        pending: VecDeque<PendingRecord<T>>,
+        limit: usize,
+        exhausted: bool,
+        #[pin]
+        bytes_stream: S
+    }
+}
+
+impl<T, S> BufferedParseStream<T, S> {
+
+    /// Creates a new buffered NDJSON-stream wrapping the given `bytes_stream` with the given
+    /// [NdjsonConfig], which deserializes up to `limit` lines at once. A `limit` of 0 is treated as
+    /// 1.
+    pub fn with_config(bytes_stream: S, config: NdjsonConfig, limit: usize)
+            -> BufferedParseStream<T, S> {
+        let engine = NdjsonEngine::with_config(config.clone())
+            .with_deserializer(|line| Ok::<_, NdjsonError>(line.to_vec()));
+
+        BufferedParseStream {
+            engine,
+            config: Arc::new(config),
+            pending: VecDeque::new(),
+            limit: limit.max(1),
+            exhausted: false,
+            bytes_stream
+        }
+    }
+}
+
+/// Starts deserializing lines from the engine until the limit of pending records is reached.
+fn spawn_pending<T>(engine: &mut NdjsonEngine<Vec<u8>>, config: &Arc<NdjsonConfig>,
+        pending: &mut VecDeque<PendingRecord<T>>, limit: usize)
+where
+    T: DeserializeOwned + Send + 'static
+{
+    while pending.len() < limit {
+        let record = match engine.pop() {
+            Some(Ok(line)) => {
+                let config = Arc::clone(config);
+
+                PendingRecord::Parsing(
+                    task::spawn_blocking(move || engine::parse_line(&line, &config)))
+            },
+            Some(Err(error)) => PendingRecord::Ready(Some(Err(error))),
+            None => return
+        };
+
+        pending.push_back(record);
+    }
+}
+
+impl<T, S> Stream for BufferedParseStream<T, S>
+where
+    T: DeserializeOwned + Send + 'static,
+    S: Stream,
+    S::Item: AsBytes
+{
+    type Item = NdjsonResult<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NdjsonResult<T>>> {
+        let mut this = self.project();
+
+        loop {
+            spawn_pending(this.engine, this.config, this.pending, *this.limit);
+
+            match this.pending.front_mut() {
+                Some(PendingRecord::Ready(result)) => {
+                    let result = result.take();
+
+                    this.pending.pop_front();
+                    return Poll::Ready(result);
+                },
+                Some(PendingRecord::Parsing(handle)) => {
+                    if let Poll::Ready(result) = Pin::new(handle).poll(cx) {
+                        this.pending.pop_front();
+
+                        let result = result
+                            .unwrap_or_else(|error| panic::resume_unwind(error.into_panic()));
+
+                        return Poll::Ready(Some(result));
+                    }
+                },
+                None if *this.exhausted => return Poll::Ready(None),
+                None => { }
+            }
+
+            if *this.exhausted || this.pending.len() >= *this.limit {
+                return Poll::Pending;
+            }
+
+            if this.engine.is_finished() {
+                *this.exhausted = true;
+                continue;
+            }
+
+            match this.bytes_stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(bytes)) => {
+                    this.engine.input(bytes);
+                },
+                Poll::Ready(None) => {
+                    this.engine.finalize();
+                    *this.exhausted = true;
+                },
+                Poll::Pending => return Poll::Pending
+            }
+        }
+    }
+}
+
+/// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
+/// implementation over parsed NDJSON-records, which deserializes up to `limit` lines concurrently
+/// on Tokio's blocking thread pool while preserving their order. The parser is configured with the
+/// default [NdjsonConfig]. See [BufferedParseStream] for more details.
+///
+/// # Example
+///
+/// ```
+/// use futures::stream::{self, StreamExt};
+///
+/// let data_blocks = vec!["1\n2\n", "3\nx\n"];
+/// let ndjson_stream = ndjson_stream::from_stream_buffered::<u32, _>(stream::iter(data_blocks), 4);
+///
+/// tokio_test::block_on(async {
+///     let records = ndjson_stream.collect::<Vec<_>>().await;
+///
+///     assert!(matches!(records[..], [Ok(1), Ok(2), Ok(3), Err(_)]));
+/// });
+/// ```
+///
+/// # Panics
+///
+/// Polling the returned stream panics if called outside a Tokio runtime. A panic while
+/// deserializing a line is propagated to the polling task.
+pub fn from_stream_buffered<T, S>(bytes_stream: S, limit: usize) -> BufferedParseStream<T, S> {
+    BufferedParseStream::with_config(bytes_stream, NdjsonConfig::default(), limit)
+}
+
+/// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
+/// implementation over parsed NDJSON-records just like [from_stream_buffered]. The parser is
+/// configured with the given [NdjsonConfig].
+pub fn from_stream_buffered_with_config<T, S>(bytes_stream: S, config: NdjsonConfig, limit: usize)
+        -> BufferedParseStream<T, S> {
+    BufferedParseStream::with_config(bytes_stream, config, limit)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use futures::stream::{self, StreamExt};
+
+    use kernal::prelude::*;
+
+    use serde::Deserialize;
+
+    use tokio::runtime::Builder;
+
+    use crate::config::EmptyLineHandling;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    /// Takes longer to deserialize the value 0 than all others, such that later lines finish
+    /// deserializing before an earlier one.
+    struct Slow(u32);
+
+    impl<'de> Deserialize<'de> for Slow {
+        fn deserialize<D>(deserializer: D) -> Result<Slow, D::Error>
+        where
+            D: serde::Deserializer<'de>
+        {
+            let value = u32::deserialize(deserializer)?;
+
+            if value == 0 {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+
+            Ok(Slow(value))
+        }
+    }
+
+    #[test]
+    fn records_are_yielded_in_input_order() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let data_blocks = vec!["0\n1\n", "2\n3\n"];
+        let records = runtime.block_on(
+            from_stream_buffered::<Slow, _>(stream::iter(data_blocks), 3)
+                .map(|result| result.unwrap().0)
+                .collect::<Vec<_>>());
+
+        assert_that!(records).contains_exactly_in_given_order([0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn config_is_applied_and_errors_keep_their_position() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_max_line_length(25)
+            .with_parse_rest(true);
+        let data_blocks = vec![
+            "{\"key\":1,\"value\":2}\n  \n",
+            "{\"key\":3,\"value\":4,\"other\":5}\n{\"key\":5,",
+            "\"value\":6}"
+        ];
+        let results = runtime.block_on(
+            from_stream_buffered_with_config::<TestStruct, _>(stream::iter(data_blocks), config, 2)
+                .collect::<Vec<_>>());
+
+        assert_that!(results.len()).is_equal_to(3);
+        assert_that!(&results[0]).is_ok();
+        assert_that!(matches!(results[1], Err(NdjsonError::LineTooLong { .. }))).is_true();
+        assert_that!(&results[2]).is_ok();
+    }
+
+    #[test]
+    fn input_is_polled_lazily() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let (sender, receiver) = mpsc::channel::<()>();
+        let data_blocks = stream::iter(vec!["1\n", "2\n"]).inspect(move |_| {
+            let _ = sender.send(());
+        });
+        let mut ndjson_stream = from_stream_buffered::<u32, _>(data_blocks, 1);
+
+        runtime.block_on(async {
+            assert_that!(ndjson_stream.next().await.map(Result::unwrap)).contains(1);
+        });
+
+        assert_that!(receiver.try_iter().count()).is_equal_to(1);
+    }
+}
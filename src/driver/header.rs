@@ -0,0 +1,273 @@
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures::Stream;
+use pin_project_lite::pin_project;
+use serde::Deserialize;
+
+use crate::as_bytes::AsBytes;
+use crate::engine::NdjsonEngine;
+use crate::error::NdjsonResult;
+use crate::fallible::FallibleNdjsonResult;
+
+#[cfg(feature = "iter")]
+use crate::driver::iter::{FallibleNdjsonIter, NdjsonIter};
+
+#[cfg(feature = "stream")]
+use crate::driver::stream::{FallibleNdjsonStream, NdjsonStream};
+
+pin_project! {
+    /// Wraps an NDJSON-driver, i.e. an [NdjsonIter], [FallibleNdjsonIter], [NdjsonStream], or
+    /// [FallibleNdjsonStream], and parses the first line which is neither skipped nor ignored as a
+    /// header of type `H`, while all subsequent lines are still returned as records by the wrapped
+    /// driver. This is useful for exports which start with a metadata line of a different shape
+    /// than the records. Created by the `with_header` method of the drivers.
+    ///
+    /// The header is parsed with [serde_json] according to the [NdjsonConfig] of the driver, even
+    /// if the driver uses a custom deserializer for its records. It is available via
+    /// [WithHeader::header] as soon as the first record is returned, or once the driver is
+    /// exhausted if the input consists only of the header. A header which cannot be parsed is
+    /// reported as an error by [WithHeader::header], without affecting the records. If the input is
+    /// empty, the header remains `None`.
+    ///
+    /// [NdjsonConfig]: crate::config::NdjsonConfig
+    pub struct WithHeader<H, D> {
+        #[pin]
+        driver: D,
+        header: Option<NdjsonResult<H>>
+    }
+}
+
+impl<H, D> WithHeader<H, D> {
+
+    pub(crate) fn new(driver: D) -> WithHeader<H, D> {
+        WithHeader {
+            driver,
+            header: None
+        }
+    }
+
+    /// Gets the result of parsing the header line, or `None` if it has not been received yet.
+    pub fn header(&self) -> Option<&NdjsonResult<H>> {
+        self.header.as_ref()
+    }
+
+    /// Gets a reference to the wrapped driver.
+    pub fn get_ref(&self) -> &D {
+        &self.driver
+    }
+
+    /// Unwraps this driver, returning the result of parsing the header line, if it has been
+    /// received yet, and the wrapped driver.
+    pub fn into_parts(self) -> (Option<NdjsonResult<H>>, D) {
+        (self.header, self.driver)
+    }
+}
+
+fn update_header<H, T>(header: &mut Option<NdjsonResult<H>>, engine: &mut NdjsonEngine<T>)
+where
+    for<'deserialize> H: Deserialize<'deserialize>,
+    for<'deserialize> T: Deserialize<'deserialize>
+{
+    if let Some(parsed_header) = engine.take_header() {
+        *header = Some(parsed_header);
+    }
+}
+
+#[cfg(feature = "iter")]
+impl<H, T, I> Iterator for WithHeader<H, NdjsonIter<T, I>>
+where
+    for<'deserialize> H: Deserialize<'deserialize>,
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes
+{
+    type Item = NdjsonResult<T>;
+
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
+        let next = self.driver.next();
+        update_header(&mut self.header, self.driver.engine_mut());
+        next
+    }
+}
+
+#[cfg(feature = "iter")]
+impl<H, T, I, B, E> Iterator for WithHeader<H, FallibleNdjsonIter<T, I>>
+where
+    for<'deserialize> H: Deserialize<'deserialize>,
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn next(&mut self) -> Option<FallibleNdjsonResult<T, E>> {
+        let next = self.driver.next();
+        update_header(&mut self.header, self.driver.engine_mut());
+        next
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<H, T, S> Stream for WithHeader<H, NdjsonStream<T, S>>
+where
+    for<'deserialize> H: Deserialize<'deserialize>,
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream,
+    S::Item: AsBytes
+{
+    type Item = NdjsonResult<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NdjsonResult<T>>> {
+        let mut this = self.project();
+        let next = this.driver.as_mut().poll_next(cx);
+        update_header(this.header, this.driver.engine_mut());
+        next
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<H, T, S, B, E> Stream for WithHeader<H, FallibleNdjsonStream<T, S>>
+where
+    for<'deserialize> H: Deserialize<'deserialize>,
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<FallibleNdjsonResult<T, E>>> {
+        let mut this = self.project();
+        let next = this.driver.as_mut().poll_next(cx);
+        update_header(this.header, this.driver.engine_mut());
+        next
+    }
+}
+
+#[cfg(all(test, feature = "iter"))]
+mod tests {
+
+    #[cfg(feature = "stream")]
+    use futures::stream::{self, StreamExt};
+
+    use kernal::prelude::*;
+
+    use crate::config::{EmptyLineHandling, NdjsonConfig};
+    use crate::error::NdjsonError;
+    use crate::test_util::{FallibleNdjsonResultAssertions, TestStruct};
+
+    use super::*;
+
+    fn collect<I>(mut ndjson_iter: WithHeader<TestStruct, I>) -> (Vec<u32>, Option<TestStruct>)
+    where
+        WithHeader<TestStruct, I>: Iterator<Item = NdjsonResult<u32>>
+    {
+        let records = ndjson_iter.by_ref().map(Result::unwrap).collect();
+        let (header, _) = ndjson_iter.into_parts();
+
+        (records, header.map(Result::unwrap))
+    }
+
+    #[test]
+    fn header_is_parsed_as_different_type() {
+        let data_blocks = vec!["{\"key\":1,", "\"value\":2}\n3\n", "4\n"];
+        let mut ndjson_iter = crate::from_iter::<u32, _>(data_blocks).with_header::<TestStruct>();
+
+        assert_that!(ndjson_iter.header()).is_none();
+        assert_that!(ndjson_iter.next()).to_value().contains_value(3);
+        assert_that!(ndjson_iter.header()).to_value().contains_value(TestStruct {
+            key: 1,
+            value: 2
+        });
+        assert_that!(ndjson_iter.next()).to_value().contains_value(4);
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn header_follows_ignored_lines() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+        let data_blocks = vec!["\n  \n{\"key\":1,\"value\":2}\n\n3\n"];
+        let ndjson_iter = crate::from_iter_with_config::<u32, _>(data_blocks, config)
+            .with_header::<TestStruct>();
+        let (records, header) = collect(ndjson_iter);
+
+        assert_that!(records).contains_exactly_in_given_order([3]);
+        assert_that!(header).contains(TestStruct { key: 1, value: 2 });
+    }
+
+    #[test]
+    fn input_consisting_only_of_header_yields_header() {
+        let ndjson_iter = crate::from_iter::<u32, _>(vec!["{\"key\":1,\"value\":2}\n"])
+            .with_header::<TestStruct>();
+        let (records, header) = collect(ndjson_iter);
+
+        assert_that!(records).is_empty();
+        assert_that!(header).contains(TestStruct { key: 1, value: 2 });
+    }
+
+    #[test]
+    fn invalid_header_does_not_affect_records() {
+        let config = NdjsonConfig::default().with_max_line_length(4);
+        let data_blocks = vec!["{\"key\":1,\"value\":2}\n1\n"];
+        let mut ndjson_iter = crate::from_iter_with_config::<u32, _>(data_blocks, config)
+            .with_header::<TestStruct>();
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(matches!(ndjson_iter.header(), Some(Err(NdjsonError::LineTooLong { .. }))))
+            .is_true();
+    }
+
+    #[test]
+    fn fallible_iter_forwards_input_errors() {
+        let data_block_results = vec![Ok("{\"key\":1,\"value\":2}\n"), Err("test"), Ok("3\n")];
+        let mut ndjson_iter = crate::from_fallible_iter::<u32, _>(data_block_results)
+            .with_header::<TestStruct>();
+
+        assert_that!(ndjson_iter.next()).to_value().is_input_error("test");
+        assert_that!(ndjson_iter.header()).to_value().contains_value(TestStruct {
+            key: 1,
+            value: 2
+        });
+        assert_that!(ndjson_iter.next()).to_value().contains_value(3);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn stream_parses_header() {
+        let data_blocks = vec!["{\"key\":1,\"value\":2}\n", "3\n"];
+        let mut ndjson_stream = crate::from_stream::<u32, _>(stream::iter(data_blocks))
+            .with_header::<TestStruct>();
+
+        tokio_test::block_on(async {
+            assert_that!(ndjson_stream.next().await).to_value().contains_value(3);
+            assert_that!(ndjson_stream.header()).to_value().contains_value(TestStruct {
+                key: 1,
+                value: 2
+            });
+            assert_that!(ndjson_stream.next().await).is_none();
+        });
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn fallible_stream_parses_header() {
+        let data_block_results = vec![Ok::<_, &str>("{\"key\":1,\"value\":2}\n3\n")];
+        let mut ndjson_stream =
+            crate::from_fallible_stream::<u32, _>(stream::iter(data_block_results))
+                .with_header::<TestStruct>();
+
+        tokio_test::block_on(async {
+            assert_that!(ndjson_stream.next().await).to_value().contains_value(3);
+            assert_that!(ndjson_stream.header()).to_value().contains_value(TestStruct {
+                key: 1,
+                value: 2
+            });
+        });
+    }
+}
@@ -1,15 +1,21 @@
 use crate::as_bytes::AsBytes;
 use crate::config::NdjsonConfig;
+use crate::driver::header::WithHeader;
+use crate::driver::line_number::WithLineNumbers;
 use crate::engine::NdjsonEngine;
+use crate::entry;
+use crate::error::{NdjsonError, NdjsonResult, ValidationError};
 use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+use crate::multi::SchemaChain;
+use crate::source::{ChunkSource, Chunks};
+use crate::splitter;
 
 use std::convert::Infallible;
-use std::iter::Fuse;
+use std::iter::FusedIterator;
 
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
-use serde_json::error::Result as JsonResult;
-
 struct MapResultInfallible<I> {
     inner: I
 }
@@ -20,6 +26,18 @@ impl<I> MapResultInfallible<I> {
             inner
         }
     }
+
+    fn get_ref(&self) -> &I {
+        &self.inner
+    }
+
+    fn get_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    fn into_inner(self) -> I {
+        self.inner
+    }
 }
 
 impl<I> Iterator for MapResultInfallible<I>
@@ -37,6 +55,64 @@ where
     }
 }
 
+/// Fuses an iterator like [Fuse](std::iter::Fuse), but allows recovering the wrapped iterator.
+struct Fused<I> {
+    inner: I,
+    exhausted: bool
+}
+
+impl<I> Fused<I> {
+    fn new(inner: I) -> Fused<I> {
+        Fused {
+            inner,
+            exhausted: false
+        }
+    }
+
+    fn get_ref(&self) -> &I {
+        &self.inner
+    }
+
+    fn get_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<I> Iterator for Fused<I>
+where
+    I: Iterator
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if self.exhausted {
+            return None;
+        }
+
+        let item = self.inner.next();
+        self.exhausted = item.is_none();
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.exhausted {
+            (0, Some(0))
+        }
+        else {
+            self.inner.size_hint()
+        }
+    }
+}
+
+impl<I> FusedIterator for Fused<I>
+where
+    I: Iterator
+{ }
+
 /// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], and offers an [Iterator]
 /// implementation over parsed NDJSON-records according to [Deserialize]. See [from_iter] and
 /// [from_iter_with_config] for more details.
@@ -68,6 +144,235 @@ where
             inner: FallibleNdjsonIter::with_config(inner_bytes_iterator, config)
         }
     }
+
+    /// Discards the next `count` records of this iterator without deserializing them. This is
+    /// useful for resuming ingestion from a checkpoint. See [NdjsonEngine::skip_records] for more
+    /// details.
+    ///
+    /// Skipping happens lazily, i.e. the wrapped iterator is only queried once the next record is
+    /// requested.
+    pub fn skip_records(&mut self, count: usize) {
+        self.inner.skip_records(count);
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this iterator, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress) or [incomplete line](NdjsonEngine::rest).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        self.inner.engine()
+    }
+
+    /// Gets a reference to the wrapped iterator, e.g. to inspect the progress of the underlying
+    /// source, such as the position of a [ReaderSource](crate::source::ReaderSource) wrapped in
+    /// [Chunks].
+    pub fn get_ref(&self) -> &I {
+        self.inner.get_ref().get_ref()
+    }
+
+    /// Gets a mutable reference to the wrapped iterator. Note that data blocks taken from it
+    /// directly are not seen by this iterator. Once the wrapped iterator has returned `None`, it is
+    /// not queried again.
+    pub fn get_mut(&mut self) -> &mut I {
+        self.inner.get_mut().get_mut()
+    }
+
+    /// Unwraps this iterator, returning the wrapped iterator, which continues after the last data
+    /// block given to the engine. Records which were parsed but not returned yet and the incomplete
+    /// line are dropped. Use [NdjsonIter::into_parts] to retain them.
+    pub fn into_inner(self) -> I {
+        self.into_parts().1
+    }
+
+    /// Finalizes the engine of this iterator without querying the wrapped iterator any further, as
+    /// [NdjsonEngine::finalize] does. This is useful when stopping consumption early, for example
+    /// after a [take_while](Iterator::take_while) on a [by_ref](Iterator::by_ref) of this
+    /// iterator. With [NdjsonConfig::with_parse_rest], the incomplete line is parsed and returned
+    /// by the next call to [NdjsonIter::next], otherwise it is discarded.
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were made available by finalizing.
+    pub fn finalize(&mut self) -> usize
+    where
+        for<'deserialize> T: Deserialize<'deserialize>
+    {
+        self.inner.finalize()
+    }
+
+    /// Unwraps this iterator, returning the incomplete line currently held by its engine without
+    /// parsing it. See [NdjsonEngine::rest] for more details.
+    pub fn into_rest(self) -> Vec<u8> {
+        self.inner.into_rest()
+    }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine], which still holds the incomplete line
+    /// and all records which were not returned yet, together with the wrapped iterator, which
+    /// continues after the last data block given to the engine. This allows handing off the input
+    /// to another consumer or resuming precisely where this iterator stopped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut ndjson_iter = ndjson_stream::from_iter::<u32, _>(vec!["1\n2", "3\n", "4\n"]);
+    ///
+    /// assert!(matches!(ndjson_iter.next(), Some(Ok(1))));
+    ///
+    /// let (engine, rest) = ndjson_iter.into_parts();
+    ///
+    /// assert_eq!(engine.rest(), b"2");
+    /// assert_eq!(rest.collect::<Vec<_>>(), vec!["3\n", "4\n"]);
+    /// ```
+    pub fn into_parts(self) -> (NdjsonEngine<T>, I) {
+        let (engine, bytes_iterator) = self.inner.into_parts();
+
+        (engine, bytes_iterator.into_inner())
+    }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine] with the incomplete line and all records
+    /// which were not returned yet. See [NdjsonIter::into_parts] for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.inner.into_engine()
+    }
+
+    /// Makes this iterator parse lines with the given `deserializer` instead of [serde_json]. See
+    /// [NdjsonEngine::with_deserializer] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new iterator with all the same state as this one, except the deserializer.
+    pub fn with_deserializer<F, E>(self, deserializer: F) -> NdjsonIter<T, I>
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<NdjsonError>
+    {
+        NdjsonIter {
+            inner: self.inner.with_deserializer(deserializer)
+        }
+    }
+
+    /// Makes this iterator check every parsed record with the given `validator`, reporting rejected
+    /// records as [NdjsonError::InvalidRecord]. See [NdjsonEngine::with_record_validator] for more
+    /// details.
+    ///
+    /// # Returns
+    ///
+    /// A new iterator with all the same state as this one, except the record validator.
+    pub fn with_record_validator<F>(self, validator: F) -> NdjsonIter<T, I>
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static
+    {
+        NdjsonIter {
+            inner: self.inner.with_record_validator(validator)
+        }
+    }
+
+    /// Makes this iterator parse the first line which is neither skipped nor ignored as a header of
+    /// type `H` rather than as a record of type `T`. See [WithHeader] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Header {
+    ///     version: u32
+    /// }
+    ///
+    /// let data_blocks = vec!["{\"version\":2}\n1\n", "2\n"];
+    /// let mut ndjson_iter =
+    ///     ndjson_stream::from_iter::<u32, _>(data_blocks).with_header::<Header>();
+    ///
+    /// assert_eq!(ndjson_iter.next().unwrap().unwrap(), 1);
+    /// assert_eq!(ndjson_iter.header().unwrap().as_ref().unwrap().version, 2);
+    /// assert_eq!(ndjson_iter.next().unwrap().unwrap(), 2);
+    /// assert!(ndjson_iter.next().is_none());
+    /// ```
+    pub fn with_header<H>(mut self) -> WithHeader<H, NdjsonIter<T, I>> {
+        self.inner.engine.expect_header();
+        WithHeader::new(self)
+    }
+
+    /// Makes this iterator yield every item together with the one-based number of the line it was
+    /// parsed from, counting all lines of the input. See [WithLineNumbers] for more details.
+    pub fn with_line_numbers(self) -> WithLineNumbers<NdjsonIter<T, I>> {
+        WithLineNumbers::new(self)
+    }
+
+    pub(crate) fn engine_mut(&mut self) -> &mut NdjsonEngine<T> {
+        self.inner.engine_mut()
+    }
+}
+
+impl<T, I> NdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes
+{
+
+    /// Gets a reference to the next record of this iterator without consuming it, i.e. the record
+    /// which would be returned by the next call to [NdjsonIter::next]. The wrapped iterator is
+    /// queried until a record is completed or it is exhausted. In contrast to wrapping this
+    /// iterator in a [Peekable](std::iter::Peekable), the inherent methods remain accessible.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut ndjson_iter = ndjson_stream::from_iter::<u32, _>(["12", "3\n456\n"]);
+    ///
+    /// assert!(matches!(ndjson_iter.peek(), Some(Ok(123))));
+    /// assert!(matches!(ndjson_iter.next(), Some(Ok(123))));
+    /// assert!(matches!(ndjson_iter.peek(), Some(Ok(456))));
+    /// ```
+    pub fn peek(&mut self) -> Option<&NdjsonResult<T>> {
+        match self.inner.fill_engine() {
+            Ok(()) => self.inner.engine.peek(),
+            Err(never) => match never { }
+        }
+    }
+
+    /// Wraps this iterator in a [TakeUntilRecordIter], which yields records up to and including the
+    /// first one satisfying the given `predicate`, the sentinel, and then stops without reading any
+    /// further data. Afterwards, [TakeUntilRecordIter::into_parts] returns the wrapped iterator
+    /// together with the bytes following the line of the sentinel, so the input can continue to be
+    /// used for a different protocol, such as binary data after an NDJSON-handshake.
+    ///
+    /// To find the end of the sentinel exactly, data blocks are given to the engine line by line
+    /// instead of at once. Records which were already parsed before calling this method are
+    /// yielded first, and data following the sentinel cannot be recovered if it is among them. In
+    /// [JSON array mode](NdjsonConfig::with_json_array_mode), elements after the sentinel on the
+    /// same line are parsed and lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let data_blocks = vec!["{\"version\":1}\n\"ready\"\n\x00\x01", "\x02"];
+    /// let mut handshake = ndjson_stream::from_iter::<serde_json::Value, _>(data_blocks)
+    ///     .take_until_record(|record| record == "ready");
+    ///
+    /// assert_eq!(handshake.by_ref().count(), 2);
+    ///
+    /// let (mut data_blocks, leftover) = handshake.into_parts();
+    ///
+    /// assert_eq!(leftover, vec![0, 1]);
+    /// assert_eq!(data_blocks.next(), Some("\x02"));
+    /// ```
+    pub fn take_until_record<P>(self, predicate: P) -> TakeUntilRecordIter<T, I, P>
+    where
+        P: FnMut(&T) -> bool
+    {
+        let FallibleNdjsonIter { engine, bytes_iterator } = self.inner;
+
+        TakeUntilRecordIter {
+            engine,
+            bytes_iterator: bytes_iterator.inner.inner,
+            exhausted: bytes_iterator.exhausted,
+            pending: None,
+            pending_start: 0,
+            finished: false,
+            predicate
+        }
+    }
 }
 
 impl<T, I> Iterator for NdjsonIter<T, I>
@@ -76,11 +381,130 @@ where
     I: Iterator,
     I::Item: AsBytes
 {
-    type Item = JsonResult<T>;
+    type Item = NdjsonResult<T>;
 
-    fn next(&mut self) -> Option<JsonResult<T>> {
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
         Some(self.inner.next()?.map_err(FallibleNdjsonError::unwrap_json_error))
     }
+
+    fn nth(&mut self, n: usize) -> Option<NdjsonResult<T>> {
+        self.skip_records(n);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, I> FusedIterator for NdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes
+{ }
+
+/// Wraps an [NdjsonIter] and yields its records up to and including the first one satisfying a
+/// predicate, after which it stops without reading further data. See
+/// [NdjsonIter::take_until_record] for more details.
+pub struct TakeUntilRecordIter<T, I, P>
+where
+    I: Iterator
+{
+    engine: NdjsonEngine<T>,
+    bytes_iterator: I,
+    exhausted: bool,
+
+    /// The data block which is currently given to the engine line by line, starting at
+    /// `pending_start`.
+    pending: Option<I::Item>,
+    pending_start: usize,
+    finished: bool,
+    predicate: P
+}
+
+impl<T, I, P> TakeUntilRecordIter<T, I, P>
+where
+    I: Iterator,
+    I::Item: AsBytes
+{
+
+    /// Gets a reference to the [NdjsonEngine] of this iterator.
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Unwraps this iterator, returning the wrapped iterator and the data which was read from it
+    /// but not given to the engine, preceded by the incomplete line held by the engine. Once the
+    /// sentinel was returned, this is exactly the data following the line ending of the sentinel.
+    pub fn into_parts(self) -> (I, Vec<u8>) {
+        let mut leftover = self.engine.rest().to_vec();
+
+        if let Some(pending) = &self.pending {
+            leftover.extend_from_slice(&pending.as_bytes()[self.pending_start..]);
+        }
+
+        (self.bytes_iterator, leftover)
+    }
+}
+
+impl<T, I, P> Iterator for TakeUntilRecordIter<T, I, P>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes,
+    P: FnMut(&T) -> bool
+{
+    type Item = NdjsonResult<T>;
+
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
+        loop {
+            if self.finished {
+                return None;
+            }
+
+            if let Some(result) = self.engine.pop() {
+                self.finished = result.as_ref().is_ok_and(&mut self.predicate);
+                return Some(result);
+            }
+
+            if self.engine.is_finished() {
+                self.finished = true;
+                continue;
+            }
+
+            if let Some(pending) = &self.pending {
+                let data = &pending.as_bytes()[self.pending_start..];
+
+                if data.is_empty() {
+                    self.pending = None;
+                }
+                else {
+                    let segment_len = splitter::first_segment_len(data);
+
+                    self.engine.input(&data[..segment_len]);
+                    self.pending_start += segment_len;
+                }
+
+                continue;
+            }
+
+            if self.exhausted {
+                return None;
+            }
+
+            match self.bytes_iterator.next() {
+                Some(data) => {
+                    self.pending = Some(data);
+                    self.pending_start = 0;
+                },
+                None => {
+                    self.exhausted = true;
+                    self.engine.finalize();
+                }
+            }
+        }
+    }
 }
 
 /// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], obtained by
@@ -140,69 +564,534 @@ where
     NdjsonIter::with_config(into_iter.into_iter(), config)
 }
 
-/// Wraps an iterator over [Result]s of data blocks, i.e. types implementing [AsBytes], and offers
-/// an [Iterator] implementation over parsed NDJSON-records according to [Deserialize], forwarding
-/// potential errors returned by the wrapped iterator. See [from_fallible_iter] and
-/// [from_fallible_iter_with_config] for more details.
-pub struct FallibleNdjsonIter<T, I> {
+/// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], obtained by
+/// [IntoIterator::into_iter] on `into_iter` and offers an [Iterator] implementation over parsed
+/// NDJSON-records, where each line is tried against the types of the [SchemaChain] `C` in order.
+/// The records are enums holding the value of the first type that matched, such as
+/// [OneOf2](crate::multi::OneOf2). The parser is configured with the default [NdjsonConfig]. See
+/// the [multi](crate::multi) module for an example.
+pub fn from_iter_multi<C, I>(into_iter: I) -> NdjsonIter<C::Output, I::IntoIter>
+where
+    C: SchemaChain,
+    I: IntoIterator
+{
+    NdjsonIter::new(into_iter.into_iter())
+}
+
+/// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], obtained by
+/// [IntoIterator::into_iter] on `into_iter` and offers an [Iterator] implementation over
+/// `(key, value)` pairs, where each line must be an object with exactly one entry. Lines with zero
+/// or multiple entries result in errors. The parser is configured with the default [NdjsonConfig].
+/// See the [entry](crate::entry) module for an example.
+pub fn from_iter_map<K, V, I>(into_iter: I) -> NdjsonIter<(K, V), I::IntoIter>
+where
+    K: DeserializeOwned + 'static,
+    V: DeserializeOwned + 'static,
+    I: IntoIterator
+{
+    NdjsonIter::new(into_iter.into_iter()).with_deserializer(entry::parse_entry::<K, V>)
+}
+
+/// Wraps an iterator over [Result]s of data blocks, i.e. types implementing [AsBytes], and offers
+/// an [Iterator] implementation over parsed NDJSON-records according to [Deserialize], forwarding
+/// potential errors returned by the wrapped iterator. See [from_fallible_iter] and
+/// [from_fallible_iter_with_config] for more details.
+pub struct FallibleNdjsonIter<T, I> {
+    engine: NdjsonEngine<T>,
+    bytes_iterator: Fused<I>
+}
+
+impl<T, I> FallibleNdjsonIter<T, I>
+where
+    I: Iterator
+{
+
+    /// Creates a new fallible NDJSON-iterator wrapping the given `bytes_iterator` with default
+    /// [NdjsonConfig].
+    pub fn new(bytes_iterator: I) -> FallibleNdjsonIter<T, I> {
+        FallibleNdjsonIter {
+            engine: NdjsonEngine::new(),
+            bytes_iterator: Fused::new(bytes_iterator)
+        }
+    }
+
+    /// Creates a new fallible NDJSON-iterator wrapping the given `bytes_iterator` with the given
+    /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(bytes_iterator: I, config: NdjsonConfig) -> FallibleNdjsonIter<T, I> {
+        FallibleNdjsonIter {
+            engine: NdjsonEngine::with_config(config),
+            bytes_iterator: Fused::new(bytes_iterator)
+        }
+    }
+
+    /// Discards the next `count` records of this iterator without deserializing them. This is
+    /// useful for resuming ingestion from a checkpoint. See [NdjsonEngine::skip_records] for more
+    /// details. Input errors are not considered records and are therefore still returned.
+    ///
+    /// Skipping happens lazily, i.e. the wrapped iterator is only queried once the next item is
+    /// requested.
+    pub fn skip_records(&mut self, count: usize) {
+        self.engine.skip_records(count);
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this iterator, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress) or [incomplete line](NdjsonEngine::rest).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Gets a reference to the wrapped iterator. See [NdjsonIter::get_ref] for more details.
+    pub fn get_ref(&self) -> &I {
+        self.bytes_iterator.get_ref()
+    }
+
+    /// Gets a mutable reference to the wrapped iterator. See [NdjsonIter::get_mut] for more
+    /// details.
+    pub fn get_mut(&mut self) -> &mut I {
+        self.bytes_iterator.get_mut()
+    }
+
+    /// Unwraps this iterator, returning the wrapped iterator. See [NdjsonIter::into_inner] for more
+    /// details.
+    pub fn into_inner(self) -> I {
+        self.bytes_iterator.into_inner()
+    }
+
+    /// Finalizes the engine of this iterator without querying the wrapped iterator any further, as
+    /// [NdjsonEngine::finalize] does. See [NdjsonIter::finalize] for more details.
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were made available by finalizing.
+    pub fn finalize(&mut self) -> usize
+    where
+        for<'deserialize> T: Deserialize<'deserialize>
+    {
+        self.engine.finalize()
+    }
+
+    /// Unwraps this iterator, returning the incomplete line currently held by its engine without
+    /// parsing it. See [NdjsonEngine::rest] for more details.
+    pub fn into_rest(self) -> Vec<u8> {
+        self.engine.rest().to_vec()
+    }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine], which still holds the incomplete line
+    /// and all records which were not returned yet, together with the wrapped iterator, which
+    /// continues after the last data block given to the engine. This allows handing off the input
+    /// to another consumer or resuming precisely where this iterator stopped.
+    pub fn into_parts(self) -> (NdjsonEngine<T>, I) {
+        (self.engine, self.bytes_iterator.into_inner())
+    }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine] with the incomplete line and all records
+    /// which were not returned yet. See [FallibleNdjsonIter::into_parts] for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.engine
+    }
+
+    /// Makes this iterator parse lines with the given `deserializer` instead of [serde_json]. See
+    /// [NdjsonEngine::with_deserializer] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new iterator with all the same state as this one, except the deserializer.
+    pub fn with_deserializer<F, E>(self, deserializer: F) -> FallibleNdjsonIter<T, I>
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<NdjsonError>
+    {
+        FallibleNdjsonIter {
+            engine: self.engine.with_deserializer(deserializer),
+            ..self
+        }
+    }
+
+    /// Makes this iterator check every parsed record with the given `validator`, reporting rejected
+    /// records as [NdjsonError::InvalidRecord]. See [NdjsonEngine::with_record_validator] for more
+    /// details.
+    ///
+    /// # Returns
+    ///
+    /// A new iterator with all the same state as this one, except the record validator.
+    pub fn with_record_validator<F>(self, validator: F) -> FallibleNdjsonIter<T, I>
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static
+    {
+        FallibleNdjsonIter {
+            engine: self.engine.with_record_validator(validator),
+            ..self
+        }
+    }
+
+    /// Makes this iterator parse the first line which is neither skipped nor ignored as a header of
+    /// type `H` rather than as a record of type `T`. See [WithHeader] for more details.
+    pub fn with_header<H>(mut self) -> WithHeader<H, FallibleNdjsonIter<T, I>> {
+        self.engine.expect_header();
+        WithHeader::new(self)
+    }
+
+    /// Makes this iterator yield every item together with the one-based number of the line it was
+    /// parsed from, counting all lines of the input. See [WithLineNumbers] for more details.
+    pub fn with_line_numbers(self) -> WithLineNumbers<FallibleNdjsonIter<T, I>> {
+        WithLineNumbers::new(self)
+    }
+
+    pub(crate) fn engine_mut(&mut self) -> &mut NdjsonEngine<T> {
+        &mut self.engine
+    }
+
+    /// Routes all lines which cannot be parsed to the given `sink` instead of returning them as
+    /// [FallibleNdjsonError::JsonError]s. The sink receives the offending line, without the line
+    /// ending, together with the error. The returned iterator only yields valid records and input
+    /// errors. For lines exceeding the maximum line length, only their first bytes are given to the
+    /// sink.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let data_block_results = vec![Ok::<_, String>("1\nx\n2\n")];
+    /// let mut dead_letters = Vec::new();
+    ///
+    /// let records = ndjson_stream::from_fallible_iter::<u32, _>(data_block_results)
+    ///     .with_dead_letter_sink(|line, _| dead_letters.push(line))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(records, vec![1, 2]);
+    /// assert_eq!(dead_letters, vec![b"x".to_vec()]);
+    /// ```
+    pub fn with_dead_letter_sink<F>(mut self, sink: F) -> DeadLetterNdjsonIter<T, I, F>
+    where
+        F: FnMut(Vec<u8>, NdjsonError)
+    {
+        self.engine.retain_failed_lines();
+
+        DeadLetterNdjsonIter {
+            inner: self,
+            sink
+        }
+    }
+
+    /// Converts all errors of the wrapped iterator with the given function `f`, without affecting
+    /// the state of parsing. This allows unifying the input error type with other errors of the
+    /// caller inline, such that they can be propagated with `?`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::fallible::FallibleNdjsonError;
+    ///
+    /// let data_block_results = vec![Ok("1\n"), Err(404)];
+    /// let mut ndjson_iter = ndjson_stream::from_fallible_iter::<u32, _>(data_block_results)
+    ///     .map_input_error(|status| format!("status {status}"));
+    ///
+    /// assert!(matches!(ndjson_iter.next(), Some(Ok(1))));
+    /// assert!(matches!(ndjson_iter.next(),
+    ///     Some(Err(FallibleNdjsonError::InputError(message))) if message == "status 404"));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new iterator with all the same state as this one, whose input errors are converted.
+    pub fn map_input_error<B, E, E2, F>(self, f: F)
+        -> FallibleNdjsonIter<T, MapInputErrorIter<I, F>>
+    where
+        I: Iterator<Item = Result<B, E>>,
+        F: FnMut(E) -> E2
+    {
+        let bytes_iterator = MapInputErrorIter {
+            inner: self.bytes_iterator,
+            f
+        };
+
+        FallibleNdjsonIter {
+            engine: self.engine,
+            bytes_iterator: Fused::new(bytes_iterator)
+        }
+    }
+
+    /// Converts all errors of the wrapped iterator into `E2` via [From], just like
+    /// [FallibleNdjsonIter::map_input_error].
+    ///
+    /// # Returns
+    ///
+    /// A new iterator with all the same state as this one, whose input errors are converted.
+    pub fn input_error_into<E2, B, E>(self)
+        -> FallibleNdjsonIter<T, MapInputErrorIter<I, FromFn<E, E2>>>
+    where
+        I: Iterator<Item = Result<B, E>>,
+        E2: From<E>
+    {
+        self.map_input_error(E2::from as FromFn<E, E2>)
+    }
+}
+
+/// The function converting input errors via [From] used by `input_error_into`.
+type FromFn<E, E2> = fn(E) -> E2;
+
+/// Wraps an iterator over [Result]s and converts all errors with a function. This is the iterator
+/// wrapped by a [FallibleNdjsonIter] after [FallibleNdjsonIter::map_input_error].
+pub struct MapInputErrorIter<I, F> {
+    inner: Fused<I>,
+    f: F
+}
+
+impl<I, F> MapInputErrorIter<I, F> {
+
+    /// Unwraps this iterator, returning the wrapped iterator whose errors are not converted.
+    pub fn into_inner(self) -> I {
+        self.inner.into_inner()
+    }
+}
+
+impl<I, F, B, E, E2> Iterator for MapInputErrorIter<I, F>
+where
+    I: Iterator<Item = Result<B, E>>,
+    F: FnMut(E) -> E2
+{
+    type Item = Result<B, E2>;
+
+    fn next(&mut self) -> Option<Result<B, E2>> {
+        Some(self.inner.next()?.map_err(&mut self.f))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I, F, B, E, E2> FusedIterator for MapInputErrorIter<I, F>
+where
+    I: Iterator<Item = Result<B, E>>,
+    F: FnMut(E) -> E2
+{ }
+
+impl<T, I, B, E> Iterator for FallibleNdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn next(&mut self) -> Option<FallibleNdjsonResult<T, E>> {
+        match self.fill_engine() {
+            Ok(()) => self.engine.pop().map(|res| res.map_err(FallibleNdjsonError::JsonError)),
+            Err(error) => Some(Err(FallibleNdjsonError::InputError(error)))
+        }
+    }
+
+    /// The lower bound is the number of records which are already parsed, while no upper bound is
+    /// known, as any data block may contain arbitrarily many records.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.engine.queued_record_count(), None)
+    }
+}
+
+impl<T, I, B, E> FusedIterator for FallibleNdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{ }
+
+impl<T, I, B, E> FallibleNdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{
+
+    /// Queries the wrapped iterator until the engine has an item available or the wrapped iterator
+    /// is exhausted, in which case the engine is finalized. Input errors are returned immediately.
+    fn fill_engine(&mut self) -> Result<(), E> {
+        while self.engine.peek().is_none() && !self.engine.is_finished() {
+            match self.bytes_iterator.next() {
+                Some(Ok(bytes)) => {
+                    self.engine.input(bytes);
+                }
+                Some(Err(error)) => return Err(error),
+                None => {
+                    self.engine.finalize();
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Wraps a [FallibleNdjsonIter] and routes all lines which cannot be parsed to a sink, so only
+/// valid records and input errors are yielded. See [FallibleNdjsonIter::with_dead_letter_sink] for
+/// more details.
+pub struct DeadLetterNdjsonIter<T, I, F> {
+    inner: FallibleNdjsonIter<T, I>,
+    sink: F
+}
+
+impl<T, I, F> DeadLetterNdjsonIter<T, I, F>
+where
+    I: Iterator
+{
+
+    /// Unwraps this iterator, returning its [NdjsonEngine] and the wrapped iterator, while the
+    /// sink is dropped. See [FallibleNdjsonIter::into_parts] for more details.
+    pub fn into_parts(self) -> (NdjsonEngine<T>, I) {
+        self.inner.into_parts()
+    }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine], while the sink is dropped. See
+    /// [FallibleNdjsonIter::into_parts] for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.inner.into_engine()
+    }
+}
+
+impl<T, I, B, E, F> Iterator for DeadLetterNdjsonIter<T, I, F>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes,
+    F: FnMut(Vec<u8>, NdjsonError)
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Result<T, E>> {
+        loop {
+            if let Err(error) = self.inner.fill_engine() {
+                return Some(Err(error));
+            }
+
+            match self.inner.engine.pop_with_failed_line()? {
+                Ok(record) => return Some(Ok(record)),
+                Err((error, line)) => (self.sink)(line, error)
+            }
+        }
+    }
+}
+
+impl<T, I, B, E, F> FusedIterator for DeadLetterNdjsonIter<T, I, F>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes,
+    F: FnMut(Vec<u8>, NdjsonError)
+{ }
+
+/// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], and checks every
+/// line against [Deserialize] for `T` without returning the records. See [validate_iter] and
+/// [validate_iter_with_config] for more details.
+pub struct ValidateNdjsonIter<T, I> {
     engine: NdjsonEngine<T>,
-    bytes_iterator: Fuse<I>
+    bytes_iterator: Fused<I>
 }
 
-impl<T, I> FallibleNdjsonIter<T, I>
+impl<T, I> ValidateNdjsonIter<T, I>
 where
     I: Iterator
 {
 
-    /// Creates a new fallible NDJSON-iterator wrapping the given `bytes_iterator` with default
-    /// [NdjsonConfig].
-    pub fn new(bytes_iterator: I) -> FallibleNdjsonIter<T, I> {
-        FallibleNdjsonIter {
-            engine: NdjsonEngine::new(),
-            bytes_iterator: bytes_iterator.fuse()
-        }
-    }
-
-    /// Creates a new fallible NDJSON-iterator wrapping the given `bytes_iterator` with the given
+    /// Creates a new validating NDJSON-iterator wrapping the given `bytes_iterator` with the given
     /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
-    pub fn with_config(bytes_iterator: I, config: NdjsonConfig) -> FallibleNdjsonIter<T, I> {
-        FallibleNdjsonIter {
+    pub fn with_config(bytes_iterator: I, config: NdjsonConfig) -> ValidateNdjsonIter<T, I> {
+        ValidateNdjsonIter {
             engine: NdjsonEngine::with_config(config),
-            bytes_iterator: bytes_iterator.fuse()
+            bytes_iterator: Fused::new(bytes_iterator)
         }
     }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine] and the wrapped iterator. See
+    /// [FallibleNdjsonIter::into_parts] for more details.
+    pub fn into_parts(self) -> (NdjsonEngine<T>, I) {
+        (self.engine, self.bytes_iterator.into_inner())
+    }
+
+    /// Unwraps this iterator, returning its [NdjsonEngine]. See [FallibleNdjsonIter::into_parts]
+    /// for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.engine
+    }
 }
 
-impl<T, I, B, E> Iterator for FallibleNdjsonIter<T, I>
+impl<T, I> Iterator for ValidateNdjsonIter<T, I>
 where
     for<'deserialize> T: Deserialize<'deserialize>,
-    I: Iterator<Item = Result<B, E>>,
-    B: AsBytes
+    I: Iterator,
+    I::Item: AsBytes
 {
-    type Item = FallibleNdjsonResult<T, E>;
+    type Item = Result<(), ValidationError>;
 
-    fn next(&mut self) -> Option<FallibleNdjsonResult<T, E>> {
+    fn next(&mut self) -> Option<Result<(), ValidationError>> {
         loop {
-            if let Some(result) = self.engine.pop() {
-                return match result {
-                    Ok(value) => Some(Ok(value)),
-                    Err(error) => Some(Err(FallibleNdjsonError::JsonError(error)))
-                }
+            if let Some(result) = self.engine.pop_validated() {
+                return Some(result);
+            }
+
+            if self.engine.is_finished() {
+                return None;
             }
 
             match self.bytes_iterator.next() {
-                Some(Ok(bytes)) => self.engine.input(bytes),
-                Some(Err(error)) => return Some(Err(FallibleNdjsonError::InputError(error))),
+                Some(bytes) => {
+                    self.engine.input(bytes);
+                },
                 None => {
                     self.engine.finalize();
-                    return self.engine.pop()
-                        .map(|res| res.map_err(FallibleNdjsonError::JsonError));
+                    return self.engine.pop_validated();
                 }
             }
         }
     }
 }
 
+impl<T, I> FusedIterator for ValidateNdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes
+{ }
+
+/// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], obtained by
+/// [IntoIterator::into_iter] on `into_iter` and offers an [Iterator] which yields, for every line
+/// which would produce a record, either `Ok(())` or a [ValidationError] holding the line number.
+/// The records themselves are dropped immediately. Choosing [IgnoredAny](serde::de::IgnoredAny)
+/// for `T` only checks that every line is well-formed JSON, without constructing any values. The
+/// parser is configured with the default [NdjsonConfig].
+///
+/// # Example
+///
+/// ```
+/// use serde::de::IgnoredAny;
+///
+/// let data_blocks = vec!["{\"a\":1}\n[tru", "e]\n{\"a\":\n"];
+///
+/// let errors = ndjson_stream::validate_iter::<IgnoredAny, _>(data_blocks)
+///     .filter_map(Result::err)
+///     .map(|error| error.line_number)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(errors, vec![3]);
+/// ```
+pub fn validate_iter<T, I>(into_iter: I) -> ValidateNdjsonIter<T, I::IntoIter>
+where
+    I: IntoIterator
+{
+    ValidateNdjsonIter::with_config(into_iter.into_iter(), NdjsonConfig::default())
+}
+
+/// Wraps an iterator of data blocks, i.e. types implementing [AsBytes], obtained by
+/// [IntoIterator::into_iter] on `into_iter` and checks every line against [Deserialize] for `T`
+/// just like [validate_iter]. The parser is configured with the given [NdjsonConfig].
+pub fn validate_iter_with_config<T, I>(into_iter: I, config: NdjsonConfig)
+    -> ValidateNdjsonIter<T, I::IntoIter>
+where
+    I: IntoIterator
+{
+    ValidateNdjsonIter::with_config(into_iter.into_iter(), config)
+}
+
 /// Wraps an iterator of [Result]s of data blocks, i.e. types implementing [AsBytes], obtained by
 /// [IntoIterator::into_iter] on `into_iter` and offers an [Iterator] implementation over parsed
 /// NDJSON-records according to [Deserialize]. Errors in the wrapped iterator are forwarded via
@@ -271,6 +1160,30 @@ where
     FallibleNdjsonIter::with_config(into_iter.into_iter(), config)
 }
 
+/// Wraps a [ChunkSource] and offers an [Iterator] implementation over parsed NDJSON-records
+/// according to [Deserialize]. Errors of the source are forwarded via
+/// [FallibleNdjsonError::InputError], while parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError]. The parser is configured with the default [NdjsonConfig]. See
+/// the [source](crate::source) module for an example.
+pub fn from_chunk_source<T, S>(source: S) -> FallibleNdjsonIter<T, Chunks<S>>
+where
+    S: ChunkSource
+{
+    FallibleNdjsonIter::new(source.into_chunks())
+}
+
+/// Wraps a [ChunkSource] and offers an [Iterator] implementation over parsed NDJSON-records
+/// according to [Deserialize]. Errors of the source are forwarded via
+/// [FallibleNdjsonError::InputError], while parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError]. The parser is configured with the given [NdjsonConfig].
+pub fn from_chunk_source_with_config<T, S>(source: S, config: NdjsonConfig)
+    -> FallibleNdjsonIter<T, Chunks<S>>
+where
+    S: ChunkSource
+{
+    FallibleNdjsonIter::with_config(source.into_chunks(), config)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -280,10 +1193,12 @@ mod tests {
 
     use std::iter;
 
-    use crate::config::EmptyLineHandling;
+    use serde::de::IgnoredAny;
+
+    use crate::config::{EmptyLineHandling, EndSentinel, TrailingDataHandling};
     use crate::test_util::{FallibleNdjsonResultAssertions, SingleThenPanicIter, TestStruct};
 
-    fn collect<I>(into_iter: I) -> Vec<JsonResult<TestStruct>>
+    fn collect<I>(into_iter: I) -> Vec<NdjsonResult<TestStruct>>
     where
         I: IntoIterator,
         I::Item: AsBytes
@@ -442,4 +1357,396 @@ mod tests {
                 |it| assert_that!(it).contains_value(TestStruct { key: 63, value: 36 })
             ));
     }
+
+    #[test]
+    fn skip_records_skips_without_deserializing() {
+        let iter =
+            iter::once("invalid json\n{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n");
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(iter);
+
+        ndjson_iter.skip_records(2);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 3, value: 4 });
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn nth_skips_records_across_data_blocks() {
+        let vec = vec![
+            "{\"key\":1,\"va",
+            "lue\":2}\ninvalid",
+            " json\n{\"key\":3,\"value\":4}\n"
+        ];
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(vec);
+
+        assert_that!(ndjson_iter.nth(2)).to_value().contains_value(TestStruct { key: 3, value: 4 });
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn fallible_skip_records_still_forwards_input_errors() {
+        let data_vec = vec![
+            Ok("{\"key\":1,\"value\":2}\n"),
+            Err("test message"),
+            Ok("{\"key\":3,\"value\":4}\n")
+        ];
+        let mut fallible_ndjson_iter: FallibleNdjsonIter<TestStruct, _> =
+            from_fallible_iter(data_vec);
+
+        fallible_ndjson_iter.skip_records(1);
+
+        assert_that!(fallible_ndjson_iter.collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).is_input_error("test message"),
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+            ));
+    }
+
+    #[test]
+    fn peek_does_not_consume_record() {
+        let vec = vec!["{\"key\":1,\"va", "lue\":2}\n{\"key\":3,\"value\":4}\n"];
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(vec);
+
+        assert_that!(ndjson_iter.peek()).to_value()
+            .contains_value(&TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.peek()).to_value()
+            .contains_value(&TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 3, value: 4 });
+        assert_that!(ndjson_iter.peek()).is_none();
+    }
+
+    #[test]
+    fn peek_keeps_skip_records_accessible() {
+        let iter = iter::once("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n");
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(iter);
+
+        assert_that!(ndjson_iter.peek()).is_some();
+
+        ndjson_iter.skip_records(1);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 3, value: 4 });
+    }
+
+    #[test]
+    fn peek_includes_parsed_rest() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let iter = iter::once("{\"key\":1,\"value\":2}");
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter_with_config(iter, config);
+
+        assert_that!(ndjson_iter.peek()).to_value()
+            .contains_value(&TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+    }
+
+    #[test]
+    fn dead_letter_sink_receives_invalid_lines_and_errors() {
+        let iter = vec![
+            Ok("{\"key\":1,\"value\":2}\ninvalid\n{\"key\":3,"),
+            Err("test message"),
+            Ok("\"value\":4}\n{\"key\":5}\n")
+        ];
+        let mut dead_letters = Vec::new();
+        let results = from_fallible_iter::<TestStruct, _>(iter)
+            .with_dead_letter_sink(|line, error| dead_letters.push((line, error)))
+            .collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([
+            Ok(TestStruct { key: 1, value: 2 }),
+            Err("test message"),
+            Ok(TestStruct { key: 3, value: 4 })
+        ]);
+        assert_that!(dead_letters.iter().map(|(line, _)| line.as_slice()).collect::<Vec<_>>())
+            .contains_exactly_in_given_order([b"invalid".as_slice(), b"{\"key\":5}".as_slice()]);
+    }
+
+    #[test]
+    fn dead_letter_sink_receives_unparsable_rest() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let iter = iter::once(Ok::<_, &str>("{\"key\":1,\"value\":2}\ninvalid"));
+        let mut dead_letters = Vec::new();
+        let results = from_fallible_iter_with_config::<TestStruct, _>(iter, config)
+            .with_dead_letter_sink(|line, _| dead_letters.push(line))
+            .collect::<Vec<_>>();
+
+        assert_that!(results)
+            .contains_exactly_in_given_order([Ok(TestStruct { key: 1, value: 2 })]);
+        assert_that!(dead_letters).contains_exactly_in_given_order([b"invalid".to_vec()]);
+    }
+
+    #[test]
+    fn size_hint_is_unknown_before_data_is_read() {
+        let iter = iter::once("{\"key\":1,\"value\":2}\n");
+        let ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(iter);
+
+        assert_that!(ndjson_iter.size_hint()).is_equal_to((0, None));
+    }
+
+    #[test]
+    fn size_hint_includes_already_parsed_records() {
+        let iter = iter::once("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\ninvalid\n");
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(iter);
+
+        assert_that!(ndjson_iter.peek()).is_some();
+        assert_that!(ndjson_iter.size_hint()).is_equal_to((3, None));
+
+        ndjson_iter.next();
+
+        assert_that!(ndjson_iter.size_hint()).is_equal_to((2, None));
+    }
+
+    #[test]
+    fn exhausted_iter_keeps_returning_none() {
+        let iter = iter::once("{\"key\":1,\"value\":2}\n");
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter(iter);
+
+        assert_that!(ndjson_iter.next()).is_some();
+        assert_that!(ndjson_iter.next()).is_none();
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn max_records_ends_iteration_without_reading_further_input() {
+        let config = NdjsonConfig::default().with_max_records(Some(1));
+        let iter = SingleThenPanicIter {
+            data: Some("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n".to_owned())
+        };
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter_with_config(iter, config);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn end_sentinel_ends_iteration_without_reading_further_input() {
+        let config = NdjsonConfig::default().with_end_sentinel(EndSentinel::null());
+        let iter = SingleThenPanicIter {
+            data: Some("{\"key\":1,\"value\":2}\nnull\n".to_owned())
+        };
+        let mut ndjson_iter: NdjsonIter<TestStruct, _> = from_iter_with_config(iter, config);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn chunk_source_records_are_parsed() {
+        let reader = std::io::Cursor::new("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n");
+        let source = crate::source::ReaderSource::with_chunk_size(reader, 5);
+        let values = from_chunk_source::<TestStruct, _>(source)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(values).contains_exactly_in_given_order([
+            TestStruct { key: 1, value: 2 },
+            TestStruct { key: 3, value: 4 }
+        ]);
+    }
+
+    #[test]
+    fn validate_iter_reports_line_numbers_of_invalid_lines() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_parse_rest(true);
+        let data_blocks = [
+            "{\"key\":1,\"value\":2}\n\n{\"key\":1}\n",
+            "{\"key\":3,\"value\":4}\n{\"key\":"
+        ];
+        let results = validate_iter_with_config::<TestStruct, _>(data_blocks, config)
+            .map(|result| result.map_err(|error| error.line_number))
+            .collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([Ok(()), Err(3), Ok(()), Err(5)]);
+    }
+
+    #[test]
+    fn validate_iter_reports_line_numbers_of_lines_with_multiple_values() {
+        let config = NdjsonConfig::default()
+            .with_trailing_data_handling(TrailingDataHandling::MultipleValues);
+        let results = validate_iter_with_config::<u32, _>(["1 2\nx\n"], config)
+            .map(|result| result.map_err(|error| error.line_number))
+            .collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([Ok(()), Ok(()), Err(2)]);
+    }
+
+    #[test]
+    fn validate_iter_reports_line_numbers_of_lines_with_salvaged_records() {
+        let config = NdjsonConfig::default().with_salvage(true);
+        let results = validate_iter_with_config::<IgnoredAny, _>(["x{\"a\":1}\ny\n"], config)
+            .map(|result| result.map_err(|error| error.line_number))
+            .collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([Err(1), Ok(()), Err(2)]);
+    }
+
+    #[test]
+    fn validate_iter_with_ignored_any_checks_only_well_formedness() {
+        let results = validate_iter::<IgnoredAny, _>(["{\"a\":[1,{}]}\n\"b\"\nc\n"])
+            .map(|result| result.map_err(|error| error.line_number))
+            .collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([Ok(()), Ok(()), Err(3)]);
+    }
+
+    #[test]
+    fn map_input_error_converts_errors_and_keeps_partial_line() {
+        let data_block_results = vec![Ok("{\"key\":1,"), Err(1), Ok("\"value\":2}\n"), Err(2)];
+        let mut ndjson_iter = from_fallible_iter::<TestStruct, _>(data_block_results);
+
+        assert_that!(ndjson_iter.next()).to_value().is_input_error(1);
+
+        let mut ndjson_iter = ndjson_iter.map_input_error(|error| error * 10);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).to_value().is_input_error(20);
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn input_error_into_converts_via_from() {
+        let data_block_results = vec![Ok::<_, u8>("1\n"), Err(3)];
+        let errors = from_fallible_iter::<u32, _>(data_block_results)
+            .input_error_into::<u64, _, _>()
+            .filter_map(|result| match result {
+                Err(FallibleNdjsonError::InputError(error)) => Some(error),
+                _ => None
+            })
+            .collect::<Vec<u64>>();
+
+        assert_that!(errors).contains_exactly_in_given_order([3]);
+    }
+
+    #[test]
+    fn finalize_parses_rest_after_stopping_early() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let data_blocks = ["{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}"];
+        let mut ndjson_iter = from_iter_with_config::<TestStruct, _>(data_blocks, config);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.engine().is_partial()).is_true();
+        assert_that!(ndjson_iter.finalize()).is_equal_to(1);
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 3, value: 4 });
+    }
+
+    #[test]
+    fn into_rest_returns_unparsed_incomplete_line() {
+        let data_blocks = vec![Ok::<_, ()>("1\n2\n3"), Ok("4")];
+        let mut ndjson_iter = from_fallible_iter::<u32, _>(data_blocks);
+
+        let consumed = ndjson_iter.by_ref()
+            .map(Result::unwrap)
+            .take_while(|&value| value < 2)
+            .count();
+
+        assert_that!(consumed).is_equal_to(1);
+        assert_that!(ndjson_iter.into_rest()).contains_exactly_in_given_order(b"3".iter());
+    }
+
+    #[test]
+    fn wrapped_source_is_accessible_and_recoverable() {
+        use std::io::Cursor;
+
+        use crate::source::ReaderSource;
+
+        let source = ReaderSource::with_chunk_size(Cursor::new("1\n2\n3\n"), 2);
+        let mut ndjson_iter = from_chunk_source::<u32, _>(source);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(ndjson_iter.get_ref().get_ref().get_ref().position()).is_equal_to(2);
+
+        ndjson_iter.get_mut().get_mut().get_mut().set_position(4);
+
+        let cursor = ndjson_iter.into_inner().into_inner().into_inner();
+
+        assert_that!(cursor.position()).is_equal_to(4);
+    }
+
+    #[test]
+    fn into_inner_of_infallible_iter_continues_after_consumed_blocks() {
+        let mut ndjson_iter = from_iter::<u32, _>(vec!["1\n", "2\n", "3\n"]);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(ndjson_iter.get_ref().len()).is_equal_to(2);
+        assert_that!(ndjson_iter.into_inner().collect::<Vec<_>>())
+            .contains_exactly_in_given_order(["2\n", "3\n"]);
+    }
+
+    #[test]
+    fn engine_of_iter_reports_progress() {
+        let mut ndjson_iter = from_iter::<u32, _>(["1\n2\n"]);
+
+        ndjson_iter.next();
+
+        assert_that!(ndjson_iter.engine().progress().records_emitted).is_equal_to(2);
+    }
+
+    #[test]
+    fn take_until_record_stops_after_sentinel_spanning_blocks() {
+        let data_blocks = vec!["1\n2", "\n3\n", "4\n"];
+        let mut ndjson_iter =
+            from_iter::<u32, _>(data_blocks).take_until_record(|&record| record == 2);
+
+        assert_that!(ndjson_iter.by_ref().map(Result::unwrap).collect::<Vec<_>>())
+            .contains_exactly_in_given_order([1, 2]);
+
+        let (data_blocks, leftover) = ndjson_iter.into_parts();
+
+        assert_that!(leftover).contains_exactly_in_given_order(b"3\n".iter());
+        assert_that!(data_blocks.collect::<Vec<_>>()).contains_exactly_in_given_order(["4\n"]);
+    }
+
+    #[test]
+    fn take_until_record_respects_crlf_line_ending() {
+        let config = NdjsonConfig::default().with_line_ending(crate::config::LineEnding::CrLf);
+        let mut ndjson_iter = from_iter_with_config::<u32, _>(vec!["1\r\n2\r\nrest"], config)
+            .take_until_record(|&record| record == 1);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(ndjson_iter.next()).is_none();
+        assert_that!(ndjson_iter.into_parts().1)
+            .contains_exactly_in_given_order(b"2\r\nrest".iter());
+    }
+
+    #[test]
+    fn take_until_record_yields_everything_without_sentinel() {
+        let data_blocks = vec!["1\nx\n", "3"];
+        let mut ndjson_iter = from_iter_with_config::<u32, _>(data_blocks,
+            NdjsonConfig::default().with_parse_rest(true)).take_until_record(|_| false);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(ndjson_iter.next()).to_value().is_err();
+        assert_that!(ndjson_iter.next()).to_value().contains_value(3);
+        assert_that!(ndjson_iter.next()).is_none();
+        assert_that!(ndjson_iter.into_parts().1).is_empty();
+    }
+
+    #[test]
+    fn fallible_iter_can_be_resumed_from_parts() {
+        let data_block_results = vec![Ok("1\n{\"key\":2,"), Err("test"), Ok("\"value\":3}\n")];
+        let mut ndjson_iter = from_fallible_iter::<TestStruct, _>(data_block_results);
+
+        assert_that!(ndjson_iter.next()).to_value().is_err();
+        assert_that!(ndjson_iter.next()).to_value().is_input_error("test");
+
+        let (mut engine, rest) = ndjson_iter.into_parts();
+
+        for data_block in rest {
+            engine.input(data_block.unwrap());
+        }
+
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 2, value: 3 });
+    }
+
+    #[test]
+    fn validate_iter_into_engine_retains_incomplete_line() {
+        let mut validate_iter = validate_iter::<IgnoredAny, _>(vec!["1\n", "[2", ",3]\n"]);
+
+        assert_that!(validate_iter.next()).to_value().is_ok();
+
+        let (engine, rest) = validate_iter.into_parts();
+
+        assert_that!(engine.rest()).is_empty();
+        assert_that!(rest.collect::<Vec<_>>()).contains_exactly_in_given_order(["[2", ",3]\n"]);
+    }
 }
@@ -0,0 +1,199 @@
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures::Stream;
+use pin_project_lite::pin_project;
+use serde::Deserialize;
+
+use crate::as_bytes::AsBytes;
+use crate::engine::NdjsonEngine;
+use crate::error::NdjsonResult;
+use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+
+#[cfg(feature = "iter")]
+use crate::driver::iter::{FallibleNdjsonIter, NdjsonIter};
+
+#[cfg(feature = "stream")]
+use crate::driver::stream::{FallibleNdjsonStream, NdjsonStream};
+
+pin_project! {
+    /// Wraps an NDJSON-driver, i.e. an [NdjsonIter], [FallibleNdjsonIter], [NdjsonStream], or
+    /// [FallibleNdjsonStream], and yields every item of the wrapped driver together with the
+    /// one-based number of the line it was parsed from. Created by the `with_line_numbers` method
+    /// of the drivers.
+    ///
+    /// Line numbers are tracked by the [NdjsonEngine] and count all lines of the input, including
+    /// empty, blank, comment, and skipped lines, so they refer to the line in the original input,
+    /// unlike counting the items with [Iterator::enumerate]. See also
+    /// [NdjsonEngine::pop_with_line_number]. Input errors of the fallible drivers are numbered with
+    /// the number of lines completed before the error.
+    pub struct WithLineNumbers<D> {
+        #[pin]
+        driver: D
+    }
+}
+
+impl<D> WithLineNumbers<D> {
+
+    pub(crate) fn new(driver: D) -> WithLineNumbers<D> {
+        WithLineNumbers {
+            driver
+        }
+    }
+
+    /// Gets a reference to the wrapped driver.
+    pub fn get_ref(&self) -> &D {
+        &self.driver
+    }
+
+    /// Unwraps this driver, returning the wrapped driver.
+    pub fn into_inner(self) -> D {
+        self.driver
+    }
+}
+
+fn line_number_of<T, E>(result: &FallibleNdjsonResult<T, E>, engine: &NdjsonEngine<T>) -> u64 {
+    match result {
+        Err(FallibleNdjsonError::InputError(_)) => engine.line_count(),
+        _ => engine.popped_line_number()
+    }
+}
+
+#[cfg(feature = "iter")]
+impl<T, I> Iterator for WithLineNumbers<NdjsonIter<T, I>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes
+{
+    type Item = (u64, NdjsonResult<T>);
+
+    fn next(&mut self) -> Option<(u64, NdjsonResult<T>)> {
+        let next = self.driver.next()?;
+
+        Some((self.driver.engine().popped_line_number(), next))
+    }
+}
+
+#[cfg(feature = "iter")]
+impl<T, I, B, E> Iterator for WithLineNumbers<FallibleNdjsonIter<T, I>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = (u64, FallibleNdjsonResult<T, E>);
+
+    fn next(&mut self) -> Option<(u64, FallibleNdjsonResult<T, E>)> {
+        let next = self.driver.next()?;
+
+        Some((line_number_of(&next, self.driver.engine()), next))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, S> Stream for WithLineNumbers<NdjsonStream<T, S>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream,
+    S::Item: AsBytes
+{
+    type Item = (u64, NdjsonResult<T>);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<(u64, NdjsonResult<T>)>> {
+        let mut this = self.project();
+
+        this.driver.as_mut().poll_next(cx)
+            .map(|next| next.map(|next| (this.driver.engine().popped_line_number(), next)))
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, S, B, E> Stream for WithLineNumbers<FallibleNdjsonStream<T, S>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = (u64, FallibleNdjsonResult<T, E>);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<(u64, FallibleNdjsonResult<T, E>)>> {
+        let mut this = self.project();
+
+        this.driver.as_mut().poll_next(cx)
+            .map(|next| next.map(|next| (line_number_of(&next, this.driver.engine()), next)))
+    }
+}
+
+#[cfg(all(test, feature = "iter"))]
+mod tests {
+
+    #[cfg(feature = "stream")]
+    use futures::stream::{self, StreamExt};
+
+    use kernal::prelude::*;
+
+    use crate::config::{EmptyLineHandling, NdjsonConfig};
+
+    fn line_numbers<I, R>(ndjson_iter: I) -> Vec<u64>
+    where
+        I: Iterator<Item = (u64, R)>
+    {
+        ndjson_iter.map(|(line_number, _)| line_number).collect()
+    }
+
+    #[test]
+    fn ignored_and_skipped_lines_are_counted() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_comment_prefix("#");
+        let data_blocks = vec!["1\n\n# comment\n2", "\n  \nx\n", "3\n"];
+        let mut ndjson_iter = crate::from_iter_with_config::<u32, _>(data_blocks, config);
+
+        ndjson_iter.engine_mut().skip_lines(1);
+
+        assert_that!(line_numbers(ndjson_iter.with_line_numbers()))
+            .contains_exactly_in_given_order([4, 6, 7]);
+    }
+
+    #[test]
+    fn input_errors_are_numbered_with_completed_lines() {
+        let data_block_results = vec![Ok("1\n2"), Err("test"), Ok("\n3\n")];
+        let ndjson_iter = crate::from_fallible_iter::<u32, _>(data_block_results)
+            .with_line_numbers();
+
+        assert_that!(line_numbers(ndjson_iter)).contains_exactly_in_given_order([1, 1, 2, 3]);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn streams_yield_line_numbers() {
+        let data_blocks = vec!["1\n", "\n2\n"];
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+        let ndjson_stream = crate::from_stream_with_config::<u32, _>(stream::iter(data_blocks),
+            config.clone());
+        let data_block_results = vec![Ok::<_, &str>("1\n\n2\n")];
+        let fallible_ndjson_stream =
+            crate::from_fallible_stream_with_config::<u32, _>(stream::iter(data_block_results),
+                config);
+
+        tokio_test::block_on(async {
+            let line_numbers = ndjson_stream.with_line_numbers()
+                .map(|(line_number, _)| line_number)
+                .collect::<Vec<_>>().await;
+            let fallible_line_numbers = fallible_ndjson_stream.with_line_numbers()
+                .map(|(line_number, _)| line_number)
+                .collect::<Vec<_>>().await;
+
+            assert_that!(line_numbers).contains_exactly_in_given_order([1, 3]);
+            assert_that!(fallible_line_numbers).contains_exactly_in_given_order([1, 3]);
+        });
+    }
+}
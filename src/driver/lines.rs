@@ -0,0 +1,510 @@
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures::{ready, Stream};
+
+#[cfg(feature = "stream")]
+use pin_project_lite::pin_project;
+
+use serde::Deserialize;
+
+use crate::as_bytes::AsBytes;
+use crate::config::NdjsonConfig;
+use crate::engine::NdjsonEngine;
+use crate::error::NdjsonResult;
+use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+
+/// Wraps an iterator of lines which are already split, i.e. types implementing [AsBytes] which
+/// each hold one line without its line ending, and offers an [Iterator] implementation over parsed
+/// NDJSON-records according to [Deserialize]. See [from_lines] and [from_lines_with_config] for
+/// more details.
+#[cfg(feature = "iter")]
+pub struct LinesNdjsonIter<T, I> {
+    engine: NdjsonEngine<T>,
+    lines: I
+}
+
+#[cfg(feature = "iter")]
+impl<T, I> LinesNdjsonIter<T, I> {
+
+    /// Creates a new NDJSON-iterator wrapping the given iterator of `lines` with the given
+    /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(lines: I, config: NdjsonConfig) -> LinesNdjsonIter<T, I> {
+        LinesNdjsonIter {
+            engine: NdjsonEngine::with_config(config),
+            lines
+        }
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this iterator, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Unwraps this iterator, returning the wrapped iterator of lines, which continues after the
+    /// last line given to the engine. Records which were parsed but not returned yet are dropped.
+    pub fn into_inner(self) -> I {
+        self.lines
+    }
+}
+
+#[cfg(feature = "iter")]
+impl<T, I> Iterator for LinesNdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator,
+    I::Item: AsBytes
+{
+    type Item = NdjsonResult<T>;
+
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
+        loop {
+            if let Some(result) = self.engine.pop() {
+                return Some(result);
+            }
+
+            if self.engine.is_finished() {
+                return None;
+            }
+
+            self.engine.input_line(self.lines.next()?);
+        }
+    }
+}
+
+/// Wraps an iterator over [Result]s of lines which are already split, such as the output of
+/// [BufRead::lines](std::io::BufRead::lines), and offers an [Iterator] implementation over parsed
+/// NDJSON-records according to [Deserialize], forwarding potential errors returned by the wrapped
+/// iterator. See [from_fallible_lines] and [from_fallible_lines_with_config] for more details.
+#[cfg(feature = "iter")]
+pub struct FallibleLinesNdjsonIter<T, I> {
+    engine: NdjsonEngine<T>,
+    lines: I
+}
+
+#[cfg(feature = "iter")]
+impl<T, I> FallibleLinesNdjsonIter<T, I> {
+
+    /// Creates a new fallible NDJSON-iterator wrapping the given iterator of `lines` with the
+    /// given [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(lines: I, config: NdjsonConfig) -> FallibleLinesNdjsonIter<T, I> {
+        FallibleLinesNdjsonIter {
+            engine: NdjsonEngine::with_config(config),
+            lines
+        }
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this iterator, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Unwraps this iterator, returning the wrapped iterator of lines, which continues after the
+    /// last line given to the engine. Records which were parsed but not returned yet are dropped.
+    pub fn into_inner(self) -> I {
+        self.lines
+    }
+}
+
+#[cfg(feature = "iter")]
+impl<T, I, B, E> Iterator for FallibleLinesNdjsonIter<T, I>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn next(&mut self) -> Option<FallibleNdjsonResult<T, E>> {
+        loop {
+            if let Some(result) = self.engine.pop() {
+                return Some(result.map_err(FallibleNdjsonError::JsonError));
+            }
+
+            if self.engine.is_finished() {
+                return None;
+            }
+
+            match self.lines.next()? {
+                Ok(line) => self.engine.input_line(line),
+                Err(error) => return Some(Err(FallibleNdjsonError::InputError(error)))
+            };
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+pin_project! {
+    /// Wraps a [Stream] of lines which are already split, i.e. types implementing [AsBytes] which
+    /// each hold one line without its line ending, such as the messages of a message queue where
+    /// every message is one record, and offers a [Stream] implementation over parsed
+    /// NDJSON-records according to [Deserialize]. See [from_line_stream] and
+    /// [from_line_stream_with_config] for more details.
+    pub struct LinesNdjsonStream<T, S> {
+        engine: NdjsonEngine<T>,
+        #[pin]
+        lines: S
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, S> LinesNdjsonStream<T, S> {
+
+    /// Creates a new NDJSON-stream wrapping the given stream of `lines` with the given
+    /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(lines: S, config: NdjsonConfig) -> LinesNdjsonStream<T, S> {
+        LinesNdjsonStream {
+            engine: NdjsonEngine::with_config(config),
+            lines
+        }
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this stream, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Unwraps this stream, returning the wrapped stream of lines, which continues after the last
+    /// line given to the engine. Records which were parsed but not returned yet are dropped.
+    pub fn into_inner(self) -> S {
+        self.lines
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, S> Stream for LinesNdjsonStream<T, S>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream,
+    S::Item: AsBytes
+{
+    type Item = NdjsonResult<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NdjsonResult<T>>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(result) = this.engine.pop() {
+                return Poll::Ready(Some(result));
+            }
+
+            if this.engine.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.lines.as_mut().poll_next(cx)) {
+                Some(line) => this.engine.input_line(line),
+                None => return Poll::Ready(None)
+            };
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+pin_project! {
+    /// Wraps a [Stream] over [Result]s of lines which are already split and offers a [Stream]
+    /// implementation over parsed NDJSON-records according to [Deserialize], forwarding potential
+    /// errors returned by the wrapped stream. See [from_fallible_line_stream] and
+    /// [from_fallible_line_stream_with_config] for more details.
+    pub struct FallibleLinesNdjsonStream<T, S> {
+        engine: NdjsonEngine<T>,
+        #[pin]
+        lines: S
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, S> FallibleLinesNdjsonStream<T, S> {
+
+    /// Creates a new fallible NDJSON-stream wrapping the given stream of `lines` with the given
+    /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(lines: S, config: NdjsonConfig) -> FallibleLinesNdjsonStream<T, S> {
+        FallibleLinesNdjsonStream {
+            engine: NdjsonEngine::with_config(config),
+            lines
+        }
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this stream, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Unwraps this stream, returning the wrapped stream of lines, which continues after the last
+    /// line given to the engine. Records which were parsed but not returned yet are dropped.
+    pub fn into_inner(self) -> S {
+        self.lines
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T, S, B, E> Stream for FallibleLinesNdjsonStream<T, S>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<FallibleNdjsonResult<T, E>>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(result) = this.engine.pop() {
+                return Poll::Ready(Some(result.map_err(FallibleNdjsonError::JsonError)));
+            }
+
+            if this.engine.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.lines.as_mut().poll_next(cx)) {
+                Some(Ok(line)) => this.engine.input_line(line),
+                Some(Err(error)) =>
+                    return Poll::Ready(Some(Err(FallibleNdjsonError::InputError(error)))),
+                None => return Poll::Ready(None)
+            };
+        }
+    }
+}
+
+/// Wraps an iterator of lines which are already split, i.e. types implementing [AsBytes] which
+/// each hold one line without its line ending, obtained by [IntoIterator::into_iter] on `into_iter`
+/// and offers an [Iterator] implementation over parsed NDJSON-records according to [Deserialize].
+/// In contrast to [from_iter](crate::from_iter), lines are not searched for line endings, so every
+/// item results in at most one record. The parser is configured with the default [NdjsonConfig].
+///
+/// # Example
+///
+/// ```
+/// let lines = vec!["1", "{\"invalid\":json}", "3"];
+/// let mut ndjson_iter = ndjson_stream::from_lines::<u32, _>(lines);
+///
+/// assert!(matches!(ndjson_iter.next(), Some(Ok(1))));
+/// assert!(matches!(ndjson_iter.next(), Some(Err(_))));
+/// assert!(matches!(ndjson_iter.next(), Some(Ok(3))));
+/// assert!(ndjson_iter.next().is_none());
+/// ```
+#[cfg(feature = "iter")]
+pub fn from_lines<T, I>(into_iter: I) -> LinesNdjsonIter<T, I::IntoIter>
+where
+    I: IntoIterator
+{
+    from_lines_with_config(into_iter, NdjsonConfig::default())
+}
+
+/// Wraps an iterator of lines which are already split, obtained by [IntoIterator::into_iter] on
+/// `into_iter`, and offers an [Iterator] implementation over parsed NDJSON-records according to
+/// [Deserialize]. The parser is configured with the given [NdjsonConfig], of which all options that
+/// apply to individual lines are respected. See [NdjsonEngine::input_line] for more details.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+///
+/// let lines = vec!["1", "  ", "2"];
+/// let config = NdjsonConfig::default().with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+/// let mut ndjson_iter = ndjson_stream::from_lines_with_config::<u32, _>(lines, config);
+///
+/// assert!(matches!(ndjson_iter.next(), Some(Ok(1))));
+/// assert!(matches!(ndjson_iter.next(), Some(Ok(2))));
+/// assert!(ndjson_iter.next().is_none());
+/// ```
+#[cfg(feature = "iter")]
+pub fn from_lines_with_config<T, I>(into_iter: I, config: NdjsonConfig)
+    -> LinesNdjsonIter<T, I::IntoIter>
+where
+    I: IntoIterator
+{
+    LinesNdjsonIter::with_config(into_iter.into_iter(), config)
+}
+
+/// Wraps an iterator of [Result]s of lines which are already split, obtained by
+/// [IntoIterator::into_iter] on `into_iter`, and offers an [Iterator] implementation over parsed
+/// NDJSON-records according to [Deserialize]. Errors in the wrapped iterator are forwarded via
+/// [FallibleNdjsonError::InputError], while parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError]. The parser is configured with the default [NdjsonConfig].
+///
+/// # Example
+///
+/// ```
+/// use std::io::BufRead;
+///
+/// let reader = "1\n2\r\n3".as_bytes();
+/// let records = ndjson_stream::from_fallible_lines::<u32, _>(reader.lines())
+///     .map(Result::unwrap)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(records, vec![1, 2, 3]);
+/// ```
+#[cfg(feature = "iter")]
+pub fn from_fallible_lines<T, I>(into_iter: I) -> FallibleLinesNdjsonIter<T, I::IntoIter>
+where
+    I: IntoIterator
+{
+    from_fallible_lines_with_config(into_iter, NdjsonConfig::default())
+}
+
+/// Wraps an iterator of [Result]s of lines which are already split, obtained by
+/// [IntoIterator::into_iter] on `into_iter`, and offers an [Iterator] implementation over parsed
+/// NDJSON-records according to [Deserialize]. Errors in the wrapped iterator are forwarded via
+/// [FallibleNdjsonError::InputError], while parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError]. The parser is configured with the given [NdjsonConfig].
+#[cfg(feature = "iter")]
+pub fn from_fallible_lines_with_config<T, I>(into_iter: I, config: NdjsonConfig)
+    -> FallibleLinesNdjsonIter<T, I::IntoIter>
+where
+    I: IntoIterator
+{
+    FallibleLinesNdjsonIter::with_config(into_iter.into_iter(), config)
+}
+
+/// Wraps a [Stream] of lines which are already split, i.e. types implementing [AsBytes] which each
+/// hold one line without its line ending, and offers a [Stream] implementation over parsed
+/// NDJSON-records according to [Deserialize]. In contrast to [from_stream](crate::from_stream),
+/// lines are not searched for line endings, so every item results in at most one record. The
+/// parser is configured with the default [NdjsonConfig].
+///
+/// # Example
+///
+/// ```
+/// use futures::StreamExt;
+///
+/// let messages = futures::stream::iter(vec!["1", "2"]);
+///
+/// futures::executor::block_on(async {
+///     let records = ndjson_stream::from_line_stream::<u32, _>(messages)
+///         .map(Result::unwrap)
+///         .collect::<Vec<_>>()
+///         .await;
+///
+///     assert_eq!(records, vec![1, 2]);
+/// });
+/// ```
+#[cfg(feature = "stream")]
+pub fn from_line_stream<T, S>(lines: S) -> LinesNdjsonStream<T, S> {
+    from_line_stream_with_config(lines, NdjsonConfig::default())
+}
+
+/// Wraps a [Stream] of lines which are already split and offers a [Stream] implementation over
+/// parsed NDJSON-records according to [Deserialize]. The parser is configured with the given
+/// [NdjsonConfig], of which all options that apply to individual lines are respected. See
+/// [NdjsonEngine::input_line] for more details.
+#[cfg(feature = "stream")]
+pub fn from_line_stream_with_config<T, S>(lines: S, config: NdjsonConfig)
+        -> LinesNdjsonStream<T, S> {
+    LinesNdjsonStream::with_config(lines, config)
+}
+
+/// Wraps a [Stream] of [Result]s of lines which are already split and offers a [Stream]
+/// implementation over parsed NDJSON-records according to [Deserialize]. Errors in the wrapped
+/// stream are forwarded via [FallibleNdjsonError::InputError], while parsing errors are indicated
+/// via [FallibleNdjsonError::JsonError]. The parser is configured with the default [NdjsonConfig].
+#[cfg(feature = "stream")]
+pub fn from_fallible_line_stream<T, S>(lines: S) -> FallibleLinesNdjsonStream<T, S> {
+    from_fallible_line_stream_with_config(lines, NdjsonConfig::default())
+}
+
+/// Wraps a [Stream] of [Result]s of lines which are already split and offers a [Stream]
+/// implementation over parsed NDJSON-records according to [Deserialize]. Errors in the wrapped
+/// stream are forwarded via [FallibleNdjsonError::InputError], while parsing errors are indicated
+/// via [FallibleNdjsonError::JsonError]. The parser is configured with the given [NdjsonConfig].
+#[cfg(feature = "stream")]
+pub fn from_fallible_line_stream_with_config<T, S>(lines: S, config: NdjsonConfig)
+        -> FallibleLinesNdjsonStream<T, S> {
+    FallibleLinesNdjsonStream::with_config(lines, config)
+}
+
+#[cfg(all(test, feature = "iter"))]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::config::EmptyLineHandling;
+    use crate::error::NdjsonError;
+    use crate::test_util::{FallibleNdjsonResultAssertions, TestStruct};
+
+    use super::*;
+
+    #[test]
+    fn lines_are_not_split_further() {
+        let lines = vec!["{\"key\":1,\"value\":2}", "1\n2"];
+        let mut ndjson_iter = from_lines::<TestStruct, _>(lines);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).to_value().is_err();
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn config_is_respected() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_comment_prefix("#")
+            .with_max_line_length(3)
+            .with_max_records(Some(3));
+        let lines = vec!["", "#c", "12", "1234", "5", "6"];
+        let mut ndjson_iter = from_lines_with_config::<u32, _>(lines, config);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(12);
+        assert_that!(matches!(ndjson_iter.next(), Some(Err(NdjsonError::LineTooLong {
+            offset: 4,
+            length: 4,
+            ..
+        })))).is_true();
+        assert_that!(ndjson_iter.next()).to_value().contains_value(5);
+        assert_that!(ndjson_iter.next()).is_none();
+        assert_that!(ndjson_iter.into_inner().collect::<Vec<_>>())
+            .contains_exactly_in_given_order(["6"]);
+    }
+
+    #[test]
+    fn empty_lines_are_parsed_by_default() {
+        let mut ndjson_iter = from_lines::<u32, _>(vec![""]);
+
+        assert_that!(ndjson_iter.next()).to_value().is_err();
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn input_errors_are_forwarded() {
+        let lines = vec![Ok("1"), Err("test"), Ok("2")];
+        let mut ndjson_iter = from_fallible_lines::<u32, _>(lines);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(ndjson_iter.next()).to_value().is_input_error("test");
+        assert_that!(ndjson_iter.next()).to_value().contains_value(2);
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn streams_parse_lines() {
+        use futures::stream::{self, StreamExt};
+
+        let lines = stream::iter(vec!["1", "x"]);
+        let fallible_lines = stream::iter(vec![Ok("1"), Err("test")]);
+
+        tokio_test::block_on(async {
+            let results = from_line_stream::<u32, _>(lines).collect::<Vec<_>>().await;
+            let fallible_results =
+                from_fallible_line_stream::<u32, _>(fallible_lines).collect::<Vec<_>>().await;
+
+            assert_that!(matches!(results.as_slice(), [Ok(1), Err(_)])).is_true();
+            assert_that!(matches!(fallible_results.as_slice(), [
+                Ok(1),
+                Err(FallibleNdjsonError::InputError("test"))
+            ])).is_true();
+        });
+    }
+}
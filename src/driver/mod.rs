@@ -1,20 +1,133 @@
 //! This module contains the higher-level drivers of the NDJSON-parser. Convenience functions to
 //! construct these are found at top-level of the crate.
 
+#[cfg(any(feature = "iter", feature = "stream"))]
+pub(crate) mod header;
+
 #[cfg(feature = "iter")]
 pub(crate) mod iter;
 
+#[cfg(any(feature = "iter", feature = "stream"))]
+pub(crate) mod line_number;
+
+#[cfg(any(feature = "iter", feature = "stream"))]
+pub(crate) mod lines;
+
+#[cfg(feature = "stream")]
+pub(crate) mod owned;
+
+pub(crate) mod push;
+
+#[cfg(feature = "iter")]
+pub(crate) mod slice;
+
+pub(crate) mod spill;
+
 #[cfg(feature = "stream")]
 pub(crate) mod stream;
 
+#[cfg(feature = "tokio")]
+pub(crate) mod blocking;
+
+#[cfg(feature = "tokio")]
+pub(crate) mod buffered;
+
+#[cfg(feature = "tokio")]
+pub(crate) mod retry;
+
+#[cfg(feature = "tokio")]
+pub(crate) mod spawn;
+
+#[cfg(feature = "tokio")]
+pub(crate) mod timeout;
+
 #[cfg(feature = "iter")]
 pub use crate::driver::iter::NdjsonIter;
 
 #[cfg(feature = "iter")]
 pub use crate::driver::iter::FallibleNdjsonIter;
 
+#[cfg(feature = "iter")]
+pub use crate::driver::iter::DeadLetterNdjsonIter;
+
+#[cfg(feature = "iter")]
+pub use crate::driver::iter::ValidateNdjsonIter;
+
+#[cfg(feature = "iter")]
+pub use crate::driver::iter::MapInputErrorIter;
+
+#[cfg(feature = "iter")]
+pub use crate::driver::slice::SliceNdjsonIter;
+
+#[cfg(feature = "iter")]
+pub use crate::driver::lines::LinesNdjsonIter;
+
+#[cfg(feature = "iter")]
+pub use crate::driver::lines::FallibleLinesNdjsonIter;
+
+#[cfg(feature = "iter")]
+pub use crate::driver::iter::TakeUntilRecordIter;
+
+#[cfg(any(feature = "iter", feature = "stream"))]
+pub use crate::driver::header::WithHeader;
+
+#[cfg(any(feature = "iter", feature = "stream"))]
+pub use crate::driver::line_number::WithLineNumbers;
+
+pub use crate::driver::push::PushNdjsonParser;
+
+pub use crate::driver::push::PushedRecords;
+
 #[cfg(feature = "stream")]
 pub use crate::driver::stream::NdjsonStream;
 
 #[cfg(feature = "stream")]
 pub use crate::driver::stream::FallibleNdjsonStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::stream::DEFAULT_YIELD_INTERVAL;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::stream::DeadLetterNdjsonStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::stream::ValidateNdjsonStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::stream::MapInputErrorStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::stream::TakeUntilRecordStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::lines::LinesNdjsonStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::lines::FallibleLinesNdjsonStream;
+
+#[cfg(feature = "stream")]
+pub use crate::driver::owned::OwnedNdjsonStream;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::timeout::IdleTimeoutStream;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::timeout::IdleTimeoutError;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::blocking::BlockingNdjsonIter;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::buffered::BufferedParseStream;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::retry::RetryPolicy;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::retry::RetryingNdjsonStream;
@@ -0,0 +1,220 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "tokio")]
+use std::io;
+
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncRead;
+
+use crate::as_bytes::AsBytes;
+use crate::config::NdjsonConfig;
+use crate::driver::stream::FallibleNdjsonStream;
+use crate::fallible::FallibleNdjsonResult;
+use crate::source::AsyncChunkSource;
+
+#[cfg(feature = "tokio")]
+use crate::source::AsyncReaderSource;
+
+/// A [Stream] over parsed NDJSON-records according to [Deserialize], which owns its input and is
+/// therefore `Send + 'static` whenever `T` and `E` are `Send + 'static`, regardless of the input.
+/// This makes it suitable for being moved into spawned tasks or stored in structs without lifetime
+/// or type parameters for the input. Errors of the input are forwarded via
+/// [FallibleNdjsonError::InputError](crate::fallible::FallibleNdjsonError::InputError), while
+/// parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError](crate::fallible::FallibleNdjsonError::JsonError).
+///
+/// The input, which must be `Send + 'static` itself, is boxed together with the parser, so every
+/// poll involves a dynamic call.
+///
+/// # Example
+///
+/// ```
+/// use futures::stream::{self, StreamExt};
+/// use ndjson_stream::config::NdjsonConfig;
+/// use ndjson_stream::driver::OwnedNdjsonStream;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let data_blocks = stream::iter(vec![Ok::<_, ()>(b"1\n2\n".to_vec())]);
+/// let ndjson_stream = OwnedNdjsonStream::<u32, _>::from_fallible_stream(data_blocks);
+///
+/// let records = runtime.block_on(async {
+///     tokio::spawn(ndjson_stream.map(Result::unwrap).collect::<Vec<_>>()).await.unwrap()
+/// });
+///
+/// assert_eq!(records, vec![1, 2]);
+/// ```
+pub struct OwnedNdjsonStream<T, E> {
+    inner: BoxStream<'static, FallibleNdjsonResult<T, E>>
+}
+
+impl<T, E> OwnedNdjsonStream<T, E>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static,
+    E: Send + 'static
+{
+
+    /// Creates a new owned NDJSON-stream over the given stream of [Result]s of data blocks with
+    /// default [NdjsonConfig].
+    pub fn from_fallible_stream<S, B>(bytes_stream: S) -> OwnedNdjsonStream<T, E>
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: AsBytes
+    {
+        OwnedNdjsonStream::from_fallible_stream_with_config(bytes_stream, NdjsonConfig::default())
+    }
+
+    /// Creates a new owned NDJSON-stream over the given stream of [Result]s of data blocks with
+    /// the given [NdjsonConfig].
+    pub fn from_fallible_stream_with_config<S, B>(bytes_stream: S, config: NdjsonConfig)
+        -> OwnedNdjsonStream<T, E>
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: AsBytes
+    {
+        OwnedNdjsonStream {
+            inner: FallibleNdjsonStream::with_config(bytes_stream, config).boxed()
+        }
+    }
+
+    /// Creates a new owned NDJSON-stream over the data blocks of the given [AsyncChunkSource] with
+    /// the given [NdjsonConfig].
+    pub fn from_async_chunk_source_with_config<S>(source: S, config: NdjsonConfig)
+        -> OwnedNdjsonStream<T, E>
+    where
+        S: AsyncChunkSource<Error = E> + Send + 'static
+    {
+        OwnedNdjsonStream::from_fallible_stream_with_config(source.into_chunk_stream(), config)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> OwnedNdjsonStream<T, io::Error>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static
+{
+
+    /// Creates a new owned NDJSON-stream reading from the given Tokio [AsyncRead], such as a file
+    /// or socket, with default [NdjsonConfig].
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn from_async_read<R>(reader: R) -> OwnedNdjsonStream<T, io::Error>
+    where
+        R: AsyncRead + Send + 'static
+    {
+        OwnedNdjsonStream::from_async_read_with_config(reader, NdjsonConfig::default())
+    }
+
+    /// Creates a new owned NDJSON-stream reading from the given Tokio [AsyncRead], such as a file
+    /// or socket, with the given [NdjsonConfig].
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn from_async_read_with_config<R>(reader: R, config: NdjsonConfig)
+        -> OwnedNdjsonStream<T, io::Error>
+    where
+        R: AsyncRead + Send + 'static
+    {
+        OwnedNdjsonStream::from_async_chunk_source_with_config(AsyncReaderSource::new(reader),
+            config)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl<T> OwnedNdjsonStream<T, reqwest::Error>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static
+{
+
+    /// Creates a new owned NDJSON-stream over the body of the given [reqwest::Response] with
+    /// default [NdjsonConfig]. The status of the response is not checked.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "reqwest")))]
+    pub fn from_response(response: reqwest::Response) -> OwnedNdjsonStream<T, reqwest::Error> {
+        OwnedNdjsonStream::from_response_with_config(response, NdjsonConfig::default())
+    }
+
+    /// Creates a new owned NDJSON-stream over the body of the given [reqwest::Response] with the
+    /// given [NdjsonConfig]. The status of the response is not checked.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "reqwest")))]
+    pub fn from_response_with_config(response: reqwest::Response, config: NdjsonConfig)
+            -> OwnedNdjsonStream<T, reqwest::Error> {
+        OwnedNdjsonStream::from_fallible_stream_with_config(response.bytes_stream(), config)
+    }
+}
+
+impl<T, E> Stream for OwnedNdjsonStream<T, E> {
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use futures::stream;
+
+    use kernal::prelude::*;
+
+    use crate::config::EmptyLineHandling;
+    use crate::fallible::FallibleNdjsonError;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    fn assert_send_static<S: Send + 'static>(_: &S) { }
+
+    #[test]
+    fn owned_streams_are_send_and_static() {
+        let data_blocks = vec![Ok::<_, String>(String::from("{\"key\":1,\"value\":2}\n"))];
+        let ndjson_stream =
+            OwnedNdjsonStream::<TestStruct, _>::from_fallible_stream(stream::iter(data_blocks));
+
+        assert_send_static(&ndjson_stream);
+    }
+
+    #[test]
+    fn owned_stream_uses_config() {
+        let data_blocks = stream::iter(vec![Ok::<_, &str>("\n1\n"), Err("error"), Ok("x\n")]);
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+        let ndjson_stream =
+            OwnedNdjsonStream::<u32, _>::from_fallible_stream_with_config(data_blocks, config);
+        let items = tokio_test::block_on(ndjson_stream.collect::<Vec<_>>());
+
+        assert_that!(items).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(1),
+            |it| assert_that!(matches!(it, Err(FallibleNdjsonError::InputError("error"))))
+                .is_true(),
+            |it| assert_that!(matches!(it, Err(FallibleNdjsonError::JsonError(_)))).is_true()
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn owned_stream_from_reader_can_be_spawned() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let reader = tokio_test::io::Builder::new()
+            .read(b"1\n2")
+            .read(b"\n")
+            .build();
+        let ndjson_stream = OwnedNdjsonStream::<u32, _>::from_async_read(reader);
+
+        assert_send_static(&ndjson_stream);
+
+        let records = runtime.block_on(async {
+            tokio::spawn(ndjson_stream.map(Result::unwrap).collect::<Vec<_>>()).await.unwrap()
+        });
+
+        assert_that!(records).contains_exactly_in_given_order([1, 2]);
+    }
+}
@@ -0,0 +1,372 @@
+use std::io;
+use std::iter::FusedIterator;
+
+use serde::Deserialize;
+
+use crate::as_bytes::AsBytes;
+use crate::config::NdjsonConfig;
+use crate::driver::spill::SpillFile;
+use crate::engine::NdjsonEngine;
+use crate::error::{NdjsonError, NdjsonResult};
+
+/// The state of a [PushNdjsonParser] which spills data to disk.
+struct Spill {
+    file: SpillFile,
+    max_queued_records: usize,
+
+    /// Whether [PushNdjsonParser::finish] was called while spilled data was still unread, in which
+    /// case the engine is finalized once all spilled data has been read back.
+    finish_pending: bool
+}
+
+/// A push-based NDJSON-parser for situations where the data source is not pulled by the consumer,
+/// but rather pushes data blocks, for example via websocket callbacks. Data blocks, i.e. types
+/// implementing [AsBytes], are supplied via [PushNdjsonParser::push], which returns an iterator
+/// over the NDJSON-records completed by that data block.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::driver::PushNdjsonParser;
+///
+/// let mut parser = PushNdjsonParser::<u32>::new();
+///
+/// assert_eq!(parser.push("12").count(), 0);
+///
+/// let records = parser.push("3\n456\n").collect::<Result<Vec<_>, _>>().unwrap();
+///
+/// assert_eq!(records, vec![123, 456]);
+/// ```
+///
+/// If the consumer is slower than the producer, unconsumed records accumulate in memory. To bound
+/// the memory usage, the parser can spill data to disk instead, see
+/// [PushNdjsonParser::with_spill_to_disk].
+pub struct PushNdjsonParser<T> {
+    engine: NdjsonEngine<T>,
+    spill: Option<Spill>
+}
+
+impl<T> PushNdjsonParser<T> {
+
+    /// Creates a new push-based NDJSON-parser with default [NdjsonConfig].
+    pub fn new() -> PushNdjsonParser<T> {
+        PushNdjsonParser {
+            engine: NdjsonEngine::new(),
+            spill: None
+        }
+    }
+
+    /// Creates a new push-based NDJSON-parser with the given [NdjsonConfig] to control its
+    /// behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(config: NdjsonConfig) -> PushNdjsonParser<T> {
+        PushNdjsonParser {
+            engine: NdjsonEngine::with_config(config),
+            spill: None
+        }
+    }
+
+    /// Makes this parser parse lines with the given `deserializer` instead of [serde_json]. See
+    /// [NdjsonEngine::with_deserializer] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new parser with all the same state as this one, except the deserializer.
+    pub fn with_deserializer<F, E>(self, deserializer: F) -> PushNdjsonParser<T>
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<NdjsonError>
+    {
+        PushNdjsonParser {
+            engine: self.engine.with_deserializer(deserializer),
+            ..self
+        }
+    }
+
+    /// Makes this parser check every parsed record with the given `validator`, reporting rejected
+    /// records as [NdjsonError::InvalidRecord]. See [NdjsonEngine::with_record_validator] for more
+    /// details.
+    ///
+    /// # Returns
+    ///
+    /// A new parser with all the same state as this one, except the record validator.
+    pub fn with_record_validator<F>(self, validator: F) -> PushNdjsonParser<T>
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static
+    {
+        PushNdjsonParser {
+            engine: self.engine.with_record_validator(validator),
+            ..self
+        }
+    }
+
+    /// Makes this parser spill data to a temporary file once `max_queued_records` unconsumed
+    /// records are held in memory, instead of parsing it. Spilled data is read back and parsed in
+    /// order once the records in memory have been consumed, so records are returned exactly as
+    /// without spilling. Since the raw data is spilled, which is already NDJSON, records need not
+    /// be serializable. The memory usage is thereby bounded by `max_queued_records` records plus
+    /// the records of one data block, which is useful if the consumer is much slower than the
+    /// producer.
+    ///
+    /// If writing to the temporary file fails, all spilled data is read back and the data is held
+    /// in memory instead. If reading spilled data fails, the error is returned as an
+    /// [NdjsonError::Custom] and all unread spilled data is discarded. The temporary file is
+    /// deleted when the parser is dropped.
+    ///
+    /// # Errors
+    ///
+    /// If the temporary file could not be created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::driver::PushNdjsonParser;
+    ///
+    /// let mut parser = PushNdjsonParser::<u32>::new().with_spill_to_disk(1).unwrap();
+    ///
+    /// let _ = parser.push("1\n2\n");
+    /// let _ = parser.push("3\n");
+    ///
+    /// assert_eq!(parser.spilled_bytes(), 2);
+    ///
+    /// let records = parser.finish().collect::<Result<Vec<_>, _>>().unwrap();
+    ///
+    /// assert_eq!(records, vec![1, 2, 3]);
+    /// ```
+    pub fn with_spill_to_disk(self, max_queued_records: usize) -> io::Result<PushNdjsonParser<T>> {
+        let spill = Spill {
+            file: SpillFile::create()?,
+            max_queued_records,
+            finish_pending: false
+        };
+
+        Ok(PushNdjsonParser {
+            spill: Some(spill),
+            ..self
+        })
+    }
+
+    /// Gets the number of bytes of data which were spilled to disk and not yet read back. This is
+    /// always `0` unless spilling is enabled via [PushNdjsonParser::with_spill_to_disk].
+    pub fn spilled_bytes(&self) -> u64 {
+        self.spill.as_ref().map_or(0, |spill| spill.file.unread_len())
+    }
+}
+
+impl<T> PushNdjsonParser<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>
+{
+
+    /// Supplies the given data block to the parser. Partial lines are buffered until they are
+    /// completed by subsequent data blocks.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over all records completed by the given data block. Records which are not
+    /// consumed from the iterator remain in the parser and are returned by the next call to this
+    /// method or [PushNdjsonParser::finish].
+    pub fn push(&mut self, data: impl AsBytes) -> PushedRecords<'_, T> {
+        if let Some(spill) = &mut self.spill {
+            let spilling = spill.file.unread_len() > 0
+                || self.engine.queued_record_count() >= spill.max_queued_records;
+
+            if spilling && spill.file.write(data.as_bytes()).is_ok() {
+                return PushedRecords {
+                    parser: self
+                };
+            }
+
+            // If writing failed, the spilled data is put into memory to preserve the order.
+            while let Ok(Some(chunk)) = spill.file.read_chunk() {
+                self.engine.input(chunk);
+            }
+        }
+
+        self.engine.input(data);
+
+        PushedRecords {
+            parser: self
+        }
+    }
+
+    /// Signals the parser that no more data will be pushed. Depending on the
+    /// [NdjsonConfig::with_parse_rest] setting, the rest after the last newline character is
+    /// parsed. See [NdjsonEngine::finalize] for more details.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over all remaining records.
+    pub fn finish(&mut self) -> PushedRecords<'_, T> {
+        match &mut self.spill {
+            Some(spill) if spill.file.unread_len() > 0 => spill.finish_pending = true,
+            _ => {
+                self.engine.finalize();
+            }
+        }
+
+        PushedRecords {
+            parser: self
+        }
+    }
+}
+
+impl<T> Default for PushNdjsonParser<T> {
+    fn default() -> PushNdjsonParser<T> {
+        PushNdjsonParser::new()
+    }
+}
+
+/// An iterator over the NDJSON-records completed by a data block pushed to a [PushNdjsonParser].
+/// See [PushNdjsonParser::push] for more details.
+///
+/// If the parser spills data to disk, spilled data is read back and parsed once all records in
+/// memory have been returned.
+pub struct PushedRecords<'parser, T> {
+    parser: &'parser mut PushNdjsonParser<T>
+}
+
+impl<T> Iterator for PushedRecords<'_, T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>
+{
+    type Item = NdjsonResult<T>;
+
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
+        let engine = &mut self.parser.engine;
+
+        loop {
+            if let Some(result) = engine.pop() {
+                return Some(result);
+            }
+
+            let spill = self.parser.spill.as_mut()?;
+
+            match spill.file.read_chunk() {
+                Ok(Some(chunk)) => {
+                    engine.input(chunk);
+                },
+                Ok(None) if spill.finish_pending => {
+                    spill.finish_pending = false;
+                    engine.finalize();
+                },
+                Ok(None) => return None,
+                Err(error) => return Some(Err(NdjsonError::Custom(error.into())))
+            }
+        }
+    }
+}
+
+impl<T> FusedIterator for PushedRecords<'_, T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>
+{ }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    #[test]
+    fn push_without_newline_yields_no_records() {
+        let mut parser = PushNdjsonParser::<TestStruct>::new();
+
+        assert_that!(parser.push("{\"key\":1,\"value\":2}").collect::<Vec<_>>()).is_empty();
+    }
+
+    #[test]
+    fn push_yields_records_completed_by_data_block() {
+        let mut parser = PushNdjsonParser::<TestStruct>::new();
+
+        parser.push("{\"key\":1,").for_each(drop);
+
+        assert_that!(parser.push("\"value\":2}\ninvalid json\n{").collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+                |it| assert_that!(it).is_err()
+            ));
+    }
+
+    #[test]
+    fn unconsumed_records_are_returned_by_next_push() {
+        let mut parser = PushNdjsonParser::<TestStruct>::new();
+
+        let _ = parser.push("{\"key\":1,\"value\":2}\n");
+
+        assert_that!(parser.push("{\"key\":3,\"value\":4}\n").collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+            ));
+    }
+
+    #[test]
+    fn finish_parses_rest_if_configured() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let mut parser = PushNdjsonParser::<TestStruct>::with_config(config);
+
+        parser.push("{\"key\":1,\"value\":2}").for_each(drop);
+
+        assert_that!(parser.finish().collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+            ));
+    }
+
+    #[test]
+    fn custom_deserializer_is_used() {
+        let mut parser = PushNdjsonParser::<TestStruct>::new()
+            .with_deserializer(|line| serde_json::from_slice::<(u64, u64)>(line)
+                .map(|(key, value)| TestStruct { key, value }));
+
+        let records = parser.push("[1,2]\n").collect::<Vec<_>>();
+
+        assert_that!(records).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+        ));
+    }
+
+    #[test]
+    fn spilled_records_are_returned_in_order() {
+        let mut parser = PushNdjsonParser::<TestStruct>::new().with_spill_to_disk(2).unwrap();
+
+        parser.push("{\"key\":1,\"value\":2}\n{\"key\":3,").for_each(drop);
+        let _ = parser.push("\"value\":4}\ninvalid\n{\"key\":5,");
+        let _ = parser.push("\"value\":6}\n{\"key\":7,\"value\":8}");
+
+        assert_that!(parser.engine.queued_record_count()).is_equal_to(2);
+        assert_that!(parser.spilled_bytes()).is_equal_to(30);
+
+        let _ = parser.push("\n");
+
+        assert_that!(parser.spilled_bytes()).is_equal_to(31);
+        assert_that!(parser.finish().collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 }),
+                |it| assert_that!(it).is_err(),
+                |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 7, value: 8 })
+            ));
+        assert_that!(parser.spilled_bytes()).is_equal_to(0);
+    }
+
+    #[test]
+    fn rest_is_parsed_after_spilled_data_on_finish() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let mut parser = PushNdjsonParser::<TestStruct>::with_config(config)
+            .with_spill_to_disk(0)
+            .unwrap();
+
+        let _ = parser.push("{\"key\":1,\"value\":2}\n");
+        let _ = parser.push("{\"key\":3,\"value\":4}");
+
+        assert_that!(parser.finish().collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+            ));
+    }
+}
@@ -0,0 +1,286 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{ready, Stream};
+use pin_project_lite::pin_project;
+use serde::Deserialize;
+use tokio::time::{self, Sleep};
+
+use crate::as_bytes::AsBytes;
+use crate::driver::stream::FallibleNdjsonStream;
+use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+
+/// The default delay before the first retry of a [RetryPolicy].
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The default maximum delay between retries of a [RetryPolicy].
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Controls how often and after which delays a [RetryingNdjsonStream] replaces its failed input.
+/// The delay starts at the initial backoff and doubles with every further attempt, up to the
+/// maximum backoff. By default, the initial backoff is 100 milliseconds and the maximum backoff is
+/// 10 seconds.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration
+}
+
+impl RetryPolicy {
+
+    /// Creates a new retry policy which retries up to `max_attempts` times in a row before giving
+    /// up.
+    pub fn new(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF
+        }
+    }
+
+    /// Sets the delay before the first retry.
+    ///
+    /// # Returns
+    ///
+    /// A new policy with all the same values as this one, except the initial backoff.
+    pub fn with_initial_backoff(self, initial_backoff: Duration) -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the maximum delay between retries.
+    ///
+    /// # Returns
+    ///
+    /// A new policy with all the same values as this one, except the maximum backoff.
+    pub fn with_max_backoff(self, max_backoff: Duration) -> RetryPolicy {
+        RetryPolicy {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Gets the maximum number of retries in a row before an input error is given up on.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Gets the delay before the retry with the given zero-based index `attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+pin_project! {
+    /// Wraps a [FallibleNdjsonStream] and replaces its input by a new stream whenever it returns
+    /// an error, according to a [RetryPolicy]. See [FallibleNdjsonStream::with_retry] for more
+    /// details.
+    pub struct RetryingNdjsonStream<T, S, F> {
+        #[pin]
+        inner: FallibleNdjsonStream<T, S>,
+        reconnect: F,
+        policy: RetryPolicy,
+        attempts: u32,
+        bytes_consumed_at_error: u64,
+        sleep: Option<Pin<Box<Sleep>>>
+    }
+}
+
+impl<T, S> FallibleNdjsonStream<T, S> {
+
+    /// Wraps this stream in a [RetryingNdjsonStream], which retries failed input according to the
+    /// given `policy`. Whenever the wrapped stream returns an error, it waits for the backoff of
+    /// the policy and then replaces the wrapped stream by the result of `reconnect`, as
+    /// [FallibleNdjsonStream::retry_input] does. `reconnect` receives the number of bytes received
+    /// so far, which is the offset at which the new stream must continue.
+    ///
+    /// The attempts are counted as long as no further data arrives between errors. Once the
+    /// maximum number of attempts is exceeded, the error is yielded and counting starts anew, so
+    /// the consumer can decide whether to keep polling.
+    ///
+    /// # Range requests
+    ///
+    /// For NDJSON served over HTTP, `reconnect` can reissue the request with a header of the form
+    /// `Range: bytes={offset}-`, such that the server only sends the data which has not been
+    /// received yet. The incomplete line received before the error is retained by the engine and
+    /// joined with the beginning of the new response, so no record is lost or duplicated, even if
+    /// the error occurred in the middle of a line. If the server ignores the header and responds
+    /// with the entire body, i.e. status 200 instead of 206, `reconnect` must skip the first
+    /// `offset` bytes itself. With the `reqwest` feature enabled, the `range` module implements
+    /// this for the reqwest crate.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use futures::stream::{self, StreamExt};
+    /// use ndjson_stream::driver::RetryPolicy;
+    ///
+    /// const BODY: &str = "1\n23\n4\n";
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    /// let response = stream::iter(vec![Ok(&BODY[..3]), Err("connection reset")]);
+    /// let policy = RetryPolicy::new(3).with_initial_backoff(Duration::from_millis(1));
+    /// let ndjson_stream = ndjson_stream::from_fallible_stream::<u32, _>(response)
+    ///     .with_retry(policy, |offset| {
+    ///         let range = format!("bytes={offset}-");
+    ///
+    ///         // A real client would send a new request with the range header here.
+    ///         assert_eq!(range, "bytes=3-");
+    ///         stream::iter(vec![Ok(&BODY[offset as usize..])])
+    ///     });
+    /// let records = runtime.block_on(ndjson_stream.map(Result::unwrap).collect::<Vec<_>>());
+    ///
+    /// assert_eq!(records, vec![1, 23, 4]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Polling the returned stream panics if an error occurs outside a Tokio runtime with the time
+    /// driver enabled.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn with_retry<F>(self, policy: RetryPolicy, reconnect: F) -> RetryingNdjsonStream<T, S, F>
+    where
+        F: FnMut(u64) -> S
+    {
+        RetryingNdjsonStream {
+            inner: self,
+            reconnect,
+            policy,
+            attempts: 0,
+            bytes_consumed_at_error: 0,
+            sleep: None
+        }
+    }
+}
+
+impl<T, S, F, B, E> Stream for RetryingNdjsonStream<T, S, F>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream<Item = Result<B, E>>,
+    B: AsBytes,
+    F: FnMut(u64) -> S
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(sleep) = this.sleep.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+                *this.sleep = None;
+
+                let bytes_consumed = this.inner.engine().progress().bytes_consumed;
+                let bytes_stream = (this.reconnect)(bytes_consumed);
+
+                this.inner.as_mut().retry_input(bytes_stream);
+            }
+
+            match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Err(FallibleNdjsonError::InputError(error))) => {
+                    let bytes_consumed = this.inner.engine().progress().bytes_consumed;
+
+                    if bytes_consumed != *this.bytes_consumed_at_error {
+                        *this.attempts = 0;
+                        *this.bytes_consumed_at_error = bytes_consumed;
+                    }
+
+                    if *this.attempts >= this.policy.max_attempts {
+                        *this.attempts = 0;
+                        return Poll::Ready(Some(Err(FallibleNdjsonError::InputError(error))));
+                    }
+
+                    let backoff = this.policy.backoff(*this.attempts);
+
+                    *this.attempts += 1;
+                    *this.sleep = Some(Box::pin(time::sleep(backoff)));
+                },
+                item => return Poll::Ready(item)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::vec::IntoIter;
+
+    use futures::stream::{self, Iter, StreamExt};
+
+    use kernal::prelude::*;
+
+    use tokio::runtime::{Builder, Runtime};
+    use tokio::time::Instant;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    type TestInput = Iter<IntoIter<Result<&'static str, &'static str>>>;
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().enable_time().start_paused(true).build().unwrap()
+    }
+
+    fn input(items: Vec<Result<&'static str, &'static str>>) -> TestInput {
+        stream::iter(items)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_maximum() {
+        let policy = RetryPolicy::new(10)
+            .with_initial_backoff(Duration::from_secs(1))
+            .with_max_backoff(Duration::from_secs(5));
+
+        assert_that!(policy.backoff(0)).is_equal_to(Duration::from_secs(1));
+        assert_that!(policy.backoff(2)).is_equal_to(Duration::from_secs(4));
+        assert_that!(policy.backoff(3)).is_equal_to(Duration::from_secs(5));
+        assert_that!(policy.backoff(100)).is_equal_to(Duration::from_secs(5));
+    }
+
+    #[test]
+    fn failed_input_is_resumed_at_consumed_offset() {
+        runtime().block_on(async {
+            let offsets = Rc::new(RefCell::new(Vec::new()));
+            let reconnect_offsets = Rc::clone(&offsets);
+            let policy = RetryPolicy::new(3).with_initial_backoff(Duration::from_secs(1));
+            let first = input(vec![Ok("{\"key\":1,"), Err("reset")]);
+            let mut connections = vec![
+                input(vec![Ok("\"value\":2}\n")]),
+                input(vec![Err("refused")])
+            ];
+            let mut stream = crate::from_fallible_stream::<TestStruct, _>(first)
+                .with_retry(policy, move |offset| {
+                    reconnect_offsets.borrow_mut().push(offset);
+                    connections.pop().unwrap()
+                });
+            let start = Instant::now();
+
+            assert_that!(stream.next().await).to_value()
+                .contains_value(TestStruct { key: 1, value: 2 });
+            assert_that!(stream.next().await).is_none();
+            assert_that!(start.elapsed()).is_equal_to(Duration::from_secs(3));
+            assert_that!(offsets.borrow().clone()).contains_exactly_in_given_order([9, 9]);
+        });
+    }
+
+    #[test]
+    fn error_is_yielded_once_attempts_are_exhausted() {
+        runtime().block_on(async {
+            let mut stream = crate::from_fallible_stream::<TestStruct, _>(input(vec![Err("a")]))
+                .with_retry(RetryPolicy::new(2), |_| input(vec![Err("b")]));
+
+            assert_that!(stream.next().await).to_value().is_err();
+        });
+    }
+}
@@ -0,0 +1,342 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use serde::Deserialize;
+
+use crate::config::{
+    EmptyLineHandling,
+    InvalidLineHandling,
+    LineEnding,
+    NdjsonConfig,
+    TrailingDataHandling,
+    DEFAULT_OVERSIZED_LINE_PREFIX_LEN
+};
+use crate::engine;
+use crate::error::{NdjsonError, NdjsonResult};
+
+/// Splits the first line off the given data according to the given [LineEnding].
+///
+/// # Returns
+///
+/// The line without its line ending, the data after the line ending, and whether a line ending was
+/// found at all. If not, the line is the entire data.
+fn split_first_line(data: &[u8], line_ending: LineEnding) -> (&[u8], &[u8], bool) {
+    let line_end = match line_ending {
+        LineEnding::Lf => data.iter().position(|&byte| byte == b'\n').map(|index| (index, 1)),
+        LineEnding::Cr => data.iter().position(|&byte| byte == b'\r').map(|index| (index, 1)),
+        LineEnding::CrLf => data.windows(2).position(|window| window == b"\r\n")
+            .map(|index| (index, 2)),
+        LineEnding::Any => data.iter().position(|&byte| byte == b'\n' || byte == b'\r')
+            .map(|index| match data[index..].starts_with(b"\r\n") {
+                true => (index, 2),
+                false => (index, 1)
+            })
+    };
+
+    match line_end {
+        Some((index, ending_len)) => (&data[..index], &data[(index + ending_len)..], true),
+        None => (data, &[], false)
+    }
+}
+
+/// Gets a description of the first option of the given config which a [SliceNdjsonIter] cannot
+/// honour, since it requires either owned lines or an engine, or `None` if there is no such option.
+fn unsupported_option(config: &NdjsonConfig) -> Option<&'static str> {
+    let options = [
+        (config.line_preprocessor.is_some(), "line preprocessors"),
+        (config.line_middleware.is_some(), "line middleware"),
+        (config.key_case.is_some(), "key case conversions"),
+        (config.salvage, "salvaging records"),
+        (config.json_array_mode, "the JSON array mode"),
+        (config.end_sentinel.is_some(), "end sentinels"),
+        (config.trailing_data_handling == TrailingDataHandling::MultipleValues,
+            "multiple values per line"),
+        (config.invalid_line_handling == InvalidLineHandling::Skip, "skipping invalid lines"),
+        (config.progress_reporting.is_some(), "progress callbacks"),
+        (config.ignored_line_callback.is_some(), "ignored-line callbacks"),
+        (config.large_record_reporting.is_some(), "large-record callbacks"),
+        (config.parse_metrics_callback.is_some(), "parse-metrics callbacks")
+    ];
+
+    options.into_iter()
+        .find(|&(is_set, _)| is_set)
+        .map(|(_, option)| option)
+}
+
+/// An [Iterator] over the NDJSON-records in a byte slice which is entirely present in memory, such
+/// as a memory-mapped file. In contrast to the other drivers, lines are not copied into an internal
+/// buffer, so records of type `T` may borrow strings and byte slices directly from the data.
+/// Created by [from_slice] or [from_slice_with_config].
+///
+/// The [NdjsonConfig] is respected, but options which require either owned lines or an engine are
+/// not supported. These are line preprocessors, line middleware, key case conversions, salvaging,
+/// the JSON array mode, end sentinels, [TrailingDataHandling::MultipleValues],
+/// [InvalidLineHandling::Skip], and all callbacks. [from_slice_with_config] panics if any of them
+/// is configured, instead of silently ignoring it.
+pub struct SliceNdjsonIter<'data, T> {
+    rest: &'data [u8],
+    offset: u64,
+    records_emitted: u64,
+    config: NdjsonConfig,
+    record: PhantomData<fn() -> T>
+}
+
+impl<'data, T> SliceNdjsonIter<'data, T> {
+
+    fn new(data: &'data [u8], config: NdjsonConfig) -> SliceNdjsonIter<'data, T> {
+        if let Some(option) = unsupported_option(&config) {
+            panic!("SliceNdjsonIter does not support {option}");
+        }
+
+        SliceNdjsonIter {
+            rest: data,
+            offset: 0,
+            records_emitted: 0,
+            config,
+            record: PhantomData
+        }
+    }
+
+    /// Gets the part of the data which has not been parsed yet.
+    pub fn rest(&self) -> &'data [u8] {
+        self.rest
+    }
+
+    /// Gets the offset of the first byte of [SliceNdjsonIter::rest] in the entire data.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn line_too_long(&self, line: &[u8], offset: u64) -> Option<NdjsonError> {
+        let max_line_length = self.config.max_line_length?;
+
+        if line.len() <= max_line_length {
+            return None;
+        }
+
+        let prefix_len = self.config.oversized_line_prefix_len
+            .unwrap_or(DEFAULT_OVERSIZED_LINE_PREFIX_LEN)
+            .min(line.len());
+
+        Some(NdjsonError::LineTooLong {
+            offset,
+            length: line.len(),
+            max_line_length,
+            prefix: line[..prefix_len].to_vec()
+        })
+    }
+}
+
+impl<'data, T> Iterator for SliceNdjsonIter<'data, T>
+where
+    T: Deserialize<'data>
+{
+    type Item = NdjsonResult<T>;
+
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
+        loop {
+            if self.rest.is_empty() || self.config.max_records
+                    .is_some_and(|max_records| self.records_emitted >= max_records) {
+                return None;
+            }
+
+            let offset = self.offset;
+            let (line, rest, is_terminated) = split_first_line(self.rest, self.config.line_ending);
+            let empty_line_handling = match (self.config.empty_line_handling, is_terminated) {
+                (EmptyLineHandling::ParseAlways, false) => EmptyLineHandling::IgnoreEmpty,
+                (empty_line_handling, _) => empty_line_handling
+            };
+
+            self.offset += (self.rest.len() - rest.len()) as u64;
+            self.rest = rest;
+
+            if !is_terminated && !self.config.parse_rest {
+                return None;
+            }
+
+            if let Some(error) = self.line_too_long(line, offset) {
+                self.records_emitted += 1;
+                return Some(Err(error));
+            }
+
+            if let Some(comment_prefix) = &self.config.comment_prefix {
+                if line.starts_with(comment_prefix) {
+                    continue;
+                }
+            }
+
+            if engine::is_ignored(line, empty_line_handling) {
+                continue;
+            }
+
+            self.records_emitted += 1;
+            return Some(engine::parse_line(line, &self.config));
+        }
+    }
+}
+
+impl<'data, T> FusedIterator for SliceNdjsonIter<'data, T>
+where
+    T: Deserialize<'data>
+{ }
+
+/// Creates an [Iterator] over the NDJSON-records in the given `data` according to [Deserialize],
+/// using the default [NdjsonConfig]. Since the data is not copied, `T` may borrow from it. This is
+/// intended for data which is entirely present in memory, such as a file mapped into memory by a
+/// crate like `memmap2`, which can then be scanned repeatedly without read syscalls or copies.
+/// See [SliceNdjsonIter] for more details.
+///
+/// # Example
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Person<'data> {
+///     name: &'data str
+/// }
+///
+/// let data = b"{\"name\":\"Alice\"}\n{\"name\":\"Bob\"}\n".to_vec();
+/// let names = ndjson_stream::from_slice::<Person, _>(&data)
+///     .map(|person| person.unwrap().name)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(names, vec!["Alice", "Bob"]);
+/// ```
+pub fn from_slice<'data, T, D>(data: &'data D) -> SliceNdjsonIter<'data, T>
+where
+    D: AsRef<[u8]> + ?Sized
+{
+    from_slice_with_config(data, NdjsonConfig::default())
+}
+
+/// Creates an [Iterator] over the NDJSON-records in the given `data` according to [Deserialize],
+/// using the given [NdjsonConfig]. See [from_slice] and [SliceNdjsonIter] for more details.
+///
+/// # Panics
+///
+/// If the config contains an option which is not supported by [SliceNdjsonIter], such as a line
+/// preprocessor, salvaging, or the JSON array mode.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+///
+/// let config = NdjsonConfig::default()
+///     .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+///     .with_parse_rest(true);
+/// let data = "\"a\"\n  \n\"b\"";
+/// let mut ndjson_iter = ndjson_stream::from_slice_with_config::<&str, _>(data, config);
+///
+/// assert!(matches!(ndjson_iter.next(), Some(Ok("a"))));
+/// assert!(matches!(ndjson_iter.next(), Some(Ok("b"))));
+/// assert!(ndjson_iter.next().is_none());
+/// ```
+pub fn from_slice_with_config<'data, T, D>(data: &'data D, config: NdjsonConfig)
+    -> SliceNdjsonIter<'data, T>
+where
+    D: AsRef<[u8]> + ?Sized
+{
+    SliceNdjsonIter::new(data.as_ref(), config)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::config::LinePreprocessor;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[test]
+    fn records_borrow_from_data() {
+        let data = String::from("\"abc\"\n\"def\"\n");
+        let records = from_slice::<&str, _>(&data).map(Result::unwrap).collect::<Vec<_>>();
+
+        assert_that!(records[0].as_ptr()).is_equal_to(data[1..].as_ptr());
+        assert_that!(records).contains_exactly_in_given_order(["abc", "def"]);
+    }
+
+    #[test]
+    fn owned_records_may_contain_escapes() {
+        let mut ndjson_iter = from_slice::<String, _>("\"a\\nb\"\n");
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value("a\nb".to_owned());
+        assert_that!(ndjson_iter.next()).is_none();
+    }
+
+    #[test]
+    fn rest_is_only_parsed_if_configured() {
+        let data = "{\"key\":1,\"value\":2}\n{\"key\":3,";
+
+        assert_that!(from_slice::<TestStruct, _>(data).count()).is_equal_to(1);
+
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let mut ndjson_iter = from_slice_with_config::<TestStruct, _>(data, config);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_iter.next()).to_value().is_err();
+        assert_that!(ndjson_iter.next()).is_none();
+        assert_that!(ndjson_iter.rest()).is_empty();
+    }
+
+    #[test]
+    fn config_is_respected() {
+        let config = NdjsonConfig::default()
+            .with_line_ending(LineEnding::CrLf)
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_comment_prefix("#")
+            .with_max_line_length(3)
+            .with_max_records(Some(3));
+        let data = "1\r\n\r\n#c\r\n2\n3\r\n1234\r\n5\r\n";
+        let mut ndjson_iter = from_slice_with_config::<u32, _>(data, config);
+
+        assert_that!(ndjson_iter.next()).to_value().contains_value(1);
+        assert_that!(ndjson_iter.next()).to_value().is_err();
+        assert_that!(matches!(ndjson_iter.next(), Some(Err(NdjsonError::LineTooLong {
+            offset: 14,
+            length: 4,
+            ..
+        })))).is_true();
+        assert_that!(ndjson_iter.next()).is_none();
+        assert_that!(ndjson_iter.offset()).is_equal_to(20);
+    }
+
+    #[test]
+    #[should_panic(expected = "SliceNdjsonIter does not support line preprocessors")]
+    fn line_preprocessor_is_rejected() {
+        let config = NdjsonConfig::default()
+            .with_line_preprocessor(LinePreprocessor::redacting_keys(["password"]));
+
+        from_slice_with_config::<TestStruct, _>("{\"password\":\"hunter2\"}\n", config);
+    }
+
+    #[test]
+    #[should_panic(expected = "SliceNdjsonIter does not support salvaging records")]
+    fn salvage_is_rejected() {
+        let config = NdjsonConfig::default().with_salvage(true);
+
+        from_slice_with_config::<TestStruct, _>("{\"key\":1,\"value\":2}\n", config);
+    }
+
+    #[test]
+    #[should_panic(expected = "SliceNdjsonIter does not support the JSON array mode")]
+    fn json_array_mode_is_rejected() {
+        let config = NdjsonConfig::default().with_json_array_mode(true);
+
+        from_slice_with_config::<TestStruct, _>("[{\"key\":1,\"value\":2}]", config);
+    }
+
+    #[test]
+    fn any_line_ending_treats_crlf_as_one() {
+        let config = NdjsonConfig::default().with_line_ending(LineEnding::Any);
+        let records = from_slice_with_config::<u32, _>("1\r\n2\r3\n", config)
+            .collect::<Vec<_>>();
+
+        assert_that!(records.len()).is_equal_to(3);
+        assert_that!(records.iter().all(Result::is_ok)).is_true();
+    }
+}
@@ -0,0 +1,241 @@
+use std::future::Future;
+use std::io;
+
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc::{self, Receiver};
+use tokio::task::{self, JoinHandle};
+
+use crate::config::NdjsonConfig;
+use crate::driver::stream;
+use crate::error::NdjsonResult;
+use crate::fallible::FallibleNdjsonError;
+use crate::source::AsyncReaderSource;
+
+/// The name of the tasks spawned by [spawn_parser]. See [spawn_named_parser] for details.
+pub const DEFAULT_PARSER_TASK_NAME: &str = "ndjson-stream parser";
+
+/// Spawns a Tokio task which reads NDJSON from the given `reader` and sends the parsed records,
+/// deserialized according to [Deserialize](serde::Deserialize), to the returned channel. The parser
+/// is configured with the given [NdjsonConfig]. The channel holds at most `capacity` records, so
+/// the task waits for the receiver if it falls behind. A `capacity` of 0 is treated as 1.
+///
+/// Parsing errors are sent through the channel just like records. An error of the reader ends the
+/// task and is returned by the [JoinHandle], which otherwise completes with `Ok(())` once the input
+/// is exhausted or the [Receiver] is dropped.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::config::NdjsonConfig;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// runtime.block_on(async {
+///     let reader: &[u8] = b"1\n2\nx\n";
+///     let (handle, mut receiver) =
+///         ndjson_stream::spawn_parser::<u32, _>(reader, NdjsonConfig::default(), 16);
+///
+///     assert_eq!(receiver.recv().await.unwrap().unwrap(), 1);
+///     assert_eq!(receiver.recv().await.unwrap().unwrap(), 2);
+///     assert!(receiver.recv().await.unwrap().is_err());
+///     assert!(receiver.recv().await.is_none());
+///     assert!(handle.await.unwrap().is_ok());
+/// });
+/// ```
+///
+/// The task is named [DEFAULT_PARSER_TASK_NAME], see [spawn_named_parser] for details.
+///
+/// # Panics
+///
+/// If called outside a Tokio runtime.
+pub fn spawn_parser<T, R>(reader: R, config: NdjsonConfig, capacity: usize)
+    -> (JoinHandle<io::Result<()>>, Receiver<NdjsonResult<T>>)
+where
+    T: DeserializeOwned + Send + 'static,
+    R: AsyncRead + Send + 'static
+{
+    spawn_named_parser(DEFAULT_PARSER_TASK_NAME, reader, config, capacity)
+}
+
+/// Spawns a Tokio task which parses NDJSON from the given `reader` just like [spawn_parser], but
+/// names the task with the given `name`, such that several parsers can be told apart in
+/// diagnostic tools.
+///
+/// Tokio only supports task names if compiled with `--cfg tokio_unstable` and its `tracing`
+/// feature, which is enabled by the `tracing` feature of this crate. Task names then show up in
+/// [tokio-console](https://github.com/tokio-rs/console). Otherwise, the name is ignored by Tokio.
+///
+/// With the `tracing` feature, the task is additionally instrumented with a span named
+/// `ndjson_parser`, which records the given `name`, and emits an event with the number of sent
+/// records and parse errors when it ends.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::config::NdjsonConfig;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// runtime.block_on(async {
+///     let reader: &[u8] = b"1\n2\n";
+///     let (handle, mut receiver) = ndjson_stream::spawn_named_parser::<u32, _>(
+///         "orders-import", reader, NdjsonConfig::default(), 16);
+///
+///     assert_eq!(receiver.recv().await.unwrap().unwrap(), 1);
+///     assert_eq!(receiver.recv().await.unwrap().unwrap(), 2);
+///     assert!(handle.await.unwrap().is_ok());
+/// });
+/// ```
+///
+/// # Panics
+///
+/// If called outside a Tokio runtime.
+pub fn spawn_named_parser<T, R>(name: &str, reader: R, config: NdjsonConfig, capacity: usize)
+    -> (JoinHandle<io::Result<()>>, Receiver<NdjsonResult<T>>)
+where
+    T: DeserializeOwned + Send + 'static,
+    R: AsyncRead + Send + 'static
+{
+    let (sender, receiver) = mpsc::channel(capacity.max(1));
+    let task = async move {
+        let source = AsyncReaderSource::new(reader);
+        let mut records = Box::pin(stream::from_async_chunk_source_with_config(source, config));
+        let mut record_count = 0u64;
+        let mut error_count = 0u64;
+
+        while let Some(result) = records.next().await {
+            let result = match result {
+                Ok(record) => {
+                    record_count += 1;
+                    Ok(record)
+                },
+                Err(FallibleNdjsonError::JsonError(error)) => {
+                    error_count += 1;
+                    Err(error)
+                },
+                Err(FallibleNdjsonError::InputError(error)) => return Err(error)
+            };
+
+            if sender.send(result).await.is_err() {
+                break;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(records = record_count, errors = error_count, "parser finished");
+
+        #[cfg(not(feature = "tracing"))]
+        let _ = (record_count, error_count);
+
+        Ok(())
+    };
+
+    #[cfg(feature = "tracing")]
+    let task = tracing::Instrument::instrument(task,
+        tracing::info_span!("ndjson_parser", name));
+
+    (spawn_task(name, task), receiver)
+}
+
+#[cfg(all(tokio_unstable, feature = "tracing"))]
+fn spawn_task<F>(name: &str, task: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static
+{
+    task::Builder::new()
+        .name(name)
+        .spawn(task)
+        .expect("failed to spawn parser task")
+}
+
+#[cfg(not(all(tokio_unstable, feature = "tracing")))]
+fn spawn_task<F>(_name: &str, task: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static
+{
+    task::spawn(task)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use tokio::runtime::Builder;
+
+    use crate::config::EmptyLineHandling;
+    use crate::error::NdjsonError;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[test]
+    fn records_and_parse_errors_are_sent_in_order() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+
+        let (results, outcome) = runtime.block_on(async {
+            let reader: &[u8] = b"{\"key\":1,\"value\":2}\n\nx\n{\"key\":3,\"value\":4}\n";
+            let (handle, mut receiver) = spawn_parser::<TestStruct, _>(reader, config, 1);
+            let mut results = Vec::new();
+
+            while let Some(result) = receiver.recv().await {
+                results.push(result);
+            }
+
+            (results, handle.await.unwrap())
+        });
+
+        assert_that!(results.len()).is_equal_to(3);
+        assert_that!(&results[0]).contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(matches!(results[1], Err(NdjsonError::Json(_)))).is_true();
+        assert_that!(&results[2]).contains_value(TestStruct { key: 3, value: 4 });
+        assert_that!(outcome).is_ok();
+    }
+
+    #[test]
+    fn reader_error_ends_task() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+
+        let (results, outcome) = runtime.block_on(async {
+            let reader = tokio_test::io::Builder::new()
+                .read(b"1\n2")
+                .read_error(io::Error::other("test"))
+                .build();
+            let (handle, mut receiver) =
+                spawn_parser::<u32, _>(reader, NdjsonConfig::default(), 4);
+            let mut results = Vec::new();
+
+            while let Some(result) = receiver.recv().await {
+                results.push(result.unwrap());
+            }
+
+            (results, handle.await.unwrap())
+        });
+
+        assert_that!(results).contains_exactly_in_given_order([1]);
+        assert_that!(outcome.map_err(|error| error.to_string())).contains_error("test".to_owned());
+    }
+
+    #[test]
+    fn dropping_receiver_ends_task() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+
+        let outcome = runtime.block_on(async {
+            let reader: &[u8] = b"1\n2\n3\n4\n";
+            let (handle, mut receiver) =
+                spawn_parser::<u32, _>(reader, NdjsonConfig::default(), 1);
+
+            assert_that!(receiver.recv().await.map(Result::unwrap)).contains(1);
+            drop(receiver);
+            handle.await.unwrap()
+        });
+
+        assert_that!(outcome).is_ok();
+    }
+}
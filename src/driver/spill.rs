@@ -0,0 +1,130 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::source::DEFAULT_CHUNK_SIZE;
+
+/// A counter to make the names of spill files created by the same process unique.
+static SPILL_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A temporary file to which raw NDJSON-data is appended and from which it is later read back in
+/// the same order. Space is reclaimed whenever all data has been read back. The file is deleted
+/// when this is dropped.
+pub(crate) struct SpillFile {
+    file: File,
+    path: PathBuf,
+    write_position: u64,
+    read_position: u64
+}
+
+impl SpillFile {
+
+    /// Creates a new, empty spill file in the temporary directory of the system.
+    pub(crate) fn create() -> io::Result<SpillFile> {
+        let name = format!("ndjson-stream-spill-{}-{}.ndjson", process::id(),
+            SPILL_FILE_COUNTER.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok(SpillFile {
+            file,
+            path,
+            write_position: 0,
+            read_position: 0
+        })
+    }
+
+    /// Gets the number of bytes which have been written, but not yet read back.
+    pub(crate) fn unread_len(&self) -> u64 {
+        self.write_position - self.read_position
+    }
+
+    /// Appends the given data to the end of this file.
+    pub(crate) fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(self.write_position))?;
+        self.file.write_all(data)?;
+        self.write_position += data.len() as u64;
+        Ok(())
+    }
+
+    /// Reads the next chunk of the unread data back.
+    ///
+    /// # Returns
+    ///
+    /// The chunk, or `None` if all data has been read back. If reading fails, all unread data is
+    /// discarded.
+    pub(crate) fn read_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.unread_len() == 0 {
+            return Ok(None);
+        }
+
+        let chunk_len = self.unread_len().min(DEFAULT_CHUNK_SIZE as u64) as usize;
+        let mut chunk = vec![0; chunk_len];
+        let result = self.file.seek(SeekFrom::Start(self.read_position))
+            .and_then(|_| self.file.read_exact(&mut chunk));
+
+        if let Err(error) = result {
+            self.clear()?;
+            return Err(error);
+        }
+
+        self.read_position += chunk_len as u64;
+
+        if self.unread_len() == 0 {
+            self.clear()?;
+        }
+
+        Ok(Some(chunk))
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.write_position = 0;
+        self.read_position = 0;
+        self.file.set_len(0)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn data_is_read_back_in_order_and_file_is_deleted() {
+        let mut spill_file = SpillFile::create().unwrap();
+        let path = spill_file.path.clone();
+        let data = (0..DEFAULT_CHUNK_SIZE + 10).map(|index| index as u8).collect::<Vec<_>>();
+
+        spill_file.write(&data[..5]).unwrap();
+        spill_file.write(&data[5..]).unwrap();
+
+        assert_that!(spill_file.unread_len()).is_equal_to(data.len() as u64);
+        assert_that!(spill_file.read_chunk().unwrap()).contains(data[..DEFAULT_CHUNK_SIZE].to_vec());
+
+        spill_file.write(b"abc").unwrap();
+
+        let expected_rest = [&data[DEFAULT_CHUNK_SIZE..], b"abc"].concat();
+
+        assert_that!(spill_file.read_chunk().unwrap()).contains(expected_rest);
+        assert_that!(spill_file.read_chunk().unwrap()).is_none();
+        assert_that!(std::fs::metadata(&path).unwrap().len()).is_equal_to(0);
+
+        drop(spill_file);
+
+        assert_that!(path.exists()).is_false();
+    }
+}
@@ -2,15 +2,26 @@ use std::convert::Infallible;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use futures::{ready, Stream};
+use futures::{ready, Stream, StreamExt};
 use pin_project_lite::pin_project;
 use serde::Deserialize;
-use serde_json::error::Result as JsonResult;
+
+#[cfg(feature = "http-body")]
+use http_body::Body;
 
 use crate::as_bytes::AsBytes;
 use crate::config::NdjsonConfig;
+use crate::driver::header::WithHeader;
+use crate::driver::line_number::WithLineNumbers;
 use crate::engine::NdjsonEngine;
+use crate::error::{NdjsonError, NdjsonResult, ValidationError};
 use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+use crate::multi::SchemaChain;
+use crate::source::{AsyncChunkSource, ChunkStream};
+use crate::splitter;
+
+#[cfg(feature = "http-body")]
+use crate::source::HttpBodySource;
 
 pin_project! {
     struct MapResultInfallible<S> {
@@ -25,6 +36,18 @@ impl<S> MapResultInfallible<S> {
             inner
         }
     }
+
+    fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    fn into_inner(self) -> S {
+        self.inner
+    }
 }
 
 impl<S> Stream for MapResultInfallible<S>
@@ -48,6 +71,13 @@ pin_project! {
     /// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
     /// implementation over parsed NDJSON-records according to [Deserialize]. See [from_stream] and
     /// [from_stream_with_config] for more details.
+    ///
+    /// # Cancel safety
+    ///
+    /// Any data block obtained from the wrapped stream is immediately passed to the internal
+    /// engine, which buffers partial lines. Therefore, dropping a future which polls this stream,
+    /// such as the one returned by [NdjsonStream::next_record], does not lose any data. The next
+    /// poll continues where the previous one left off.
     pub struct NdjsonStream<T, S> {
         #[pin]
         inner: FallibleNdjsonStream<T, MapResultInfallible<S>>
@@ -74,6 +104,131 @@ impl<T, S> NdjsonStream<T, S> {
             inner: FallibleNdjsonStream::with_config(inner_bytes_stream, config)
         }
     }
+
+    /// Makes this stream parse lines with the given `deserializer` instead of [serde_json]. See
+    /// [NdjsonEngine::with_deserializer] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new stream with all the same state as this one, except the deserializer.
+    pub fn with_deserializer<F, E>(self, deserializer: F) -> NdjsonStream<T, S>
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<NdjsonError>
+    {
+        NdjsonStream {
+            inner: self.inner.with_deserializer(deserializer)
+        }
+    }
+
+    /// Makes this stream check every parsed record with the given `validator`, reporting rejected
+    /// records as [NdjsonError::InvalidRecord]. See [NdjsonEngine::with_record_validator] for more
+    /// details.
+    ///
+    /// # Returns
+    ///
+    /// A new stream with all the same state as this one, except the record validator.
+    pub fn with_record_validator<F>(self, validator: F) -> NdjsonStream<T, S>
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static
+    {
+        NdjsonStream {
+            inner: self.inner.with_record_validator(validator)
+        }
+    }
+
+    /// Sets the number of consecutive records after which this stream yields to the executor, or
+    /// disables yielding if `None` is given. See
+    /// [FallibleNdjsonStream::with_yield_interval] for more details.
+    ///
+    /// # Returns
+    ///
+    /// This stream with the given yield interval.
+    pub fn with_yield_interval(self, yield_interval: Option<usize>) -> NdjsonStream<T, S> {
+        NdjsonStream {
+            inner: self.inner.with_yield_interval(yield_interval)
+        }
+    }
+
+    /// Makes this stream parse the first line which is neither skipped nor ignored as a header of
+    /// type `H` rather than as a record of type `T`. See [WithHeader] for more details.
+    pub fn with_header<H>(mut self) -> WithHeader<H, NdjsonStream<T, S>> {
+        self.inner.engine.expect_header();
+        WithHeader::new(self)
+    }
+
+    /// Makes this stream yield every item together with the one-based number of the line it was
+    /// parsed from, counting all lines of the input. See [WithLineNumbers] for more details.
+    pub fn with_line_numbers(self) -> WithLineNumbers<NdjsonStream<T, S>> {
+        WithLineNumbers::new(self)
+    }
+
+    pub(crate) fn engine_mut(self: Pin<&mut Self>) -> &mut NdjsonEngine<T> {
+        self.project().inner.engine_mut()
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this stream, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress) or [incomplete line](NdjsonEngine::rest).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        self.inner.engine()
+    }
+
+    /// Gets a reference to the wrapped stream, e.g. to inspect the progress of the underlying
+    /// source.
+    pub fn get_ref(&self) -> &S {
+        self.inner.get_ref().get_ref()
+    }
+
+    /// Gets a mutable reference to the wrapped stream. Note that data blocks taken from it directly
+    /// are not seen by this stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.inner.get_mut().get_mut()
+    }
+
+    /// Unwraps this stream, returning the wrapped stream, which continues after the last data block
+    /// given to the engine. Records which were parsed but not returned yet and the incomplete line
+    /// are dropped. Use [NdjsonStream::into_parts] to retain them.
+    pub fn into_inner(self) -> S {
+        self.into_parts().1
+    }
+
+    /// Finalizes the engine of this stream without polling the wrapped stream any further, as
+    /// [NdjsonEngine::finalize] does. This is useful when stopping consumption early. With
+    /// [NdjsonConfig::with_parse_rest], the incomplete line is parsed and returned by the next
+    /// poll, otherwise it is discarded.
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were made available by finalizing.
+    pub fn finalize(&mut self) -> usize
+    where
+        for<'deserialize> T: Deserialize<'deserialize>
+    {
+        self.inner.finalize()
+    }
+
+    /// Unwraps this stream, returning the incomplete line currently held by its engine without
+    /// parsing it. See [NdjsonEngine::rest] for more details.
+    pub fn into_rest(self) -> Vec<u8> {
+        self.inner.into_rest()
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine], which still holds the incomplete line and
+    /// all records which were not returned yet, together with the wrapped stream, which continues
+    /// after the last data block given to the engine. This allows handing off the input to another
+    /// consumer or resuming precisely where this stream stopped, e.g. instead of dropping it
+    /// mid-line.
+    pub fn into_parts(self) -> (NdjsonEngine<T>, S) {
+        let (engine, bytes_stream) = self.inner.into_parts();
+
+        (engine, bytes_stream.into_inner())
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine] with the incomplete line and all records
+    /// which were not returned yet. See [NdjsonStream::into_parts] for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.inner.into_engine()
+    }
 }
 
 impl<T, S> Stream for NdjsonStream<T, S>
@@ -82,9 +237,9 @@ where
     S: Stream,
     S::Item: AsBytes
 {
-    type Item = JsonResult<T>;
+    type Item = NdjsonResult<T>;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<JsonResult<T>>> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NdjsonResult<T>>> {
         let mut this = self.project();
         let inner_next = ready!(this.inner.as_mut().poll_next(cx));
         let next = inner_next
@@ -94,6 +249,180 @@ where
     }
 }
 
+impl<T, S> NdjsonStream<T, S>
+where
+    S: Stream
+{
+
+    /// Wraps this stream in a [TakeUntilRecordStream], which yields records up to and including
+    /// the first one satisfying the given `predicate`, the sentinel, and then ends without polling
+    /// the wrapped stream any further. Afterwards, [TakeUntilRecordStream::into_parts] returns the
+    /// wrapped stream together with the bytes following the line of the sentinel. See
+    /// [NdjsonIter::take_until_record](crate::driver::NdjsonIter::take_until_record) for more
+    /// details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::stream::{self, StreamExt};
+    ///
+    /// let data_blocks = vec!["\"hello\"\n\"ready\"\nrest", " of it"];
+    /// let mut handshake =
+    ///     ndjson_stream::from_stream::<String, _>(stream::iter(data_blocks))
+    ///         .take_until_record(|record| record == "ready");
+    ///
+    /// tokio_test::block_on(async {
+    ///     assert_eq!(handshake.by_ref().count().await, 2);
+    ///
+    ///     let (data_blocks, leftover) = handshake.into_parts();
+    ///
+    ///     assert_eq!(leftover, b"rest");
+    ///     assert_eq!(data_blocks.collect::<Vec<_>>().await, vec![" of it"]);
+    /// });
+    /// ```
+    pub fn take_until_record<P>(self, predicate: P) -> TakeUntilRecordStream<T, S, P>
+    where
+        P: FnMut(&T) -> bool
+    {
+        let FallibleNdjsonStream { engine, bytes_stream, .. } = self.inner;
+
+        TakeUntilRecordStream {
+            engine,
+            bytes_stream: bytes_stream.inner,
+            exhausted: false,
+            pending: None,
+            pending_start: 0,
+            finished: false,
+            predicate
+        }
+    }
+}
+
+pin_project! {
+    /// Wraps an [NdjsonStream] and yields its records up to and including the first one satisfying
+    /// a predicate, after which it ends without polling the wrapped stream any further. See
+    /// [NdjsonStream::take_until_record] for more details.
+    pub struct TakeUntilRecordStream<T, S, P>
+    where
+        S: Stream
+    {
+        engine: NdjsonEngine<T>,
+        #[pin]
+        bytes_stream: S,
+        exhausted: bool,
+        pending: Option<S::Item>,
+        pending_start: usize,
+        finished: bool,
+        predicate: P
+    }
+}
+
+impl<T, S, P> TakeUntilRecordStream<T, S, P>
+where
+    S: Stream,
+    S::Item: AsBytes
+{
+
+    /// Gets a reference to the [NdjsonEngine] of this stream.
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Unwraps this stream, returning the wrapped stream and the data which was read from it but
+    /// not given to the engine, preceded by the incomplete line held by the engine. Once the
+    /// sentinel was returned, this is exactly the data following the line ending of the sentinel.
+    pub fn into_parts(self) -> (S, Vec<u8>) {
+        let mut leftover = self.engine.rest().to_vec();
+
+        if let Some(pending) = &self.pending {
+            leftover.extend_from_slice(&pending.as_bytes()[self.pending_start..]);
+        }
+
+        (self.bytes_stream, leftover)
+    }
+}
+
+impl<T, S, P> Stream for TakeUntilRecordStream<T, S, P>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream,
+    S::Item: AsBytes,
+    P: FnMut(&T) -> bool
+{
+    type Item = NdjsonResult<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NdjsonResult<T>>> {
+        let mut this = self.project();
+
+        loop {
+            if *this.finished {
+                return Poll::Ready(None);
+            }
+
+            if let Some(result) = this.engine.pop() {
+                *this.finished = result.as_ref().is_ok_and(&mut *this.predicate);
+                return Poll::Ready(Some(result));
+            }
+
+            if this.engine.is_finished() {
+                *this.finished = true;
+                continue;
+            }
+
+            if let Some(pending) = this.pending.as_ref() {
+                let data = &pending.as_bytes()[*this.pending_start..];
+
+                if data.is_empty() {
+                    *this.pending = None;
+                }
+                else {
+                    let segment_len = splitter::first_segment_len(data);
+
+                    this.engine.input(&data[..segment_len]);
+                    *this.pending_start += segment_len;
+                }
+
+                continue;
+            }
+
+            if *this.exhausted {
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.bytes_stream.as_mut().poll_next(cx)) {
+                Some(data) => {
+                    *this.pending = Some(data);
+                    *this.pending_start = 0;
+                },
+                None => {
+                    *this.exhausted = true;
+                    this.engine.finalize();
+                }
+            }
+        }
+    }
+}
+
+impl<T, S> NdjsonStream<T, S>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream + Unpin,
+    S::Item: AsBytes
+{
+
+    /// Reads the next parsed NDJSON-record from this stream. This is equivalent to
+    /// [StreamExt::next], but documents the guarantees made by this stream.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before it completes, no
+    /// record is lost and partial lines which have been read from the wrapped stream remain
+    /// buffered. It is therefore safe to use this method as a branch of `tokio::select!`.
+    pub async fn next_record(&mut self) -> Option<NdjsonResult<T>> {
+        self.next().await
+    }
+}
+
 /// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
 /// implementation over parsed NDJSON-records according to [Deserialize]. The parser is configured
 /// with the default [NdjsonConfig].
@@ -151,18 +480,155 @@ pub fn from_stream_with_config<T, S>(bytes_stream: S, config: NdjsonConfig) -> N
     NdjsonStream::with_config(bytes_stream, config)
 }
 
+/// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
+/// implementation over parsed NDJSON-records, where each line is tried against the types of the
+/// [SchemaChain] `C` in order. The records are enums holding the value of the first type that
+/// matched, such as [OneOf2](crate::multi::OneOf2). The parser is configured with the default
+/// [NdjsonConfig]. See the [multi](crate::multi) module for an example using iterators.
+pub fn from_stream_multi<C, S>(bytes_stream: S) -> NdjsonStream<C::Output, S>
+where
+    C: SchemaChain
+{
+    NdjsonStream::new(bytes_stream)
+}
+
+pin_project! {
+    /// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and checks every line
+    /// against [Deserialize] for `T` without returning the records. See [validate_stream] and
+    /// [validate_stream_with_config] for more details.
+    pub struct ValidateNdjsonStream<T, S> {
+        engine: NdjsonEngine<T>,
+        #[pin]
+        bytes_stream: S
+    }
+}
+
+impl<T, S> ValidateNdjsonStream<T, S> {
+
+    /// Creates a new validating NDJSON-stream wrapping the given `bytes_stream` with the given
+    /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
+    pub fn with_config(bytes_stream: S, config: NdjsonConfig) -> ValidateNdjsonStream<T, S> {
+        ValidateNdjsonStream {
+            engine: NdjsonEngine::with_config(config),
+            bytes_stream
+        }
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine] and the wrapped stream. See
+    /// [FallibleNdjsonStream::into_parts] for more details.
+    pub fn into_parts(self) -> (NdjsonEngine<T>, S) {
+        (self.engine, self.bytes_stream)
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine]. See [FallibleNdjsonStream::into_parts]
+    /// for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.engine
+    }
+}
+
+impl<T, S> Stream for ValidateNdjsonStream<T, S>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream,
+    S::Item: AsBytes
+{
+    type Item = Result<(), ValidationError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(result) = this.engine.pop_validated() {
+                return Poll::Ready(Some(result));
+            }
+
+            if this.engine.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.bytes_stream.as_mut().poll_next(cx)) {
+                Some(bytes) => {
+                    this.engine.input(bytes);
+                },
+                None => {
+                    this.engine.finalize();
+                    return Poll::Ready(this.engine.pop_validated());
+                }
+            }
+        }
+    }
+}
+
+/// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and offers a [Stream]
+/// which yields, for every line which would produce a record, either `Ok(())` or a
+/// [ValidationError] holding the line number. The records themselves are dropped immediately.
+/// Choosing [IgnoredAny](serde::de::IgnoredAny) for `T` only checks that every line is well-formed
+/// JSON, without constructing any values. The parser is configured with the default
+/// [NdjsonConfig].
+///
+/// # Example
+///
+/// ```
+/// use futures::stream::{self, StreamExt};
+/// use serde::de::IgnoredAny;
+///
+/// let data_blocks = vec!["1\nx\n", "3\n"];
+/// let mut validate_stream =
+///     ndjson_stream::validate_stream::<IgnoredAny, _>(stream::iter(data_blocks));
+///
+/// tokio_test::block_on(async {
+///     assert!(matches!(validate_stream.next().await, Some(Ok(()))));
+///     assert!(matches!(validate_stream.next().await, Some(Err(error)) if error.line_number == 2));
+///     assert!(matches!(validate_stream.next().await, Some(Ok(()))));
+///     assert!(validate_stream.next().await.is_none());
+/// });
+/// ```
+pub fn validate_stream<T, S>(bytes_stream: S) -> ValidateNdjsonStream<T, S> {
+    ValidateNdjsonStream::with_config(bytes_stream, NdjsonConfig::default())
+}
+
+/// Wraps a [Stream] of data blocks, i.e. types implementing [AsBytes], and checks every line
+/// against [Deserialize] for `T` just like [validate_stream]. The parser is configured with the
+/// given [NdjsonConfig].
+pub fn validate_stream_with_config<T, S>(bytes_stream: S, config: NdjsonConfig)
+    -> ValidateNdjsonStream<T, S> {
+    ValidateNdjsonStream::with_config(bytes_stream, config)
+}
+
 pin_project! {
     /// Wraps a [Stream] of [Result]s of data blocks, i.e. types implementing [AsBytes], and offers
     /// a [Stream] mplementation over parsed NDJSON-records according to [Deserialize], forwarding
     /// potential errors returned by the wrapped iterator. See [from_fallible_stream] and
     /// [from_fallible_stream_with_config] for more details.
+    ///
+    /// # Cancel safety
+    ///
+    /// Any data block obtained from the wrapped stream is immediately passed to the internal
+    /// engine, which buffers partial lines. Therefore, dropping a future which polls this stream,
+    /// such as the one returned by [FallibleNdjsonStream::next_record], does not lose any data. The
+    /// next poll continues where the previous one left off.
+    ///
+    /// # Cooperative scheduling
+    ///
+    /// A single data block may contain thousands of records, all of which are available without
+    /// polling the wrapped stream. To avoid starving other tasks of an executor, this stream yields
+    /// after [DEFAULT_YIELD_INTERVAL] consecutive items without the wrapped stream being pending,
+    /// by waking its task and returning [Poll::Pending] once. This can be configured via
+    /// [FallibleNdjsonStream::with_yield_interval].
     pub struct FallibleNdjsonStream<T, S> {
         engine: NdjsonEngine<T>,
+        yield_interval: Option<usize>,
+        ready_streak: usize,
         #[pin]
         bytes_stream: S
     }
 }
 
+/// The default number of consecutive items after which a [FallibleNdjsonStream] or [NdjsonStream]
+/// yields to the executor, see [FallibleNdjsonStream::with_yield_interval].
+pub const DEFAULT_YIELD_INTERVAL: usize = 128;
+
 impl<T, S> FallibleNdjsonStream<T, S> {
 
     /// Creates a new fallible NDJSON-stream wrapping the given `bytes_stream` with default
@@ -170,6 +636,8 @@ impl<T, S> FallibleNdjsonStream<T, S> {
     pub fn new(bytes_stream: S) -> FallibleNdjsonStream<T, S> {
         FallibleNdjsonStream {
             engine: NdjsonEngine::new(),
+            yield_interval: Some(DEFAULT_YIELD_INTERVAL),
+            ready_streak: 0,
             bytes_stream
         }
     }
@@ -179,9 +647,313 @@ impl<T, S> FallibleNdjsonStream<T, S> {
     pub fn with_config(bytes_stream: S, config: NdjsonConfig) -> FallibleNdjsonStream<T, S> {
         FallibleNdjsonStream {
             engine: NdjsonEngine::with_config(config),
+            yield_interval: Some(DEFAULT_YIELD_INTERVAL),
+            ready_streak: 0,
             bytes_stream
         }
     }
+
+    /// Makes this stream parse lines with the given `deserializer` instead of [serde_json]. See
+    /// [NdjsonEngine::with_deserializer] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new stream with all the same state as this one, except the deserializer.
+    pub fn with_deserializer<F, E>(self, deserializer: F) -> FallibleNdjsonStream<T, S>
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<NdjsonError>
+    {
+        FallibleNdjsonStream {
+            engine: self.engine.with_deserializer(deserializer),
+            ..self
+        }
+    }
+
+    /// Makes this stream check every parsed record with the given `validator`, reporting rejected
+    /// records as [NdjsonError::InvalidRecord]. See [NdjsonEngine::with_record_validator] for more
+    /// details.
+    ///
+    /// # Returns
+    ///
+    /// A new stream with all the same state as this one, except the record validator.
+    pub fn with_record_validator<F>(self, validator: F) -> FallibleNdjsonStream<T, S>
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static
+    {
+        FallibleNdjsonStream {
+            engine: self.engine.with_record_validator(validator),
+            ..self
+        }
+    }
+
+    /// Sets the number of consecutive items after which this stream yields to the executor, even
+    /// though further records are available, or disables yielding if `None` is given. The default
+    /// is [DEFAULT_YIELD_INTERVAL]. See the [type documentation](FallibleNdjsonStream) for details.
+    ///
+    /// # Returns
+    ///
+    /// This stream with the given yield interval. An interval of 0 is treated as 1.
+    pub fn with_yield_interval(mut self, yield_interval: Option<usize>)
+            -> FallibleNdjsonStream<T, S> {
+        self.yield_interval = yield_interval.map(|yield_interval| yield_interval.max(1));
+        self
+    }
+
+    /// Makes this stream parse the first line which is neither skipped nor ignored as a header of
+    /// type `H` rather than as a record of type `T`. See [WithHeader] for more details.
+    pub fn with_header<H>(mut self) -> WithHeader<H, FallibleNdjsonStream<T, S>> {
+        self.engine.expect_header();
+        WithHeader::new(self)
+    }
+
+    /// Makes this stream yield every item together with the one-based number of the line it was
+    /// parsed from, counting all lines of the input. See [WithLineNumbers] for more details.
+    pub fn with_line_numbers(self) -> WithLineNumbers<FallibleNdjsonStream<T, S>> {
+        WithLineNumbers::new(self)
+    }
+
+    pub(crate) fn engine_mut(self: Pin<&mut Self>) -> &mut NdjsonEngine<T> {
+        self.project().engine
+    }
+
+    /// Gets a reference to the [NdjsonEngine] of this stream, e.g. to inspect its
+    /// [progress](NdjsonEngine::progress) or [incomplete line](NdjsonEngine::rest).
+    pub fn engine(&self) -> &NdjsonEngine<T> {
+        &self.engine
+    }
+
+    /// Gets a reference to the wrapped stream. See [NdjsonStream::get_ref] for more details.
+    pub fn get_ref(&self) -> &S {
+        &self.bytes_stream
+    }
+
+    /// Gets a mutable reference to the wrapped stream. See [NdjsonStream::get_mut] for more
+    /// details.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.bytes_stream
+    }
+
+    /// Unwraps this stream, returning the wrapped stream. See [NdjsonStream::into_inner] for more
+    /// details.
+    pub fn into_inner(self) -> S {
+        self.bytes_stream
+    }
+
+    /// Finalizes the engine of this stream without polling the wrapped stream any further, as
+    /// [NdjsonEngine::finalize] does. See [NdjsonStream::finalize] for more details.
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were made available by finalizing.
+    pub fn finalize(&mut self) -> usize
+    where
+        for<'deserialize> T: Deserialize<'deserialize>
+    {
+        self.engine.finalize()
+    }
+
+    /// Unwraps this stream, returning the incomplete line currently held by its engine without
+    /// parsing it. See [NdjsonEngine::rest] for more details.
+    pub fn into_rest(self) -> Vec<u8> {
+        self.engine.rest().to_vec()
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine], which still holds the incomplete line and
+    /// all records which were not returned yet, together with the wrapped stream, which continues
+    /// after the last data block given to the engine. This allows handing off the input to another
+    /// consumer or resuming precisely where this stream stopped, e.g. instead of dropping it
+    /// mid-line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::stream::{self, StreamExt};
+    ///
+    /// let data_block_results = vec![Ok::<_, ()>("1\n{\"a\""), Ok(":2}\n")];
+    /// let mut ndjson_stream = ndjson_stream::from_fallible_stream::<u32, _>(
+    ///     stream::iter(data_block_results));
+    ///
+    /// tokio_test::block_on(async {
+    ///     assert!(matches!(ndjson_stream.next().await, Some(Ok(1))));
+    ///
+    ///     let (engine, rest) = ndjson_stream.into_parts();
+    ///
+    ///     assert_eq!(engine.rest(), b"{\"a\"");
+    ///     assert_eq!(rest.collect::<Vec<_>>().await, vec![Ok(":2}\n")]);
+    /// });
+    /// ```
+    pub fn into_parts(self) -> (NdjsonEngine<T>, S) {
+        (self.engine, self.bytes_stream)
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine] with the incomplete line and all records
+    /// which were not returned yet. See [FallibleNdjsonStream::into_parts] for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.engine
+    }
+
+    /// Replaces the wrapped stream by the given `bytes_stream`, for example after the wrapped
+    /// stream returned an error due to a transient network failure. The state of the engine is
+    /// retained, including the incomplete line received so far, so the new stream must continue
+    /// exactly after the last data block of the previous one. The number of bytes received so far
+    /// is given by the `bytes_consumed` of the engine's [progress](NdjsonEngine::progress), which
+    /// can be used as the resume offset of a request. With the `tokio` feature, `with_retry`
+    /// automates this according to a retry policy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::pin::Pin;
+    ///
+    /// use futures::stream::{self, StreamExt};
+    /// use ndjson_stream::fallible::FallibleNdjsonError;
+    /// use serde_json::{json, Value};
+    ///
+    /// let connection = stream::iter(vec![Ok("{\"key\":"), Err("reset")]);
+    /// let mut ndjson_stream = ndjson_stream::from_fallible_stream::<Value, _>(connection);
+    ///
+    /// tokio_test::block_on(async {
+    ///     assert!(matches!(ndjson_stream.next().await,
+    ///         Some(Err(FallibleNdjsonError::InputError("reset")))));
+    ///     assert_eq!(ndjson_stream.engine().progress().bytes_consumed, 7);
+    ///
+    ///     let resumed_connection = stream::iter(vec![Ok("1}\n")]);
+    ///     Pin::new(&mut ndjson_stream).retry_input(resumed_connection);
+    ///
+    ///     assert_eq!(ndjson_stream.next().await.unwrap().unwrap(), json!({ "key": 1 }));
+    ///     assert!(ndjson_stream.next().await.is_none());
+    /// });
+    /// ```
+    pub fn retry_input(self: Pin<&mut Self>, bytes_stream: S) {
+        self.project().bytes_stream.set(bytes_stream);
+    }
+
+    /// Routes all lines which cannot be parsed to the given `sink` instead of returning them as
+    /// [FallibleNdjsonError::JsonError]s. The sink receives the offending line, without the line
+    /// ending, together with the error. The returned stream only yields valid records and input
+    /// errors. For lines exceeding the maximum line length, only their first bytes are given to the
+    /// sink.
+    pub fn with_dead_letter_sink<F>(mut self, sink: F) -> DeadLetterNdjsonStream<T, S, F>
+    where
+        F: FnMut(Vec<u8>, NdjsonError)
+    {
+        self.engine.retain_failed_lines();
+
+        DeadLetterNdjsonStream {
+            inner: self,
+            sink
+        }
+    }
+
+    /// Converts all errors of the wrapped stream with the given function `f`, without affecting
+    /// the state of parsing. This allows unifying the input error type with other errors of the
+    /// caller inline, such that they can be propagated with `?`.
+    ///
+    /// # Returns
+    ///
+    /// A new stream with all the same state as this one, whose input errors are converted.
+    pub fn map_input_error<B, E, E2, F>(self, f: F)
+        -> FallibleNdjsonStream<T, MapInputErrorStream<S, F>>
+    where
+        S: Stream<Item = Result<B, E>>,
+        F: FnMut(E) -> E2
+    {
+        FallibleNdjsonStream {
+            engine: self.engine,
+            yield_interval: self.yield_interval,
+            ready_streak: self.ready_streak,
+            bytes_stream: MapInputErrorStream {
+                inner: self.bytes_stream,
+                f
+            }
+        }
+    }
+
+    /// Converts all errors of the wrapped stream into `E2` via [From], just like
+    /// [FallibleNdjsonStream::map_input_error].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io;
+    ///
+    /// use futures::stream::{self, StreamExt};
+    /// use ndjson_stream::fallible::FallibleNdjsonError;
+    ///
+    /// #[derive(Debug)]
+    /// enum AppError {
+    ///     Io(io::Error)
+    /// }
+    ///
+    /// impl From<io::Error> for AppError {
+    ///     fn from(error: io::Error) -> AppError {
+    ///         AppError::Io(error)
+    ///     }
+    /// }
+    ///
+    /// let data_block_results = vec![Ok("1\n"), Err(io::Error::other("connection reset"))];
+    /// let mut ndjson_stream =
+    ///     ndjson_stream::from_fallible_stream::<u32, _>(stream::iter(data_block_results))
+    ///         .input_error_into::<AppError, _, _>();
+    ///
+    /// tokio_test::block_on(async {
+    ///     assert!(matches!(ndjson_stream.next().await, Some(Ok(1))));
+    ///     assert!(matches!(ndjson_stream.next().await,
+    ///         Some(Err(FallibleNdjsonError::InputError(AppError::Io(_))))));
+    /// });
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new stream with all the same state as this one, whose input errors are converted.
+    pub fn input_error_into<E2, B, E>(self)
+        -> FallibleNdjsonStream<T, MapInputErrorStream<S, FromFn<E, E2>>>
+    where
+        S: Stream<Item = Result<B, E>>,
+        E2: From<E>
+    {
+        self.map_input_error(E2::from as FromFn<E, E2>)
+    }
+}
+
+/// The function converting input errors via [From] used by `input_error_into`.
+type FromFn<E, E2> = fn(E) -> E2;
+
+pin_project! {
+    /// Wraps a [Stream] of [Result]s and converts all errors with a function. This is the stream
+    /// wrapped by a [FallibleNdjsonStream] after [FallibleNdjsonStream::map_input_error].
+    pub struct MapInputErrorStream<S, F> {
+        #[pin]
+        inner: S,
+        f: F
+    }
+}
+
+impl<S, F> MapInputErrorStream<S, F> {
+
+    /// Unwraps this stream, returning the wrapped stream whose errors are not converted.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, F, B, E, E2> Stream for MapInputErrorStream<S, F>
+where
+    S: Stream<Item = Result<B, E>>,
+    F: FnMut(E) -> E2
+{
+    type Item = Result<B, E2>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let res = ready!(this.inner.poll_next(cx));
+        Poll::Ready(res.map(|res| res.map_err(this.f)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
 }
 
 impl<T, S, B, E> Stream for FallibleNdjsonStream<T, S>
@@ -195,6 +967,14 @@ where
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
 
+        if this.yield_interval.is_some_and(|yield_interval| *this.ready_streak >= yield_interval) {
+            *this.ready_streak = 0;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        *this.ready_streak += 1;
+
         loop {
             if let Some(result) = this.engine.pop() {
                 return match result {
@@ -203,10 +983,22 @@ where
                 }
             }
 
-            let bytes = ready!(this.bytes_stream.as_mut().poll_next(cx));
+            if this.engine.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            let bytes = match this.bytes_stream.as_mut().poll_next(cx) {
+                Poll::Ready(bytes) => bytes,
+                Poll::Pending => {
+                    *this.ready_streak = 0;
+                    return Poll::Pending;
+                }
+            };
 
             match bytes {
-                Some(Ok(bytes)) => this.engine.input(bytes),
+                Some(Ok(bytes)) => {
+                    this.engine.input(bytes);
+                }
                 Some(Err(error)) =>
                     return Poll::Ready(Some(Err(FallibleNdjsonError::InputError(error)))),
                 None => {
@@ -219,6 +1011,100 @@ where
     }
 }
 
+impl<T, S, B, E> FallibleNdjsonStream<T, S>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsBytes
+{
+
+    /// Reads the next parsed NDJSON-record or input error from this stream. This is equivalent to
+    /// [StreamExt::next], but documents the guarantees made by this stream.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before it completes, no
+    /// record or error is lost and partial lines which have been read from the wrapped stream
+    /// remain buffered. It is therefore safe to use this method as a branch of `tokio::select!`.
+    pub async fn next_record(&mut self) -> Option<FallibleNdjsonResult<T, E>> {
+        self.next().await
+    }
+}
+
+pin_project! {
+    /// Wraps a [FallibleNdjsonStream] and routes all lines which cannot be parsed to a sink, so
+    /// only valid records and input errors are yielded. See
+    /// [FallibleNdjsonStream::with_dead_letter_sink] for more details.
+    pub struct DeadLetterNdjsonStream<T, S, F> {
+        #[pin]
+        inner: FallibleNdjsonStream<T, S>,
+        sink: F
+    }
+}
+
+impl<T, S, F> DeadLetterNdjsonStream<T, S, F> {
+
+    /// Unwraps this stream, returning its [NdjsonEngine] and the wrapped stream, while the sink is
+    /// dropped. See [FallibleNdjsonStream::into_parts] for more details.
+    pub fn into_parts(self) -> (NdjsonEngine<T>, S) {
+        self.inner.into_parts()
+    }
+
+    /// Unwraps this stream, returning its [NdjsonEngine], while the sink is dropped. See
+    /// [FallibleNdjsonStream::into_parts] for more details.
+    pub fn into_engine(self) -> NdjsonEngine<T> {
+        self.inner.into_engine()
+    }
+}
+
+impl<T, S, B, E, F> Stream for DeadLetterNdjsonStream<T, S, F>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    S: Stream<Item = Result<B, E>>,
+    B: AsBytes,
+    F: FnMut(Vec<u8>, NdjsonError)
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let sink = this.sink;
+        let mut inner = this.inner.project();
+
+        loop {
+            while let Some(result) = inner.engine.pop_with_failed_line() {
+                match result {
+                    Ok(record) => return Poll::Ready(Some(Ok(record))),
+                    Err((error, line)) => sink(line, error)
+                }
+            }
+
+            if inner.engine.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            match ready!(inner.bytes_stream.as_mut().poll_next(cx)) {
+                Some(Ok(bytes)) => {
+                    inner.engine.input(bytes);
+                }
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => {
+                    inner.engine.finalize();
+
+                    while let Some(result) = inner.engine.pop_with_failed_line() {
+                        match result {
+                            Ok(record) => return Poll::Ready(Some(Ok(record))),
+                            Err((error, line)) => sink(line, error)
+                        }
+                    }
+
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
 /// Wraps a [Stream] of [Result]s of data blocks, i.e. types implementing [AsBytes], and offers a
 /// [Stream] implementation over parsed NDJSON-records according to [Deserialize]. Errors in the
 /// wrapped iterator are forwarded via [FallibleNdjsonError::InputError] , while parsing errors are
@@ -249,6 +1135,26 @@ where
 ///     assert!(ndjson_stream.next().await.is_none());
 /// });
 /// ```
+///
+/// To parse an `http_body::Body`, such as the body of a hyper response, use [from_http_body]
+/// with the `http-body` feature.
+///
+/// # WebAssembly
+///
+/// In the browser, the body of a `fetch` response is a `web_sys::ReadableStream`, which the
+/// `wasm-streams` crate converts into a stream of `Result<JsValue, JsValue>`. Each chunk is a
+/// `Uint8Array`, which can be copied into a `Vec<u8>` to obtain a stream this function accepts.
+///
+/// ```ignore
+/// use futures::StreamExt;
+/// use js_sys::Uint8Array;
+/// use wasm_streams::ReadableStream;
+///
+/// let chunks = ReadableStream::from_raw(response.body().unwrap())
+///     .into_stream()
+///     .map(|chunk| chunk.map(|chunk| Uint8Array::new(&chunk).to_vec()));
+/// let records = ndjson_stream::from_fallible_stream::<Record, _>(chunks);
+/// ```
 pub fn from_fallible_stream<T, S>(bytes_stream: S) -> FallibleNdjsonStream<T, S> {
     FallibleNdjsonStream::new(bytes_stream)
 }
@@ -291,23 +1197,112 @@ pub fn from_fallible_stream_with_config<T, S>(bytes_stream: S, config: NdjsonCon
     FallibleNdjsonStream::with_config(bytes_stream, config)
 }
 
+/// Wraps an [AsyncChunkSource] and offers a [Stream] implementation over parsed NDJSON-records
+/// according to [Deserialize]. Errors of the source are forwarded via
+/// [FallibleNdjsonError::InputError], while parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError]. The parser is configured with the default [NdjsonConfig].
+pub fn from_async_chunk_source<T, S>(source: S) -> FallibleNdjsonStream<T, ChunkStream<S>>
+where
+    S: AsyncChunkSource
+{
+    FallibleNdjsonStream::new(source.into_chunk_stream())
+}
+
+/// Wraps an [AsyncChunkSource] and offers a [Stream] implementation over parsed NDJSON-records
+/// according to [Deserialize]. Errors of the source are forwarded via
+/// [FallibleNdjsonError::InputError], while parsing errors are indicated via
+/// [FallibleNdjsonError::JsonError]. The parser is configured with the given [NdjsonConfig].
+pub fn from_async_chunk_source_with_config<T, S>(source: S, config: NdjsonConfig)
+    -> FallibleNdjsonStream<T, ChunkStream<S>>
+where
+    S: AsyncChunkSource
+{
+    FallibleNdjsonStream::with_config(source.into_chunk_stream(), config)
+}
+
+/// Wraps an `http_body::Body`, such as the body of a hyper request or response, and offers a
+/// [Stream] implementation over parsed NDJSON-records according to [Deserialize]. Errors of the
+/// body are forwarded via [FallibleNdjsonError::InputError], while parsing errors are indicated
+/// via [FallibleNdjsonError::JsonError]. Trailers are skipped. Since many servers do not
+/// terminate the last record of a body, the parser is configured with the default [NdjsonConfig],
+/// except that the rest after the last line ending is parsed once the body ends, see
+/// [NdjsonConfig::with_parse_rest].
+///
+/// # Example
+///
+/// ```
+/// use std::convert::Infallible;
+/// use std::pin::Pin;
+/// use std::task::{Context, Poll};
+///
+/// use bytes::Bytes;
+/// use futures::stream::StreamExt;
+/// use http_body::{Body, Frame};
+///
+/// // stand-in for the body of a hyper response
+/// struct FullBody(Option<Bytes>);
+///
+/// impl Body for FullBody {
+///     type Data = Bytes;
+///     type Error = Infallible;
+///
+///     fn poll_frame(mut self: Pin<&mut Self>, _: &mut Context<'_>)
+///             -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+///         Poll::Ready(self.0.take().map(|data| Ok(Frame::data(data))))
+///     }
+/// }
+///
+/// let body = FullBody(Some(Bytes::from_static(b"1\n2")));
+/// let records = ndjson_stream::from_http_body::<u32, _>(body)
+///     .map(Result::unwrap)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(tokio_test::block_on(records), vec![1, 2]);
+/// ```
+#[cfg(feature = "http-body")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "http-body")))]
+pub fn from_http_body<T, B>(body: B)
+    -> FallibleNdjsonStream<T, ChunkStream<HttpBodySource<B>>>
+where
+    B: Body,
+    B::Data: AsBytes
+{
+    from_http_body_with_config(body, NdjsonConfig::default().with_parse_rest(true))
+}
+
+/// Wraps an `http_body::Body`, such as the body of a hyper request or response, and offers a
+/// [Stream] implementation over parsed NDJSON-records according to [Deserialize] just like
+/// [from_http_body]. The parser is configured with the given [NdjsonConfig], so the rest after the
+/// last line ending is only parsed if [NdjsonConfig::with_parse_rest] is set.
+#[cfg(feature = "http-body")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "http-body")))]
+pub fn from_http_body_with_config<T, B>(body: B, config: NdjsonConfig)
+    -> FallibleNdjsonStream<T, ChunkStream<HttpBodySource<B>>>
+where
+    B: Body,
+    B::Data: AsBytes
+{
+    from_async_chunk_source_with_config(HttpBodySource::new(body), config)
+}
+
 #[cfg(test)]
 mod tests {
     use std::pin::pin;
 
     use futures::{Stream, StreamExt};
+    use futures::channel::mpsc;
     use futures::stream;
     use kernal::prelude::*;
     use tokio_test::assert_pending;
     use tokio_test::task;
 
     use crate::as_bytes::AsBytes;
-    use crate::config::EmptyLineHandling;
+    use crate::config::{EmptyLineHandling, TrailingDataHandling};
     use crate::test_util::{FallibleNdjsonResultAssertions, SingleThenPanicIter, TestStruct};
 
     use super::*;
 
-    async fn collect<S>(bytes_stream: S) -> Vec<JsonResult<TestStruct>>
+    async fn collect<S>(bytes_stream: S) -> Vec<NdjsonResult<TestStruct>>
     where
         S: Stream,
         S::Item: AsBytes
@@ -334,6 +1329,47 @@ mod tests {
         assert_pending!(next.poll());
     }
 
+    fn count_yields<S>(ndjson_stream: S) -> (usize, usize)
+    where
+        S: Stream
+    {
+        let mut ndjson_stream = task::spawn(ndjson_stream);
+        let mut items = 0;
+        let mut yields = 0;
+
+        loop {
+            match ndjson_stream.poll_next() {
+                Poll::Ready(Some(_)) => items += 1,
+                Poll::Ready(None) => return (items, yields),
+                Poll::Pending => {
+                    assert_that!(ndjson_stream.is_woken()).is_true();
+                    yields += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stream_yields_periodically_if_many_records_are_available() {
+        let data = "1\n".repeat(DEFAULT_YIELD_INTERVAL * 2 + 1);
+        let ndjson_stream = from_stream::<u32, _>(stream::iter([data.clone()]));
+        let limited_stream = from_stream::<u32, _>(stream::iter([data]))
+            .with_yield_interval(Some(50));
+
+        assert_that!(count_yields(ndjson_stream))
+            .is_equal_to((DEFAULT_YIELD_INTERVAL * 2 + 1, 2));
+        assert_that!(count_yields(limited_stream)).is_equal_to((DEFAULT_YIELD_INTERVAL * 2 + 1, 5));
+    }
+
+    #[test]
+    fn stream_without_yield_interval_never_yields() {
+        let data = "1\n".repeat(DEFAULT_YIELD_INTERVAL * 2);
+        let ndjson_stream = from_fallible_stream::<u32, _>(stream::iter([Ok::<_, ()>(data)]))
+            .with_yield_interval(None);
+
+        assert_that!(count_yields(ndjson_stream)).is_equal_to((DEFAULT_YIELD_INTERVAL * 2, 0));
+    }
+
     #[test]
     fn empty_stream_results_in_empty_results() {
         let collected = tokio_test::block_on(collect::<_>(stream::empty::<&[u8]>()));
@@ -464,4 +1500,196 @@ mod tests {
                 |it| assert_that!(it).contains_value(TestStruct { key: 55, value: 66 })
             ));
     }
+
+    #[test]
+    fn dropping_next_record_future_does_not_lose_partial_line() {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut ndjson_stream = from_stream::<TestStruct, _>(receiver);
+
+        sender.unbounded_send("{\"key\":1,").unwrap();
+
+        {
+            let mut next = task::spawn(ndjson_stream.next_record());
+            assert_pending!(next.poll());
+        }
+
+        sender.unbounded_send("\"value\":2}\n").unwrap();
+
+        assert_that!(tokio_test::block_on(ndjson_stream.next_record()))
+            .to_value()
+            .contains_value(TestStruct { key: 1, value: 2 });
+    }
+
+    #[test]
+    fn dropping_fallible_next_record_future_does_not_lose_partial_line() {
+        let (sender, receiver) = mpsc::unbounded::<Result<&str, &str>>();
+        let mut fallible_ndjson_stream = from_fallible_stream::<TestStruct, _>(receiver);
+
+        sender.unbounded_send(Ok("{\"key\":3,")).unwrap();
+
+        {
+            let mut next = task::spawn(fallible_ndjson_stream.next_record());
+            assert_pending!(next.poll());
+        }
+
+        sender.unbounded_send(Ok("\"value\":4}\n")).unwrap();
+
+        assert_that!(tokio_test::block_on(fallible_ndjson_stream.next_record()))
+            .to_value()
+            .contains_value(TestStruct { key: 3, value: 4 });
+    }
+
+    #[test]
+    fn dead_letter_sink_receives_invalid_lines_and_errors() {
+        let data_block_results = vec![
+            Ok("{\"key\":1,\"value\":2}\ninvalid\n{\"key\":3,"),
+            Err("test message"),
+            Ok("\"value\":4}\n{\"key\":5}")
+        ];
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let mut dead_letters = Vec::new();
+        let results = tokio_test::block_on(
+            from_fallible_stream_with_config::<TestStruct, _>(stream::iter(data_block_results),
+                    config)
+                .with_dead_letter_sink(|line, _| dead_letters.push(line))
+                .collect::<Vec<_>>());
+
+        assert_that!(results).contains_exactly_in_given_order([
+            Ok(TestStruct { key: 1, value: 2 }),
+            Err("test message"),
+            Ok(TestStruct { key: 3, value: 4 })
+        ]);
+        assert_that!(dead_letters)
+            .contains_exactly_in_given_order([b"invalid".to_vec(), b"{\"key\":5}".to_vec()]);
+    }
+
+    #[test]
+    fn max_records_ends_stream_without_polling_further_input() {
+        let config = NdjsonConfig::default().with_max_records(Some(2));
+        let data_stream = stream::iter(["{\"key\":1,\"value\":2}\n{\"key\":3,", "\"value\":4}\n"])
+            .chain(stream::pending());
+        let mut ndjson_stream = from_stream_with_config::<TestStruct, _>(data_stream, config);
+
+        assert_that!(ndjson_stream.next_blocking()).to_value()
+            .contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(ndjson_stream.next_blocking()).to_value()
+            .contains_value(TestStruct { key: 3, value: 4 });
+        assert_that!(ndjson_stream.next_blocking()).is_none();
+    }
+
+    #[test]
+    fn async_chunk_source_records_are_parsed() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let source = stream::iter([Ok::<_, &str>("{\"key\":1,"), Ok("\"value\":2}")]);
+        let values = tokio_test::block_on(
+            from_async_chunk_source_with_config::<TestStruct, _>(source, config)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>());
+
+        assert_that!(values).contains_exactly_in_given_order([TestStruct { key: 1, value: 2 }]);
+    }
+
+    #[test]
+    fn validate_stream_reports_line_numbers_of_invalid_lines() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+        let data_blocks = [
+            "{\"key\":1,\"value\":2}\n  \n{\"key\":1",
+            "}\n{\"key\":3,\"value\":4}\n"
+        ];
+        let results = tokio_test::block_on(
+            validate_stream_with_config::<TestStruct, _>(stream::iter(data_blocks), config)
+                .map(|result| result.map_err(|error| error.line_number))
+                .collect::<Vec<_>>());
+
+        assert_that!(results).contains_exactly_in_given_order([Ok(()), Err(3), Ok(())]);
+    }
+
+    #[test]
+    fn validate_stream_reports_line_numbers_of_lines_with_multiple_values() {
+        let config = NdjsonConfig::default()
+            .with_trailing_data_handling(TrailingDataHandling::MultipleValues);
+        let results = tokio_test::block_on(
+            validate_stream_with_config::<u32, _>(stream::iter(["1 2\n", "x\n"]), config)
+                .map(|result| result.map_err(|error| error.line_number))
+                .collect::<Vec<_>>());
+
+        assert_that!(results).contains_exactly_in_given_order([Ok(()), Ok(()), Err(2)]);
+    }
+
+    #[test]
+    fn map_input_error_converts_errors_of_stream() {
+        let data_block_results = vec![Ok("{\"key\":1,"), Err(1), Ok("\"value\":2}\n")];
+        let ndjson_stream = from_fallible_stream::<TestStruct, _>(stream::iter(data_block_results))
+            .map_input_error(|error| format!("error {error}"));
+        let results = tokio_test::block_on(ndjson_stream.collect::<Vec<_>>());
+
+        assert_that!(results).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_input_error("error 1".to_owned()),
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+        ));
+    }
+
+    #[test]
+    fn finalize_parses_rest_of_stream_after_stopping_early() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let data_blocks = ["{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}"];
+        let mut ndjson_stream =
+            from_stream_with_config::<TestStruct, _>(stream::iter(data_blocks), config);
+
+        tokio_test::block_on(async {
+            assert_that!(ndjson_stream.next().await)
+                .to_value()
+                .contains_value(TestStruct { key: 1, value: 2 });
+            assert_that!(ndjson_stream.finalize()).is_equal_to(1);
+            assert_that!(ndjson_stream.next().await)
+                .to_value()
+                .contains_value(TestStruct { key: 3, value: 4 });
+        });
+    }
+
+    #[test]
+    fn into_rest_returns_incomplete_line_of_stream() {
+        let data_block_results = vec![Ok::<_, ()>("1\n2")];
+        let mut ndjson_stream =
+            from_fallible_stream::<u32, _>(stream::iter(data_block_results));
+
+        tokio_test::block_on(async {
+            assert_that!(ndjson_stream.next().await).to_value().contains_value(1);
+        });
+
+        assert_that!(ndjson_stream.into_rest()).contains_exactly_in_given_order(b"2".iter());
+    }
+
+    #[test]
+    fn wrapped_stream_is_accessible_and_recoverable() {
+        let mut ndjson_stream = from_stream::<u32, _>(stream::iter(vec!["1\n", "2\n", "3\n"]));
+
+        tokio_test::block_on(async {
+            assert_that!(ndjson_stream.next().await).to_value().contains_value(1);
+            assert_that!(ndjson_stream.get_ref().size_hint()).is_equal_to((2, Some(2)));
+            assert_that!(ndjson_stream.get_mut().next().await).contains("2\n");
+            assert_that!(ndjson_stream.into_inner().collect::<Vec<_>>().await)
+                .contains_exactly_in_given_order(["3\n"]);
+        });
+    }
+
+    #[test]
+    fn take_until_record_does_not_poll_stream_after_sentinel() {
+        let data_blocks = SingleThenPanicIter {
+            data: Some("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\nabc".to_owned())
+        };
+        let mut ndjson_stream = from_stream::<TestStruct, _>(stream::iter(data_blocks))
+            .take_until_record(|record| record.key == 1);
+
+        tokio_test::block_on(async {
+            assert_that!(ndjson_stream.next().await)
+                .to_value()
+                .contains_value(TestStruct { key: 1, value: 2 });
+            assert_that!(ndjson_stream.next().await).is_none();
+        });
+
+        assert_that!(ndjson_stream.into_parts().1)
+            .contains_exactly_in_given_order(b"{\"key\":3,\"value\":4}\nabc".iter());
+    }
 }
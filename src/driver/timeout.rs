@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{ready, Stream};
+use pin_project_lite::pin_project;
+use tokio::time::{self, Instant, Sleep};
+
+use crate::driver::stream::{FallibleNdjsonStream, NdjsonStream};
+
+/// The error item yielded by an [IdleTimeoutStream] if no record arrived within the configured
+/// idle timeout.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct IdleTimeoutError {
+
+    /// The idle timeout which elapsed.
+    pub timeout: Duration
+}
+
+impl Display for IdleTimeoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "no record received within {:?}", self.timeout)
+    }
+}
+
+impl Error for IdleTimeoutError { }
+
+pin_project! {
+    /// Wraps an NDJSON-stream and yields an [IdleTimeoutError] whenever no complete record arrives
+    /// within a configured time window. Data blocks which do not complete a record do not reset the
+    /// timer. After a timeout has been reported, the timer restarts and the wrapped stream can
+    /// continue to be polled, so the consumer can decide whether to give up or keep waiting. See
+    /// [NdjsonStream::with_idle_timeout] and [FallibleNdjsonStream::with_idle_timeout].
+    pub struct IdleTimeoutStream<S> {
+        #[pin]
+        inner: S,
+        sleep: Pin<Box<Sleep>>,
+        timeout: Duration
+    }
+}
+
+impl<S> IdleTimeoutStream<S> {
+
+    /// Creates a new idle-timeout-stream wrapping the given stream. The time window for the first
+    /// record starts immediately.
+    ///
+    /// # Panics
+    ///
+    /// If called outside a Tokio runtime with the time driver enabled.
+    pub fn new(inner: S, timeout: Duration) -> IdleTimeoutStream<S> {
+        IdleTimeoutStream {
+            inner,
+            sleep: Box::pin(time::sleep(timeout)),
+            timeout
+        }
+    }
+}
+
+impl<S> Stream for IdleTimeoutStream<S>
+where
+    S: Stream
+{
+    type Item = Result<S::Item, IdleTimeoutError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if let Poll::Ready(item) = this.inner.poll_next(cx) {
+            this.sleep.as_mut().reset(Instant::now() + *this.timeout);
+            return Poll::Ready(item.map(Ok));
+        }
+
+        ready!(this.sleep.as_mut().poll(cx));
+        this.sleep.as_mut().reset(Instant::now() + *this.timeout);
+
+        Poll::Ready(Some(Err(IdleTimeoutError {
+            timeout: *this.timeout
+        })))
+    }
+}
+
+impl<T, S> NdjsonStream<T, S> {
+
+    /// Wraps this stream in an [IdleTimeoutStream], which yields an [IdleTimeoutError] if no
+    /// complete record arrives within the given `timeout`. This is useful for detecting dead
+    /// connections of long-poll NDJSON APIs.
+    ///
+    /// # Panics
+    ///
+    /// If called outside a Tokio runtime with the time driver enabled.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn with_idle_timeout(self, timeout: Duration) -> IdleTimeoutStream<NdjsonStream<T, S>> {
+        IdleTimeoutStream::new(self, timeout)
+    }
+}
+
+impl<T, S> FallibleNdjsonStream<T, S> {
+
+    /// Wraps this stream in an [IdleTimeoutStream], which yields an [IdleTimeoutError] if neither a
+    /// complete record nor an input error arrives within the given `timeout`. This is useful for
+    /// detecting dead connections of long-poll NDJSON APIs.
+    ///
+    /// # Panics
+    ///
+    /// If called outside a Tokio runtime with the time driver enabled.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub fn with_idle_timeout(self, timeout: Duration)
+            -> IdleTimeoutStream<FallibleNdjsonStream<T, S>> {
+        IdleTimeoutStream::new(self, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+
+    use kernal::prelude::*;
+
+    use tokio::runtime::{Builder, Runtime};
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().enable_time().start_paused(true).build().unwrap()
+    }
+
+    #[test]
+    fn records_arriving_in_time_are_forwarded() {
+        runtime().block_on(async {
+            let data = futures::stream::iter(vec!["{\"key\":1,\"value\":2}\n"]);
+            let mut stream = crate::from_stream::<TestStruct, _>(data).with_idle_timeout(TIMEOUT);
+
+            assert_that!(stream.next().await).to_value().to_value()
+                .contains_value(TestStruct { key: 1, value: 2 });
+            assert_that!(stream.next().await).is_none();
+        });
+    }
+
+    #[test]
+    fn timeout_is_reported_if_no_complete_record_arrives() {
+        runtime().block_on(async {
+            let (sender, receiver) = mpsc::unbounded();
+            let mut stream = crate::from_stream::<TestStruct, _>(receiver)
+                .with_idle_timeout(TIMEOUT);
+
+            sender.unbounded_send("{\"key\":1,").unwrap();
+            let start = Instant::now();
+
+            assert_that!(stream.next().await).to_value().contains_error(IdleTimeoutError {
+                timeout: TIMEOUT
+            });
+            assert_that!(start.elapsed()).is_greater_than_or_equal_to(TIMEOUT);
+        });
+    }
+
+    #[test]
+    fn stream_continues_after_timeout() {
+        runtime().block_on(async {
+            let (sender, receiver) = mpsc::unbounded::<Result<&str, &str>>();
+            let mut stream = crate::from_fallible_stream::<TestStruct, _>(receiver)
+                .with_idle_timeout(TIMEOUT);
+
+            sender.unbounded_send(Ok("{\"key\":3,")).unwrap();
+
+            assert_that!(stream.next().await).to_value().is_err();
+
+            sender.unbounded_send(Ok("\"value\":4}\n")).unwrap();
+
+            assert_that!(stream.next().await).to_value().to_value().to_value()
+                .is_equal_to(TestStruct { key: 3, value: 4 });
+        });
+    }
+}
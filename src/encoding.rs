@@ -0,0 +1,472 @@
+//! This module offers the [TranscodingSource], which converts the data blocks of a [ChunkSource]
+//! from UTF-16 to UTF-8 before they are split into lines. Some exporters, particularly on Windows,
+//! write NDJSON as UTF-16, which would otherwise produce a parse error for every line.
+//!
+//! The encoding is detected from the first bytes of the input, see [TextEncoding::detect]. Data
+//! which cannot be decoded is reported as a [TranscodeError::Encoding], while errors of the wrapped
+//! source are forwarded as [TranscodeError::Input].
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::encoding::TranscodingSource;
+//!
+//! let utf16 = "\u{feff}1\n\"ä\"\n".encode_utf16()
+//!     .flat_map(u16::to_le_bytes)
+//!     .collect::<Vec<_>>();
+//! let source = TranscodingSource::new(vec![Ok::<_, std::io::Error>(utf16)].into_iter());
+//! let mut ndjson_iter = ndjson_stream::from_chunk_source::<serde_json::Value, _>(source);
+//!
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), 1);
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), "ä");
+//! assert!(ndjson_iter.next().is_none());
+//! ```
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+
+use crate::as_bytes::AsBytes;
+use crate::source::ChunkSource;
+
+/// The number of bytes required to detect a [TextEncoding].
+const DETECTION_LEN: usize = 4;
+
+/// The text encodings which can be converted by a [TranscodingSource].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TextEncoding {
+
+    /// UTF-8, which is passed through unchanged except for a leading byte order mark.
+    Utf8,
+
+    /// UTF-16 with little-endian code units.
+    Utf16Le,
+
+    /// UTF-16 with big-endian code units.
+    Utf16Be
+}
+
+impl TextEncoding {
+
+    /// Detects the encoding of data starting with the given `prefix`, of which at most the first
+    /// four bytes are considered. A byte order mark takes precedence. Otherwise, since every JSON
+    /// text starts with an ASCII character, a zero byte in one of the first two bytes indicates an
+    /// ASCII character encoded as UTF-16. All other data is considered UTF-8.
+    ///
+    /// # Returns
+    ///
+    /// The detected encoding and the length of the byte order mark, which is `0` if there is none.
+    pub fn detect(prefix: &[u8]) -> (TextEncoding, usize) {
+        match prefix {
+            [0xef, 0xbb, 0xbf, ..] => (TextEncoding::Utf8, 3),
+            [0xff, 0xfe, ..] => (TextEncoding::Utf16Le, 2),
+            [0xfe, 0xff, ..] => (TextEncoding::Utf16Be, 2),
+            [first, 0, ..] if *first != 0 => (TextEncoding::Utf16Le, 0),
+            [0, second, ..] if *second != 0 => (TextEncoding::Utf16Be, 0),
+            _ => (TextEncoding::Utf8, 0)
+        }
+    }
+}
+
+/// An error raised when data given to a [TranscodingSource] is not valid in its [TextEncoding].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum EncodingError {
+
+    /// A UTF-16 surrogate was not part of a valid surrogate pair.
+    UnpairedSurrogate {
+
+        /// The offset of the first byte of the surrogate in the entire input, in bytes.
+        offset: u64
+    },
+
+    /// The input ended in the middle of a UTF-16 code unit, i.e. after an odd number of bytes.
+    IncompleteCodeUnit {
+
+        /// The offset of the first byte of the incomplete code unit in the entire input, in bytes.
+        offset: u64
+    }
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::UnpairedSurrogate { offset } =>
+                write!(f, "unpaired UTF-16 surrogate at offset {offset}"),
+            EncodingError::IncompleteCodeUnit { offset } =>
+                write!(f, "incomplete UTF-16 code unit at offset {offset}")
+        }
+    }
+}
+
+impl Error for EncodingError { }
+
+/// The errors raised by a [TranscodingSource].
+#[derive(Debug, Eq, PartialEq)]
+pub enum TranscodeError<E> {
+
+    /// The wrapped source failed to provide the next data block.
+    Input(E),
+
+    /// The data could not be decoded. See [EncodingError].
+    Encoding(EncodingError)
+}
+
+impl<E: Display> Display for TranscodeError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscodeError::Input(error) => write!(f, "{error}"),
+            TranscodeError::Encoding(error) => write!(f, "{error}")
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for TranscodeError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TranscodeError::Input(error) => Some(error),
+            TranscodeError::Encoding(error) => Some(error)
+        }
+    }
+}
+
+/// Decodes UTF-16 data given in arbitrary chunks, keeping incomplete code units and surrogate
+/// pairs spanning chunk boundaries.
+struct Utf16Decoder {
+    big_endian: bool,
+    pending_byte: Option<u8>,
+    high_surrogate: Option<u16>,
+    offset: u64
+}
+
+impl Utf16Decoder {
+
+    fn new(big_endian: bool, offset: u64) -> Utf16Decoder {
+        Utf16Decoder {
+            big_endian,
+            pending_byte: None,
+            high_surrogate: None,
+            offset
+        }
+    }
+
+    fn push_char(c: char, utf8: &mut Vec<u8>) {
+        utf8.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    fn decode(&mut self, bytes: &[u8], utf8: &mut Vec<u8>) -> Result<(), EncodingError> {
+        let mut first_error = None;
+
+        utf8.reserve(bytes.len());
+
+        for &byte in bytes {
+            let Some(first_byte) = self.pending_byte.take()
+            else {
+                self.pending_byte = Some(byte);
+                continue;
+            };
+            let unit = match self.big_endian {
+                true => u16::from_be_bytes([first_byte, byte]),
+                false => u16::from_le_bytes([first_byte, byte])
+            };
+            let unit_offset = self.offset;
+
+            self.offset += 2;
+
+            let error = match (self.high_surrogate.take(), unit) {
+                (None, 0xd800..=0xdbff) => {
+                    self.high_surrogate = Some(unit);
+                    continue;
+                },
+                (None, 0xdc00..=0xdfff) => EncodingError::UnpairedSurrogate { offset: unit_offset },
+                (None, unit) => {
+                    Self::push_char(char::from_u32(unit as u32).unwrap(), utf8);
+                    continue;
+                },
+                (Some(high), 0xdc00..=0xdfff) => {
+                    let code_point =
+                        0x10000 + (((high as u32) - 0xd800) << 10) + ((unit as u32) - 0xdc00);
+
+                    Self::push_char(char::from_u32(code_point).unwrap(), utf8);
+                    continue;
+                },
+                (Some(_), unit) => {
+                    // Only the high surrogate is invalid, the unit following it is kept.
+                    match unit {
+                        0xd800..=0xdbff => self.high_surrogate = Some(unit),
+                        _ => Self::push_char(char::from_u32(unit as u32).unwrap(), utf8)
+                    }
+
+                    EncodingError::UnpairedSurrogate { offset: unit_offset - 2 }
+                }
+            };
+
+            first_error.get_or_insert(error);
+        }
+
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(())
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), EncodingError> {
+        if self.pending_byte.take().is_some() {
+            self.high_surrogate = None;
+            return Err(EncodingError::IncompleteCodeUnit { offset: self.offset });
+        }
+
+        if self.high_surrogate.take().is_some() {
+            return Err(EncodingError::UnpairedSurrogate { offset: self.offset - 2 });
+        }
+
+        Ok(())
+    }
+}
+
+enum State {
+    Detecting(Vec<u8>),
+    Utf8,
+    Utf16(Utf16Decoder),
+    Finished
+}
+
+/// A [ChunkSource] which wraps another one and converts its data blocks to UTF-8, such that they
+/// can be parsed by the drivers of this crate. The encoding is either detected from the first
+/// bytes of the input or given explicitly. A leading byte order mark is removed in either case.
+///
+/// Since a UTF-16 code unit or surrogate pair may span several data blocks, the wrapped source is
+/// transcoded incrementally. If a data block contains invalid data, the entire data block is
+/// discarded and a [TranscodeError::Encoding] for the first invalid data is returned instead.
+/// Decoding resumes with the subsequent data blocks.
+pub struct TranscodingSource<S> {
+    source: S,
+    state: State,
+    forced_encoding: Option<TextEncoding>
+}
+
+impl<S> TranscodingSource<S> {
+
+    /// Creates a new transcoding source wrapping the given `source`, which detects the encoding
+    /// from the first bytes of the input according to [TextEncoding::detect].
+    pub fn new(source: S) -> TranscodingSource<S> {
+        TranscodingSource {
+            source,
+            state: State::Detecting(Vec::new()),
+            forced_encoding: None
+        }
+    }
+
+    /// Creates a new transcoding source wrapping the given `source`, which assumes the given
+    /// `encoding` for the input. A byte order mark matching the encoding is still removed.
+    pub fn with_encoding(source: S, encoding: TextEncoding) -> TranscodingSource<S> {
+        TranscodingSource {
+            source,
+            state: State::Detecting(Vec::new()),
+            forced_encoding: Some(encoding)
+        }
+    }
+
+    /// Gets the encoding of the input, or `None` if it has not been detected yet.
+    pub fn encoding(&self) -> Option<TextEncoding> {
+        match &self.state {
+            State::Detecting(_) => self.forced_encoding,
+            State::Utf8 => Some(TextEncoding::Utf8),
+            State::Utf16(decoder) if decoder.big_endian => Some(TextEncoding::Utf16Be),
+            State::Utf16(_) => Some(TextEncoding::Utf16Le),
+            State::Finished => None
+        }
+    }
+
+    /// Gets a reference to the wrapped source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Unwraps this source, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn start(&mut self, prefix: &[u8]) -> usize {
+        let (detected_encoding, bom_len) = TextEncoding::detect(prefix);
+        let (encoding, bom_len) = match self.forced_encoding {
+            Some(encoding) if encoding != detected_encoding => (encoding, 0),
+            Some(encoding) => (encoding, bom_len),
+            None => (detected_encoding, bom_len)
+        };
+
+        self.state = match encoding {
+            TextEncoding::Utf8 => State::Utf8,
+            TextEncoding::Utf16Le => State::Utf16(Utf16Decoder::new(false, bom_len as u64)),
+            TextEncoding::Utf16Be => State::Utf16(Utf16Decoder::new(true, bom_len as u64))
+        };
+
+        bom_len
+    }
+
+    fn transcode(&mut self, data: &[u8]) -> Result<Vec<u8>, EncodingError> {
+        match &mut self.state {
+            State::Utf16(decoder) => {
+                let mut utf8 = Vec::new();
+
+                decoder.decode(data, &mut utf8)?;
+                Ok(utf8)
+            },
+            _ => Ok(data.to_vec())
+        }
+    }
+}
+
+impl<S> ChunkSource for TranscodingSource<S>
+where
+    S: ChunkSource
+{
+    type Chunk = Vec<u8>;
+    type Error = TranscodeError<S::Error>;
+
+    fn next_chunk(&mut self) -> Option<Result<Vec<u8>, TranscodeError<S::Error>>> {
+        loop {
+            if let State::Finished = self.state {
+                return None;
+            }
+
+            let chunk = match self.source.next_chunk() {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(error)) => return Some(Err(TranscodeError::Input(error))),
+                None => {
+                    let result = match mem::replace(&mut self.state, State::Finished) {
+                        State::Detecting(prefix) if !prefix.is_empty() => {
+                            let bom_len = self.start(&prefix);
+                            let result = self.transcode(&prefix[bom_len..]);
+                            let finished = match &mut self.state {
+                                State::Utf16(decoder) => decoder.finish(),
+                                _ => Ok(())
+                            };
+
+                            self.state = State::Finished;
+                            finished.and(result).map(Some)
+                        },
+                        State::Utf16(mut decoder) => decoder.finish().map(|()| None),
+                        _ => Ok(None)
+                    };
+
+                    return result.map_err(TranscodeError::Encoding).transpose();
+                }
+            };
+
+            let prefix = match &mut self.state {
+                State::Detecting(prefix) => {
+                    prefix.extend_from_slice(chunk.as_bytes());
+
+                    if prefix.len() < DETECTION_LEN {
+                        continue;
+                    }
+
+                    mem::take(prefix)
+                },
+                _ => return Some(self.transcode(chunk.as_bytes()).map_err(TranscodeError::Encoding))
+            };
+            let bom_len = self.start(&prefix);
+
+            return Some(self.transcode(&prefix[bom_len..]).map_err(TranscodeError::Encoding));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::convert::Infallible;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match big_endian {
+                true => unit.to_be_bytes(),
+                false => unit.to_le_bytes()
+            })
+            .collect()
+    }
+
+    fn transcode_chunks(chunks: Vec<Vec<u8>>) -> Vec<Result<Vec<u8>, TranscodeError<Infallible>>> {
+        TranscodingSource::new(chunks.into_iter().map(Ok)).into_chunks().collect()
+    }
+
+    fn transcode(data: &[u8], chunk_size: usize) -> Result<String, TranscodeError<Infallible>> {
+        let chunks = data.chunks(chunk_size).map(<[u8]>::to_vec).collect();
+        let utf8 = transcode_chunks(chunks).into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+
+        Ok(String::from_utf8(utf8).unwrap())
+    }
+
+    #[test]
+    fn encoding_is_detected_from_bom_or_zero_bytes() {
+        let cases: [(&[u8], _); 7] = [
+            (b"\xef\xbb\xbf{}", (TextEncoding::Utf8, 3)),
+            (b"\xff\xfe{\x00", (TextEncoding::Utf16Le, 2)),
+            (b"\xfe\xff\x00{", (TextEncoding::Utf16Be, 2)),
+            (b"{\x00}\x00", (TextEncoding::Utf16Le, 0)),
+            (b"\x00{\x00}", (TextEncoding::Utf16Be, 0)),
+            (b"{}", (TextEncoding::Utf8, 0)),
+            (b"", (TextEncoding::Utf8, 0))
+        ];
+
+        for (prefix, expected) in cases {
+            assert_that!(TextEncoding::detect(prefix)).is_equal_to(expected);
+        }
+    }
+
+    #[test]
+    fn utf16_is_transcoded_independent_of_chunk_size() {
+        let text = "{\"a\":\"ä😀\"}\n[1]\n";
+
+        for big_endian in [false, true] {
+            let data = [utf16("\u{feff}", big_endian), utf16(text, big_endian)].concat();
+
+            for chunk_size in 1..=7 {
+                assert_that!(transcode(&data, chunk_size)).contains_value(text.to_owned());
+            }
+        }
+    }
+
+    #[test]
+    fn utf8_is_passed_through_without_bom() {
+        assert_that!(transcode("\u{feff}1\nä\n".as_bytes(), 2))
+            .contains_value("1\nä\n".to_owned());
+        assert_that!(transcode(b"1", 1)).contains_value("1".to_owned());
+    }
+
+    #[test]
+    fn invalid_utf16_is_reported_with_offset() {
+        let unpaired_low = [vec![0x00, 0xdc], utf16("\n2\n", false)].concat();
+        let unpaired_high = [vec![0x00, 0xd8], utf16("3\n", false)].concat();
+        let results = transcode_chunks(vec![utf16("1\n", false), unpaired_low, unpaired_high,
+            utf16("4\n", false)]);
+
+        assert_that!(results).contains_exactly_in_given_order([
+            Ok(b"1\n".to_vec()),
+            Err(TranscodeError::Encoding(EncodingError::UnpairedSurrogate { offset: 4 })),
+            Err(TranscodeError::Encoding(EncodingError::UnpairedSurrogate { offset: 12 })),
+            Ok(b"4\n".to_vec())
+        ]);
+        assert_that!(transcode(&utf16("12\n", false)[..5], 2)).contains_error(
+            TranscodeError::Encoding(EncodingError::IncompleteCodeUnit { offset: 4 }));
+    }
+
+    #[test]
+    fn input_errors_are_forwarded() {
+        let chunks = vec![Ok(utf16("1\n", true)), Err("test"), Ok(utf16("2\n", true))];
+        let results = TranscodingSource::new(chunks.into_iter()).into_chunks().collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([
+            Ok(b"1\n".to_vec()),
+            Err(TranscodeError::Input("test")),
+            Ok(b"2\n".to_vec())
+        ]);
+    }
+}
@@ -1,34 +1,149 @@
 //! This module contains the low-level NDJSON parsing logic in the form of the [NdjsonEngine]. You
 //! should usually not have to use this directly, but rather access a higher-level interface such as
 //! iterators.
-
-use std::collections::VecDeque;
-use std::str;
-
-use serde::Deserialize;
-
-use serde_json::error::Result as JsonResult;
-
-use crate::as_bytes::AsBytes;
-use crate::config::{EmptyLineHandling, NdjsonConfig};
-
-fn index_of<T: Eq>(data: &[T], search: T) -> Option<usize> {
-    data.iter().enumerate()
-        .find(|&(_, item)| item == &search)
-        .map(|(index, _)| index)
-}
-
-const NEW_LINE: u8 = b'\n';
+//!
+//! # Performance
+//!
+//! Line endings are searched with the vectorized routines of the [memchr] crate, so this crate does
+//! not need any `unsafe` code of its own. Every chunk is scanned once. Complete lines within a chunk
+//! are parsed directly from the chunk without copying, so only the incomplete line at the end of a
+//! chunk is buffered until its remainder arrives. Consequently, parsing data given in chunks of a
+//! few KiB is at least as fast as a loop which reads every line with `BufRead::read_until` and
+//! parses it with [serde_json::from_slice], and faster for long lines, where copying and searching
+//! line endings outweigh parsing. This comparison is part of the benchmark in `benches/engine.rs`,
+//! which can be run with `cargo bench`.
+
+use alloc::borrow::Cow;
+use alloc::collections::{vec_deque, BTreeSet, VecDeque};
+use alloc::sync::Arc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::{fmt, mem, str};
+use core::iter::FusedIterator;
+
+use serde::de::{DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use serde_json::error::{Error as JsonError, Result as JsonResult};
+
+use crate::array::JsonArraySplitter;
+use crate::as_bytes::{AsByteChunks, AsBytes};
+use crate::config::{
+    EmptyLineHandling,
+    InvalidLineHandling,
+    NdjsonConfig,
+    Progress,
+    ProgressInterval,
+    SchemaValidator,
+    TrailingDataHandling,
+    DEFAULT_OVERSIZED_LINE_PREFIX_LEN
+};
+use crate::error::{LocatedError, NdjsonError, NdjsonResult, SchemaViolation};
+#[cfg(any(feature = "iter", feature = "stream"))]
+use crate::error::ValidationError;
+use crate::event::NdjsonEvent;
+use crate::histogram::RecordSizeHistogram;
+use crate::key_case;
+use crate::middleware::{LineMiddleware, SharedMiddleware};
+use crate::splitter::{NdjsonLineSplitter, SplitLine};
+
+type DeserializeFn<T> = dyn Fn(&[u8]) -> NdjsonResult<T> + Send + Sync;
+
+type ValidateFn<T> = dyn Fn(&T) -> Result<(), String> + Send + Sync;
+
+/// A function parsing a single line into a record. It is given to the line handling of the
+/// [NdjsonEngine] as a generic parameter rather than a trait object, so that the parser can be
+/// inlined into the loop over the lines of a chunk.
+trait LineParseFn<T>: FnMut(&[u8], &NdjsonConfig) -> NdjsonResult<T> { }
+
+impl<T, F> LineParseFn<T> for F
+where
+    F: FnMut(&[u8], &NdjsonConfig) -> NdjsonResult<T>
+{ }
 
 /// The low-level engine parsing NDJSON-data given as byte slices into objects of the type parameter
 /// `T`. Data is supplied in chunks and parsed objects can subsequently be read from a queue.
 ///
 /// Users of this crate should usually not have to use this struct but rather a higher-level
 /// interface such as iterators.
+///
+/// Alternatively, an engine created with [NdjsonEngine::with_events] reports the outcome of every
+/// line as an [NdjsonEvent], which can be read via [NdjsonEngine::pop_event].
 pub struct NdjsonEngine<T> {
-    in_queue: Vec<u8>,
-    out_queue: VecDeque<JsonResult<T>>,
-    config: NdjsonConfig
+    splitter: NdjsonLineSplitter,
+    array_splitter: Option<JsonArraySplitter>,
+    out_queue: VecDeque<QueuedItem<T>>,
+    config: NdjsonConfig,
+    lines_to_skip: usize,
+    records_to_skip: usize,
+    emit_events: bool,
+    retain_failed_lines: bool,
+    deserializer: Option<Arc<DeserializeFn<T>>>,
+    record_validator: Option<Arc<ValidateFn<T>>>,
+    progress: Progress,
+
+    /// The number of lines given to the parsing logic so far, used for the line numbers of
+    /// ignored lines.
+    line_count: u64,
+
+    /// Whether the next line which is neither skipped nor ignored is a header, which is stored in
+    /// `header_line` instead of being parsed as a record.
+    expects_header: bool,
+    header_line: Option<NdjsonResult<Vec<u8>>>,
+
+    /// The line number of the record or parse error which was most recently removed from the
+    /// queue, or `0` if there is none.
+    popped_line_number: u64,
+
+    /// The offset of the first byte of the data from which the next record is parsed in the
+    /// entire input, i.e. usually the start of the current line.
+    record_offset: u64,
+    size_histogram: RecordSizeHistogram,
+
+    /// Whether a line recognized by the configured [EndSentinel](crate::config::EndSentinel) was
+    /// seen, after which all further input is discarded.
+    end_sentinel_seen: bool
+}
+
+/// An item in the output queue of an [NdjsonEngine]. Lines which contain no record are only queued
+/// if the engine emits events.
+enum QueuedItem<T> {
+    Parsed {
+        result: NdjsonResult<T>,
+        is_rest: bool,
+
+        /// The one-based number of the line from which the record was parsed.
+        line_number: u64,
+
+        /// The offset of the first byte of the data from which the record was parsed in the entire
+        /// input.
+        offset: u64,
+
+        /// The unparsable line, which is only stored if the engine emits events or retains failed
+        /// lines.
+        line: Vec<u8>
+    },
+    EmptyLine,
+    BlankLine,
+    CommentLine
+}
+
+impl<T> QueuedItem<T> {
+    fn into_event(self) -> NdjsonEvent<T> {
+        match self {
+            QueuedItem::Parsed { result: Ok(record), is_rest: false, .. } =>
+                NdjsonEvent::Record(record),
+            QueuedItem::Parsed { result: Ok(record), is_rest: true, .. } =>
+                NdjsonEvent::RestParsed(record),
+            QueuedItem::Parsed { result: Err(error), line, .. } =>
+                NdjsonEvent::ParseError { error, line },
+            QueuedItem::EmptyLine => NdjsonEvent::EmptyLine,
+            QueuedItem::BlankLine => NdjsonEvent::BlankLine,
+            QueuedItem::CommentLine => NdjsonEvent::CommentLine
+        }
+    }
 }
 
 impl<T> NdjsonEngine<T> {
@@ -42,10 +157,231 @@ impl<T> NdjsonEngine<T> {
     /// Creates a new NDJSON-engine for objects of the given type parameter with the given
     /// [NdjsonConfig] to control its behavior. See [NdjsonConfig] for more details.
     pub fn with_config(config: NdjsonConfig) -> NdjsonEngine<T> {
+        let mut splitter = NdjsonLineSplitter::with_line_ending(config.line_ending);
+        let mut array_splitter = config.json_array_mode.then(JsonArraySplitter::default);
+
+        if let Some(max_line_length) = config.max_line_length {
+            let prefix_len =
+                config.oversized_line_prefix_len.unwrap_or(DEFAULT_OVERSIZED_LINE_PREFIX_LEN);
+
+            splitter.set_max_line_length(max_line_length, prefix_len);
+
+            if let Some(array_splitter) = &mut array_splitter {
+                array_splitter.set_max_element_length(max_line_length, prefix_len);
+            }
+        }
+
         NdjsonEngine {
-            in_queue: Vec::new(),
+            splitter,
+            array_splitter,
             out_queue: VecDeque::new(),
-            config
+            config,
+            lines_to_skip: 0,
+            records_to_skip: 0,
+            emit_events: false,
+            retain_failed_lines: false,
+            deserializer: None,
+            record_validator: None,
+            progress: Progress::default(),
+            line_count: 0,
+            expects_header: false,
+            header_line: None,
+            popped_line_number: 0,
+            record_offset: 0,
+            size_histogram: RecordSizeHistogram::new(),
+            end_sentinel_seen: false
+        }
+    }
+
+    /// Creates a new NDJSON-engine for objects of the given type parameter with the given
+    /// [NdjsonConfig], which reports the outcome of every line as an [NdjsonEvent]. Events are read
+    /// via [NdjsonEngine::pop_event], while [NdjsonEngine::pop] still returns only records and
+    /// errors and discards all other events.
+    ///
+    /// In this mode, empty and blank lines are always reported as [NdjsonEvent::EmptyLine] and
+    /// [NdjsonEvent::BlankLine] instead of being parsed or ignored, regardless of the configured
+    /// [EmptyLineHandling]. Lines skipped via [NdjsonEngine::skip_lines] and records skipped via
+    /// [NdjsonEngine::skip_records] produce no events.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::NdjsonConfig;
+    /// use ndjson_stream::engine::NdjsonEngine;
+    /// use ndjson_stream::event::NdjsonEvent;
+    ///
+    /// let mut engine = NdjsonEngine::<u32>::with_events(NdjsonConfig::default());
+    ///
+    /// engine.input("1\n\n  \nx\n");
+    ///
+    /// assert!(matches!(engine.pop_event(), Some(NdjsonEvent::Record(1))));
+    /// assert!(matches!(engine.pop_event(), Some(NdjsonEvent::EmptyLine)));
+    /// assert!(matches!(engine.pop_event(), Some(NdjsonEvent::BlankLine)));
+    /// assert!(matches!(engine.pop_event(),
+    ///     Some(NdjsonEvent::ParseError { line, .. }) if line == b"x"));
+    /// assert!(engine.pop_event().is_none());
+    /// ```
+    pub fn with_events(config: NdjsonConfig) -> NdjsonEngine<T> {
+        NdjsonEngine {
+            emit_events: true,
+            ..NdjsonEngine::with_config(config)
+        }
+    }
+
+    /// Makes this engine parse lines with the given `deserializer` instead of [serde_json], for
+    /// example to use a different JSON parser or a non-JSON line format such as logfmt. The
+    /// deserializer receives every line after preprocessing, without the line ending, and errors
+    /// are converted into [NdjsonError]s, for which [NdjsonError::Custom] can be used. All other
+    /// options of the config still apply, except [NdjsonConfig::with_trailing_data_handling],
+    /// [NdjsonConfig::with_reject_duplicate_keys], and [NdjsonConfig::with_schema_validator], which
+    /// are specific to [serde_json]. Since the parsing methods of the engine are shared with the
+    /// default deserializer, `T` must still implement [Deserialize].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::engine::NdjsonEngine;
+    /// use ndjson_stream::error::NdjsonError;
+    ///
+    /// let mut engine = NdjsonEngine::<u32>::new().with_deserializer(|line| {
+    ///     let line = std::str::from_utf8(line)
+    ///         .map_err(|error| NdjsonError::Custom(error.into()))?;
+    ///
+    ///     line.parse::<u32>().map_err(|error| NdjsonError::Custom(error.into()))
+    /// });
+    ///
+    /// engine.input("12\nx\n");
+    ///
+    /// assert!(matches!(engine.pop(), Some(Ok(12))));
+    /// assert!(matches!(engine.pop(), Some(Err(NdjsonError::Custom(_)))));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new engine with all the same state as this one, except the deserializer.
+    pub fn with_deserializer<F, E>(self, deserializer: F) -> NdjsonEngine<T>
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        E: Into<NdjsonError>
+    {
+        NdjsonEngine {
+            deserializer: Some(Arc::new(move |line| deserializer(line).map_err(Into::into))),
+            ..self
+        }
+    }
+
+    /// Makes this engine check every parsed record with the given `validator`, for semantic
+    /// constraints which cannot be expressed by the type `T`, such as value ranges. If the
+    /// validator returns an error message, an [NdjsonError::InvalidRecord] holding it is reported
+    /// instead of the record, in the same place as parse errors. This applies to records parsed by
+    /// any deserializer, including salvaged ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::engine::NdjsonEngine;
+    /// use ndjson_stream::error::NdjsonError;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Person {
+    ///     age: u32
+    /// }
+    ///
+    /// let mut engine = NdjsonEngine::<Person>::new().with_record_validator(|person| {
+    ///     if person.age <= 130 {
+    ///         Ok(())
+    ///     }
+    ///     else {
+    ///         Err(format!("implausible age {}", person.age))
+    ///     }
+    /// });
+    ///
+    /// engine.input("{\"age\":42}\n{\"age\":420}\n");
+    ///
+    /// assert_eq!(engine.pop().unwrap().unwrap().age, 42);
+    /// assert!(matches!(engine.pop(),
+    ///     Some(Err(NdjsonError::InvalidRecord { message })) if message == "implausible age 420"));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A new engine with all the same state as this one, except the record validator.
+    pub fn with_record_validator<F>(self, validator: F) -> NdjsonEngine<T>
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static
+    {
+        NdjsonEngine {
+            record_validator: Some(Arc::new(validator)),
+            ..self
+        }
+    }
+
+    /// Gets the current [Progress] of this engine, i.e. the number of bytes given to it and the
+    /// number of records emitted by it so far.
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    /// Gets the [RecordSizeHistogram] of the lengths in bytes of all lines that were parsed into a
+    /// record or parse error so far, without line endings and after preprocessing. Skipped,
+    /// ignored, comment, and header lines are not included, while oversized lines are included
+    /// with their full length. See also [NdjsonConfig::with_large_record_callback] to be notified
+    /// of individual large records.
+    pub fn record_size_histogram(&self) -> &RecordSizeHistogram {
+        &self.size_histogram
+    }
+
+    /// Gets the number of lines given to the parsing logic so far, including skipped, ignored, and
+    /// comment lines.
+    pub(crate) fn line_count(&self) -> u64 {
+        self.line_count
+    }
+
+    /// Indicates whether this engine currently holds an incomplete line, i.e. data given to
+    /// [NdjsonEngine::input] after the last line ending. If the input ended now, this line would
+    /// either be parsed by [NdjsonEngine::finalize] or be dropped, depending on
+    /// [NdjsonConfig::with_parse_rest].
+    pub fn is_partial(&self) -> bool {
+        self.pending_bytes() > 0
+    }
+
+    /// Gets the number of bytes of the incomplete line currently held by this engine, or `0` if
+    /// there is none. For lines exceeding the maximum line length, this includes the bytes which
+    /// have already been discarded. In JSON array mode, this is the length of the incomplete array
+    /// element instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let mut engine = NdjsonEngine::<u32>::new();
+    ///
+    /// engine.input("1\n23");
+    ///
+    /// assert!(engine.is_partial());
+    /// assert_eq!(engine.pending_bytes(), 2);
+    ///
+    /// engine.input("\n");
+    ///
+    /// assert!(!engine.is_partial());
+    /// ```
+    pub fn pending_bytes(&self) -> usize {
+        match &self.array_splitter {
+            Some(array_splitter) => array_splitter.pending_len(),
+            None => self.splitter.pending_len()
+        }
+    }
+
+    /// Gets the incomplete line currently held by this engine, i.e. the data given to
+    /// [NdjsonEngine::input] after the last line ending, which is empty if there is none. For
+    /// lines exceeding the maximum line length, only the retained first bytes are returned. In
+    /// JSON array mode, this is the incomplete array element instead.
+    pub fn rest(&self) -> &[u8] {
+        match &self.array_splitter {
+            Some(array_splitter) => array_splitter.pending(),
+            None => self.splitter.rest()
         }
     }
 
@@ -53,461 +389,3102 @@ impl<T> NdjsonEngine<T> {
     /// supplied previously via [NdjsonEngine::input], that is, a newline character has been
     /// observed. If the input until the newline is not valid JSON, the parse error is returned. If
     /// no element is available in the queue, `None` is returned.
-    pub fn pop(&mut self) -> Option<JsonResult<T>> {
-        self.out_queue.pop_front()
+    pub fn pop(&mut self) -> Option<NdjsonResult<T>> {
+        self.pop_with_line_number().map(|(_, result)| result)
+    }
+
+    /// Reads the next element from the queue of parsed items like [NdjsonEngine::pop], but returns
+    /// it together with the one-based number of the line it was parsed from. Line numbers count
+    /// all lines given to this engine, including empty, blank, comment, and skipped lines, so they
+    /// refer to the line in the original input. In JSON array mode, elements are counted instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let config = NdjsonConfig::default()
+    ///     .with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+    /// let mut engine = NdjsonEngine::<u32>::with_config(config);
+    ///
+    /// engine.input("1\n\n  \n2\n");
+    ///
+    /// assert!(matches!(engine.pop_with_line_number(), Some((1, Ok(1)))));
+    /// assert!(matches!(engine.pop_with_line_number(), Some((4, Ok(2)))));
+    /// ```
+    pub fn pop_with_line_number(&mut self) -> Option<(u64, NdjsonResult<T>)> {
+        loop {
+            if let QueuedItem::Parsed { result, line_number, .. } = self.out_queue.pop_front()? {
+                self.popped_line_number = line_number;
+                return Some((line_number, result));
+            }
+        }
+    }
+
+    /// Reads the next element from the queue of parsed items like [NdjsonEngine::pop], but returns
+    /// parse errors as a [LocatedError], which holds the position of the error in the entire input.
+    /// This allows navigating directly to the offending byte, for example in large files.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let mut engine = NdjsonEngine::<serde_json::Value>::new();
+    ///
+    /// engine.input("1\n2\n{\"a\": }\n");
+    ///
+    /// assert!(engine.pop_located().unwrap().is_ok());
+    /// assert!(engine.pop_located().unwrap().is_ok());
+    ///
+    /// let error = engine.pop_located().unwrap().unwrap_err();
+    ///
+    /// assert_eq!(error.line_number, 3);
+    /// assert_eq!(error.line_offset, 4);
+    /// assert_eq!(error.offset(), 10);
+    /// ```
+    pub fn pop_located(&mut self) -> Option<Result<T, LocatedError>> {
+        loop {
+            if let QueuedItem::Parsed { result, line_number, offset, .. } =
+                    self.out_queue.pop_front()? {
+                self.popped_line_number = line_number;
+                return Some(result.map_err(|error| LocatedError {
+                    line_number,
+                    line_offset: offset,
+                    error
+                }));
+            }
+        }
+    }
+
+    /// Gets the line number of the record or parse error which was most recently removed from the
+    /// queue, as it would have been returned by [NdjsonEngine::pop_with_line_number], or `0` if no
+    /// element was removed yet.
+    #[cfg(any(feature = "iter", feature = "stream"))]
+    pub(crate) fn popped_line_number(&self) -> u64 {
+        self.popped_line_number
+    }
+
+    /// Removes all elements from the queue of parsed items at once, in the order in which they
+    /// would be returned by [NdjsonEngine::pop]. Elements which are not consumed by the returned
+    /// iterator are discarded when it is dropped. This is convenient for batch-oriented consumers
+    /// which process everything that is available after each call to [NdjsonEngine::input].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let mut engine = NdjsonEngine::<u32>::new();
+    ///
+    /// engine.input("1\n2\n3");
+    ///
+    /// let records = engine.drain().map(Result::unwrap).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(records, vec![1, 2]);
+    /// assert!(engine.pop().is_none());
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        Drain {
+            queue: self.out_queue.drain(..)
+        }
+    }
+
+    /// Moves all elements from the queue of parsed items to the end of the given `buffer`, just
+    /// like [NdjsonEngine::drain]. This allows reusing the allocation of the buffer for every
+    /// batch.
+    ///
+    /// # Returns
+    ///
+    /// The number of elements which were added to `buffer`.
+    pub fn drain_into(&mut self, buffer: &mut Vec<NdjsonResult<T>>) -> usize {
+        let length_before = buffer.len();
+
+        buffer.extend(self.drain());
+        buffer.len() - length_before
+    }
+
+    /// Makes this engine store the offending line of every subsequent parse error, so it can be
+    /// obtained via [NdjsonEngine::pop_with_failed_line].
+    #[cfg(any(feature = "iter", feature = "stream"))]
+    pub(crate) fn retain_failed_lines(&mut self) {
+        self.retain_failed_lines = true;
+    }
+
+    /// Reads the next element from the queue of parsed items like [NdjsonEngine::pop], but returns
+    /// parse errors together with the offending line. The line is empty unless it was processed
+    /// after a call to [NdjsonEngine::retain_failed_lines] or this engine emits events.
+    #[cfg(any(feature = "iter", feature = "stream"))]
+    pub(crate) fn pop_with_failed_line(&mut self) -> Option<Result<T, (NdjsonError, Vec<u8>)>> {
+        loop {
+            if let QueuedItem::Parsed { result, line, line_number, .. } =
+                    self.out_queue.pop_front()? {
+                self.popped_line_number = line_number;
+                return Some(result.map_err(|error| (error, line)));
+            }
+        }
+    }
+
+    /// Reads the next element from the queue of parsed items like
+    /// [NdjsonEngine::pop_with_line_number], but drops the record. This is used by the validating
+    /// drivers, which report parse errors together with the number of the line they occurred in.
+    #[cfg(any(feature = "iter", feature = "stream"))]
+    pub(crate) fn pop_validated(&mut self) -> Option<Result<(), ValidationError>> {
+        let (line_number, result) = self.pop_with_line_number()?;
+
+        Some(result.map(|_| ()).map_err(|error| ValidationError { line_number, error }))
+    }
+
+    /// Indicates whether this engine has emitted the maximum number of records configured via
+    /// [NdjsonConfig::with_max_records]. In this case, all further input is discarded, so drivers
+    /// can stop reading once the queue is empty.
+    pub fn is_max_records_reached(&self) -> bool {
+        self.config.max_records
+            .is_some_and(|max_records| self.progress.records_emitted >= max_records)
+    }
+
+    /// Indicates whether this engine discards all further input, since it has emitted the maximum
+    /// number of records configured via [NdjsonConfig::with_max_records] or has seen the end
+    /// sentinel configured via [NdjsonConfig::with_end_sentinel]. Drivers can stop reading once
+    /// this is the case and the queue is empty.
+    pub fn is_finished(&self) -> bool {
+        self.end_sentinel_seen || self.is_max_records_reached()
+    }
+
+    /// Counts the records and parse errors which are currently available via [NdjsonEngine::pop].
+    #[cfg(feature = "std")]
+    pub(crate) fn queued_record_count(&self) -> usize {
+        self.out_queue.iter()
+            .filter(|item| matches!(item, QueuedItem::Parsed { .. }))
+            .count()
+    }
+
+    fn stores_failed_lines(&self) -> bool {
+        self.emit_events || self.retain_failed_lines
+    }
+
+    /// Reads the next event from the queue of processed lines. Unless this engine was created with
+    /// [NdjsonEngine::with_events], only [NdjsonEvent::Record], [NdjsonEvent::RestParsed], and
+    /// [NdjsonEvent::ParseError] without the offending line are reported. If no event is available
+    /// in the queue, `None` is returned.
+    pub fn pop_event(&mut self) -> Option<NdjsonEvent<T>> {
+        self.out_queue.pop_front().map(QueuedItem::into_event)
+    }
+
+    /// Gets a reference to the next element from the queue of parsed items without removing it,
+    /// i.e. the element which would be returned by the next call to [NdjsonEngine::pop]. If no
+    /// element is available in the queue, `None` is returned.
+    pub fn peek(&self) -> Option<&NdjsonResult<T>> {
+        self.out_queue.iter().find_map(|item| match item {
+            QueuedItem::Parsed { result, .. } => Some(result),
+            _ => None
+        })
+    }
+
+    /// Discards the next `count` lines which are completed by subsequent calls to
+    /// [NdjsonEngine::input] or [NdjsonEngine::finalize] without deserializing them. Every line is
+    /// counted, including those which would be ignored according to the configured
+    /// [EmptyLineHandling]. Records which are already available via [NdjsonEngine::pop] are not
+    /// affected.
+    ///
+    /// Repeated calls accumulate, i.e. calling this method with `2` and then with `3` skips the
+    /// next five lines.
+    pub fn skip_lines(&mut self, count: usize) {
+        self.lines_to_skip += count;
+    }
+
+    /// Discards the next `count` records without deserializing them where possible. A record is any
+    /// line which would produce an output, i.e. lines ignored according to the configured
+    /// [EmptyLineHandling] are not counted. Records which are already available via
+    /// [NdjsonEngine::pop] are discarded first, while the remaining ones are skipped as soon as
+    /// their lines are completed by subsequent input.
+    ///
+    /// This is useful for resuming ingestion from a checkpoint without paying for deserialization
+    /// of the records before it.
+    pub fn skip_records(&mut self, count: usize) {
+        let mut remaining = count;
+
+        while remaining > 0 {
+            match self.out_queue.pop_front() {
+                Some(QueuedItem::Parsed { .. }) => remaining -= 1,
+                Some(_) => { },
+                None => break
+            }
+        }
+
+        self.records_to_skip += remaining;
+    }
+
+    /// Makes this engine store the next line which is neither skipped nor ignored as a header
+    /// instead of parsing it as a record. The header is obtained via [NdjsonEngine::take_header].
+    #[cfg(any(feature = "iter", feature = "stream"))]
+    pub(crate) fn expect_header(&mut self) {
+        self.expects_header = true;
+    }
+}
+
+/// A draining [Iterator] over the queue of parsed items of an [NdjsonEngine], created by
+/// [NdjsonEngine::drain].
+pub struct Drain<'engine, T> {
+    queue: vec_deque::Drain<'engine, QueuedItem<T>>
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = NdjsonResult<T>;
+
+    fn next(&mut self) -> Option<NdjsonResult<T>> {
+        loop {
+            if let QueuedItem::Parsed { result, .. } = self.queue.next()? {
+                return Some(result);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.queue.size_hint().1)
     }
 }
 
+impl<T> FusedIterator for Drain<'_, T> { }
+
 fn is_blank(string: &str) -> bool {
     string.chars().all(char::is_whitespace)
 }
 
-fn parse_line<T>(bytes: &[u8], empty_line_handling: EmptyLineHandling) -> Option<JsonResult<T>>
+/// Indicates whether the given byte is whitespace according to the JSON grammar, which, unlike
+/// [u8::is_ascii_whitespace], does not include form feeds.
+fn is_json_whitespace(byte: &u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+fn trailing_data_error(trailing_data: &[u8]) -> JsonError {
+    JsonError::custom(format!("trailing data after JSON value: `{}`",
+        String::from_utf8_lossy(trailing_data)))
+}
+
+fn parse_json<'data, T>(bytes: &'data [u8], trailing_data_handling: TrailingDataHandling)
+    -> JsonResult<T>
 where
-    for<'deserialize> T: Deserialize<'deserialize>
+    T: Deserialize<'data>
 {
-    let should_ignore = match empty_line_handling {
-        EmptyLineHandling::ParseAlways => false,
-        EmptyLineHandling::IgnoreEmpty => bytes.is_empty() || bytes == [b'\r'],
-        EmptyLineHandling::IgnoreBlank => str::from_utf8(bytes).is_ok_and(is_blank)
+    if matches!(trailing_data_handling,
+            TrailingDataHandling::Error | TrailingDataHandling::MultipleValues) {
+        return serde_json::from_slice(bytes);
+    }
+
+    let mut values = serde_json::Deserializer::from_slice(bytes).into_iter();
+
+    let value = match values.next() {
+        Some(value) => value?,
+
+        // The stream deserializer does not report an error for lines without any JSON value, so we
+        // fall back to the ordinary parser to obtain an appropriate error.
+        None => return serde_json::from_slice(bytes)
     };
 
-    if should_ignore {
-        None
+    let trailing_data = &bytes[values.byte_offset()..];
+
+    if trailing_data_handling == TrailingDataHandling::IncludeInError
+            && !trailing_data.iter().all(is_json_whitespace) {
+        return Err(trailing_data_error(trailing_data));
     }
-    else {
-        Some(serde_json::from_slice(bytes))
+
+    Ok(value)
+}
+
+/// Deserializes any JSON value and records the first key which occurs multiple times in the same
+/// object, if any.
+struct FirstDuplicateKey(Option<String>);
+
+impl<'de> Deserialize<'de> for FirstDuplicateKey {
+    fn deserialize<D>(deserializer: D) -> Result<FirstDuplicateKey, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_any(FirstDuplicateKeyVisitor)
     }
 }
 
-impl<T> NdjsonEngine<T>
-where
-    for<'deserialize> T: Deserialize<'deserialize>
-{
+struct FirstDuplicateKeyVisitor;
 
-    /// Parses the given data as NDJSON. In case the end does not match up with a newline, the rest
-    /// is stored in an internal cache. Consequently, the rest from a previous call to this method
-    /// is prepended to the given data in case a newline is encountered.
-    pub fn input(&mut self, data: impl AsBytes) {
-        let mut data = data.as_bytes();
+impl<'de> Visitor<'de> for FirstDuplicateKeyVisitor {
+    type Value = FirstDuplicateKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<FirstDuplicateKey, E> {
+        Ok(FirstDuplicateKey(None))
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<FirstDuplicateKey, E> {
+        Ok(FirstDuplicateKey(None))
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<FirstDuplicateKey, E> {
+        Ok(FirstDuplicateKey(None))
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<FirstDuplicateKey, E> {
+        Ok(FirstDuplicateKey(None))
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<FirstDuplicateKey, E> {
+        Ok(FirstDuplicateKey(None))
+    }
 
-        while let Some(newline_idx) = index_of(data, NEW_LINE) {
-            let data_until_split = &data[..newline_idx];
+    fn visit_unit<E>(self) -> Result<FirstDuplicateKey, E> {
+        Ok(FirstDuplicateKey(None))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<FirstDuplicateKey, A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        let mut duplicate_key = None;
+
+        while let Some(FirstDuplicateKey(nested_duplicate_key)) = seq.next_element()? {
+            duplicate_key = duplicate_key.or(nested_duplicate_key);
+        }
 
-            let next_item_bytes = if self.in_queue.is_empty() {
-                data_until_split
+        Ok(FirstDuplicateKey(duplicate_key))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<FirstDuplicateKey, A::Error>
+    where
+        A: MapAccess<'de>
+    {
+        let mut keys = BTreeSet::new();
+        let mut duplicate_key = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            if duplicate_key.is_none() && keys.contains(&key) {
+                duplicate_key = Some(key);
             }
             else {
-                self.in_queue.extend_from_slice(data_until_split);
-                &self.in_queue
-            };
-
-            if let Some(item) = parse_line(next_item_bytes, self.config.empty_line_handling) {
-                self.out_queue.push_back(item);
+                keys.insert(key);
             }
 
-            self.in_queue.clear();
-            data = &data[(newline_idx + 1)..];
+            let FirstDuplicateKey(nested_duplicate_key) = map.next_value()?;
+            duplicate_key = duplicate_key.or(nested_duplicate_key);
         }
 
-        self.in_queue.extend_from_slice(data);
+        Ok(FirstDuplicateKey(duplicate_key))
     }
+}
 
-    /// Parses the rest leftover from previous calls to [NdjsonEngine::input], i.e. the data after
-    /// the last given newline character, if all of the following conditions are met.
-    ///
-    /// * The engine uses a config with [NdjsonConfig::with_parse_rest] set to `true`.
-    /// * There is non-empty data left to parse. In other words, the previous provided input did not
-    /// end with a newline character.
-    /// * The rest is not considered empty by the handling configured in
-    /// [NdjsonConfig::with_empty_line_handling]. That is, if the rest consists only of whitespace
-    /// and [EmptyLineHandling::IgnoreBlank] is used, the rest is not parsed.
-    ///
-    /// In any case, the rest is discarded from the input buffer. Therefore, this function is
-    /// idempotent.
-    ///
-    /// Note: This function is intended to be called after the input ended, but there is no
-    /// validation in place to check that [NdjsonEngine::input] is not called afterwards. Doing this
-    /// anyway may lead to unexpected behavior, as JSON-lines may be partially discarded.
-    pub fn finalize(&mut self) {
-        if self.config.parse_rest {
-            let empty_line_handling = match self.config.empty_line_handling {
-                EmptyLineHandling::ParseAlways => EmptyLineHandling::IgnoreEmpty,
-                empty_line_handling => empty_line_handling
-            };
+fn find_duplicate_key(bytes: &[u8]) -> Option<String> {
+    let mut values = serde_json::Deserializer::from_slice(bytes).into_iter::<FirstDuplicateKey>();
 
-            if let Some(item) = parse_line(&self.in_queue, empty_line_handling) {
-                self.out_queue.push_back(item);
-            }
+    // Invalid JSON is reported by the subsequent typed parsing, so errors are ignored here.
+    values.next()?.ok()?.0
+}
+
+fn find_schema_violation(bytes: &[u8], schema_validator: &SchemaValidator)
+    -> Option<SchemaViolation>
+{
+    let mut values = serde_json::Deserializer::from_slice(bytes).into_iter::<serde_json::Value>();
+
+    // Invalid JSON is reported by the subsequent typed parsing, so errors are ignored here.
+    schema_validator.validate(&values.next()?.ok()?).err()
+}
+
+/// Performs the checks configured via [NdjsonConfig::with_reject_duplicate_keys] and
+/// [NdjsonConfig::with_schema_validator], which precede the typed parsing of a line.
+fn check_line(bytes: &[u8], config: &NdjsonConfig) -> NdjsonResult<()> {
+    if config.reject_duplicate_keys {
+        if let Some(key) = find_duplicate_key(bytes) {
+            return Err(NdjsonError::DuplicateKey { key });
         }
+    }
 
-        self.in_queue.clear();
+    if let Some(schema_validator) = &config.schema_validator {
+        if let Some(violation) = find_schema_violation(bytes, schema_validator) {
+            return Err(NdjsonError::SchemaViolation(violation));
+        }
     }
+
+    Ok(())
 }
 
-impl<T> Default for NdjsonEngine<T> {
-    fn default() -> NdjsonEngine<T> {
-        NdjsonEngine::new()
+/// Gets the [TrailingDataHandling] with which lines are parsed. If invalid records are skipped,
+/// errors including the trailing data are never read, so they are not constructed.
+fn effective_trailing_data_handling(config: &NdjsonConfig) -> TrailingDataHandling {
+    match (config.trailing_data_handling, config.invalid_line_handling) {
+        (TrailingDataHandling::IncludeInError, InvalidLineHandling::Skip) =>
+            TrailingDataHandling::Error,
+        (trailing_data_handling, _) => trailing_data_handling
     }
 }
 
-#[cfg(test)]
-mod tests {
+pub(crate) fn parse_line<'data, T>(bytes: &'data [u8], config: &NdjsonConfig) -> NdjsonResult<T>
+where
+    T: Deserialize<'data>
+{
+    check_line(bytes, config)?;
 
-    use kernal::prelude::*;
+    Ok(parse_json(bytes, effective_trailing_data_handling(config))?)
+}
+
+fn parse_json_seed<S, V>(bytes: &[u8], trailing_data_handling: TrailingDataHandling, seed: S)
+    -> JsonResult<V>
+where
+    S: for<'deserialize> DeserializeSeed<'deserialize, Value = V>
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = seed.deserialize(&mut deserializer)?;
+
+    match (deserializer.end(), trailing_data_handling) {
+        (Ok(()), _) | (Err(_), TrailingDataHandling::Ignore) => Ok(value),
+        (Err(error), TrailingDataHandling::Error | TrailingDataHandling::MultipleValues) =>
+            Err(error),
+        (Err(_), TrailingDataHandling::IncludeInError) => {
+            // The deserializer does not expose its position, so the end of the value is determined
+            // by parsing it again, which is only necessary for erroneous lines.
+            let mut values = serde_json::Deserializer::from_slice(bytes).into_iter::<IgnoredAny>();
+            values.next();
+
+            Err(trailing_data_error(&bytes[values.byte_offset()..]))
+        }
+    }
+}
 
-    use serde_json::error::Result as JsonResult;
+fn parse_line_seed<S, V>(bytes: &[u8], config: &NdjsonConfig, seed: S) -> NdjsonResult<V>
+where
+    S: for<'deserialize> DeserializeSeed<'deserialize, Value = V>
+{
+    check_line(bytes, config)?;
 
-    use std::borrow::Cow;
-    use std::iter;
-    use std::rc::Rc;
-    use std::sync::Arc;
-    use crate::config::{EmptyLineHandling, NdjsonConfig};
+    Ok(parse_json_seed(bytes, effective_trailing_data_handling(config), seed)?)
+}
 
-    use crate::engine::NdjsonEngine;
-    use crate::test_util::TestStruct;
+fn is_empty(bytes: &[u8]) -> bool {
+    bytes.is_empty() || bytes == [b'\r']
+}
 
-    fn collect_output(mut engine: NdjsonEngine<TestStruct>)
-            -> Vec<JsonResult<TestStruct>> {
-        iter::from_fn(|| engine.pop()).collect::<Vec<_>>()
+pub(crate) fn is_ignored(bytes: &[u8], empty_line_handling: EmptyLineHandling) -> bool {
+    match empty_line_handling {
+        EmptyLineHandling::ParseAlways => false,
+        EmptyLineHandling::IgnoreEmpty => is_empty(bytes),
+        EmptyLineHandling::IgnoreBlank => str::from_utf8(bytes).is_ok_and(is_blank)
+    }
+}
+
+impl<T> NdjsonEngine<T> {
+
+    /// Checks whether the given line is ignored rather than parsed. If events are emitted, this
+    /// is the case for all empty and blank lines, which are reported as the corresponding events.
+    fn handle_ignored_line(&mut self, bytes: &[u8], empty_line_handling: EmptyLineHandling)
+            -> bool {
+        if !self.emit_events {
+            if !is_ignored(bytes, empty_line_handling) {
+                return false;
+            }
+
+            self.progress.lines_ignored += 1;
+
+            if let Some(callback) = &self.config.ignored_line_callback {
+                callback.report(bytes, self.line_count);
+            }
+
+            return true;
+        }
+
+        let item = if is_empty(bytes) {
+            QueuedItem::EmptyLine
+        }
+        else if str::from_utf8(bytes).is_ok_and(is_blank) {
+            QueuedItem::BlankLine
+        }
+        else {
+            return false;
+        };
+
+        self.out_queue.push_back(item);
+        true
+    }
+
+    fn push_parsed(&mut self, result: NdjsonResult<T>, is_rest: bool, line: Vec<u8>) {
+        self.out_queue.push_back(QueuedItem::Parsed {
+            result,
+            is_rest,
+            line_number: self.line_count,
+            offset: self.record_offset,
+            line
+        });
+        self.progress.records_emitted += 1;
+
+        if let Some((ProgressInterval::Records(interval), callback)) =
+                &self.config.progress_reporting {
+            if self.progress.records_emitted.is_multiple_of((*interval).max(1)) {
+                callback.report(self.progress);
+            }
+        }
+    }
+
+    /// Queues the error constructed by `make_error` for a record which could not be parsed, together
+    /// with the given line if failed lines are stored. If invalid records are skipped according to
+    /// [InvalidLineHandling::Skip], neither the error nor the line is materialized.
+    fn push_error(&mut self, make_error: impl FnOnce() -> NdjsonError, is_rest: bool, line: &[u8]) {
+        if self.config.invalid_line_handling == InvalidLineHandling::Skip {
+            self.progress.invalid_records_skipped += 1;
+            return;
+        }
+
+        let line = if self.stores_failed_lines() { line.to_vec() } else { Vec::new() };
+
+        self.push_parsed(Err(make_error()), is_rest, line);
+    }
+
+    /// Queues the given result of parsing the given line. See [NdjsonEngine::push_error] for
+    /// errors. Records rejected by the record validator are reported as errors as well.
+    fn push_result(&mut self, result: NdjsonResult<T>, is_rest: bool, line: &[u8]) {
+        let validation = match (&result, &self.record_validator) {
+            (Ok(record), Some(validator)) => validator(record),
+            _ => Ok(())
+        };
+
+        match (result, validation) {
+            (Ok(record), Ok(())) => self.push_parsed(Ok(record), is_rest, Vec::new()),
+            (Ok(_), Err(message)) =>
+                self.push_error(|| NdjsonError::InvalidRecord { message }, is_rest, line),
+            (Err(error), _) => self.push_error(|| error, is_rest, line)
+        }
+    }
+
+    /// Adds a record of the given length to the size histogram and reports it to the configured
+    /// [LargeRecordCallback](crate::config::LargeRecordCallback) if it exceeds the threshold.
+    fn track_record_size(&mut self, length: usize) {
+        self.size_histogram.record(length as u64);
+
+        if let Some((threshold, callback)) = &self.config.large_record_reporting {
+            if length > *threshold {
+                callback.report(length, self.line_count);
+            }
+        }
+    }
+
+    fn handle_split_line(&mut self, line: SplitLine<'_>, offset: u64,
+            empty_line_handling: EmptyLineHandling, is_rest: bool,
+            parse: &mut impl LineParseFn<T>) {
+        if self.is_finished() {
+            return;
+        }
+
+        self.record_offset = offset;
+
+        match line {
+            SplitLine::Complete(bytes) =>
+                self.handle_line(bytes, empty_line_handling, is_rest, parse),
+            SplitLine::Oversized { prefix, length } =>
+                self.handle_oversized_line(prefix, length, offset, is_rest)
+        }
+    }
+
+    fn handle_oversized_line(&mut self, prefix: &[u8], length: usize, offset: u64, is_rest: bool) {
+        self.line_count += 1;
+
+        if self.lines_to_skip > 0 {
+            self.lines_to_skip -= 1;
+            return;
+        }
+
+        // The prefix is preprocessed like complete lines, so it does not reveal redacted values.
+        let prefix = match &self.config.line_preprocessor {
+            Some(line_preprocessor) => line_preprocessor.preprocess(prefix),
+            None => Cow::Borrowed(prefix)
+        };
+        let max_line_length = self.config.max_line_length.unwrap_or(usize::MAX);
+        let make_error = || NdjsonError::LineTooLong {
+            offset,
+            length,
+            max_line_length,
+            prefix: prefix.to_vec()
+        };
+
+        if self.expects_header {
+            self.expects_header = false;
+            self.header_line = Some(Err(make_error()));
+            return;
+        }
+
+        if self.records_to_skip > 0 {
+            self.records_to_skip -= 1;
+            return;
+        }
+
+        self.track_record_size(length);
+        self.push_error(make_error, is_rest, &prefix);
+    }
+
+    fn handle_line(&mut self, bytes: &[u8], empty_line_handling: EmptyLineHandling,
+            is_rest: bool, parse: &mut impl LineParseFn<T>) {
+        self.line_count += 1;
+
+        if self.lines_to_skip > 0 {
+            self.lines_to_skip -= 1;
+            return;
+        }
+
+        if let Some(comment_prefix) = &self.config.comment_prefix {
+            if bytes.starts_with(comment_prefix) {
+                if self.emit_events {
+                    self.out_queue.push_back(QueuedItem::CommentLine);
+                }
+
+                return;
+            }
+        }
+
+        let bytes = match &self.config.line_preprocessor {
+            Some(line_preprocessor) => line_preprocessor.preprocess(bytes),
+            None => Cow::Borrowed(bytes)
+        };
+
+        if self.handle_ignored_line(&bytes, empty_line_handling) {
+            return;
+        }
+
+        let transformed = match &self.config.line_middleware {
+            Some(SharedMiddleware(line_middleware)) => line_middleware.transform(&bytes),
+            None => Ok(Cow::Borrowed(&*bytes))
+        };
+        let transformed = match (transformed, self.config.key_case) {
+            (Ok(transformed), Some(key_case)) =>
+                Ok(key_case::normalize_keys_cow(transformed, key_case)),
+            (transformed, _) => transformed
+        };
+
+        if self.expects_header {
+            self.expects_header = false;
+            self.header_line = Some(transformed.map(Cow::into_owned));
+            return;
+        }
+
+        if let (Some(end_sentinel), Ok(transformed)) = (&self.config.end_sentinel, &transformed) {
+            if end_sentinel.is_sentinel(transformed) {
+                self.end_sentinel_seen = true;
+                return;
+            }
+        }
+
+        if self.records_to_skip > 0 {
+            self.records_to_skip -= 1;
+            return;
+        }
+
+        let bytes = match transformed {
+            Ok(transformed) => transformed,
+            Err(error) => {
+                self.track_record_size(bytes.len());
+                self.push_error(|| error, is_rest, &bytes);
+                return;
+            }
+        };
+
+        self.track_record_size(bytes.len());
+
+        if self.config.trailing_data_handling == TrailingDataHandling::MultipleValues {
+            self.handle_multiple_values(&bytes, is_rest, parse);
+            return;
+        }
+
+        let result = self.parse_measured(&bytes, parse);
+        let salvaged = match (&result, self.config.salvage) {
+            (Err(_), true) => self.salvage(&bytes, parse),
+            _ => None
+        };
+        let corrupted_len = salvaged.as_ref().map_or(bytes.len(), |(start, _)| *start);
+
+        self.push_result(result, is_rest, &bytes[..corrupted_len]);
+
+        if let Some((start, record)) = salvaged {
+            if !self.is_max_records_reached() {
+                self.record_offset += start as u64;
+                self.push_result(Ok(record), is_rest, &bytes[start..]);
+            }
+        }
+    }
+
+    /// Parses every JSON value in the given line as a separate record, according to
+    /// [TrailingDataHandling::MultipleValues]. Once a value cannot be parsed, the rest of the line
+    /// is reported as a single parse error.
+    fn handle_multiple_values(&mut self, bytes: &[u8], is_rest: bool,
+            parse: &mut impl LineParseFn<T>) {
+        let mut values = serde_json::Deserializer::from_slice(bytes).into_iter::<IgnoredAny>();
+        let line_offset = self.record_offset;
+        let mut start = 0;
+
+        while let Some(Ok(_)) = values.next() {
+            let end = values.byte_offset();
+            self.record_offset = line_offset + start as u64;
+            let result = self.parse_measured(&bytes[start..end], parse);
+
+            self.push_result(result, is_rest, &bytes[start..end]);
+            start = end;
+
+            if self.is_max_records_reached() {
+                return;
+            }
+        }
+
+        let rest = &bytes[start..];
+
+        self.record_offset = line_offset + start as u64;
+
+        // Lines without any value, such as empty lines which are not ignored, are still reported.
+        if start == 0 || !rest.iter().all(is_json_whitespace) {
+            let result = self.parse_measured(rest, parse);
+
+            self.push_result(result, is_rest, rest);
+        }
+    }
+
+    /// Searches the given line, which could not be parsed, for the next `{"` sequence after its
+    /// start which begins a record that can be parsed until the end of the line.
+    ///
+    /// # Returns
+    ///
+    /// The index at which the salvaged record starts together with the record, or `None` if no
+    /// record could be salvaged.
+    fn salvage(&self, bytes: &[u8], parse: &mut impl LineParseFn<T>) -> Option<(usize, T)> {
+        let mut start = 1;
+
+        while let Some(index) = bytes.get(start..)?.windows(2).position(|window| window == b"{\"") {
+            start += index;
+
+            if let Ok(record) = self.parse_measured(&bytes[start..], parse) {
+                return Some((start, record));
+            }
+
+            start += 1;
+        }
+
+        None
+    }
+
+    /// Parses the given line with the given function and reports the duration to the configured
+    /// [ParseMetricsCallback](crate::config::ParseMetricsCallback), if there is one.
+    fn parse_measured(&self, bytes: &[u8], parse: &mut impl LineParseFn<T>) -> NdjsonResult<T> {
+        #[cfg(feature = "std")]
+        if let Some(callback) = &self.config.parse_metrics_callback {
+            let started = std::time::Instant::now();
+            let result = parse(bytes, &self.config);
+
+            callback.report(crate::config::ParseMetrics {
+                line_length: bytes.len(),
+                duration: started.elapsed(),
+                is_error: result.is_err()
+            });
+
+            return result;
+        }
+
+        parse(bytes, &self.config)
+    }
+
+    fn input_with(&mut self, data: impl AsBytes, parse: &mut impl LineParseFn<T>) -> usize {
+        let empty_line_handling = self.config.empty_line_handling;
+        let bytes_consumed_before = self.progress.bytes_consumed;
+        let records_emitted_before = self.progress.records_emitted;
+        let mut splitter = mem::take(&mut self.splitter);
+
+        self.progress.bytes_consumed += data.as_bytes().len() as u64;
+
+        if let Some(mut array_splitter) = self.array_splitter.take() {
+            array_splitter.split_elements(data, |element, offset|
+                self.handle_split_line(element, offset, empty_line_handling, false, parse));
+            self.array_splitter = Some(array_splitter);
+        }
+        else {
+            splitter.split_lines(data, |line, offset|
+                self.handle_split_line(line, offset, empty_line_handling, false, parse));
+        }
+
+        self.splitter = splitter;
+        self.report_byte_progress(bytes_consumed_before);
+
+        (self.progress.records_emitted - records_emitted_before) as usize
+    }
+
+    /// Reports the current progress to the configured [ProgressCallback] if the number of consumed
+    /// bytes crossed a multiple of the configured [ProgressInterval::Bytes] since the given number.
+    fn report_byte_progress(&self, bytes_consumed_before: u64) {
+        if let Some((ProgressInterval::Bytes(interval), callback)) =
+                &self.config.progress_reporting {
+            let interval = (*interval).max(1);
+
+            if bytes_consumed_before / interval < self.progress.bytes_consumed / interval {
+                callback.report(self.progress);
+            }
+        }
+    }
+
+    fn input_line_with(&mut self, line: &[u8], parse: &mut impl LineParseFn<T>) -> usize {
+        let bytes_consumed_before = self.progress.bytes_consumed;
+        let records_emitted_before = self.progress.records_emitted;
+        let length = line.len();
+        let line = match self.config.max_line_length {
+            Some(max_line_length) if length > max_line_length => {
+                let prefix_len = self.config.oversized_line_prefix_len
+                    .unwrap_or(DEFAULT_OVERSIZED_LINE_PREFIX_LEN)
+                    .min(length);
+
+                SplitLine::Oversized {
+                    prefix: &line[..prefix_len],
+                    length
+                }
+            },
+            _ => SplitLine::Complete(line)
+        };
+
+        self.progress.bytes_consumed += length as u64;
+        self.handle_split_line(line, bytes_consumed_before, self.config.empty_line_handling, false,
+            parse);
+        self.report_byte_progress(bytes_consumed_before);
+
+        (self.progress.records_emitted - records_emitted_before) as usize
+    }
+
+    fn finalize_with(&mut self, parse: &mut impl LineParseFn<T>) -> usize {
+        let records_emitted_before = self.progress.records_emitted;
+
+        if let Some(mut array_splitter) = self.array_splitter.take() {
+            let empty_line_handling = self.config.empty_line_handling;
+
+            if self.config.parse_rest {
+                array_splitter.finalize_elements(|element, offset|
+                    self.handle_split_line(element, offset, empty_line_handling, true, parse));
+            }
+            else {
+                array_splitter.finalize_elements(|_, _| { });
+            }
+
+            self.array_splitter = Some(array_splitter);
+            return (self.progress.records_emitted - records_emitted_before) as usize;
+        }
+
+        let mut splitter = mem::take(&mut self.splitter);
+
+        if self.config.parse_rest {
+            let empty_line_handling = match self.config.empty_line_handling {
+                EmptyLineHandling::ParseAlways => EmptyLineHandling::IgnoreEmpty,
+                empty_line_handling => empty_line_handling
+            };
+
+            splitter.finalize_lines(|rest, offset|
+                self.handle_split_line(rest, offset, empty_line_handling, true, parse));
+        }
+        else {
+            splitter.finalize_lines(|_, _| { });
+        }
+
+        self.splitter = splitter;
+
+        (self.progress.records_emitted - records_emitted_before) as usize
+    }
+
+    /// Parses the given data as NDJSON just like [NdjsonEngine::input], but deserializes every
+    /// completed line with a clone of the given [DeserializeSeed] instead of requiring
+    /// `T: Deserialize`. This enables stateful deserialization, such as interning strings or
+    /// decoding records according to a schema known only at runtime. The [NdjsonConfig] of this
+    /// engine is respected, but a deserializer set by [NdjsonEngine::with_deserializer] is ignored.
+    ///
+    /// To deserialize lines with a seed only when they are removed from the queue, use
+    /// [RawNdjsonEngine::pop_seed] instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    ///
+    /// use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// /// Numbers every record in order of deserialization.
+    /// #[derive(Clone, Copy)]
+    /// struct Numbered<'counter>(&'counter Cell<usize>);
+    ///
+    /// impl<'de> DeserializeSeed<'de> for Numbered<'_> {
+    ///     type Value = (usize, String);
+    ///
+    ///     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>
+    ///     {
+    ///         let number = self.0.replace(self.0.get() + 1);
+    ///
+    ///         Ok((number, String::deserialize(deserializer)?))
+    ///     }
+    /// }
+    ///
+    /// let counter = Cell::new(0);
+    /// let mut engine = NdjsonEngine::new();
+    ///
+    /// assert_eq!(engine.input_seeded("\"a\"\n\"b\"\n", Numbered(&counter)), 2);
+    /// assert_eq!(engine.pop().unwrap().unwrap(), (0, "a".to_owned()));
+    /// assert_eq!(engine.pop().unwrap().unwrap(), (1, "b".to_owned()));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items by this chunk.
+    pub fn input_seeded<S>(&mut self, data: impl AsBytes, seed: S) -> usize
+    where
+        S: Clone + for<'deserialize> DeserializeSeed<'deserialize, Value = T>
+    {
+        self.input_with(data, &mut |bytes, config| parse_line_seed(bytes, config, seed.clone()))
+    }
+
+    /// Parses the rest leftover from previous calls to [NdjsonEngine::input_seeded] with the given
+    /// [DeserializeSeed] under the same conditions as [NdjsonEngine::finalize].
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items, i.e. `1` if the rest was parsed and `0` otherwise.
+    pub fn finalize_seeded<S>(&mut self, seed: S) -> usize
+    where
+        S: Clone + for<'deserialize> DeserializeSeed<'deserialize, Value = T>
+    {
+        self.finalize_with(&mut |bytes, config| parse_line_seed(bytes, config, seed.clone()))
+    }
+}
+
+impl<T> NdjsonEngine<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>
+{
+
+    /// Creates the function used to parse lines given to [NdjsonEngine::input], which uses the
+    /// custom deserializer of this engine, if there is one, and [serde_json] otherwise.
+    fn default_parse_fn(&self) -> impl FnMut(&[u8], &NdjsonConfig) -> NdjsonResult<T> {
+        let deserializer = self.deserializer.clone();
+
+        move |bytes, config| match &deserializer {
+            Some(deserializer) => deserializer(bytes),
+            None => parse_line(bytes, config)
+        }
+    }
+
+    /// Takes the header line stored since [NdjsonEngine::expect_header] was called and parses it
+    /// as `H` according to the configuration of this engine, ignoring a custom deserializer.
+    ///
+    /// # Returns
+    ///
+    /// The parsed header, or `None` if no header line was received since the last call.
+    #[cfg(any(feature = "iter", feature = "stream"))]
+    pub(crate) fn take_header<H>(&mut self) -> Option<NdjsonResult<H>>
+    where
+        for<'deserialize> H: Deserialize<'deserialize>
+    {
+        let header_line = self.header_line.take()?;
+
+        Some(header_line.and_then(|line| parse_line(&line, &self.config)))
+    }
+
+    /// Parses the given data as NDJSON. In case the end does not match up with a newline, the rest
+    /// is stored in an internal cache. Consequently, the rest from a previous call to this method
+    /// is prepended to the given data in case a newline is encountered.
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items by this chunk. This can be used for backpressure or metrics without having to count
+    /// while popping.
+    pub fn input(&mut self, data: impl AsBytes) -> usize {
+        let mut parse = self.default_parse_fn();
+
+        self.input_with(data, &mut parse)
+    }
+
+    /// Parses the given data as a single complete line without its line ending, bypassing the
+    /// search for line endings. This is intended for input which is already split into lines, such
+    /// as the output of [BufRead::lines](std::io::BufRead::lines) or a message queue where every
+    /// message holds one record. All options of the [NdjsonConfig] which apply to individual lines
+    /// are respected, such as the [EmptyLineHandling], comment prefix, and maximum line length.
+    /// The line ending and JSON-array-mode do not apply. Offsets of oversized lines count the bytes
+    /// of all lines given to this method, without line endings.
+    ///
+    /// This method should not be mixed with [NdjsonEngine::input], as the incomplete line held by
+    /// this engine is not terminated by the given line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let config = NdjsonConfig::default()
+    ///     .with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+    /// let mut engine = NdjsonEngine::<u32>::with_config(config);
+    ///
+    /// assert_eq!(engine.input_line("1"), 1);
+    /// assert_eq!(engine.input_line("  "), 0);
+    /// assert!(matches!(engine.pop(), Some(Ok(1))));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items, i.e. `1` if the line was parsed and `0` otherwise.
+    pub fn input_line(&mut self, line: impl AsBytes) -> usize {
+        let mut parse = self.default_parse_fn();
+
+        self.input_line_with(line.as_bytes(), &mut parse)
+    }
+
+    /// Parses the given data, which is assumed to contain the entire remaining input, and finalizes
+    /// this engine afterwards. This is equivalent to calling [NdjsonEngine::input] followed by
+    /// [NdjsonEngine::finalize] and intended for documents which are entirely present in memory.
+    /// The data is split and parsed in a single pass, such that only the rest after the last line
+    /// ending is buffered before it is parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::config::NdjsonConfig;
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let config = NdjsonConfig::default().with_parse_rest(true);
+    /// let mut engine = NdjsonEngine::<u32>::with_config(config);
+    ///
+    /// assert_eq!(engine.input_complete("1\n2\n3"), 3);
+    /// assert!(matches!(engine.pop(), Some(Ok(1))));
+    /// assert!(matches!(engine.pop(), Some(Ok(2))));
+    /// assert!(matches!(engine.pop(), Some(Ok(3))));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items.
+    pub fn input_complete(&mut self, data: impl AsBytes) -> usize {
+        self.input(data) + self.finalize()
+    }
+
+    /// Parses the given non-contiguous data, such as the two halves of a ring buffer, by giving
+    /// every contiguous chunk to [NdjsonEngine::input] in order. Lines spanning several chunks are
+    /// joined as they would be across calls to [NdjsonEngine::input].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::VecDeque;
+    ///
+    /// use ndjson_stream::engine::NdjsonEngine;
+    ///
+    /// let ring_buffer = VecDeque::from(b"1\n2\n".to_vec());
+    /// let mut engine = NdjsonEngine::<u32>::new();
+    ///
+    /// assert_eq!(engine.input_chunks(&ring_buffer), 2);
+    /// assert!(matches!(engine.pop(), Some(Ok(1))));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items by all chunks.
+    pub fn input_chunks<C>(&mut self, data: &C) -> usize
+    where
+        C: AsByteChunks + ?Sized
+    {
+        let mut added_records = 0;
+
+        data.for_each_chunk(&mut |chunk| added_records += self.input(chunk));
+        added_records
+    }
+
+    /// Parses the rest leftover from previous calls to [NdjsonEngine::input], i.e. the data after
+    /// the last given newline character, if all of the following conditions are met.
+    ///
+    /// * The engine uses a config with [NdjsonConfig::with_parse_rest] set to `true`.
+    /// * There is non-empty data left to parse. In other words, the previous provided input did not
+    ///   end with a newline character.
+    /// * The rest is not considered empty by the handling configured in
+    ///   [NdjsonConfig::with_empty_line_handling]. That is, if the rest consists only of whitespace
+    ///   and [EmptyLineHandling::IgnoreBlank] is used, the rest is not parsed.
+    ///
+    /// In any case, the rest is discarded from the input buffer. Therefore, this function is
+    /// idempotent.
+    ///
+    /// Note: This function is intended to be called after the input ended, but there is no
+    /// validation in place to check that [NdjsonEngine::input] is not called afterwards. Doing this
+    /// anyway may lead to unexpected behavior, as JSON-lines may be partially discarded.
+    ///
+    /// # Returns
+    ///
+    /// The number of records, including parse errors, which were added to the queue of parsed
+    /// items, i.e. `1` if the rest was parsed and `0` otherwise.
+    pub fn finalize(&mut self) -> usize {
+        let mut parse = self.default_parse_fn();
+
+        self.finalize_with(&mut parse)
+    }
+}
+
+impl<T> Default for NdjsonEngine<T> {
+    fn default() -> NdjsonEngine<T> {
+        NdjsonEngine::new()
+    }
+}
+
+impl<T, B> Extend<B> for NdjsonEngine<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    B: AsBytes
+{
+
+    /// Gives every data block of the given iterator to [NdjsonEngine::input] in order. The engine
+    /// is not finalized afterwards, so further data may follow.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = B>
+    {
+        for data in iter {
+            self.input(data);
+        }
+    }
+}
+
+/// Parses all data blocks of the given iterator with a new engine using the default
+/// [NdjsonConfig], finalizes it, and returns all records and parse errors in order. This is a
+/// shorthand for batch jobs which do not require lazy processing. See [parse_all_with_config] for
+/// more details.
+///
+/// # Example
+///
+/// ```
+/// let results = ndjson_stream::engine::parse_all::<u32, _>(["1\n2", "3\nx\n"]);
+///
+/// assert_eq!(results.len(), 3);
+/// assert!(matches!(results[0], Ok(1)));
+/// assert!(matches!(results[1], Ok(23)));
+/// assert!(results[2].is_err());
+/// ```
+pub fn parse_all<T, I>(data_blocks: I) -> Vec<NdjsonResult<T>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: IntoIterator,
+    I::Item: AsBytes
+{
+    parse_all_with_config(data_blocks, NdjsonConfig::default())
+}
+
+/// Parses all data blocks of the given iterator with a new engine using the given [NdjsonConfig],
+/// finalizes it, and returns all records and parse errors in order. In contrast to the drivers,
+/// all records are held in memory at once, so this is only suitable for inputs of bounded size.
+pub fn parse_all_with_config<T, I>(data_blocks: I, config: NdjsonConfig) -> Vec<NdjsonResult<T>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    I: IntoIterator,
+    I::Item: AsBytes
+{
+    let mut engine = NdjsonEngine::with_config(config);
+    let mut results = Vec::new();
+
+    for data in data_blocks {
+        engine.input(data);
+        engine.drain_into(&mut results);
+    }
+
+    engine.finalize();
+    engine.drain_into(&mut results);
+    results
+}
+
+/// An untyped variant of the [NdjsonEngine], which queues complete lines without deserializing
+/// them, such that the type of every record can be decided when it is popped via
+/// [RawNdjsonEngine::pop_as]. This is useful for multiplexed protocols in which the expected type
+/// of a record depends on earlier records.
+///
+/// Lines are split, preprocessed, and filtered according to the [NdjsonConfig] just like by the
+/// [NdjsonEngine], and oversized lines are still queued as [NdjsonError::LineTooLong]. All other
+/// options which affect parsing, such as [NdjsonConfig::with_reject_duplicate_keys], are applied
+/// once a line is popped via [RawNdjsonEngine::pop_as]. Consequently, [InvalidLineHandling::Skip]
+/// only skips oversized lines, while errors of popped lines are still returned.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::engine::RawNdjsonEngine;
+///
+/// let mut engine = RawNdjsonEngine::new();
+///
+/// engine.input("\"numbers\"\n[1,2]\n\"text\"\n\"a\"\n");
+///
+/// while let Some(kind) = engine.pop_as::<String>() {
+///     match kind.unwrap().as_str() {
+///         "numbers" => assert_eq!(engine.pop_as::<Vec<u32>>().unwrap().unwrap(), vec![1, 2]),
+///         _ => assert_eq!(engine.pop_as::<String>().unwrap().unwrap(), "a")
+///     }
+/// }
+/// ```
+pub struct RawNdjsonEngine {
+    engine: NdjsonEngine<Vec<u8>>
+}
+
+impl RawNdjsonEngine {
+
+    /// Creates a new raw NDJSON-engine with default [NdjsonConfig].
+    pub fn new() -> RawNdjsonEngine {
+        RawNdjsonEngine::with_config(NdjsonConfig::default())
+    }
+
+    /// Creates a new raw NDJSON-engine with the given [NdjsonConfig] to control its behavior. See
+    /// [NdjsonConfig] for more details.
+    pub fn with_config(config: NdjsonConfig) -> RawNdjsonEngine {
+        let engine = NdjsonEngine::with_config(config)
+            .with_deserializer(|line| Ok::<_, NdjsonError>(line.to_vec()));
+
+        RawNdjsonEngine {
+            engine
+        }
+    }
+
+    /// Gets the current [Progress] of this engine. See [NdjsonEngine::progress].
+    pub fn progress(&self) -> Progress {
+        self.engine.progress()
+    }
+
+    /// Gets the incomplete line currently held by this engine. See [NdjsonEngine::rest].
+    pub fn rest(&self) -> &[u8] {
+        self.engine.rest()
+    }
+
+    /// Splits the given data into lines and queues all complete ones, just like
+    /// [NdjsonEngine::input] does.
+    ///
+    /// # Returns
+    ///
+    /// The number of lines, including oversized ones, which were added to the queue.
+    pub fn input(&mut self, data: impl AsBytes) -> usize {
+        self.engine.input(data)
+    }
+
+    /// Queues the rest leftover from previous calls to [RawNdjsonEngine::input], if the conditions
+    /// described in [NdjsonEngine::finalize] are met.
+    ///
+    /// # Returns
+    ///
+    /// The number of lines which were added to the queue, i.e. `1` if the rest was queued and `0`
+    /// otherwise.
+    pub fn finalize(&mut self) -> usize {
+        self.engine.finalize()
+    }
+
+    /// Gets a reference to the next line in the queue without removing it, or the error for an
+    /// oversized line. If no line is available, `None` is returned.
+    pub fn peek_raw(&self) -> Option<&NdjsonResult<Vec<u8>>> {
+        self.engine.peek()
+    }
+
+    /// Removes the next line from the queue without deserializing it, or the error for an
+    /// oversized line. If no line is available, `None` is returned.
+    pub fn pop_raw(&mut self) -> Option<NdjsonResult<Vec<u8>>> {
+        self.engine.pop()
+    }
+
+    /// Removes the next line from the queue and deserializes it as `T` according to the
+    /// [NdjsonConfig] of this engine. If no line is available, `None` is returned.
+    pub fn pop_as<T>(&mut self) -> Option<NdjsonResult<T>>
+    where
+        for<'deserialize> T: Deserialize<'deserialize>
+    {
+        let line = self.engine.pop()?;
+
+        Some(line.and_then(|line| parse_line(&line, &self.engine.config)))
+    }
+
+    /// Removes the next line from the queue and deserializes it with the given [DeserializeSeed]
+    /// according to the [NdjsonConfig] of this engine. If no line is available, `None` is returned
+    /// and the seed is dropped.
+    ///
+    /// Since the seed carries state into deserialization, this allows records to be built in
+    /// memory provided by the caller, such as an arena which is reset after every batch, instead
+    /// of allocating every record on the heap. Only the queued line itself is heap-allocated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+    /// use ndjson_stream::engine::RawNdjsonEngine;
+    ///
+    /// /// Appends a sequence of numbers to a shared buffer and returns its range therein.
+    /// struct AppendTo<'buffer>(&'buffer mut Vec<u32>);
+    ///
+    /// impl<'de> DeserializeSeed<'de> for AppendTo<'_> {
+    ///     type Value = std::ops::Range<usize>;
+    ///
+    ///     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    ///     where
+    ///         D: Deserializer<'de>
+    ///     {
+    ///         let start = self.0.len();
+    ///         self.0.extend(Vec::<u32>::deserialize(deserializer)?);
+    ///         Ok(start..self.0.len())
+    ///     }
+    /// }
+    ///
+    /// let mut engine = RawNdjsonEngine::new();
+    /// let mut buffer = Vec::new();
+    ///
+    /// engine.input("[1,2]\n[3]\n");
+    ///
+    /// assert_eq!(engine.pop_seed(AppendTo(&mut buffer)).unwrap().unwrap(), 0..2);
+    /// assert_eq!(engine.pop_seed(AppendTo(&mut buffer)).unwrap().unwrap(), 2..3);
+    /// assert_eq!(buffer, vec![1, 2, 3]);
+    /// ```
+    pub fn pop_seed<S, V>(&mut self, seed: S) -> Option<NdjsonResult<V>>
+    where
+        S: for<'deserialize> DeserializeSeed<'deserialize, Value = V>
+    {
+        let line = self.engine.pop()?;
+
+        Some(line.and_then(|line| parse_line_seed(&line, &self.engine.config, seed)))
+    }
+}
+
+impl Default for RawNdjsonEngine {
+    fn default() -> RawNdjsonEngine {
+        RawNdjsonEngine::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use std::borrow::Cow;
+    use std::iter;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use crate::config::{
+        EmptyLineHandling,
+        EndSentinel,
+        IgnoredLineCallback,
+        InvalidLineHandling,
+        KeyCase,
+        LineEnding,
+        LinePreprocessor,
+        NdjsonConfig,
+        Progress,
+        ProgressCallback,
+        ProgressInterval,
+        SchemaValidator,
+        TrailingDataHandling
+    };
+
+    use serde::de::DeserializeSeed;
+    use serde::{Deserialize, Deserializer};
+
+    use crate::engine::{NdjsonEngine, RawNdjsonEngine};
+    use crate::error::{NdjsonError, NdjsonResult, SchemaViolation};
+    use crate::event::NdjsonEvent;
+    use crate::middleware::Base64Decoder;
+    use crate::test_util::TestStruct;
+
+    fn collect_output(mut engine: NdjsonEngine<TestStruct>)
+            -> Vec<NdjsonResult<TestStruct>> {
+        iter::from_fn(|| engine.pop()).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn no_input() {
+        let engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    #[test]
+    fn incomplete_input() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":3,\"val");
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    #[test]
+    fn single_exact_input() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+            ));
+    }
+
+    #[test]
+    fn single_item_split_into_two_inputs() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":42,");
+        engine.input("\"value\":24}\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 42, value: 24 })
+            ));
+    }
+
+    #[test]
+    fn two_items_in_single_input() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":1,\"value\":1}\n{\"key\":2,\"value\":2}\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 1 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 2, value: 2 })
+            ));
+    }
+
+    #[test]
+    fn two_items_in_many_inputs_with_rest() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":12,\"v");
+        engine.input("alue\":3");
+        engine.input("4}\n{\"key");
+        engine.input("\":56,\"valu");
+        engine.input("e\":78}\n{\"key\":");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 12, value: 34 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 56, value: 78 })
+            ));
+    }
+
+    #[test]
+    fn input_completing_previous_rest_then_multiple_complete_items_and_more_rest() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":9,\"value\":");
+        engine.input("8}\n{\"key\":7,\"value\":6}\n{\"key\":5,\"value\":4}\n{\"key\":");
+        engine.input("3,\"value\":2}\n{");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 9, value: 8 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 7, value: 6 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 4 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 2 })
+            ));
+    }
+
+    #[test]
+    fn carriage_return_handled_gracefully() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":1,\"value\":2}\r\n{\"key\":3,\"value\":4}\r\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+            ));
+    }
+
+    #[test]
+    fn whitespace_handled_gracefully() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("\t{ \"key\":\t13,  \"value\":   37 } \r\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 13, value: 37 })
+            ));
+    }
+
+    #[test]
+    fn erroneous_entry_emitted_as_json_error() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":1}\n{\"key\":1,\"value\":1}\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).is_err(),
+                |it| assert_that!(it).is_ok()
+            ));
+    }
+
+    #[test]
+    fn error_from_split_entry() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":100,\"value\":200}\n{\"key\":");
+        engine.input("\"should be a number\",\"value\":0}\n{\"key\":300,\"value\":400}\n");
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 100, value: 200 }),
+                |it| assert_that!(it).is_err(),
+                |it| assert_that!(it).contains_value(TestStruct { key: 300, value: 400 })
+            ));
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    fn engine_input_works_for_different_types() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::default();
+
+        engine.input(b"{\"k");
+        engine.input(b"ey\"".to_vec());
+        engine.input(":12".to_string());
+        engine.input(&mut ",\"v".to_string());
+        engine.input("alu".to_string().into_boxed_str());
+        engine.input(b"e\"".to_vec().into_boxed_slice());
+        engine.input(Arc::<str>::from(":3"));
+        engine.input(Rc::<[u8]>::from(&b"4}"[..]));
+        engine.input(Cow::Borrowed(&b"\r\n".to_vec()));
+
+        assert_that!(collect_output(engine))
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 12, value: 34 })
+            ));
+    }
+
+    #[test]
+    fn old_data_is_discarded() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+        let count = 20;
+
+        engine.input("{ \"key\": 1, ");
+
+        for _ in 0..(count - 1) {
+            engine.input("\"value\": 2 }\r\n{ \"key\": 1, ");
+        }
+
+        engine.input("\"value\": 2 }\r\n");
+
+        assert_that!(engine.splitter.rest()).is_empty();
+        assert_that!(engine.out_queue.len()).is_equal_to(count);
+    }
+
+    fn configured_engine(configure: impl FnOnce(NdjsonConfig) -> NdjsonConfig)
+            -> NdjsonEngine<TestStruct> {
+        let config = configure(NdjsonConfig::default());
+        NdjsonEngine::with_config(config)
+    }
+
+    fn engine_with_empty_line_handling(empty_line_handling: EmptyLineHandling)
+            -> NdjsonEngine<TestStruct> {
+        configured_engine(|config| config.with_empty_line_handling(empty_line_handling))
+    }
+
+    #[test]
+    fn raises_error_when_parsing_empty_line_in_parse_always_mode() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::ParseAlways);
+
+        engine.input("{\"key\":1,\"value\":2}\n\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).contains_elements_matching(Result::is_err);
+    }
+
+    #[test]
+    fn does_not_raise_error_when_parsing_empty_line_in_ignore_empty_mode() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+
+        engine.input("{\"key\":1,\"value\":2}\n\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).does_not_contain_elements_matching(Result::is_err);
+    }
+
+    #[test]
+    fn does_not_raise_error_when_parsing_empty_line_with_carriage_return_in_ignore_empty_mode() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+
+        engine.input("{\"key\":1,\"value\":2}\r\n\r\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).does_not_contain_elements_matching(Result::is_err);
+    }
+
+    #[test]
+    fn raises_error_when_parsing_non_empty_blank_line_in_ignore_empty_mode() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+
+        engine.input("{\"key\":1,\"value\":2}\n \t\r\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).contains_elements_matching(Result::is_err);
+    }
+
+    #[test]
+    fn does_not_raise_error_when_parsing_non_empty_blank_line_in_ignore_blank_mode() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+
+        engine.input("{\"key\":1,\"value\":2}\n \t\r\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).does_not_contain_elements_matching(Result::is_err);
+    }
+
+    #[test]
+    fn finalize_ignores_rest_if_parse_rest_is_false() {
+        let mut engine = configured_engine(|config| config.with_parse_rest(false));
+
+        engine.input("{\"key\":1,\"value\":2}");
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    #[test]
+    fn finalize_parses_valid_rest() {
+        const EMPTY_LINE_HANDLINGS: [EmptyLineHandling; 3] = [
+            EmptyLineHandling::ParseAlways,
+            EmptyLineHandling::IgnoreEmpty,
+            EmptyLineHandling::IgnoreBlank
+        ];
+
+        for empty_line_handling in EMPTY_LINE_HANDLINGS {
+            let mut engine = configured_engine(|config| config
+                .with_empty_line_handling(empty_line_handling)
+                .with_parse_rest(true));
+
+            engine.input("{\"key\":1,\"value\":2}");
+            engine.finalize();
+
+            assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+            ));
+        }
+    }
+
+    #[test]
+    fn finalize_raises_error_on_invalid_rest() {
+        let mut engine = configured_engine(|config| config.with_parse_rest(true));
+
+        engine.input("invalid json");
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn finalize_ignores_empty_rest_even_if_empty_line_handling_is_parse_always() {
+        let mut engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::ParseAlways)
+            .with_parse_rest(true));
+
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    #[test]
+    fn finalize_ignores_empty_rest_if_empty_line_handling_is_ignore_empty() {
+        let mut engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_parse_rest(true));
+
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    #[test]
+    fn finalize_does_not_ignore_non_empty_blank_rest_if_empty_line_handling_is_ignore_empty() {
+        let mut engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_parse_rest(true));
+
+        engine.input(" ");
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn finalize_ignores_non_empty_blank_rest_if_empty_line_handling_is_ignore_blank() {
+        let mut engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_parse_rest(true));
+
+        engine.input(" ");
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    #[test]
+    fn finalize_is_idempotent() {
+        let mut engine = configured_engine(|config| config.with_parse_rest(true));
+
+        engine.input("{\"key\":13,\"value\":37}");
+        engine.finalize();
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 13, value: 37 })
+        ));
+    }
+
+    fn engine_with_trailing_data_handling(trailing_data_handling: TrailingDataHandling)
+            -> NdjsonEngine<TestStruct> {
+        configured_engine(|config| config.with_trailing_data_handling(trailing_data_handling))
+    }
+
+    #[test]
+    fn raises_error_on_trailing_data_in_error_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::Error);
+
+        engine.input("{\"key\":1,\"value\":2} trailing\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn ignores_trailing_data_in_ignore_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::Ignore);
+
+        engine.input("{\"key\":1,\"value\":2} trailing\n{\"key\":3,\"value\":4}{}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn raises_error_containing_trailing_data_in_include_in_error_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::IncludeInError);
+
+        engine.input("{\"key\":1,\"value\":2} trailing\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it.as_ref().unwrap_err().to_string()).contains("trailing")
+        ));
+    }
+
+    #[test]
+    fn accepts_trailing_whitespace_in_all_trailing_data_handling_modes() {
+        const TRAILING_DATA_HANDLINGS: [TrailingDataHandling; 4] = [
+            TrailingDataHandling::Error,
+            TrailingDataHandling::Ignore,
+            TrailingDataHandling::IncludeInError,
+            TrailingDataHandling::MultipleValues
+        ];
+
+        for trailing_data_handling in TRAILING_DATA_HANDLINGS {
+            let mut engine = engine_with_trailing_data_handling(trailing_data_handling);
+
+            engine.input("{\"key\":1,\"value\":2} \t\r\n");
+
+            assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_form_feed_in_include_in_error_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::IncludeInError);
+
+        engine.input("{\"key\":1,\"value\":2}\x0c\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn outputs_every_value_in_multiple_values_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::MultipleValues);
+
+        engine.input("{\"key\":1,\"value\":2} {\"key\":3,\"value\":4}{\"key\":5,\"value\":6}\n");
+        engine.input("{\"key\":7,\"value\":8} trailing {\"key\":9,\"value\":10}\n\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 7, value: 8 }),
+            |it| assert_that!(it).is_err(),
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn reports_form_feed_after_last_value_in_multiple_values_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::MultipleValues);
+
+        engine.input("{\"key\":1,\"value\":2}\x0c\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn multiple_values_respect_max_records() {
+        let config = NdjsonConfig::default()
+            .with_trailing_data_handling(TrailingDataHandling::MultipleValues)
+            .with_max_records(Some(2));
+        let mut engine = NdjsonEngine::<u32>::with_config(config);
+
+        assert_that!(engine.input("1 2 3\n4\n")).is_equal_to(2);
+    }
+
+    #[test]
+    fn raises_error_on_empty_line_in_ignore_trailing_data_mode() {
+        let mut engine = engine_with_trailing_data_handling(TrailingDataHandling::Ignore);
+
+        engine.input("\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn skip_lines_discards_following_lines_including_empty_ones() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+
+        engine.skip_lines(2);
+        engine.input("\ninvalid json\n{\"key\":1,\"value\":2}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+        ));
+    }
+
+    #[test]
+    fn skip_lines_accumulates_and_spans_multiple_inputs() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.skip_lines(1);
+        engine.skip_lines(1);
+        engine.input("invalid ");
+        engine.input("json\ninvalid json\n{\"key\":3,");
+        engine.input("\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn skip_records_does_not_count_ignored_lines() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+
+        engine.skip_records(1);
+        engine.input(" \ninvalid json\n \n{\"key\":5,\"value\":6}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 })
+        ));
+    }
+
+    #[test]
+    fn skip_records_discards_queued_records_first() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":1,\"value\":1}\n");
+        engine.skip_records(2);
+        engine.input("{\"key\":2,\"value\":2}\n{\"key\":3,\"value\":3}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 3 })
+        ));
+    }
+
+    #[test]
+    fn skip_records_applies_to_finalized_rest() {
+        let mut engine = configured_engine(|config| config.with_parse_rest(true));
+
+        engine.skip_records(1);
+        engine.input("{\"key\":1,\"value\":2}");
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).is_empty();
+    }
+
+    fn engine_rejecting_duplicate_keys<T>() -> NdjsonEngine<T> {
+        NdjsonEngine::with_config(NdjsonConfig::default().with_reject_duplicate_keys(true))
+    }
+
+    #[test]
+    fn accepts_duplicate_keys_by_default() {
+        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+
+        engine.input("{\"key\":1,\"value\":2,\"key\":3}\n");
+
+        assert_that!(collect_output(engine)).does_not_contain_elements_matching(|res| matches!(
+            res, Err(NdjsonError::DuplicateKey { .. })));
+    }
+
+    #[test]
+    fn rejects_duplicate_top_level_key_if_configured() {
+        let mut engine = engine_rejecting_duplicate_keys::<serde_json::Value>();
+
+        engine.input("{\"key\":1,\"value\":2,\"key\":3}\n{\"key\":1,\"value\":2}\n");
+
+        assert_that!(iter::from_fn(|| engine.pop()).collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(
+                    matches!(it, Err(NdjsonError::DuplicateKey { key }) if key == "key")).is_true(),
+                |it| assert_that!(it).is_ok()
+            ));
+    }
+
+    #[test]
+    fn rejects_duplicate_nested_key_if_configured() {
+        let mut engine = engine_rejecting_duplicate_keys::<serde_json::Value>();
+
+        engine.input("[{\"a\":{\"b\":1}},{\"a\":{\"c\":1,\"c\":2}}]\n");
+
+        let first = engine.pop();
+
+        assert_that!(matches!(first, Some(Err(NdjsonError::DuplicateKey { key })) if key == "c"))
+            .is_true();
+    }
+
+    #[test]
+    fn same_key_in_different_objects_is_not_a_duplicate() {
+        let mut engine = engine_rejecting_duplicate_keys::<serde_json::Value>();
+
+        engine.input("{\"a\":{\"key\":1},\"b\":{\"key\":2},\"key\":3}\n");
+
+        assert_that!(engine.pop()).to_value().is_ok();
+    }
+
+    #[test]
+    fn invalid_json_is_reported_as_json_error_if_rejecting_duplicate_keys() {
+        let mut engine = engine_rejecting_duplicate_keys::<serde_json::Value>();
+
+        engine.input("{\"a\":1,\"a\"\n");
+
+        assert_that!(matches!(engine.pop(), Some(Err(NdjsonError::Json(_))))).is_true();
+    }
+
+    fn positive_key_validator() -> SchemaValidator {
+        SchemaValidator::new(|value| match value.get("key") {
+            Some(key) if key.as_i64().is_some_and(|key| key > 0) => Ok(()),
+            _ => Err(SchemaViolation::new("/key", "expected positive integer"))
+        })
+    }
+
+    fn engine_requiring_positive_key() -> NdjsonEngine<TestStruct> {
+        configured_engine(|config| config.with_schema_validator(positive_key_validator()))
+    }
+
+    #[test]
+    fn reports_schema_violation_instead_of_record() {
+        let mut engine = engine_requiring_positive_key();
+
+        engine.input("{\"key\":1,\"value\":2}\n{\"key\":-3,\"value\":4}\n");
+
+        assert_that!(iter::from_fn(|| engine.pop()).collect::<Vec<_>>())
+            .satisfies_exactly_in_given_order(dyn_assertions!(
+                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+                |it| assert_that!(matches!(it, Err(NdjsonError::SchemaViolation(violation))
+                    if violation.pointer == "/key")).is_true()
+            ));
+    }
+
+    #[test]
+    fn schema_violation_precedes_typed_parsing() {
+        let mut engine = engine_requiring_positive_key();
+
+        engine.input("{\"key\":0}\n");
+
+        assert_that!(matches!(engine.pop(), Some(Err(NdjsonError::SchemaViolation(_))))).is_true();
+    }
+
+    #[test]
+    fn invalid_json_is_reported_as_json_error_if_checking_schema() {
+        let mut engine = engine_requiring_positive_key();
+
+        engine.input("{\"key\":1,\n");
+
+        assert_that!(matches!(engine.pop(), Some(Err(NdjsonError::Json(_))))).is_true();
+    }
+
+    #[test]
+    fn schema_is_checked_for_every_value_in_multiple_values_mode() {
+        let mut engine = configured_engine(|config| config
+            .with_trailing_data_handling(TrailingDataHandling::MultipleValues)
+            .with_schema_validator(positive_key_validator()));
+
+        engine.input("{\"key\":1,\"value\":2} {\"key\":0,\"value\":3}\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(matches!(engine.pop(), Some(Err(NdjsonError::SchemaViolation(_))))).is_true();
+    }
+
+    #[test]
+    fn cr_line_ending_separates_records_if_configured() {
+        let mut engine = configured_engine(|config| config.with_line_ending(LineEnding::Cr));
+
+        engine.input("{\"key\":1,\"value\":2}\r{\"key\":3,");
+        engine.input("\"value\":4}\r");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn any_line_ending_does_not_produce_empty_line_for_cr_lf() {
+        let mut engine = configured_engine(|config| config.with_line_ending(LineEnding::Any));
+
+        engine.input("{\"key\":1,\"value\":2}\r");
+        engine.input("\n{\"key\":3,\"value\":4}\r{\"key\":5,\"value\":6}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 })
+        ));
+    }
+
+    #[test]
+    fn lines_with_comment_prefix_are_ignored() {
+        let mut engine = configured_engine(|config| config.with_comment_prefix("//"));
+
+        engine.input("// header\n{\"key\":1,\"value\":2}\n/ not a comment\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn empty_comment_prefix_disables_comment_detection() {
+        let mut engine = configured_engine(|config| config
+            .with_comment_prefix("#")
+            .with_comment_prefix(""));
+
+        engine.input("#\n");
+
+        assert_that!(engine.pop()).to_value().is_err();
+    }
+
+    #[test]
+    fn line_preprocessor_is_applied_before_parsing() {
+        let preprocessor = LinePreprocessor::new(|line| match line.strip_prefix(b"data: ") {
+            Some(stripped) => Cow::Borrowed(stripped),
+            None => Cow::Borrowed(line)
+        });
+        let mut engine = configured_engine(|config| config
+            .with_line_preprocessor(preprocessor)
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty));
+
+        engine.input("data: {\"key\":1,\"value\":2}\ndata: \n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn line_middleware_is_applied_after_ignoring_empty_lines() {
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_line_middleware(Base64Decoder::new()));
+
+        // "eyJrZXkiOjEsInZhbHVlIjoyfQ" is the base64-encoding of `{"key":1,"value":2}`
+        engine.input("eyJrZXkiOjEsInZhbHVlIjoyfQ
+
+not base64
+");
+
+        assert_that!(collect_events(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(matches!(it, NdjsonEvent::Record(TestStruct { key: 1, value: 2 })))
+                .is_true(),
+            |it| assert_that!(matches!(it, NdjsonEvent::EmptyLine)).is_true(),
+            |it| assert_that!(matches!(it, NdjsonEvent::ParseError {
+                error: NdjsonError::Custom(_),
+                line
+            } if line == b"not base64")).is_true()
+        ));
+    }
+
+    #[test]
+    fn located_errors_have_absolute_offsets_across_chunks() {
+        let mut engine = NdjsonEngine::<TestStruct>::new();
+
+        engine.input("{\"key\":1,\"value\":2}\n{\"ke");
+        engine.input("y\":3,\"value\":x}\n[");
+
+        assert_that!(engine.pop_located()).to_value().is_ok();
+
+        let error = engine.pop_located().unwrap().unwrap_err();
+
+        assert_that!(error.line_number).is_equal_to(2);
+        assert_that!(error.line_offset).is_equal_to(20);
+        assert_that!(error.offset()).is_equal_to(37);
+        assert_that!(error.to_string()).starts_with("line 2 (offset 37): ");
+    }
+
+    #[test]
+    fn located_errors_of_multiple_values_refer_to_offending_value() {
+        let mut engine = NdjsonEngine::<u32>::with_config(NdjsonConfig::default()
+            .with_trailing_data_handling(TrailingDataHandling::MultipleValues));
+
+        engine.input("1\n2 3 x\n");
+
+        assert_that!(iter::from_fn(|| engine.pop_located()).map(|result| result.map_err(|error|
+            (error.line_offset, error.offset()))).collect::<Vec<_>>())
+            .contains_exactly_in_given_order([Ok(1), Ok(2), Ok(3), Err((5, 6))]);
+    }
+
+    #[test]
+    fn key_case_is_normalized_in_nested_objects() {
+        let mut engine = NdjsonEngine::<serde_json::Value>::with_config(
+            NdjsonConfig::default().with_key_case(KeyCase::Snake));
+
+        engine.input("{\"someKey\":[{\"nestedKey\":\"valueCase\"}]}\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(
+            serde_json::json!({ "some_key": [{ "nested_key": "valueCase" }] }));
+    }
+
+    #[test]
+    fn keys_are_normalized_before_checking_for_duplicates() {
+        let mut engine = NdjsonEngine::<serde_json::Value>::with_config(NdjsonConfig::default()
+            .with_key_case(KeyCase::Camel)
+            .with_reject_duplicate_keys(true));
+
+        engine.input("{\"user_id\":1,\"userId\":2}\n");
+
+        assert_that!(matches!(engine.pop(), Some(Err(NdjsonError::DuplicateKey { key }))
+            if key == "userId")).is_true();
+    }
+
+    #[test]
+    fn redacted_values_do_not_appear_in_failed_lines() {
+        let preprocessor = LinePreprocessor::redacting_keys(["password"]);
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(
+            NdjsonConfig::default().with_line_preprocessor(preprocessor));
+
+        engine.input("{\"key\":1,\"password\":\"secret\"}\n");
+
+        assert_that!(collect_events(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(matches!(it, NdjsonEvent::ParseError { line, .. }
+                if line == br#"{"key":1,"password":"[REDACTED]"}"#)).is_true()
+        ));
+    }
+
+    #[test]
+    fn redacted_values_do_not_appear_in_prefixes_of_oversized_lines() {
+        let preprocessor = LinePreprocessor::redacting_keys(["password"]);
+        let config = NdjsonConfig::default()
+            .with_line_preprocessor(preprocessor)
+            .with_max_line_length(30);
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(config.clone());
+        let mut truncating_engine =
+            NdjsonEngine::<TestStruct>::with_events(config.with_oversized_line_prefix_len(18));
+        let line = "{\"password\":\"hunter2\",\"key\":1,\"value\":2}\n";
+
+        engine.input(line);
+        truncating_engine.input(line);
+
+        let events = collect_events(engine).into_iter().chain(collect_events(truncating_engine));
+
+        assert_that!(events.collect::<Vec<_>>()).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(matches!(it, NdjsonEvent::ParseError {
+                error: error @ NdjsonError::LineTooLong { prefix, .. },
+                line
+            } if prefix == br#"{"password":"[REDACTED]","key":1,"# && line == prefix
+                && !error.to_string().contains("hunter2"))).is_true(),
+            |it| assert_that!(matches!(it, NdjsonEvent::ParseError {
+                error: error @ NdjsonError::LineTooLong { prefix, .. },
+                line
+            } if prefix == br#"{"password":"[REDACTED]""# && line == prefix
+                && !error.to_string().contains("hunt"))).is_true()
+        ));
+    }
+
+    #[test]
+    fn peek_returns_next_item_without_removing_it() {
+        let mut engine = NdjsonEngine::<TestStruct>::new();
+
+        assert_that!(engine.peek()).is_none();
+
+        engine.input("{\"key\":1,\"value\":2}\n");
+
+        assert_that!(engine.peek()).to_value().contains_value(&TestStruct { key: 1, value: 2 });
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(engine.peek()).is_none();
+    }
+
+    #[test]
+    fn drain_removes_parsed_items_and_keeps_rest() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_comment_prefix("#");
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(config);
+
+        engine.input("{\"key\":1,\"value\":2}\n\n#\n{\"key\":3,\"value\":4}\n{\"key\":5,");
+
+        assert_that!(engine.drain().map(Result::unwrap).collect::<Vec<_>>())
+            .contains_exactly_in_given_order([
+                TestStruct { key: 1, value: 2 },
+                TestStruct { key: 3, value: 4 }
+            ]);
+        assert_that!(engine.pop_event().is_none()).is_true();
+
+        engine.input("\"value\":6}\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 5, value: 6 });
+    }
+
+    #[test]
+    fn drain_into_appends_to_buffer() {
+        let mut engine = NdjsonEngine::<TestStruct>::new();
+        let mut buffer = Vec::new();
+
+        engine.input("{\"key\":1,\"value\":2}\n");
+
+        assert_that!(engine.drain_into(&mut buffer)).is_equal_to(1);
+
+        engine.input("invalid\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(engine.drain_into(&mut buffer)).is_equal_to(2);
+        assert_that!(buffer).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).is_err(),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+        assert_that!(engine.pop()).is_none();
+    }
+
+    fn collect_events<T>(mut engine: NdjsonEngine<T>) -> Vec<NdjsonEvent<T>> {
+        iter::from_fn(|| engine.pop_event()).collect()
+    }
+
+    #[test]
+    fn events_report_every_line() {
+        let config = NdjsonConfig::default().with_comment_prefix("#").with_parse_rest(true);
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(config);
+
+        engine.input("{\"key\":1,\"value\":2}\n\r\n \t\n# comment\ninvalid\n");
+        engine.input("{\"key\":3,\"value\":4}");
+        engine.finalize();
+
+        assert_that!(collect_events(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(matches!(it, NdjsonEvent::Record(TestStruct { key: 1, value: 2 })))
+                .is_true(),
+            |it| assert_that!(matches!(it, NdjsonEvent::EmptyLine)).is_true(),
+            |it| assert_that!(matches!(it, NdjsonEvent::BlankLine)).is_true(),
+            |it| assert_that!(matches!(it, NdjsonEvent::CommentLine)).is_true(),
+            |it| assert_that!(
+                matches!(it, NdjsonEvent::ParseError { line, .. } if line == b"invalid")).is_true(),
+            |it| assert_that!(
+                matches!(it, NdjsonEvent::RestParsed(TestStruct { key: 3, value: 4 }))).is_true()
+        ));
+    }
+
+    #[test]
+    fn events_report_empty_lines_regardless_of_empty_line_handling() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::ParseAlways);
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(config);
+
+        engine.input("\n");
+
+        assert_that!(matches!(engine.pop_event(), Some(NdjsonEvent::EmptyLine))).is_true();
+    }
+
+    #[test]
+    fn pop_discards_line_events() {
+        let mut engine = NdjsonEngine::<TestStruct>::with_events(NdjsonConfig::default());
+
+        engine.input("\n  \n{\"key\":1,\"value\":2}\n");
+
+        assert_that!(engine.peek()).to_value().contains_value(&TestStruct { key: 1, value: 2 });
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(engine.pop_event()).is_none();
+    }
+
+    #[test]
+    fn pop_event_does_not_report_ignored_lines_without_events() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+
+        engine.input("\n  \ninvalid\n");
+
+        assert_that!(matches!(engine.pop_event(), Some(NdjsonEvent::ParseError { line, .. })
+            if line.is_empty())).is_true();
+        assert_that!(engine.pop_event()).is_none();
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes_chunks_are_parsed_across_chunk_boundaries() {
+        use bytes::Bytes;
+
+        let mut engine = NdjsonEngine::new();
+
+        engine.input(Bytes::from_static(b"{\"key\":1,\"value\":2}\n{\"key\":3,"));
+        engine.input(Bytes::from_static(b"\"value\":4}\n"));
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    fn engine_with_max_line_length(max_line_length: usize, prefix_len: usize)
+            -> NdjsonEngine<TestStruct> {
+        configured_engine(|config| config
+            .with_max_line_length(max_line_length)
+            .with_oversized_line_prefix_len(prefix_len))
+    }
+
+    fn assert_line_too_long(item: &NdjsonResult<TestStruct>, expected_offset: u64,
+            expected_length: usize, expected_prefix: &[u8]) {
+        match item {
+            Err(NdjsonError::LineTooLong { offset, length, prefix, .. }) => {
+                assert_that!(*offset).is_equal_to(expected_offset);
+                assert_that!(*length).is_equal_to(expected_length);
+                assert_that!(prefix.as_slice()).is_equal_to(expected_prefix);
+            },
+            _ => panic!("expected line-too-long-error, got {item:?}")
+        }
+    }
+
+    #[test]
+    fn line_within_max_line_length_is_parsed() {
+        let mut engine = engine_with_max_line_length(20, 4);
+
+        engine.input("{\"key\":1,\"value\":2}\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+    }
+
+    #[test]
+    fn line_exceeding_max_line_length_within_chunk_is_reported_with_prefix_and_offset() {
+        let mut engine = engine_with_max_line_length(20, 4);
+
+        engine.input("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4,\"x\":5}\n");
+        engine.input("{\"key\":6,\"value\":7}\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_line_too_long(it, 20, 25, b"{\"ke"),
+            |it| assert_that!(it).contains_value(TestStruct { key: 6, value: 7 })
+        ));
+    }
+
+    #[test]
+    fn line_exceeding_max_line_length_across_chunks_is_not_buffered() {
+        let mut engine = engine_with_max_line_length(8, 3);
+
+        engine.input("1\n[1,");
+
+        for _ in 0..100 {
+            engine.input("2,");
+        }
+
+        assert_that!(engine.splitter.rest().len()).is_less_than_or_equal_to(3);
+
+        engine.input("3]\n4\n");
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err(),
+            |it| assert_line_too_long(it, 2, 205, b"[1,"),
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn joined_line_exceeding_max_line_length_is_reported() {
+        let mut engine = engine_with_max_line_length(10, 64);
+
+        engine.input("[1,2,3");
+        engine.input(",4,5,6]\n");
+
+        assert_line_too_long(&engine.pop().unwrap(), 0, 13, b"[1,2,3,4,5");
+    }
+
+    #[test]
+    fn oversized_line_respects_cr_lf_split_between_chunks() {
+        let mut engine = configured_engine(|config| config
+            .with_line_ending(LineEnding::CrLf)
+            .with_max_line_length(4)
+            .with_oversized_line_prefix_len(2));
+
+        engine.input("123456\r");
+        engine.input("\n{\"key\":1,");
+
+        assert_line_too_long(&engine.pop().unwrap(), 0, 6, b"12");
+    }
+
+    #[test]
+    fn oversized_rest_is_reported_if_parse_rest_is_enabled() {
+        let mut engine = configured_engine(|config| config
+            .with_parse_rest(true)
+            .with_max_line_length(4)
+            .with_oversized_line_prefix_len(2));
+
+        engine.input("1\n23456");
+        engine.finalize();
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).is_err(),
+            |it| assert_line_too_long(it, 2, 5, b"23")
+        ));
+    }
+
+    fn engine_reporting_progress(interval: ProgressInterval)
+            -> (NdjsonEngine<TestStruct>, Arc<Mutex<Vec<Progress>>>) {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_in_callback = Arc::clone(&reports);
+        let callback = ProgressCallback::new(move |progress|
+            reports_in_callback.lock().unwrap().push(progress));
+        let engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_progress_callback(interval, callback));
+
+        (engine, reports)
+    }
+
+    #[test]
+    fn progress_is_reported_every_n_records() {
+        let (mut engine, reports) = engine_reporting_progress(ProgressInterval::Records(2));
+
+        engine.input("{\"key\":1,\"value\":2}\n\ninvalid\n");
+        engine.input("{\"key\":3,\"value\":4}\n{\"key\":5,\"value\":6}\n");
+
+        assert_that!(reports.lock().unwrap().clone()).contains_exactly_in_given_order([
+            Progress {
+                bytes_consumed: 29,
+                records_emitted: 2,
+                lines_ignored: 1,
+                invalid_records_skipped: 0
+            },
+            Progress {
+                bytes_consumed: 69,
+                records_emitted: 4,
+                lines_ignored: 1,
+                invalid_records_skipped: 0
+            }
+        ]);
+    }
+
+    #[test]
+    fn progress_is_reported_every_n_bytes() {
+        let (mut engine, reports) = engine_reporting_progress(ProgressInterval::Bytes(10));
+
+        engine.input("{\"key\":1,");
+        engine.input("\"value\":2}\n");
+        engine.input("\n");
+
+        assert_that!(reports.lock().unwrap().clone()).contains_exactly_in_given_order([
+            Progress {
+                bytes_consumed: 20,
+                records_emitted: 1,
+                lines_ignored: 0,
+                invalid_records_skipped: 0
+            }
+        ]);
+        assert_that!(engine.progress())
+            .is_equal_to(Progress {
+                bytes_consumed: 21,
+                records_emitted: 1,
+                lines_ignored: 1,
+                invalid_records_skipped: 0
+            });
+    }
+
+    #[test]
+    fn invalid_records_are_skipped_and_counted() {
+        let mut engine = configured_engine(|config| config
+            .with_invalid_line_handling(InvalidLineHandling::Skip)
+            .with_max_line_length(24)
+            .with_max_records(Some(2)));
+
+        engine.input("{\"key\":1,\"value\":2}\ninvalid\n");
+        engine.input("{\"key\":3,\"value\":4,\"padding\":null}\n{\"key\":5,\"value\":6}\n");
+        engine.input("{\"key\":7,\"value\":8}\n");
+
+        let progress = engine.progress();
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 })
+        ));
+        assert_that!(progress.records_emitted).is_equal_to(2);
+        assert_that!(progress.invalid_records_skipped).is_equal_to(2);
+    }
+
+    #[test]
+    fn trailing_data_is_not_formatted_into_skipped_errors() {
+        let config = NdjsonConfig::default()
+            .with_trailing_data_handling(TrailingDataHandling::IncludeInError);
+        let line = b"{\"key\":1,\"value\":2} trailing";
+        let reported = super::parse_line::<TestStruct>(line, &config).unwrap_err();
+        let skipped = super::parse_line::<TestStruct>(line,
+            &config.with_invalid_line_handling(InvalidLineHandling::Skip)).unwrap_err();
+
+        assert_that!(reported.to_string()).contains("` trailing`");
+        assert_that!(skipped.to_string()).does_not_contain("` trailing`");
+    }
+
+    #[test]
+    fn input_returns_number_of_completed_records() {
+        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+
+        assert_that!(engine.input("{\"key\":1,\"value\":2}\n\ninvalid\n{\"key\":")).is_equal_to(2);
+        assert_that!(engine.input("3,")).is_equal_to(0);
+        assert_that!(engine.input("\"value\":4}\n")).is_equal_to(1);
+    }
+
+    #[test]
+    fn finalize_returns_number_of_parsed_rests() {
+        let mut engine = configured_engine(|config| config.with_parse_rest(true));
+
+        engine.input("{\"key\":1,\"value\":2}");
+
+        assert_that!(engine.finalize()).is_equal_to(1);
+        assert_that!(engine.finalize()).is_equal_to(0);
+    }
+
+    #[test]
+    fn max_records_stops_emitting_records() {
+        let mut engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_parse_rest(true)
+            .with_max_records(Some(2)));
+
+        let completed =
+            engine.input("{\"key\":1,\"value\":2}\n\ninvalid\n{\"key\":3,\"value\":4}\n");
+        engine.input("{\"key\":5,\"value\":6}");
+        engine.finalize();
+
+        assert_that!(completed).is_equal_to(2);
+        assert_that!(engine.is_max_records_reached()).is_true();
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).is_err()
+        ));
+    }
+
+    #[test]
+    fn max_records_does_not_count_skipped_records() {
+        let mut engine = configured_engine(|config| config.with_max_records(Some(1)));
+
+        engine.skip_records(1);
+        engine.input("{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n");
+
+        assert_that!(engine.is_max_records_reached()).is_true();
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
     }
 
     #[test]
-    fn no_input() {
-        let engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn max_records_none_is_not_reached() {
+        let mut engine = configured_engine(|config| config.with_max_records(None));
 
-        assert_that!(collect_output(engine)).is_empty();
+        engine.input("{\"key\":1,\"value\":2}\n");
+
+        assert_that!(engine.is_max_records_reached()).is_false();
     }
 
     #[test]
-    fn incomplete_input() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn end_sentinel_discards_all_further_input() {
+        let mut engine = configured_engine(|config| config
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_parse_rest(true)
+            .with_end_sentinel(EndSentinel::value(serde_json::json!({ "type": "end" }))));
 
-        engine.input("{\"key\":3,\"val");
+        let completed = engine.input("{\"key\":1,\"value\":2}\n  \n{ \"type\": \"end\" }\n");
 
-        assert_that!(collect_output(engine)).is_empty();
+        assert_that!(engine.is_finished()).is_true();
+        assert_that!(engine.is_max_records_reached()).is_false();
+
+        engine.input("{\"key\":3,\"value\":4}\n{\"key\":5,\"value\":6}");
+        engine.finalize();
+
+        assert_that!(completed).is_equal_to(1);
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
+        ));
     }
 
     #[test]
-    fn single_exact_input() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn json_array_mode_parses_elements_across_chunks() {
+        let mut engine = configured_engine(|config| config.with_json_array_mode(true));
 
-        engine.input("{\"key\":3,\"value\":4}\n");
+        engine.input(" [{\"key\":1,\"value\":2},\n{\"key\":3,");
+        engine.input("\"value\":4} , invalid]\n");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
-            ));
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 }),
+            |it| assert_that!(it).is_err()
+        ));
     }
 
     #[test]
-    fn single_item_split_into_two_inputs() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn json_array_mode_treats_unterminated_array_as_rest() {
+        let mut engine = configured_engine(|config| config
+            .with_json_array_mode(true)
+            .with_parse_rest(true));
 
-        engine.input("{\"key\":42,");
-        engine.input("\"value\":24}\n");
+        engine.input("[{\"key\":1,\"value\":2},{\"key\":3,\"value\":4}");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 42, value: 24 })
-            ));
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(engine.pop()).is_none();
+        assert_that!(engine.finalize()).is_equal_to(1);
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 3, value: 4 });
     }
 
     #[test]
-    fn two_items_in_single_input() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn json_array_mode_ignores_unterminated_array_without_parse_rest() {
+        let mut engine = configured_engine(|config| config.with_json_array_mode(true));
 
-        engine.input("{\"key\":1,\"value\":1}\n{\"key\":2,\"value\":2}\n");
+        engine.input("[{\"key\":1,\"value\":2},{\"key\":3,\"value\":4}");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 1 }),
-                |it| assert_that!(it).contains_value(TestStruct { key: 2, value: 2 })
-            ));
+        assert_that!(engine.finalize()).is_equal_to(0);
     }
 
     #[test]
-    fn two_items_in_many_inputs_with_rest() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn json_array_mode_applies_max_line_length_to_elements() {
+        let mut engine = configured_engine(|config| config
+            .with_json_array_mode(true)
+            .with_max_line_length(20)
+            .with_oversized_line_prefix_len(3));
 
-        engine.input("{\"key\":12,\"v");
-        engine.input("alue\":3");
-        engine.input("4}\n{\"key");
-        engine.input("\":56,\"valu");
-        engine.input("e\":78}\n{\"key\":");
+        engine.input("[{\"key\":1,\"value\":2}, {\"key\":1,\"value\":2222}]");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 12, value: 34 }),
-                |it| assert_that!(it).contains_value(TestStruct { key: 56, value: 78 })
-            ));
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_line_too_long(&engine.pop().unwrap(), 22, 22, b"{\"k");
     }
 
     #[test]
-    fn input_completing_previous_rest_then_multiple_complete_items_and_more_rest() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn input_complete_parses_lines_and_rest() {
+        let mut engine = configured_engine(|config| config.with_parse_rest(true));
 
-        engine.input("{\"key\":9,\"value\":");
-        engine.input("8}\n{\"key\":7,\"value\":6}\n{\"key\":5,\"value\":4}\n{\"key\":");
-        engine.input("3,\"value\":2}\n{");
+        engine.input("{\"key\":1,");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 9, value: 8 }),
-                |it| assert_that!(it).contains_value(TestStruct { key: 7, value: 6 }),
-                |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 4 }),
-                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 2 })
-            ));
+        let completed = engine.input_complete("\"value\":2}\ninvalid\n{\"key\":3,\"value\":4}");
+
+        assert_that!(completed).is_equal_to(3);
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).is_err(),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    fn parse_key_value(line: &[u8]) -> NdjsonResult<TestStruct> {
+        let line = str::from_utf8(line).map_err(|error| NdjsonError::Custom(error.into()))?;
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| NdjsonError::Custom("missing `=`".into()))?;
+        let parse = |number: &str| number.parse().map_err(|error| NdjsonError::Custom(
+            Box::new(error)));
+
+        Ok(TestStruct {
+            key: parse(key)?,
+            value: parse(value)?
+        })
     }
 
     #[test]
-    fn carriage_return_handled_gracefully() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn custom_deserializer_is_used_instead_of_json() {
+        let mut engine = NdjsonEngine::with_config(
+            NdjsonConfig::default().with_empty_line_handling(EmptyLineHandling::IgnoreEmpty))
+            .with_deserializer(parse_key_value);
 
-        engine.input("{\"key\":1,\"value\":2}\r\n{\"key\":3,\"value\":4}\r\n");
+        engine.input("1=2\n\n{\"key\":3,\"value\":4}\n5=6\n");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
-                |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
-            ));
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(matches!(it, Err(NdjsonError::Custom(_)))).is_true(),
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 })
+        ));
     }
 
     #[test]
-    fn whitespace_handled_gracefully() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
-
-        engine.input("\t{ \"key\":\t13,  \"value\":   37 } \r\n");
+    fn custom_deserializer_receives_preprocessed_line() {
+        let preprocessor = LinePreprocessor::new(|line| Cow::Owned(line.to_ascii_lowercase()));
+        let mut engine =
+            NdjsonEngine::with_config(NdjsonConfig::default().with_line_preprocessor(preprocessor))
+                .with_deserializer(|line| match line {
+                    b"one" => Ok(TestStruct { key: 1, value: 1 }),
+                    _ => Err(NdjsonError::Custom("unknown".into()))
+                });
+
+        engine.input("ONE\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 1 });
+    }
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 13, value: 37 })
-            ));
+    fn reject_odd_values(record: &TestStruct) -> Result<(), String> {
+        if record.value.is_multiple_of(2) {
+            Ok(())
+        }
+        else {
+            Err(format!("odd value {}", record.value))
+        }
     }
 
     #[test]
-    fn erroneous_entry_emitted_as_json_error() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn rejected_records_are_reported_inline_with_parse_errors() {
+        let mut engine = NdjsonEngine::with_config(NdjsonConfig::default().with_salvage(true))
+            .with_record_validator(reject_odd_values);
 
-        engine.input("{\"key\":1}\n{\"key\":1,\"value\":1}\n");
+        engine.input("{\"key\":1,\"value\":2}\n{\"key\":2,\"value\":3}\ninvalid\n");
+        engine.input("{\"key\":3,\"value\":5}{\"key\":4,\"value\":6}\n{\"key\":5,\"value\":7}\n");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).is_err(),
-                |it| assert_that!(it).is_ok()
-            ));
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(matches!(it, Err(NdjsonError::InvalidRecord { message })
+                if message == "odd value 3")).is_true(),
+            |it| assert_that!(matches!(it, Err(NdjsonError::Json(_)))).is_true(),
+            |it| assert_that!(matches!(it, Err(NdjsonError::Json(_)))).is_true(),
+            |it| assert_that!(it).contains_value(TestStruct { key: 4, value: 6 }),
+            |it| assert_that!(matches!(it, Err(NdjsonError::InvalidRecord { .. }))).is_true()
+        ));
     }
 
     #[test]
-    fn error_from_split_entry() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
+    fn rejected_records_are_skipped_with_invalid_records() {
+        let mut engine = configured_engine(|config| config
+                .with_invalid_line_handling(InvalidLineHandling::Skip))
+            .with_record_validator(reject_odd_values);
 
-        engine.input("{\"key\":100,\"value\":200}\n{\"key\":");
-        engine.input("\"should be a number\",\"value\":0}\n{\"key\":300,\"value\":400}\n");
+        engine.input("{\"key\":1,\"value\":1}\n{\"key\":2,\"value\":2}\n");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 100, value: 200 }),
-                |it| assert_that!(it).is_err(),
-                |it| assert_that!(it).contains_value(TestStruct { key: 300, value: 400 })
-            ));
+        let progress = engine.progress();
+
+        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 2, value: 2 })
+        ));
+        assert_that!(progress.invalid_records_skipped).is_equal_to(1);
     }
 
     #[test]
-    fn engine_input_works_for_different_types() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::default();
+    fn pending_bytes_counts_discarded_bytes_of_oversized_line() {
+        let config = NdjsonConfig::default().with_max_line_length(4);
+        let mut engine = NdjsonEngine::<TestStruct>::with_config(config);
 
-        engine.input(b"{\"k");
-        engine.input(b"ey\"".to_vec());
-        engine.input(":12".to_string());
-        engine.input(&mut ",\"v".to_string());
-        engine.input("alu".to_string().into_boxed_str());
-        engine.input(b"e\"".to_vec().into_boxed_slice());
-        engine.input(Arc::<str>::from(":3"));
-        engine.input(Rc::<[u8]>::from(&b"4}"[..]));
-        engine.input(Cow::Borrowed(&b"\r\n".to_vec()));
+        engine.input("{}\n123");
+        engine.input("4567");
 
-        assert_that!(collect_output(engine))
-            .satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 12, value: 34 })
-            ));
+        assert_that!(engine.is_partial()).is_true();
+        assert_that!(engine.pending_bytes()).is_equal_to(7);
+
+        engine.finalize();
+
+        assert_that!(engine.is_partial()).is_false();
+        assert_that!(engine.pending_bytes()).is_equal_to(0);
     }
 
     #[test]
-    fn old_data_is_discarded() {
-        let mut engine: NdjsonEngine<TestStruct> = NdjsonEngine::new();
-        let count = 20;
+    fn pending_bytes_in_json_array_mode_counts_incomplete_element() {
+        let config = NdjsonConfig::default().with_json_array_mode(true);
+        let mut engine = NdjsonEngine::<TestStruct>::with_config(config);
 
-        engine.input("{ \"key\": 1, ");
+        engine.input("[ {\"key\":1,");
 
-        for _ in 0..(count - 1) {
-            engine.input("\"value\": 2 }\r\n{ \"key\": 1, ");
-        }
+        assert_that!(engine.pending_bytes()).is_equal_to(9);
 
-        engine.input("\"value\": 2 }\r\n");
+        engine.input("\"value\":2} ,");
 
-        assert_that!(engine.in_queue).is_empty();
-        assert_that!(engine.out_queue).has_length(count);
+        assert_that!(engine.is_partial()).is_false();
     }
 
-    fn configured_engine(configure: impl FnOnce(NdjsonConfig) -> NdjsonConfig)
-            -> NdjsonEngine<TestStruct> {
-        let config = configure(NdjsonConfig::default());
-        NdjsonEngine::with_config(config)
-    }
+    #[test]
+    fn rest_returns_incomplete_line() {
+        let mut engine = NdjsonEngine::<TestStruct>::new();
 
-    fn engine_with_empty_line_handling(empty_line_handling: EmptyLineHandling)
-            -> NdjsonEngine<TestStruct> {
-        configured_engine(|config| config.with_empty_line_handling(empty_line_handling))
-    }
+        engine.input("{\"key\":1,\"value\":2}\n{\"key\":");
 
-    #[test]
-    fn raises_error_when_parsing_empty_line_in_parse_always_mode() {
-        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::ParseAlways);
+        assert_that!(engine.rest()).contains_exactly_in_given_order(b"{\"key\":".iter());
 
-        engine.input("{\"key\":1,\"value\":2}\n\n{\"key\":3,\"value\":4}\n");
+        engine.finalize();
 
-        assert_that!(collect_output(engine)).contains_elements_matching(Result::is_err);
+        assert_that!(engine.rest()).is_empty();
     }
 
     #[test]
-    fn does_not_raise_error_when_parsing_empty_line_in_ignore_empty_mode() {
-        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+    fn extend_inputs_all_chunks_without_finalizing() {
+        let mut engine = NdjsonEngine::<TestStruct>::with_config(
+            NdjsonConfig::default().with_parse_rest(true));
 
-        engine.input("{\"key\":1,\"value\":2}\n\n{\"key\":3,\"value\":4}\n");
+        engine.extend(vec!["{\"key\":1,", "\"value\":2}\n{\"key\":3,", "\"value\":4}"]);
 
-        assert_that!(collect_output(engine)).does_not_contain_elements_matching(Result::is_err);
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(engine.pop()).is_none();
+        assert_that!(engine.is_partial()).is_true();
     }
 
     #[test]
-    fn does_not_raise_error_when_parsing_empty_line_with_carriage_return_in_ignore_empty_mode() {
-        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+    fn parse_all_with_config_finalizes_engine() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let results = super::parse_all_with_config::<TestStruct, _>(
+            ["{\"key\":1,\"value\":2}\n{\"key\":3,", "\"value\":4}"], config);
+
+        assert_that!(results.into_iter().map(Result::unwrap).collect::<Vec<_>>())
+            .contains_exactly_in_given_order([
+                TestStruct { key: 1, value: 2 },
+                TestStruct { key: 3, value: 4 }
+            ]);
+    }
 
-        engine.input("{\"key\":1,\"value\":2}\r\n\r\n{\"key\":3,\"value\":4}\n");
+    #[test]
+    fn ignored_lines_are_counted_and_reported_with_line_number() {
+        let ignored_lines = Arc::new(Mutex::new(Vec::new()));
+        let ignored_lines_in_callback = Arc::clone(&ignored_lines);
+        let callback = IgnoredLineCallback::new(move |line, line_number| {
+            ignored_lines_in_callback.lock().unwrap().push((line.to_vec(), line_number));
+        });
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_comment_prefix("#")
+            .with_ignored_line_callback(callback);
+        let mut engine = NdjsonEngine::<TestStruct>::with_config(config);
 
-        assert_that!(collect_output(engine)).does_not_contain_elements_matching(Result::is_err);
+        engine.input("# comment\n \t\n{\"key\":1,\"value\":2}\n\n");
+
+        assert_that!(engine.progress().lines_ignored).is_equal_to(2);
+        assert_that!(ignored_lines.lock().unwrap().clone()).contains_exactly_in_given_order([
+            (b" \t".to_vec(), 2),
+            (Vec::new(), 4)
+        ]);
     }
 
     #[test]
-    fn raises_error_when_parsing_non_empty_blank_line_in_ignore_empty_mode() {
-        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+    fn raw_engine_deserializes_lines_as_requested_type() {
+        let mut engine = RawNdjsonEngine::new();
+
+        let added_lines = engine.input("\"pair\"\n{\"key\":1,\"value\":2}\n3\n{\"key\"");
+
+        assert_that!(added_lines).is_equal_to(3);
+        assert_that!(engine.pop_as::<String>()).to_value().contains_value("pair".to_owned());
+        assert_that!(engine.peek_raw()).to_value()
+            .contains_value(b"{\"key\":1,\"value\":2}".to_vec());
+        assert_that!(engine.pop_as::<TestStruct>()).to_value()
+            .contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(engine.pop_as::<TestStruct>()).to_value().is_err();
+        assert_that!(engine.pop_as::<u32>()).is_none();
+        assert_that!(engine.rest()).is_equal_to(b"{\"key\"".as_slice());
+    }
 
-        engine.input("{\"key\":1,\"value\":2}\n \t\r\n{\"key\":3,\"value\":4}\n");
+    #[test]
+    fn raw_engine_applies_config_on_pop() {
+        let config = NdjsonConfig::default()
+            .with_max_line_length(16)
+            .with_reject_duplicate_keys(true)
+            .with_parse_rest(true);
+        let mut engine = RawNdjsonEngine::with_config(config);
+
+        engine.input("{\"a\":1,\"a\":2}\n[1,2,3,4,5,6,7,8,9]\n");
+        engine.finalize();
 
-        assert_that!(collect_output(engine)).contains_elements_matching(Result::is_err);
+        let duplicate_key_result = engine.pop_as::<serde_json::Value>();
+
+        assert_that!(matches!(duplicate_key_result, Some(Err(NdjsonError::DuplicateKey { .. }))))
+            .is_true();
+        assert_that!(matches!(engine.pop_raw(), Some(Err(NdjsonError::LineTooLong { .. }))))
+            .is_true();
+        assert_that!(engine.pop_raw()).is_none();
     }
 
-    #[test]
-    fn does_not_raise_error_when_parsing_non_empty_blank_line_in_ignore_blank_mode() {
-        let mut engine = engine_with_empty_line_handling(EmptyLineHandling::IgnoreBlank);
+    /// Deserializes a number and adds it to a running total, which is returned.
+    struct AddTo<'total>(&'total mut u32);
 
-        engine.input("{\"key\":1,\"value\":2}\n \t\r\n{\"key\":3,\"value\":4}\n");
+    impl<'de> DeserializeSeed<'de> for AddTo<'_> {
+        type Value = u32;
 
-        assert_that!(collect_output(engine)).does_not_contain_elements_matching(Result::is_err);
+        fn deserialize<D>(self, deserializer: D) -> Result<u32, D::Error>
+        where
+            D: Deserializer<'de>
+        {
+            *self.0 += u32::deserialize(deserializer)?;
+            Ok(*self.0)
+        }
     }
 
     #[test]
-    fn finalize_ignores_rest_if_parse_rest_is_false() {
-        let mut engine = configured_engine(|config| config.with_parse_rest(false));
+    fn raw_engine_deserializes_with_seed() {
+        let mut engine = RawNdjsonEngine::new();
+        let mut total = 0;
 
-        engine.input("{\"key\":1,\"value\":2}");
-        engine.finalize();
+        engine.input("1\n2\nx\n");
 
-        assert_that!(collect_output(engine)).is_empty();
+        assert_that!(engine.pop_seed(AddTo(&mut total))).to_value().contains_value(1);
+        assert_that!(engine.pop_seed(AddTo(&mut total))).to_value().contains_value(3);
+        assert_that!(engine.pop_seed(AddTo(&mut total))).to_value().is_err();
+        assert_that!(engine.pop_seed(AddTo(&mut total))).is_none();
+        assert_that!(total).is_equal_to(3);
     }
 
     #[test]
-    fn finalize_parses_valid_rest() {
-        const EMPTY_LINE_HANDLINGS: [EmptyLineHandling; 3] = [
-            EmptyLineHandling::ParseAlways,
-            EmptyLineHandling::IgnoreEmpty,
-            EmptyLineHandling::IgnoreBlank
-        ];
+    fn raw_engine_seed_applies_trailing_data_handling() {
+        let pop_with = |trailing_data_handling| {
+            let config = NdjsonConfig::default()
+                .with_trailing_data_handling(trailing_data_handling);
+            let mut engine = RawNdjsonEngine::with_config(config);
+            let mut total = 0;
+
+            engine.input("1 x\n");
+            engine.pop_seed(AddTo(&mut total)).unwrap().map_err(|error| error.to_string())
+        };
+
+        assert_that!(pop_with(TrailingDataHandling::Ignore)).contains_value(1);
+        assert_that!(pop_with(TrailingDataHandling::Error)).is_err();
+        assert_that!(pop_with(TrailingDataHandling::IncludeInError).unwrap_err())
+            .contains("` x`");
+    }
 
-        for empty_line_handling in EMPTY_LINE_HANDLINGS {
-            let mut engine = configured_engine(|config| config
-                .with_empty_line_handling(empty_line_handling)
-                .with_parse_rest(true));
+    /// A number which does not implement [Deserialize] itself.
+    #[derive(Debug, PartialEq)]
+    struct Scaled(u32);
 
-            engine.input("{\"key\":1,\"value\":2}");
-            engine.finalize();
+    /// Deserializes a number and multiplies it by a factor.
+    #[derive(Clone, Copy)]
+    struct ScaleBy(u32);
 
-            assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
-                |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 })
-            ));
+    impl<'de> DeserializeSeed<'de> for ScaleBy {
+        type Value = Scaled;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Scaled, D::Error>
+        where
+            D: Deserializer<'de>
+        {
+            Ok(Scaled(u32::deserialize(deserializer)? * self.0))
         }
     }
 
     #[test]
-    fn finalize_raises_error_on_invalid_rest() {
-        let mut engine = configured_engine(|config| config.with_parse_rest(true));
+    fn engine_deserializes_input_and_rest_with_seed() {
+        let config = NdjsonConfig::default().with_parse_rest(true);
+        let mut engine = NdjsonEngine::with_config(config);
 
-        engine.input("invalid json");
-        engine.finalize();
+        assert_that!(engine.input_seeded("1\nx\n2", ScaleBy(3))).is_equal_to(2);
+        assert_that!(engine.finalize_seeded(ScaleBy(5))).is_equal_to(1);
 
-        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
-            |it| assert_that!(it).is_err()
-        ));
+        assert_that!(engine.pop()).to_value().contains_value(Scaled(3));
+        assert_that!(engine.pop()).to_value().is_err();
+        assert_that!(engine.pop()).to_value().contains_value(Scaled(10));
+        assert_that!(engine.pop()).is_none();
     }
 
     #[test]
-    fn finalize_ignores_empty_rest_even_if_empty_line_handling_is_parse_always() {
-        let mut engine = configured_engine(|config| config
-            .with_empty_line_handling(EmptyLineHandling::ParseAlways)
-            .with_parse_rest(true));
+    fn seeded_input_respects_config() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_comment_prefix("#");
+        let mut engine = NdjsonEngine::with_config(config);
 
-        engine.finalize();
+        engine.input_seeded("# comment\n  \n4\n", ScaleBy(2));
 
-        assert_that!(collect_output(engine)).is_empty();
+        assert_that!(engine.pop()).to_value().contains_value(Scaled(8));
+        assert_that!(engine.pop()).is_none();
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn finalize_ignores_empty_rest_if_empty_line_handling_is_ignore_empty() {
-        let mut engine = configured_engine(|config| config
+    fn parse_metrics_are_reported_for_deserialized_lines() {
+        use crate::config::ParseMetricsCallback;
+
+        let metrics = Arc::new(Mutex::new(Vec::new()));
+        let metrics_in_callback = Arc::clone(&metrics);
+        let callback = ParseMetricsCallback::new(move |metrics| {
+            metrics_in_callback.lock().unwrap().push((metrics.line_length, metrics.is_error));
+        });
+        let config = NdjsonConfig::default()
             .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
-            .with_parse_rest(true));
+            .with_max_line_length(4)
+            .with_parse_metrics_callback(callback);
+        let mut engine = NdjsonEngine::<u32>::with_config(config);
 
-        engine.finalize();
+        engine.input("12\n\nx\n123456\n");
 
-        assert_that!(collect_output(engine)).is_empty();
+        assert_that!(metrics.lock().unwrap().as_slice())
+            .contains_exactly_in_given_order([(2, false), (1, true)]);
     }
 
     #[test]
-    fn finalize_does_not_ignore_non_empty_blank_rest_if_empty_line_handling_is_ignore_empty() {
-        let mut engine = configured_engine(|config| config
+    fn record_sizes_are_tracked() {
+        let config = NdjsonConfig::default()
             .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
-            .with_parse_rest(true));
+            .with_comment_prefix("#")
+            .with_max_line_length(4);
+        let mut engine = NdjsonEngine::<u32>::with_config(config);
 
-        engine.input(" ");
-        engine.finalize();
+        engine.skip_lines(1);
+        engine.input("1234\n1\n\n#c\n23\nx\n123456\n");
 
-        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
-            |it| assert_that!(it).is_err()
-        ));
+        assert_that!(engine.record_size_histogram().buckets().collect::<Vec<_>>())
+            .contains_exactly_in_given_order([(1..=1, 2), (2..=3, 1), (4..=7, 1)]);
     }
 
     #[test]
-    fn finalize_ignores_non_empty_blank_rest_if_empty_line_handling_is_ignore_blank() {
-        let mut engine = configured_engine(|config| config
-            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
-            .with_parse_rest(true));
-
-        engine.input(" ");
-        engine.finalize();
+    fn large_records_are_reported_but_parsed() {
+        use crate::config::LargeRecordCallback;
+
+        let large_records = Arc::new(Mutex::new(Vec::new()));
+        let large_records_in_callback = Arc::clone(&large_records);
+        let callback = LargeRecordCallback::new(move |length, line_number| {
+            large_records_in_callback.lock().unwrap().push((length, line_number));
+        });
+        let config = NdjsonConfig::default()
+            .with_max_line_length(5)
+            .with_large_record_callback(2, callback);
+        let mut engine = NdjsonEngine::<u32>::with_config(config);
+
+        engine.input("12\n123\n1234567\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(12);
+        assert_that!(engine.pop()).to_value().contains_value(123);
+        assert_that!(engine.pop()).to_value().is_err();
+        assert_that!(large_records.lock().unwrap().as_slice())
+            .contains_exactly_in_given_order([(3, 2), (7, 3)]);
+    }
 
-        assert_that!(collect_output(engine)).is_empty();
+    #[test]
+    fn records_are_salvaged_after_torn_writes() {
+        let config = NdjsonConfig::default().with_salvage(true);
+        let mut engine = NdjsonEngine::<TestStruct>::with_config(config);
+
+        engine.input("{\"key\":1,\"val{\"key\":{\"key\":2,\"value\":3}\n");
+        engine.input("{\"key\":4\n{\"key\":5,\"value\":6}\n");
+
+        assert_that!(engine.pop()).to_value().is_err();
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 2, value: 3 });
+        assert_that!(engine.pop()).to_value().is_err();
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 5, value: 6 });
+        assert_that!(engine.pop()).is_none();
     }
 
+    #[cfg(any(feature = "iter", feature = "stream"))]
     #[test]
-    fn finalize_is_idempotent() {
-        let mut engine = configured_engine(|config| config.with_parse_rest(true));
+    fn salvaged_records_are_separated_from_failed_lines() {
+        let config = NdjsonConfig::default().with_salvage(true);
+        let mut engine = NdjsonEngine::<TestStruct>::with_config(config);
 
-        engine.input("{\"key\":13,\"value\":37}");
-        engine.finalize();
-        engine.finalize();
+        engine.retain_failed_lines();
+        engine.input("x{\"key\":1,\"value\":2}\n");
 
-        assert_that!(collect_output(engine)).satisfies_exactly_in_given_order(dyn_assertions!(
-            |it| assert_that!(it).contains_value(TestStruct { key: 13, value: 37 })
-        ));
+        let failed_line = engine.pop_with_failed_line().unwrap().err().unwrap().1;
+
+        assert_that!(failed_line).contains_exactly_in_given_order(*b"x");
+        assert_that!(engine.pop()).to_value().contains_value(TestStruct { key: 1, value: 2 });
     }
 }
@@ -0,0 +1,114 @@
+//! This module offers parsing of NDJSON-lines which each consist of a single-entry object
+//! `{"<key>": <value>}`, as used by several export formats, into `(key, value)` pairs. See
+//! [parse_entry] and [from_iter_map](crate::from_iter_map).
+//!
+//! # Example
+//!
+//! ```
+//! let data_blocks = vec!["{\"alice\":25}\n{\"bob\":35}\n", "{}\n"];
+//!
+//! let mut ndjson_iter = ndjson_stream::from_iter_map::<String, u32, _>(data_blocks);
+//!
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), ("alice".to_owned(), 25));
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), ("bob".to_owned(), 35));
+//! assert!(ndjson_iter.next().unwrap().is_err());
+//! assert!(ndjson_iter.next().is_none());
+//! ```
+
+use core::fmt::{self, Formatter};
+use core::marker::PhantomData;
+
+use serde::de::{DeserializeOwned, Error as _, IgnoredAny, MapAccess, Visitor};
+use serde::Deserializer;
+
+use serde_json::error::Result as JsonResult;
+
+struct EntryVisitor<K, V> {
+    marker: PhantomData<(K, V)>
+}
+
+impl<'de, K, V> Visitor<'de> for EntryVisitor<K, V>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned
+{
+    type Value = (K, V);
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "an object with exactly one entry")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(K, V), A::Error>
+    where
+        A: MapAccess<'de>
+    {
+        let entry = map.next_entry::<K, V>()?.ok_or_else(|| A::Error::custom(
+            "expected an object with exactly one entry, found none"))?;
+
+        if map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+            return Err(A::Error::custom(
+                "expected an object with exactly one entry, found multiple"));
+        }
+
+        Ok(entry)
+    }
+}
+
+/// Parses the given NDJSON-`line`, which must be an object with exactly one entry, into a pair of
+/// the key and value of that entry. This can be used as the custom deserializer of an engine or
+/// driver, such as via
+/// [NdjsonEngine::with_deserializer](crate::engine::NdjsonEngine::with_deserializer).
+///
+/// # Errors
+///
+/// If the line is not valid JSON, is not an object, has zero or multiple entries, or the key or
+/// value cannot be deserialized into `K` or `V`, respectively.
+pub fn parse_entry<K, V>(line: &[u8]) -> JsonResult<(K, V)>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(line);
+    let entry = deserializer.deserialize_map(EntryVisitor { marker: PhantomData })?;
+
+    deserializer.end()?;
+
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn single_entry_is_parsed() {
+        assert_that!(parse_entry::<String, Value>(br#" {"key": [1, 2]} "#))
+            .contains_value(("key".to_owned(), serde_json::json!([1, 2])));
+    }
+
+    #[test]
+    fn integer_keys_are_parsed() {
+        assert_that!(parse_entry::<u32, bool>(br#"{"12":true}"#)).contains_value((12, true));
+    }
+
+    #[test]
+    fn objects_without_exactly_one_entry_are_rejected() {
+        let empty = parse_entry::<String, u32>(b"{}").unwrap_err();
+        let multiple = parse_entry::<String, u32>(br#"{"a":1,"b":2}"#).unwrap_err();
+
+        assert_that!(empty.to_string()).contains("found none");
+        assert_that!(multiple.to_string()).contains("found multiple");
+    }
+
+    #[test]
+    fn non_objects_and_trailing_data_are_rejected() {
+        assert_that!(parse_entry::<String, u32>(b"[1]").unwrap_err().to_string())
+            .contains("expected an object with exactly one entry");
+        assert_that!(parse_entry::<String, u32>(br#"{"a":1} 2"#)).is_err();
+    }
+}
@@ -0,0 +1,425 @@
+//! This module defines the error- and result-type used for errors which occur while parsing an
+//! individual NDJSON-line. See [NdjsonError] for more details.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+use serde_json::Error as JsonError;
+
+use crate::checksum::IntegrityError;
+
+#[cfg(doc)]
+use crate::config::NdjsonConfig;
+
+/// The errors which can occur when parsing an individual NDJSON-line.
+#[derive(Debug)]
+pub enum NdjsonError {
+
+    /// The line is not valid JSON or does not match the schema of the output type. The
+    /// [serde_json::Error] is wrapped in this variant.
+    Json(JsonError),
+
+    /// The line contains an object which has the same key multiple times. This is only raised if
+    /// enabled via [NdjsonConfig::with_reject_duplicate_keys].
+    DuplicateKey {
+
+        /// The first key which occurred multiple times in the same object.
+        key: String
+    },
+
+    /// The line is longer than the maximum configured via
+    /// [NdjsonConfig::with_max_line_length]. The line is not parsed.
+    LineTooLong {
+
+        /// The offset of the first byte of the line in the entire input, in bytes.
+        offset: u64,
+
+        /// The length of the line in bytes, excluding the line ending.
+        length: usize,
+
+        /// The configured maximum line length.
+        max_line_length: usize,
+
+        /// The first bytes of the line, whose number is configured via
+        /// [NdjsonConfig::with_oversized_line_prefix_len].
+        prefix: Vec<u8>
+    },
+
+    /// The line was parsed, but the record was rejected by a record validator, as configured via
+    /// [NdjsonEngine::with_record_validator](crate::engine::NdjsonEngine::with_record_validator).
+    InvalidRecord {
+
+        /// The message returned by the validator.
+        message: String
+    },
+
+    /// The line does not conform to the schema checked by a
+    /// [SchemaValidator](crate::config::SchemaValidator), as configured via
+    /// [NdjsonConfig::with_schema_validator]. The line is not deserialized into the output type.
+    SchemaViolation(SchemaViolation),
+
+    /// The line failed an integrity check, for example because its checksum does not match, as
+    /// verified by a [Crc32Verifier](crate::checksum::Crc32Verifier). The line is not parsed.
+    Integrity(IntegrityError),
+
+    /// The line could not be deserialized by a custom deserializer, as configured for example via
+    /// [NdjsonEngine::with_deserializer](crate::engine::NdjsonEngine::with_deserializer). The
+    /// error returned by the deserializer is wrapped in this variant.
+    Custom(Box<dyn Error + Send + Sync>)
+}
+
+impl Display for NdjsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NdjsonError::Json(error) => write!(f, "{error}"),
+            NdjsonError::DuplicateKey { key } => write!(f, "duplicate key `{key}`"),
+            NdjsonError::LineTooLong { offset, length, max_line_length, prefix } =>
+                write!(f, "line at offset {offset} has length {length}, which exceeds the maximum \
+                    of {max_line_length}, starting with `{}`", String::from_utf8_lossy(prefix)),
+            NdjsonError::InvalidRecord { message } => write!(f, "invalid record: {message}"),
+            NdjsonError::SchemaViolation(violation) => write!(f, "{violation}"),
+            NdjsonError::Integrity(error) => write!(f, "{error}"),
+            NdjsonError::Custom(error) => write!(f, "{error}")
+        }
+    }
+}
+
+impl Error for NdjsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NdjsonError::Json(error) => Some(error),
+            NdjsonError::Integrity(error) => Some(error),
+            NdjsonError::SchemaViolation(violation) => Some(violation),
+            NdjsonError::Custom(error) => Some(error.as_ref()),
+            NdjsonError::DuplicateKey { .. } | NdjsonError::LineTooLong { .. } |
+                NdjsonError::InvalidRecord { .. } => None
+        }
+    }
+}
+
+/// A violation of a schema reported by a [SchemaValidator](crate::config::SchemaValidator) and
+/// wrapped in [NdjsonError::SchemaViolation].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchemaViolation {
+
+    /// A JSON pointer (RFC 6901) to the value within the line which violates the schema, such as
+    /// `/items/0/id`. The empty string refers to the entire value of the line.
+    pub pointer: String,
+
+    /// A description of the violation.
+    pub message: String
+}
+
+impl SchemaViolation {
+
+    /// Creates a new schema violation of the value at the given JSON `pointer` with the given
+    /// `message`.
+    pub fn new(pointer: impl Into<String>, message: impl Into<String>) -> SchemaViolation {
+        SchemaViolation {
+            pointer: pointer.into(),
+            message: message.into()
+        }
+    }
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "schema violation at `{}`: {}", self.pointer, self.message)
+    }
+}
+
+impl Error for SchemaViolation { }
+
+impl NdjsonError {
+
+    /// Gets the one-based column of the byte at which this error occurred within the offending
+    /// line, if known. This is the case for all [NdjsonError::Json] errors which [serde_json]
+    /// reports with a position, which includes syntax errors as well as data errors caused by the
+    /// output type, such as an invalid type or a missing field. For the latter, the column usually
+    /// points at the end of the value which could not be deserialized. Errors without a position,
+    /// such as those about trailing data, as well as all other variants, have no column.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            NdjsonError::Json(error) if error.line() == 1 && error.column() > 0 =>
+                Some(error.column()),
+            _ => None
+        }
+    }
+
+    /// Creates an [ErrorSnippet], displaying this error together with the offending `line` and a
+    /// caret under the [column](NdjsonError::column) of the error, if known. The line can be
+    /// obtained for example from a [ParseError](crate::event::NdjsonEvent::ParseError) event or a
+    /// dead letter sink.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::engine::NdjsonEngine;
+    /// use ndjson_stream::event::NdjsonEvent;
+    /// use ndjson_stream::config::NdjsonConfig;
+    ///
+    /// let mut engine = NdjsonEngine::<serde_json::Value>::with_events(NdjsonConfig::default());
+    ///
+    /// engine.input("{\"a\": 1, \"b\": }\n");
+    ///
+    /// let Some(NdjsonEvent::ParseError { error, line }) = engine.pop_event()
+    /// else {
+    ///     panic!("expected parse error");
+    /// };
+    /// let snippet = error.snippet(&line).with_line_number(42).to_string();
+    ///
+    /// assert_eq!(snippet, concat!(
+    ///     "error: expected value at line 1 column 15\n",
+    ///     "  --> line 42, column 15\n",
+    ///     "   |\n",
+    ///     "42 | {\"a\": 1, \"b\": }\n",
+    ///     "   |               ^"));
+    /// ```
+    pub fn snippet<'error>(&'error self, line: &'error [u8]) -> ErrorSnippet<'error> {
+        ErrorSnippet {
+            error: self,
+            line,
+            line_number: None
+        }
+    }
+}
+
+/// The maximum number of characters of the offending line shown by an [ErrorSnippet]. Longer lines
+/// are cut around the column of the error.
+const MAX_SNIPPET_WIDTH: usize = 80;
+
+/// A human-readable rendering of an [NdjsonError] together with the offending line, created by
+/// [NdjsonError::snippet]. Its [Display] implementation shows the error message, followed by the
+/// line and a caret under the column of the error, if known. Lines longer than 80 characters are
+/// cut around the error, which is marked by `...`.
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorSnippet<'error> {
+    error: &'error NdjsonError,
+    line: &'error [u8],
+    line_number: Option<u64>
+}
+
+impl ErrorSnippet<'_> {
+
+    /// Sets the one-based `line_number` of the offending line, which is shown in the location and
+    /// gutter of this snippet. It can be obtained for example from a [ValidationError].
+    ///
+    /// # Returns
+    ///
+    /// A new snippet with all the same values as this one, except the line number.
+    pub fn with_line_number(self, line_number: u64) -> Self {
+        ErrorSnippet {
+            line_number: Some(line_number),
+            ..self
+        }
+    }
+}
+
+impl Display for ErrorSnippet<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let column = self.error.column();
+        let line = match self.error {
+            NdjsonError::LineTooLong { prefix, .. } => prefix.as_slice(),
+            _ => self.line
+        };
+        let gutter = match self.line_number {
+            Some(line_number) => line_number.to_string(),
+            None => String::new()
+        };
+        let padding = " ".repeat(gutter.len());
+
+        write!(f, "error: {}", self.error)?;
+
+        match (self.line_number, column) {
+            (Some(line_number), Some(column)) =>
+                write!(f, "\n{padding}--> line {line_number}, column {column}")?,
+            (Some(line_number), None) => write!(f, "\n{padding}--> line {line_number}")?,
+            (None, Some(column)) => write!(f, "\n{padding}--> column {column}")?,
+            (None, None) => { }
+        }
+
+        // The column counts bytes, so it is mapped to the character containing that byte.
+        let line = String::from_utf8_lossy(line);
+        let chars = line.chars().collect::<Vec<_>>();
+        let caret_index = column.map(|column| {
+            let byte_index = column - 1;
+
+            String::from_utf8_lossy(&self.line[..byte_index.min(self.line.len())]).chars().count()
+        });
+        let window_start = match caret_index {
+            Some(caret_index) if chars.len() > MAX_SNIPPET_WIDTH =>
+                caret_index.saturating_sub(MAX_SNIPPET_WIDTH / 2)
+                    .min(chars.len() - MAX_SNIPPET_WIDTH),
+            _ => 0
+        };
+        let window_end = (window_start + MAX_SNIPPET_WIDTH).min(chars.len());
+        let leading_ellipsis = if window_start > 0 { "..." } else { "" };
+        let trailing_ellipsis = if window_end < chars.len() { "..." } else { "" };
+        let window = chars[window_start..window_end].iter().collect::<String>();
+
+        write!(f, "\n{padding} |\n{gutter} | {leading_ellipsis}{window}{trailing_ellipsis}")?;
+
+        if let Some(caret_index) = caret_index {
+            let ellipsis_padding = " ".repeat(leading_ellipsis.len());
+            let indentation = chars[window_start..caret_index.min(window_end)].iter()
+                .map(|&char| if char == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+
+            write!(f, "\n{padding} | {ellipsis_padding}{indentation}^")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<JsonError> for NdjsonError {
+    fn from(error: JsonError) -> NdjsonError {
+        NdjsonError::Json(error)
+    }
+}
+
+/// Syntactic sugar for a [Result] with the given value type `V` and an [NdjsonError].
+pub type NdjsonResult<V> = Result<V, NdjsonError>;
+
+/// An [NdjsonError] reported by a validating driver, such as
+/// [validate_iter](crate::validate_iter), together with the number of the offending line.
+#[derive(Debug)]
+pub struct ValidationError {
+
+    /// The one-based number of the offending line. All lines are counted, including those which
+    /// are ignored according to the configured
+    /// [EmptyLineHandling](crate::config::EmptyLineHandling) or are comments. In JSON array mode,
+    /// this is the number of the array element instead.
+    pub line_number: u64,
+
+    /// The error which occurred while parsing the line.
+    pub error: NdjsonError
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line_number, self.error)
+    }
+}
+
+impl Error for ValidationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An [NdjsonError] together with the position of the offending line in the entire input, as
+/// returned by [NdjsonEngine::pop_located](crate::engine::NdjsonEngine::pop_located).
+#[derive(Debug)]
+pub struct LocatedError {
+
+    /// The one-based number of the offending line, counted like the line numbers returned by
+    /// [NdjsonEngine::pop_with_line_number](crate::engine::NdjsonEngine::pop_with_line_number).
+    pub line_number: u64,
+
+    /// The offset of the first byte of the offending line in the entire input, in bytes. If the
+    /// line contains multiple values according to
+    /// [TrailingDataHandling::MultipleValues](crate::config::TrailingDataHandling::MultipleValues),
+    /// this is the offset of the first byte of the offending value instead.
+    pub line_offset: u64,
+
+    /// The error which occurred while parsing the line.
+    pub error: NdjsonError
+}
+
+impl LocatedError {
+
+    /// Gets the offset of the byte at which the error occurred in the entire input, in bytes. This
+    /// translates the [column](NdjsonError::column) of the error into an absolute offset. If the
+    /// column is unknown, the [line offset](LocatedError::line_offset) is returned instead.
+    ///
+    /// Note that the column refers to the line as it was parsed. If the line was changed before,
+    /// for example by a [LinePreprocessor](crate::config::LinePreprocessor), the offset may be
+    /// inaccurate.
+    pub fn offset(&self) -> u64 {
+        match self.error.column() {
+            Some(column) => self.line_offset + column as u64 - 1,
+            None => self.line_offset
+        }
+    }
+}
+
+impl Display for LocatedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} (offset {}): {}", self.line_number, self.offset(), self.error)
+    }
+}
+
+impl Error for LocatedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    fn json_error(line: &str) -> NdjsonError {
+        NdjsonError::Json(serde_json::from_str::<serde_json::Value>(line).unwrap_err())
+    }
+
+    #[test]
+    fn snippet_without_line_number_only_shows_column() {
+        let line = "[1,\t?]";
+        let error = json_error(line);
+
+        assert_that!(error.snippet(line.as_bytes()).to_string()).is_equal_to(
+            "error: expected value at line 1 column 5\n--> column 5\n |\n | [1,\t?]\n |    \t^"
+                .to_owned());
+    }
+
+    #[test]
+    fn snippet_places_caret_by_characters() {
+        let line = "[\"äö\" x]";
+        let error = json_error(line);
+        let snippet = error.snippet(line.as_bytes()).with_line_number(3).to_string();
+
+        assert_that!(snippet.lines().last()).contains("  |       ^");
+    }
+
+    #[test]
+    fn snippet_cuts_long_line_around_error() {
+        let line = format!("[{}x{}]", "1,".repeat(50), ",1".repeat(50));
+        let error = json_error(&line);
+        let snippet = error.snippet(line.as_bytes()).to_string();
+        let snippet_lines = snippet.lines().collect::<Vec<_>>();
+
+        assert_that!(snippet_lines[3].len()).is_equal_to(" | ".len() + 80 + 6);
+        assert_that!(snippet_lines[3].starts_with(" | ...")).is_true();
+        assert_that!(snippet_lines[3].ends_with("...")).is_true();
+        assert_that!(snippet_lines[4].find('^')).is_equal_to(snippet_lines[3].find('x'));
+    }
+
+    #[test]
+    fn data_error_has_column() {
+        let line = "{\"key\":\"a\",\"value\":2}";
+        let error = NdjsonError::Json(serde_json::from_str::<TestStruct>(line).unwrap_err());
+
+        assert_that!(error.column()).contains(10);
+    }
+
+    #[test]
+    fn snippet_of_error_without_column_has_no_caret() {
+        let error = NdjsonError::DuplicateKey { key: "a".to_owned() };
+
+        let snippet = error.snippet(b"{\"a\":1,\"a\":2}").with_line_number(7).to_string();
+
+        assert_that!(snippet).is_equal_to(
+            "error: duplicate key `a`\n --> line 7\n  |\n7 | {\"a\":1,\"a\":2}".to_owned());
+    }
+}
@@ -0,0 +1,63 @@
+//! This module defines the [NdjsonEvent], which describes the outcome of processing a single
+//! NDJSON-line in detail. Events are produced by an [NdjsonEngine](crate::engine::NdjsonEngine)
+//! created with [NdjsonEngine::with_events](crate::engine::NdjsonEngine::with_events).
+
+use alloc::vec::Vec;
+
+use crate::error::{NdjsonError, NdjsonResult};
+
+/// Describes the outcome of processing a single NDJSON-line. In contrast to the records returned by
+/// [NdjsonEngine::pop](crate::engine::NdjsonEngine::pop), events also report lines which contain no
+/// record, so consumers can implement their own policies, such as counting blank lines or logging
+/// errors together with the offending line.
+#[derive(Debug)]
+pub enum NdjsonEvent<T> {
+
+    /// A line was successfully parsed into the contained record.
+    Record(T),
+
+    /// A line contained no characters, or only a single `\r` character for compatibility with
+    /// `\r\n`-style linebreaks.
+    EmptyLine,
+
+    /// A line contained only whitespace characters, but was not empty.
+    BlankLine,
+
+    /// A line started with the comment prefix configured via
+    /// [NdjsonConfig::with_comment_prefix](crate::config::NdjsonConfig::with_comment_prefix).
+    CommentLine,
+
+    /// A line, or the rest after the last line ending, could not be parsed.
+    ParseError {
+
+        /// The error which occurred while parsing the line.
+        error: NdjsonError,
+
+        /// The line which could not be parsed, without the line ending. For lines exceeding the
+        /// maximum line length, only their first bytes are included.
+        line: Vec<u8>
+    },
+
+    /// The rest after the last line ending was successfully parsed into the contained record. This
+    /// only occurs if enabled via
+    /// [NdjsonConfig::with_parse_rest](crate::config::NdjsonConfig::with_parse_rest).
+    RestParsed(T)
+}
+
+impl<T> NdjsonEvent<T> {
+
+    /// Converts this event into the result a record-based interface would report for it.
+    ///
+    /// # Returns
+    ///
+    /// `Some(Ok(record))` for [NdjsonEvent::Record] and [NdjsonEvent::RestParsed],
+    /// `Some(Err(error))` for [NdjsonEvent::ParseError], and `None` for all events which do not
+    /// correspond to a record.
+    pub fn into_result(self) -> Option<NdjsonResult<T>> {
+        match self {
+            NdjsonEvent::Record(record) | NdjsonEvent::RestParsed(record) => Some(Ok(record)),
+            NdjsonEvent::ParseError { error, .. } => Some(Err(error)),
+            NdjsonEvent::EmptyLine | NdjsonEvent::BlankLine | NdjsonEvent::CommentLine => None
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! This module defines the [CsvWriter], which writes records implementing [Serialize] as rows of a
+//! CSV-file. Records must have a flat structure, i.e. serialize as a JSON-object, such as a struct
+//! or map. The columns of the file are inferred from the fields of the first record, unless
+//! configured explicitly via [CsvWriter::with_columns].
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::export::csv::CsvWriter;
+//!
+//! let data = "{\"name\":\"Alice\",\"age\":25}\n{\"name\":\"Bob, Jr.\",\"age\":35}\n";
+//! let records = ndjson_stream::from_iter::<serde_json::Value, _>([data])
+//!     .map(Result::unwrap);
+//! let mut writer = CsvWriter::new(Vec::new());
+//!
+//! writer.write_all(records).unwrap();
+//!
+//! assert_eq!(writer.into_inner(), b"age,name\n25,Alice\n35,\"Bob, Jr.\"\n");
+//! ```
+
+use std::fmt;
+use std::io::{self, Write};
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::Serialize;
+
+use serde_json::value::RawValue;
+
+use crate::writer::WriteLineEnding;
+
+/// The fields of a serialized record in order, with their values as JSON-text.
+struct Fields<'data>(Vec<(String, &'data RawValue)>);
+
+impl<'de> Deserialize<'de> for Fields<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Fields<'de>, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        struct FieldsVisitor;
+
+        impl<'de> Visitor<'de> for FieldsVisitor {
+            type Value = Fields<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a record serializing as a JSON-object")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Fields<'de>, A::Error>
+            where
+                A: MapAccess<'de>
+            {
+                let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0));
+
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+
+                Ok(Fields(fields))
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor)
+    }
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Writes records implementing [Serialize] as rows of a CSV-file into a wrapped [Write]. The first
+/// row is a header with the names of the columns. Cells are quoted if they contain the delimiter,
+/// a double quote, or a line break, with double quotes being escaped by doubling them.
+///
+/// Strings are written without their JSON-quotes and escapes, `null` is written as an empty cell,
+/// while all other values, such as numbers, booleans, and nested arrays or objects, are written as
+/// their compact JSON-text. Fields of a record which are missing in a column are written as empty
+/// cells, while fields which are not part of the columns cause an error.
+///
+/// Since every row is written with a separate call to the wrapped writer, it is recommended to
+/// wrap unbuffered writers such as files in a [BufWriter](std::io::BufWriter).
+pub struct CsvWriter<W> {
+    writer: W,
+    delimiter: u8,
+    line_ending: WriteLineEnding,
+    columns: Option<Vec<String>>,
+    is_header_written: bool,
+    json: Vec<u8>,
+    row: Vec<u8>
+}
+
+impl<W> CsvWriter<W>
+where
+    W: Write
+{
+
+    /// Creates a new CSV-writer wrapping the given `writer`, which separates cells by commas and
+    /// infers its columns from the first record.
+    pub fn new(writer: W) -> CsvWriter<W> {
+        CsvWriter {
+            writer,
+            delimiter: b',',
+            line_ending: WriteLineEnding::Lf,
+            columns: None,
+            is_header_written: false,
+            json: Vec::new(),
+            row: Vec::new()
+        }
+    }
+
+    /// Sets the byte by which this writer separates cells, such as `b';'` or `b'\t'`. By default,
+    /// this is a comma.
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the delimiter.
+    pub fn with_delimiter(self, delimiter: u8) -> CsvWriter<W> {
+        CsvWriter {
+            delimiter,
+            ..self
+        }
+    }
+
+    /// Sets the character sequence by which this writer terminates rows. By default, this is
+    /// [WriteLineEnding::Lf].
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the line ending.
+    pub fn with_line_ending(self, line_ending: WriteLineEnding) -> CsvWriter<W> {
+        CsvWriter {
+            line_ending,
+            ..self
+        }
+    }
+
+    /// Sets the columns of the CSV-file explicitly instead of inferring them from the first record.
+    /// This is useful if later records may have fields which the first one does not have. This
+    /// has no effect once the first record has been written.
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the columns.
+    pub fn with_columns<I>(self, columns: I) -> CsvWriter<W>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>
+    {
+        CsvWriter {
+            columns: Some(columns.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
+
+    /// Gets the columns of the CSV-file, if they are known, i.e. they have been configured or a
+    /// record has been written.
+    pub fn columns(&self) -> Option<&[String]> {
+        self.columns.as_deref()
+    }
+
+    /// Gets a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwraps this writer, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn push_cell(&mut self, text: &str) {
+        let needs_quotes = text.bytes()
+            .any(|byte| byte == self.delimiter || matches!(byte, b'"' | b'\n' | b'\r'));
+
+        if needs_quotes {
+            self.row.push(b'"');
+            self.row.extend_from_slice(text.replace('"', "\"\"").as_bytes());
+            self.row.push(b'"');
+        }
+        else {
+            self.row.extend_from_slice(text.as_bytes());
+        }
+    }
+
+    fn push_line_ending(&mut self) {
+        match self.line_ending {
+            WriteLineEnding::Lf => self.row.push(b'\n'),
+            WriteLineEnding::CrLf => self.row.extend_from_slice(b"\r\n")
+        }
+    }
+
+    fn write_header(&mut self, columns: &[String]) -> io::Result<()> {
+        self.row.clear();
+
+        for (index, column) in columns.iter().enumerate() {
+            if index > 0 {
+                self.row.push(self.delimiter);
+            }
+
+            self.push_cell(column);
+        }
+
+        self.push_line_ending();
+        self.writer.write_all(&self.row)
+    }
+
+    /// Serializes the given record and writes it as a row of the CSV-file. If this is the first
+    /// record, the header row is written first.
+    ///
+    /// # Errors
+    ///
+    /// If serialization fails, the record does not serialize as a JSON-object, or it has a field
+    /// which is not a column of the CSV-file, all of which are reported as an [io::Error] of kind
+    /// [io::ErrorKind::InvalidData] without writing anything, or if writing to the wrapped writer
+    /// fails.
+    pub fn write<T>(&mut self, record: &T) -> io::Result<()>
+    where
+        T: Serialize + ?Sized
+    {
+        let mut json = std::mem::take(&mut self.json);
+        let result = self.write_serialized(record, &mut json);
+
+        self.json = json;
+        result
+    }
+
+    fn write_serialized<T>(&mut self, record: &T, json: &mut Vec<u8>) -> io::Result<()>
+    where
+        T: Serialize + ?Sized
+    {
+        json.clear();
+        serde_json::to_writer(&mut *json, record).map_err(invalid_data)?;
+
+        let Fields(fields) = serde_json::from_slice(json).map_err(invalid_data)?;
+        let columns = self.columns.get_or_insert_with(||
+            fields.iter().map(|(name, _)| name.clone()).collect());
+        let cells = assign_cells(columns, fields)?;
+
+        if !self.is_header_written {
+            let columns = columns.clone();
+
+            self.write_header(&columns)?;
+            self.is_header_written = true;
+        }
+
+        self.row.clear();
+
+        for (index, cell) in cells.into_iter().enumerate() {
+            if index > 0 {
+                self.row.push(self.delimiter);
+            }
+
+            if let Some(value) = cell {
+                self.push_value(value)?;
+            }
+        }
+
+        self.push_line_ending();
+        self.writer.write_all(&self.row)
+    }
+
+    fn push_value(&mut self, value: &RawValue) -> io::Result<()> {
+        let text = value.get();
+
+        match text.as_bytes().first() {
+            Some(b'"') => {
+                let string: String = serde_json::from_str(text).map_err(invalid_data)?;
+
+                self.push_cell(&string);
+            },
+            Some(b'n') => { },
+            _ => self.push_cell(text)
+        }
+
+        Ok(())
+    }
+
+    /// Writes every record of the given iterator as a row of the CSV-file via [CsvWriter::write].
+    /// This can be used for example with the output of an
+    /// [NdjsonIter](crate::driver::NdjsonIter) after handling parse errors.
+    ///
+    /// # Errors
+    ///
+    /// As soon as writing a record fails, see [CsvWriter::write]. Records before the failing one
+    /// have been written.
+    ///
+    /// # Returns
+    ///
+    /// The number of written records.
+    pub fn write_all<I>(&mut self, records: I) -> io::Result<usize>
+    where
+        I: IntoIterator,
+        I::Item: Serialize
+    {
+        let mut count = 0;
+
+        for record in records {
+            self.write(&record)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Flushes the wrapped writer.
+    ///
+    /// # Errors
+    ///
+    /// If flushing the wrapped writer fails.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Orders the values of the given fields according to the given columns.
+fn assign_cells<'data>(columns: &[String], fields: Vec<(String, &'data RawValue)>)
+        -> io::Result<Vec<Option<&'data RawValue>>> {
+    let mut cells = vec![None; columns.len()];
+
+    for (name, value) in fields {
+        let index = columns.iter().position(|column| *column == name)
+            .ok_or_else(|| invalid_data(format!("field `{name}` is not a CSV-column")))?;
+
+        cells[index] = Some(value);
+    }
+
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::BTreeMap;
+
+    use kernal::prelude::*;
+
+    use serde_json::json;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    fn output(writer: CsvWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn columns_are_inferred_in_field_order() {
+        let mut writer = CsvWriter::new(Vec::new());
+
+        writer.write(&TestStruct { key: 1, value: 2 }).unwrap();
+        writer.write(&TestStruct { key: 3, value: 4 }).unwrap();
+
+        assert_that!(writer.columns()).to_value()
+            .contains_exactly_in_given_order(["key".to_owned(), "value".to_owned()]);
+        assert_that!(output(writer).as_str()).is_equal_to("key,value\n1,2\n3,4\n");
+    }
+
+    #[test]
+    fn values_are_flattened_and_quoted() {
+        let mut writer = CsvWriter::new(Vec::new()).with_line_ending(WriteLineEnding::CrLf);
+
+        writer.write(&json!({
+            "a": "say \"hi\"",
+            "b": null,
+            "c": [1, 2],
+            "d": true,
+            "e": "x\ny"
+        })).unwrap();
+
+        assert_that!(output(writer).as_str())
+            .is_equal_to("a,b,c,d,e\r\n\"say \"\"hi\"\"\",,\"[1,2]\",true,\"x\ny\"\r\n");
+    }
+
+    #[test]
+    fn configured_columns_allow_missing_fields() {
+        let mut writer = CsvWriter::new(Vec::new())
+            .with_delimiter(b';')
+            .with_columns(["value", "key", "extra"]);
+        let mut record = BTreeMap::new();
+
+        record.insert("key", "a;b");
+        writer.write(&TestStruct { key: 1, value: 2 }).unwrap();
+        writer.write(&record).unwrap();
+
+        assert_that!(output(writer).as_str()).is_equal_to("value;key;extra\n2;1;\n;\"a;b\";\n");
+    }
+
+    #[test]
+    fn invalid_records_are_rejected_without_writing() {
+        let mut writer = CsvWriter::new(Vec::new());
+
+        assert_that!(writer.write(&1).map_err(|error| error.kind()))
+            .contains_error(io::ErrorKind::InvalidData);
+        assert_that!(writer.columns()).is_none();
+
+        writer.write(&json!({ "a": 1 })).unwrap();
+
+        assert_that!(writer.write(&json!({ "b": 1 })).map_err(|error| error.kind()))
+            .contains_error(io::ErrorKind::InvalidData);
+        assert_that!(output(writer).as_str()).is_equal_to("a\n1\n");
+    }
+}
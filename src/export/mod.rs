@@ -0,0 +1,4 @@
+//! This module contains exporters which convert parsed NDJSON-records into other formats for
+//! downstream tools. Currently, the [csv] module offers flattening records into CSV-rows.
+
+pub mod csv;
@@ -1,32 +1,43 @@
 //! This module defines the error- and result-type used for fallible NDJSON, i.e. where the data
 //! source can raise an error. See [FallibleNdjsonError] for more details.
 
-use serde_json::Error as JsonError;
+#[cfg(any(feature = "iter", feature = "stream"))]
+use core::convert::Infallible;
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
 
-use std::convert::Infallible;
-
-use thiserror::Error;
+use crate::error::NdjsonError;
 
 /// The errors which can occur when using a fallible-input-interface, such as
 /// [FallibleNdjsonIter](crate::driver::iter::FallibleNdjsonIter) or
 /// [FallibleNdjsonStream](crate::driver::stream::FallibleNdjsonStream).
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum FallibleNdjsonError<E> {
 
     /// Reading the fallible input failed. The error returned by the input on trying to read is
     /// wrapped in this variant.
-    #[error("error reading input: {0}")]
     InputError(E),
 
-    /// Parsing a JSON-line failed. The [serde_json::Error] is wrapped in this variant.
-    #[error("error parsing line: {0}")]
-    JsonError(JsonError)
+    /// Parsing a JSON-line failed. The [NdjsonError] is wrapped in this variant.
+    JsonError(NdjsonError)
 }
 
+impl<E: Display> Display for FallibleNdjsonError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FallibleNdjsonError::InputError(error) => write!(f, "error reading input: {error}"),
+            FallibleNdjsonError::JsonError(error) => write!(f, "error parsing line: {error}")
+        }
+    }
+}
+
+impl<E: Debug + Display> Error for FallibleNdjsonError<E> { }
+
 // TODO replace with never-type once available (https://github.com/rust-lang/rust/issues/35121)
 
+#[cfg(any(feature = "iter", feature = "stream"))]
 impl FallibleNdjsonError<Infallible> {
-    pub(crate) fn unwrap_json_error(self) -> JsonError {
+    pub(crate) fn unwrap_json_error(self) -> NdjsonError {
         match self {
             FallibleNdjsonError::JsonError(err) => err,
             FallibleNdjsonError::InputError(err) => match err { }
@@ -0,0 +1,186 @@
+//! This module offers harnesses which check invariants of the parser on arbitrary inputs, intended
+//! to be called from fuzz targets or property tests. All checks panic if the invariant is violated,
+//! which is how fuzzers detect failures.
+//!
+//! The central invariant is that the output of the [NdjsonEngine](crate::engine::NdjsonEngine)
+//! does not depend on how the input is split into chunks. For example, a `cargo fuzz` target can
+//! simply forward its data to [fuzz_chunking].
+//!
+//! ```
+//! ndjson_stream::fuzz::fuzz_chunking(b"\x03\x01\x00\x05{\"a\":1}\n[1,\n2]\n  \nx");
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use core::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use serde_json::Value;
+
+use crate::config::{EmptyLineHandling, NdjsonConfig};
+use crate::engine;
+use crate::error::NdjsonResult;
+use crate::writer;
+
+/// Splits the given `corpus` into chunks of the given lengths, which are used cyclically until the
+/// corpus is exhausted. Lengths of 0 produce empty chunks, but if all lengths are 0, the entire
+/// corpus is returned as a single chunk to guarantee termination.
+pub fn chunk_by_lengths<'corpus>(corpus: &'corpus [u8], chunk_lengths: &[usize])
+        -> Vec<&'corpus [u8]> {
+    if chunk_lengths.iter().all(|&length| length == 0) {
+        return Vec::from([corpus]);
+    }
+
+    let mut chunks = Vec::new();
+    let mut rest = corpus;
+
+    for &length in chunk_lengths.iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+
+        let (chunk, remaining) = rest.split_at(length.min(rest.len()));
+
+        chunks.push(chunk);
+        rest = remaining;
+    }
+
+    chunks
+}
+
+/// Converts parse results into a comparable representation, in which errors are represented by
+/// their message.
+fn comparable<T>(results: Vec<NdjsonResult<T>>) -> Vec<Result<T, String>> {
+    results.into_iter()
+        .map(|result| result.map_err(|error| error.to_string()))
+        .collect()
+}
+
+/// Asserts that parsing the given `corpus` with the given [NdjsonConfig] produces the same records
+/// and errors when it is given to the engine at once and when it is split into chunks of the given
+/// lengths, as done by [chunk_by_lengths]. Errors are compared by their message.
+///
+/// # Panics
+///
+/// If the outputs differ.
+pub fn assert_chunking_invariant<T>(corpus: &[u8], chunk_lengths: &[usize], config: NdjsonConfig)
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Debug + PartialEq
+{
+    let chunks = chunk_by_lengths(corpus, chunk_lengths);
+    let expected = comparable(engine::parse_all_with_config::<T, _>([corpus], config.clone()));
+    let actual = comparable(engine::parse_all_with_config::<T, _>(chunks, config));
+
+    assert_eq!(actual, expected, "output differs for chunk lengths {chunk_lengths:?}");
+}
+
+/// Asserts that the given `values` are parsed back unchanged from the NDJSON-data produced by
+/// [serialize_line](writer::serialize_line), independent of the chunk lengths in which the data is
+/// given to the engine. Values which cannot be serialized are skipped.
+///
+/// # Panics
+///
+/// If a value is not parsed back unchanged.
+pub fn assert_round_trip<T>(values: &[T], chunk_lengths: &[usize])
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Debug + PartialEq + Serialize
+{
+    let mut data = Vec::new();
+    let mut expected = Vec::new();
+
+    for value in values {
+        if writer::serialize_line(value, &mut data).is_ok() {
+            expected.push(Ok::<_, &String>(value));
+        }
+    }
+
+    let chunks = chunk_by_lengths(&data, chunk_lengths);
+    let actual = comparable(engine::parse_all::<T, _>(chunks));
+    let actual = actual.iter().map(Result::as_ref).collect::<Vec<_>>();
+
+    assert_eq!(actual, expected, "values differ for chunk lengths {chunk_lengths:?}");
+}
+
+/// An entry point for fuzz targets, which interprets arbitrary `data` as a chunking and a corpus
+/// and checks the chunking invariant of [assert_chunking_invariant] for [Value]s with a selection
+/// of configs. The first byte determines how many of the following bytes, up to 15, are chunk
+/// lengths, while all remaining bytes form the corpus.
+///
+/// # Panics
+///
+/// If the output of the engine depends on the chunking.
+pub fn fuzz_chunking(data: &[u8]) {
+    let Some((&length_count, data)) = data.split_first()
+    else {
+        return;
+    };
+    let length_count = (length_count as usize % 16).min(data.len());
+    let (chunk_lengths, corpus) = data.split_at(length_count);
+    let chunk_lengths = chunk_lengths.iter().map(|&length| length as usize).collect::<Vec<_>>();
+    let configs = [
+        NdjsonConfig::default(),
+        NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_parse_rest(true),
+        NdjsonConfig::default()
+            .with_max_line_length(8)
+            .with_comment_prefix("#"),
+        NdjsonConfig::default()
+            .with_json_array_mode(true)
+            .with_parse_rest(true)
+    ];
+
+    for config in configs {
+        assert_chunking_invariant::<Value>(corpus, &chunk_lengths, config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[test]
+    fn corpus_is_chunked_cyclically() {
+        assert_that!(chunk_by_lengths(b"abcdefg", &[2, 0, 3])).contains_exactly_in_given_order([
+            b"ab".as_slice(),
+            b"",
+            b"cde",
+            b"fg"
+        ]);
+        assert_that!(chunk_by_lengths(b"abc", &[0])).contains_exactly_in_given_order([
+            b"abc".as_slice()
+        ]);
+    }
+
+    #[test]
+    fn fuzz_entry_point_accepts_arbitrary_data() {
+        let inputs: [&[u8]; 5] = [
+            b"",
+            b"\x00",
+            b"\x02\x01\x03{\"a\":[1,2]}\r\n\n  x\n\"\xff\"\n[1,",
+            b"\x01\x05[{\"a\":\"]\"}, 2] [3",
+            b"\x0f\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01\x01#c\n123456789\n"
+        ];
+
+        for input in inputs {
+            fuzz_chunking(input);
+        }
+    }
+
+    #[test]
+    fn values_survive_round_trip() {
+        assert_round_trip(&[
+            TestStruct { key: 1, value: 2 },
+            TestStruct { key: 3, value: 4 }
+        ], &[1, 5]);
+    }
+}
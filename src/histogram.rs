@@ -0,0 +1,157 @@
+//! This module defines the [RecordSizeHistogram], in which an
+//! [NdjsonEngine](crate::engine::NdjsonEngine) tracks the distribution of the sizes of its records
+//! for capacity planning and the detection of outliers. Unusually large records can additionally
+//! be reported as they occur, see [NdjsonConfig](crate::config::NdjsonConfig).
+
+use core::ops::RangeInclusive;
+
+/// The number of buckets of a [RecordSizeHistogram], i.e. one for empty records and one for every
+/// possible bit length of a size.
+const BUCKET_COUNT: usize = u64::BITS as usize + 1;
+
+/// A histogram of record sizes in bytes with exponentially growing buckets. The first bucket
+/// contains empty records, while bucket `i > 0` contains the records with sizes from `2^(i-1)` to
+/// `2^i - 1`. This keeps the histogram small and cheap to update, while still distinguishing
+/// typical records from outliers by orders of magnitude.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::histogram::RecordSizeHistogram;
+///
+/// let mut histogram = RecordSizeHistogram::new();
+///
+/// histogram.record(1);
+/// histogram.record(5);
+/// histogram.record(6);
+///
+/// assert_eq!(histogram.count(), 3);
+/// assert_eq!(histogram.buckets().collect::<Vec<_>>(), vec![(1..=1, 1), (4..=7, 2)]);
+/// assert_eq!(histogram.quantile_upper_bound(0.5), Some(7));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RecordSizeHistogram {
+    bucket_counts: [u64; BUCKET_COUNT]
+}
+
+fn bucket_index(size: u64) -> usize {
+    (u64::BITS - size.leading_zeros()) as usize
+}
+
+fn bucket_range(index: usize) -> RangeInclusive<u64> {
+    match index {
+        0 => 0..=0,
+        index => (1 << (index - 1))..=(u64::MAX >> (BUCKET_COUNT - 1 - index))
+    }
+}
+
+impl RecordSizeHistogram {
+
+    /// Creates a new, empty histogram.
+    pub fn new() -> RecordSizeHistogram {
+        RecordSizeHistogram {
+            bucket_counts: [0; BUCKET_COUNT]
+        }
+    }
+
+    /// Adds a record with the given `size` in bytes to this histogram.
+    pub fn record(&mut self, size: u64) {
+        self.bucket_counts[bucket_index(size)] += 1;
+    }
+
+    /// Gets the total number of records in this histogram.
+    pub fn count(&self) -> u64 {
+        self.bucket_counts.iter().sum()
+    }
+
+    /// Gets an [Iterator] over all non-empty buckets in ascending order of sizes, each given as the
+    /// range of sizes it contains together with the number of records in it.
+    pub fn buckets(&self) -> impl Iterator<Item = (RangeInclusive<u64>, u64)> + '_ {
+        self.bucket_counts.iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(index, &count)| (bucket_range(index), count))
+    }
+
+    /// Gets an upper bound for the given `quantile` of the record sizes, such as `0.99` for the
+    /// 99th percentile, which is the largest size of the bucket containing that quantile. The
+    /// quantile is clamped to the range from `0.0` to `1.0`.
+    ///
+    /// # Returns
+    ///
+    /// The upper bound, or `None` if this histogram is empty.
+    pub fn quantile_upper_bound(&self, quantile: f64) -> Option<u64> {
+        let count = self.count();
+
+        if count == 0 {
+            return None;
+        }
+
+        // f64::ceil is not available without std
+        let scaled_rank = quantile.clamp(0.0, 1.0) * count as f64;
+        let mut rank = scaled_rank as u64;
+
+        if (rank as f64) < scaled_rank || rank == 0 {
+            rank += 1;
+        }
+
+        let mut records_so_far = 0;
+
+        self.buckets()
+            .find(|(_, bucket_count)| {
+                records_so_far += bucket_count;
+                records_so_far >= rank
+            })
+            .map(|(range, _)| *range.end())
+    }
+}
+
+impl Default for RecordSizeHistogram {
+    fn default() -> RecordSizeHistogram {
+        RecordSizeHistogram::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn sizes_are_sorted_into_exponential_buckets() {
+        let mut histogram = RecordSizeHistogram::new();
+
+        for size in [0, 1, 2, 3, 4, 1000, u64::MAX] {
+            histogram.record(size);
+        }
+
+        assert_that!(histogram.buckets().collect::<Vec<_>>()).contains_exactly_in_given_order([
+            (0..=0, 1),
+            (1..=1, 1),
+            (2..=3, 2),
+            (4..=7, 1),
+            (512..=1023, 1),
+            ((1 << 63)..=u64::MAX, 1)
+        ]);
+    }
+
+    #[test]
+    fn quantiles_are_bounded_by_bucket_ends() {
+        let mut histogram = RecordSizeHistogram::new();
+
+        assert_that!(histogram.quantile_upper_bound(0.5)).is_none();
+
+        for _ in 0..99 {
+            histogram.record(10);
+        }
+
+        histogram.record(5000);
+
+        assert_that!(histogram.quantile_upper_bound(0.0)).contains(15);
+        assert_that!(histogram.quantile_upper_bound(0.99)).contains(15);
+        assert_that!(histogram.quantile_upper_bound(0.995)).contains(8191);
+        assert_that!(histogram.quantile_upper_bound(2.0)).contains(8191);
+    }
+}
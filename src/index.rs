@@ -0,0 +1,393 @@
+//! This module offers random access to the records of seekable NDJSON-files. A [RecordIndex] is
+//! built by scanning the data once and stores the byte offset of every `N`-th record, so its size
+//! is only a fraction of the number of records. Accessing a record then requires seeking to the
+//! closest preceding indexed offset and skipping at most `N - 1` records.
+//!
+//! Records are counted starting at 0. Blank lines, i.e. lines which contain only whitespace, are
+//! not counted as records.
+//!
+//! The index is not updated if the file is modified afterwards. In that case, a new index must be
+//! built via [open_indexed] or [RecordIndex::build].
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::Write;
+//!
+//! use ndjson_stream::index;
+//!
+//! let path = std::env::temp_dir().join("ndjson-stream-index-doc-example.ndjson");
+//! let mut file = std::fs::File::create(&path).unwrap();
+//!
+//! for record in 0..100 {
+//!     writeln!(file, "{}", record).unwrap();
+//! }
+//!
+//! let mut indexed_file = index::open_indexed(&path).unwrap();
+//!
+//! assert_eq!(indexed_file.record_count(), 100);
+//! assert!(matches!(indexed_file.get::<u32>(42), Some(Ok(42))));
+//!
+//! let records = indexed_file.range::<u32>(97..105).unwrap()
+//!     .map(Result::unwrap)
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(records, vec![97, 98, 99]);
+//!
+//! std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::fs::File;
+use std::io;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::config::NdjsonConfig;
+use crate::engine;
+use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+use crate::source::{ChunkSource, LineSpans, ReaderSource};
+
+/// The number of records between two consecutive offsets stored in an index built by
+/// [open_indexed].
+pub const DEFAULT_INDEX_INTERVAL: u64 = 1024;
+
+fn is_record(line: &[u8]) -> bool {
+    !line.iter().all(u8::is_ascii_whitespace)
+}
+
+/// A compact index of the records of NDJSON-data, which stores the byte offset of every `N`-th
+/// record, where `N` is the [interval](RecordIndex::interval) of the index.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RecordIndex {
+    interval: u64,
+    offsets: Vec<u64>,
+    record_count: u64
+}
+
+impl RecordIndex {
+
+    /// Builds a new index by scanning all data of the given `source` once, storing the byte offset
+    /// of every `interval`-th record. An `interval` of 0 is treated as 1. The records are not
+    /// parsed in the process.
+    ///
+    /// # Errors
+    ///
+    /// If the source reports an error. In that case, no index is built.
+    pub fn build<S>(source: S, interval: u64) -> Result<RecordIndex, S::Error>
+    where
+        S: ChunkSource
+    {
+        let interval = interval.max(1);
+        let mut offsets = Vec::new();
+        let mut record_count = 0;
+
+        for line in source.lines() {
+            let (range, line) = line?;
+
+            if !is_record(&line) {
+                continue;
+            }
+
+            if record_count % interval == 0 {
+                offsets.push(range.start);
+            }
+
+            record_count += 1;
+        }
+
+        Ok(RecordIndex {
+            interval,
+            offsets,
+            record_count
+        })
+    }
+
+    /// Gets the number of records between two consecutive stored offsets.
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Gets the stored byte offsets. The `i`-th offset is the start of the record with number
+    /// `i * interval`.
+    pub fn offsets(&self) -> &[u64] {
+        &self.offsets
+    }
+
+    /// Gets the total number of records in the indexed data.
+    pub fn record_count(&self) -> u64 {
+        self.record_count
+    }
+
+    /// Gets the byte offset from which to start reading in order to reach the record with the given
+    /// `record_number`, together with the number of records to skip from there.
+    ///
+    /// # Returns
+    ///
+    /// The offset and number of records to skip, or `None` if there is no record with the given
+    /// number.
+    pub fn locate(&self, record_number: u64) -> Option<(u64, u64)> {
+        if record_number >= self.record_count {
+            return None;
+        }
+
+        let offset = self.offsets[(record_number / self.interval) as usize];
+
+        Some((offset, record_number % self.interval))
+    }
+}
+
+/// An NDJSON-file together with a [RecordIndex] of its records, which allows reading individual
+/// records or ranges of records without parsing the entire file. Obtained via [open_indexed] or
+/// [IndexedFile::open].
+///
+/// Records are parsed according to the duplicate key and trailing data handling of the default
+/// [NdjsonConfig].
+#[derive(Debug)]
+pub struct IndexedFile {
+    file: File,
+    index: RecordIndex
+}
+
+impl IndexedFile {
+
+    /// Opens the file at the given `path` and builds an index storing the offset of every
+    /// `interval`-th record. An `interval` of 0 is treated as 1. Smaller intervals make accessing
+    /// records faster at the cost of a larger index.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or read.
+    pub fn open(path: impl AsRef<Path>, interval: u64) -> io::Result<IndexedFile> {
+        let file = File::open(path)?;
+        let index = RecordIndex::build(ReaderSource::new(&file), interval)?;
+
+        Ok(IndexedFile::with_index(file, index))
+    }
+
+    /// Creates a new indexed file from a `file` and an `index` which was previously built from its
+    /// data, for example one loaded from a cache.
+    pub fn with_index(file: File, index: RecordIndex) -> IndexedFile {
+        IndexedFile {
+            file,
+            index
+        }
+    }
+
+    /// Gets the index of the records of this file.
+    pub fn index(&self) -> &RecordIndex {
+        &self.index
+    }
+
+    /// Gets the total number of records in this file.
+    pub fn record_count(&self) -> u64 {
+        self.index.record_count()
+    }
+
+    /// Reads and parses the record with the given `record_number`.
+    ///
+    /// # Returns
+    ///
+    /// The parsed record, or `None` if there is no record with the given number. If reading the
+    /// file or parsing the record fails, the corresponding error is returned.
+    pub fn get<T>(&mut self, record_number: u64) -> Option<FallibleNdjsonResult<T, io::Error>>
+    where
+        T: DeserializeOwned
+    {
+        match self.range(record_number..record_number.saturating_add(1)) {
+            Ok(mut records) => records.next(),
+            Err(error) => Some(Err(FallibleNdjsonError::InputError(error)))
+        }
+    }
+
+    /// Creates an iterator over the parsed records with numbers in the given `range`. Parts of the
+    /// range beyond the last record are ignored. The iterator borrows this file mutably, since it
+    /// moves the position of the underlying file handle.
+    ///
+    /// # Errors
+    ///
+    /// If seeking to the start of the range fails.
+    pub fn range<T>(&mut self, range: Range<u64>) -> io::Result<IndexedRecords<'_, T>>
+    where
+        T: DeserializeOwned
+    {
+        let end = range.end.min(self.record_count());
+        let mut source = ReaderSource::new(&self.file);
+        let (records_to_skip, remaining) = match self.index.locate(range.start) {
+            Some((offset, records_to_skip)) if range.start < end => {
+                source.seek_to_record(offset)?;
+                (records_to_skip, end - range.start)
+            },
+            _ => (0, 0)
+        };
+
+        Ok(IndexedRecords {
+            lines: source.lines(),
+            records_to_skip,
+            remaining,
+            record_type: PhantomData
+        })
+    }
+}
+
+/// Opens the file at the given `path` and builds an index storing the offset of every
+/// [DEFAULT_INDEX_INTERVAL]-th record. Use [IndexedFile::open] to specify a different interval.
+///
+/// # Errors
+///
+/// If the file cannot be opened or read.
+pub fn open_indexed(path: impl AsRef<Path>) -> io::Result<IndexedFile> {
+    IndexedFile::open(path, DEFAULT_INDEX_INTERVAL)
+}
+
+/// An iterator over a range of parsed records of an [IndexedFile]. Obtained via
+/// [IndexedFile::range].
+pub struct IndexedRecords<'file, T> {
+    lines: LineSpans<ReaderSource<&'file File>>,
+    records_to_skip: u64,
+    remaining: u64,
+    record_type: PhantomData<fn() -> T>
+}
+
+impl<T> IndexedRecords<'_, T> {
+
+    fn next_record_line(&mut self) -> Option<io::Result<Vec<u8>>> {
+        loop {
+            match self.lines.next() {
+                Some(Ok((_, line))) if is_record(&line) => return Some(Ok(line)),
+                Some(Ok(_)) => { },
+                Some(Err(error)) => return Some(Err(error)),
+                None => return None
+            }
+        }
+    }
+}
+
+impl<T> Iterator for IndexedRecords<'_, T>
+where
+    T: DeserializeOwned
+{
+    type Item = FallibleNdjsonResult<T, io::Error>;
+
+    fn next(&mut self) -> Option<FallibleNdjsonResult<T, io::Error>> {
+        while self.remaining > 0 && self.records_to_skip > 0 {
+            match self.next_record_line() {
+                Some(Ok(_)) => self.records_to_skip -= 1,
+                Some(Err(error)) => return Some(Err(FallibleNdjsonError::InputError(error))),
+                None => self.remaining = 0
+            }
+        }
+
+        if self.remaining == 0 {
+            return None;
+        }
+
+        match self.next_record_line() {
+            Some(Ok(line)) => {
+                self.remaining -= 1;
+                Some(engine::parse_line(&line, &NdjsonConfig::default())
+                    .map_err(FallibleNdjsonError::JsonError))
+            },
+            Some(Err(error)) => Some(Err(FallibleNdjsonError::InputError(error))),
+            None => {
+                self.remaining = 0;
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, usize::try_from(self.remaining).ok())
+    }
+}
+
+impl<T> FusedIterator for IndexedRecords<'_, T>
+where
+    T: DeserializeOwned
+{ }
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, data: &str) -> TempFile {
+            let path = std::env::temp_dir()
+                .join(format!("ndjson-stream-index-{}-{}.ndjson", name, std::process::id()));
+            std::fs::write(&path, data).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn records(count: u64) -> String {
+        (0..count)
+            .map(|key| format!("{{\"key\":{},\"value\":{}}}\n", key, key * 2))
+            .collect()
+    }
+
+    #[test]
+    fn index_stores_every_nth_offset_and_ignores_blank_lines() {
+        let data = "1\n\n22\n  \n333\n4444\n5";
+        let index = RecordIndex::build(ReaderSource::with_chunk_size(Cursor::new(data), 3), 2)
+            .unwrap();
+
+        assert_that!(index.record_count()).is_equal_to(5);
+        assert_that!(index.offsets()).contains_exactly_in_given_order([0, 9, 18]);
+        assert_that!(index.locate(3)).contains((9, 1));
+        assert_that!(index.locate(5)).is_none();
+    }
+
+    #[test]
+    fn get_reads_records_across_index_intervals() {
+        let file = TempFile::new("get", &records(25));
+        let mut indexed_file = IndexedFile::open(&file.0, 4).unwrap();
+
+        assert_that!(indexed_file.record_count()).is_equal_to(25);
+
+        for key in [0, 3, 4, 13, 24] {
+            assert_that!(indexed_file.get::<TestStruct>(key)).to_value()
+                .contains_value(TestStruct { key, value: key * 2 });
+        }
+
+        assert_that!(indexed_file.get::<TestStruct>(25)).is_none();
+    }
+
+    #[test]
+    fn range_is_clamped_to_record_count() {
+        let file = TempFile::new("range", &records(10));
+        let mut indexed_file = IndexedFile::open(&file.0, 3).unwrap();
+        let keys = indexed_file.range::<TestStruct>(5..20).unwrap()
+            .map(|record| record.unwrap().key)
+            .collect::<Vec<_>>();
+
+        assert_that!(keys).contains_exactly_in_given_order([5, 6, 7, 8, 9]);
+        assert_that!(indexed_file.range::<TestStruct>(12..15).unwrap().next()).is_none();
+    }
+
+    #[test]
+    fn invalid_record_is_reported_as_json_error() {
+        let file = TempFile::new("invalid", "{\"key\":1,\"value\":2}\ninvalid\n");
+        let mut indexed_file = open_indexed(&file.0).unwrap();
+
+        assert!(matches!(indexed_file.get::<TestStruct>(1),
+            Some(Err(FallibleNdjsonError::JsonError(_)))));
+    }
+}
@@ -0,0 +1,461 @@
+//! This module offers [join], which joins the records of two NDJSON-sources that are each sorted by
+//! some key, as is common in ETL jobs correlating two exports. The join is a sort-merge join, so
+//! instead of loading one side entirely, only the records of the right source with the current key
+//! are held in memory, in addition to one record per source.
+//!
+//! Records are matched by equal keys and returned as [Joined] values. Which unmatched records are
+//! returned as well is configured via [JoinKind]. If several records of both sources have the same
+//! key, every combination of them is returned, left records in their order first.
+//!
+//! If a source is not sorted by the key, records with equal keys which are not adjacent are not
+//! matched.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::join::{self, Joined, JoinKind};
+//! use serde::Deserialize;
+//!
+//! #[derive(Clone, Debug, Deserialize, PartialEq)]
+//! struct User {
+//!     id: u64,
+//!     name: String
+//! }
+//!
+//! #[derive(Clone, Debug, Deserialize, PartialEq)]
+//! struct Order {
+//!     user_id: u64,
+//!     amount: u64
+//! }
+//!
+//! let users = ndjson_stream::from_fallible_iter::<User, _>(vec![Ok::<_, ()>(
+//!     "{\"id\":1,\"name\":\"Alice\"}\n{\"id\":2,\"name\":\"Bob\"}\n")]);
+//! let orders = ndjson_stream::from_fallible_iter::<Order, _>(vec![Ok::<_, ()>(
+//!     "{\"user_id\":1,\"amount\":10}\n{\"user_id\":1,\"amount\":20}\n")]);
+//! let joined = join::join(users, orders, |user: &User| user.id, |order: &Order| order.user_id)
+//!     .with_kind(JoinKind::Left)
+//!     .map(|joined| match joined.unwrap() {
+//!         Joined::Both(user, order) => (user.name, Some(order.amount)),
+//!         Joined::Left(user) => (user.name, None),
+//!         Joined::Right(_) => unreachable!()
+//!     })
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(joined, vec![
+//!     ("Alice".to_owned(), Some(10)),
+//!     ("Alice".to_owned(), Some(20)),
+//!     ("Bob".to_owned(), None)
+//! ]);
+//! ```
+
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+
+use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+
+/// Specifies which records of a [Join] are returned in addition to the matched pairs.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum JoinKind {
+
+    /// Only matched pairs are returned. This is the default.
+    #[default]
+    Inner,
+
+    /// Left records without a matching right record are returned as well, as [Joined::Left].
+    Left,
+
+    /// Right records without a matching left record are returned as well, as [Joined::Right].
+    Right,
+
+    /// Left and right records without a match are returned as well, as [Joined::Left] and
+    /// [Joined::Right] respectively.
+    Full
+}
+
+impl JoinKind {
+    fn includes_left(self) -> bool {
+        matches!(self, JoinKind::Left | JoinKind::Full)
+    }
+
+    fn includes_right(self) -> bool {
+        matches!(self, JoinKind::Right | JoinKind::Full)
+    }
+}
+
+/// A record returned by a [Join].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Joined<L, R> {
+
+    /// A left and a right record with the same key.
+    Both(L, R),
+
+    /// A left record without a matching right record. Only returned for [JoinKind::Left] and
+    /// [JoinKind::Full].
+    Left(L),
+
+    /// A right record without a matching left record. Only returned for [JoinKind::Right] and
+    /// [JoinKind::Full].
+    Right(R)
+}
+
+/// An [Iterator] which joins the records of two sources, each sorted by a key, by equal keys.
+/// Errors of either source are returned as soon as they are encountered and do not end the join.
+/// Created by [join], see there and the [module documentation](self) for details.
+pub struct Join<L, R, IL, IR, K, FL, FR> {
+    left: IL,
+    right: IR,
+    left_key: FL,
+    right_key: FR,
+    kind: JoinKind,
+
+    left_head: Option<(K, L)>,
+    right_head: Option<(K, R)>,
+    left_done: bool,
+    right_done: bool,
+
+    /// The key and records of the right source with that key which are matched against the left
+    /// records.
+    group: Option<(K, Vec<R>)>,
+    group_matched: bool,
+    pending: VecDeque<Joined<L, R>>
+}
+
+impl<L, R, E, IL, IR, K, FL, FR> Join<L, R, IL, IR, K, FL, FR>
+where
+    L: Clone,
+    R: Clone,
+    IL: Iterator<Item = FallibleNdjsonResult<L, E>>,
+    IR: Iterator<Item = FallibleNdjsonResult<R, E>>,
+    K: Ord,
+    FL: FnMut(&L) -> K,
+    FR: FnMut(&R) -> K
+{
+
+    /// Creates a new inner join of the given `left` and `right` sources, which must be sorted by
+    /// the keys computed by `left_key` and `right_key` respectively.
+    pub fn new(left: IL, right: IR, left_key: FL, right_key: FR)
+            -> Join<L, R, IL, IR, K, FL, FR> {
+        Join {
+            left,
+            right,
+            left_key,
+            right_key,
+            kind: JoinKind::default(),
+            left_head: None,
+            right_head: None,
+            left_done: false,
+            right_done: false,
+            group: None,
+            group_matched: false,
+            pending: VecDeque::new()
+        }
+    }
+
+    /// Sets the [JoinKind], which specifies whether unmatched records are returned as well.
+    ///
+    /// # Returns
+    ///
+    /// This join with the given kind.
+    pub fn with_kind(mut self, kind: JoinKind) -> Join<L, R, IL, IR, K, FL, FR> {
+        self.kind = kind;
+        self
+    }
+
+    /// Gets the [JoinKind] of this join.
+    pub fn kind(&self) -> JoinKind {
+        self.kind
+    }
+
+    fn fetch_heads(&mut self) -> Result<(), FallibleNdjsonError<E>> {
+        if self.left_head.is_none() && !self.left_done {
+            match self.left.next() {
+                Some(record) => {
+                    let record = record?;
+                    self.left_head = Some(((self.left_key)(&record), record));
+                },
+                None => self.left_done = true
+            }
+        }
+
+        if self.right_head.is_none() && !self.right_done {
+            match self.right.next() {
+                Some(record) => {
+                    let record = record?;
+                    self.right_head = Some(((self.right_key)(&record), record));
+                },
+                None => self.right_done = true
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_unmatched_left(&mut self, record: L) {
+        if self.kind.includes_left() {
+            self.pending.push_back(Joined::Left(record));
+        }
+    }
+
+    fn push_unmatched_right(&mut self, record: R) {
+        if self.kind.includes_right() {
+            self.pending.push_back(Joined::Right(record));
+        }
+    }
+
+    fn discard_group(&mut self) {
+        if let Some((_, records)) = self.group.take() {
+            if !self.group_matched {
+                for record in records {
+                    self.push_unmatched_right(record);
+                }
+            }
+        }
+    }
+
+    fn push_matches(&mut self, left: L) {
+        let Some((_, records)) = &self.group
+        else {
+            return;
+        };
+        let Some((last, records)) = records.split_last()
+        else {
+            return;
+        };
+
+        for record in records {
+            self.pending.push_back(Joined::Both(left.clone(), record.clone()));
+        }
+
+        self.pending.push_back(Joined::Both(left, last.clone()));
+        self.group_matched = true;
+    }
+
+    /// Advances the join by one step, i.e. consumes one record of either source or the current
+    /// group.
+    ///
+    /// # Returns
+    ///
+    /// `false` if both sources are exhausted.
+    fn step(&mut self) -> bool {
+        let group_key = self.group.as_ref().map(|(key, _)| key);
+
+        if let (Some(group_key), Some((right_key, _))) = (group_key, &self.right_head) {
+            if group_key == right_key {
+                let (_, record) = self.right_head.take().unwrap();
+                self.group.as_mut().unwrap().1.push(record);
+                return true;
+            }
+        }
+
+        let Some((left_key, left)) = self.left_head.take()
+        else {
+            if self.group.is_some() {
+                self.discard_group();
+            }
+            else if let Some((_, right)) = self.right_head.take() {
+                self.push_unmatched_right(right);
+            }
+            else {
+                return false;
+            }
+
+            return true;
+        };
+
+        if let Some((group_key, _)) = &self.group {
+            if *group_key == left_key {
+                self.push_matches(left);
+            }
+            else if *group_key < left_key {
+                self.discard_group();
+                self.left_head = Some((left_key, left));
+            }
+            else {
+                self.push_unmatched_left(left);
+            }
+
+            return true;
+        }
+
+        match self.right_head.take() {
+            Some((right_key, right)) if right_key < left_key => {
+                self.push_unmatched_right(right);
+                self.left_head = Some((left_key, left));
+            },
+            Some((right_key, right)) if right_key == left_key => {
+                self.group = Some((right_key, vec![right]));
+                self.group_matched = false;
+                self.left_head = Some((left_key, left));
+            },
+            right_head => {
+                self.right_head = right_head;
+                self.push_unmatched_left(left);
+            }
+        }
+
+        true
+    }
+}
+
+impl<L, R, E, IL, IR, K, FL, FR> Iterator for Join<L, R, IL, IR, K, FL, FR>
+where
+    L: Clone,
+    R: Clone,
+    IL: Iterator<Item = FallibleNdjsonResult<L, E>>,
+    IR: Iterator<Item = FallibleNdjsonResult<R, E>>,
+    K: Ord,
+    FL: FnMut(&L) -> K,
+    FR: FnMut(&R) -> K
+{
+    type Item = FallibleNdjsonResult<Joined<L, R>, E>;
+
+    fn next(&mut self) -> Option<FallibleNdjsonResult<Joined<L, R>, E>> {
+        loop {
+            if let Some(joined) = self.pending.pop_front() {
+                return Some(Ok(joined));
+            }
+
+            if let Err(error) = self.fetch_heads() {
+                return Some(Err(error));
+            }
+
+            if !self.step() {
+                return None;
+            }
+        }
+    }
+}
+
+impl<L, R, E, IL, IR, K, FL, FR> FusedIterator for Join<L, R, IL, IR, K, FL, FR>
+where
+    L: Clone,
+    R: Clone,
+    IL: Iterator<Item = FallibleNdjsonResult<L, E>>,
+    IR: Iterator<Item = FallibleNdjsonResult<R, E>>,
+    K: Ord,
+    FL: FnMut(&L) -> K,
+    FR: FnMut(&R) -> K
+{ }
+
+/// Creates an inner [Join] of the given `left` and `right` sources, such as
+/// [FallibleNdjsonIter](crate::driver::FallibleNdjsonIter)s, which must be sorted by the keys
+/// computed by `left_key` and `right_key` respectively. Other kinds of joins can be configured via
+/// [Join::with_kind].
+///
+/// Read errors are reported via [FallibleNdjsonError::InputError] and parse errors via
+/// [FallibleNdjsonError::JsonError]. Both are returned as soon as they are encountered and do not
+/// end the join.
+pub fn join<L, R, E, IL, IR, K, FL, FR>(left: IL, right: IR, left_key: FL, right_key: FR)
+    -> Join<L, R, IL, IR, K, FL, FR>
+where
+    L: Clone,
+    R: Clone,
+    IL: Iterator<Item = FallibleNdjsonResult<L, E>>,
+    IR: Iterator<Item = FallibleNdjsonResult<R, E>>,
+    K: Ord,
+    FL: FnMut(&L) -> K,
+    FR: FnMut(&R) -> K
+{
+    Join::new(left, right, left_key, right_key)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::driver::FallibleNdjsonIter;
+    use crate::from_fallible_iter;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    type Records = FallibleNdjsonIter<TestStruct, std::vec::IntoIter<Result<String, ()>>>;
+
+    fn records(pairs: &[(u64, u64)]) -> Records {
+        let data = pairs.iter()
+            .map(|(key, value)| format!("{{\"key\":{},\"value\":{}}}\n", key, value))
+            .collect::<String>();
+
+        from_fallible_iter(vec![Ok(data)])
+    }
+
+    fn joined_values(left: &[(u64, u64)], right: &[(u64, u64)], kind: JoinKind)
+            -> Vec<Joined<u64, u64>> {
+        join(records(left), records(right), |record| record.key, |record| record.key)
+            .with_kind(kind)
+            .map(|joined| match joined.unwrap() {
+                Joined::Both(left, right) => Joined::Both(left.value, right.value),
+                Joined::Left(left) => Joined::Left(left.value),
+                Joined::Right(right) => Joined::Right(right.value)
+            })
+            .collect()
+    }
+
+    const LEFT: &[(u64, u64)] = &[(1, 10), (2, 20), (2, 21), (4, 40), (6, 60)];
+    const RIGHT: &[(u64, u64)] = &[(0, 0), (2, 22), (2, 23), (3, 30), (6, 61), (7, 70)];
+
+    #[test]
+    fn inner_join_returns_all_combinations_of_equal_keys() {
+        assert_that!(joined_values(LEFT, RIGHT, JoinKind::Inner)).contains_exactly_in_given_order([
+            Joined::Both(20, 22),
+            Joined::Both(20, 23),
+            Joined::Both(21, 22),
+            Joined::Both(21, 23),
+            Joined::Both(60, 61)
+        ]);
+    }
+
+    #[test]
+    fn left_join_returns_unmatched_left_records() {
+        assert_that!(joined_values(LEFT, RIGHT, JoinKind::Left)).contains_exactly_in_given_order([
+            Joined::Left(10),
+            Joined::Both(20, 22),
+            Joined::Both(20, 23),
+            Joined::Both(21, 22),
+            Joined::Both(21, 23),
+            Joined::Left(40),
+            Joined::Both(60, 61)
+        ]);
+    }
+
+    #[test]
+    fn full_join_returns_all_unmatched_records() {
+        assert_that!(joined_values(LEFT, RIGHT, JoinKind::Full)).contains_exactly_in_given_order([
+            Joined::Right(0),
+            Joined::Left(10),
+            Joined::Both(20, 22),
+            Joined::Both(20, 23),
+            Joined::Both(21, 22),
+            Joined::Both(21, 23),
+            Joined::Right(30),
+            Joined::Left(40),
+            Joined::Both(60, 61),
+            Joined::Right(70)
+        ]);
+    }
+
+    #[test]
+    fn right_join_with_empty_left_returns_all_right_records() {
+        assert_that!(joined_values(&[], &[(1, 10), (1, 11)], JoinKind::Right))
+            .contains_exactly_in_given_order([Joined::Right(10), Joined::Right(11)]);
+        assert_that!(joined_values(&[], &[(1, 10)], JoinKind::Inner)).is_empty();
+    }
+
+    #[test]
+    fn errors_are_returned_when_encountered_and_join_continues() {
+        let left = from_fallible_iter::<u32, _>(vec![Ok("1\ninvalid\n2\n"), Err("read error")]);
+        let right = from_fallible_iter::<u32, _>(vec![Ok("1\n2\n3\n")]);
+        let mut joined = join(left, right, |&record| record, |&record| record)
+            .with_kind(JoinKind::Full);
+
+        assert_that!(joined.next()).to_value().contains_value(Joined::Both(1, 1));
+        assert_that!(matches!(joined.next(), Some(Err(FallibleNdjsonError::JsonError(_)))))
+            .is_true();
+        assert_that!(joined.next()).to_value().contains_value(Joined::Both(2, 2));
+        assert_that!(matches!(joined.next(), Some(Err(FallibleNdjsonError::InputError(_)))))
+            .is_true();
+        assert_that!(joined.next()).to_value().contains_value(Joined::Right(3));
+        assert_that!(joined.next()).is_none();
+    }
+}
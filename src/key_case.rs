@@ -0,0 +1,163 @@
+//! This module contains the byte-level scanner behind
+//! [NdjsonConfig::with_key_case](crate::config::NdjsonConfig::with_key_case), which rewrites the
+//! keys of all objects in a line to a [KeyCase] without fully parsing the line.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use crate::config::KeyCase;
+use crate::redact::{skip_whitespace, string_end};
+
+fn to_snake_case(key: &[u8], converted: &mut Vec<u8>) {
+    for (index, &byte) in key.iter().enumerate() {
+        if !byte.is_ascii_uppercase() {
+            converted.push(byte);
+            continue;
+        }
+
+        let is_word_start = index.checked_sub(1).map(|previous| key[previous])
+            .is_some_and(|previous| previous.is_ascii_lowercase() || previous.is_ascii_digit() ||
+                (previous.is_ascii_uppercase() &&
+                    key.get(index + 1).is_some_and(u8::is_ascii_lowercase)));
+
+        if is_word_start {
+            converted.push(b'_');
+        }
+
+        converted.push(byte.to_ascii_lowercase());
+    }
+}
+
+fn to_camel_case(key: &[u8], converted: &mut Vec<u8>) {
+    let mut in_leading_underscores = true;
+    let mut capitalize_next = false;
+
+    for &byte in key {
+        if byte == b'_' && !in_leading_underscores {
+            capitalize_next = true;
+            continue;
+        }
+
+        in_leading_underscores &= byte == b'_';
+
+        if capitalize_next {
+            converted.push(byte.to_ascii_uppercase());
+            capitalize_next = false;
+        }
+        else {
+            converted.push(byte);
+        }
+    }
+
+    if capitalize_next {
+        converted.push(b'_');
+    }
+}
+
+/// Rewrites all object keys in `line`, i.e. strings followed by a colon, to the given [KeyCase].
+/// Keys containing escape sequences are left unchanged. Scanning is best-effort: it stops at an
+/// unterminated string, leaving the rest of the line unchanged, since the subsequent parsing
+/// reports the error.
+pub(crate) fn normalize_keys(line: &[u8], key_case: KeyCase) -> Cow<'_, [u8]> {
+    let mut normalized = Vec::new();
+    let mut copied_until = 0;
+    let mut index = 0;
+    let mut converted = Vec::new();
+
+    while index < line.len() {
+        if line[index] != b'"' {
+            index += 1;
+            continue;
+        }
+
+        let Some(end) = string_end(line, index)
+        else {
+            break;
+        };
+        let key = &line[index + 1..end - 1];
+
+        if line.get(skip_whitespace(line, end)) == Some(&b':') && !key.contains(&b'\\') {
+            converted.clear();
+
+            match key_case {
+                KeyCase::Snake => to_snake_case(key, &mut converted),
+                KeyCase::Camel => to_camel_case(key, &mut converted)
+            }
+
+            if converted != key {
+                normalized.extend_from_slice(&line[copied_until..index + 1]);
+                normalized.extend_from_slice(&converted);
+                copied_until = end - 1;
+            }
+        }
+
+        index = end;
+    }
+
+    if copied_until == 0 {
+        return Cow::Borrowed(line);
+    }
+
+    normalized.extend_from_slice(&line[copied_until..]);
+    Cow::Owned(normalized)
+}
+
+/// Applies [normalize_keys] to a line which may already be owned, avoiding a copy if no key is
+/// changed.
+pub(crate) fn normalize_keys_cow(line: Cow<'_, [u8]>, key_case: KeyCase) -> Cow<'_, [u8]> {
+    match line {
+        Cow::Borrowed(line) => normalize_keys(line, key_case),
+        Cow::Owned(line) => {
+            let normalized = match normalize_keys(&line, key_case) {
+                Cow::Owned(normalized) => Some(normalized),
+                Cow::Borrowed(_) => None
+            };
+
+            Cow::Owned(normalized.unwrap_or(line))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn normalize(line: &str, key_case: KeyCase) -> String {
+        String::from_utf8(normalize_keys(line.as_bytes(), key_case).into_owned()).unwrap()
+    }
+
+    #[test]
+    fn line_without_changed_keys_is_borrowed() {
+        let normalized = normalize_keys(b"{\"user_id\":\"someValue\"}", KeyCase::Snake);
+
+        assert_that!(matches!(normalized, Cow::Borrowed(_))).is_true();
+    }
+
+    #[test]
+    fn keys_are_converted_to_snake_case() {
+        let line = r#"{"userId":1,"HTTPServer":"camelCase","userID":{"nestedKey":[{"aB1c":2}]}}"#;
+
+        assert_that!(normalize(line, KeyCase::Snake)).is_equal_to(
+            r#"{"user_id":1,"http_server":"camelCase","user_id":{"nested_key":[{"a_b1c":2}]}}"#
+                .to_owned());
+    }
+
+    #[test]
+    fn keys_are_converted_to_camel_case() {
+        let line = r#"{"user_id":"snake_case","_private":1, "a__b" : 2,"trailing_":3}"#;
+
+        assert_that!(normalize(line, KeyCase::Camel)).is_equal_to(
+            r#"{"userId":"snake_case","_private":1, "aB" : 2,"trailing_":3}"#.to_owned());
+    }
+
+    #[test]
+    fn keys_with_escapes_and_unterminated_strings_are_left_unchanged() {
+        let line = r#"{"a\"B":1,"someKey":"x\"y:","otherKey"#;
+
+        assert_that!(normalize(line, KeyCase::Snake))
+            .is_equal_to(r#"{"a\"B":1,"some_key":"x\"y:","otherKey"#.to_owned());
+    }
+}
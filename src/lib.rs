@@ -4,6 +4,8 @@
 //! NDJSON processing, which are available at the crate root (see for example [from_iter]). The
 //! parser accepts any input which implements the [AsBytes](as_bytes::AsBytes) trait, which are the
 //! most common data containers in core Rust and the standard library (e.g. `Vec<u8>` or `&str`).
+//! If only the splitting into lines is required, it is available on its own in the [splitter]
+//! module.
 //!
 //! `ndjson-stream` uses the [serde_json] crate to parse individual lines. Hence, the output type of
 //! the parser must implement [Deserialize](serde::Deserialize).
@@ -112,22 +114,152 @@
 //!
 //! # Crate features
 //!
+//! * `bstr`: Offers an implementation of [AsBytes](as_bytes::AsBytes) on [BStr](bstr::BStr) and
+//!   [BString](bstr::BString) from the [bstr] crate.
 //! * `bytes`: Offers an implementation of [AsBytes](as_bytes::AsBytes) on [Bytes](bytes::Bytes) and
-//! [BytesMut](bytes::BytesMut) from the [bytes] crate.
-//! * `iter` (default): Enables the [Iterator]-style interface ([from_iter] family).
+//!   [BytesMut](bytes::BytesMut) from the [bytes] crate. Incomplete lines at the end of `Bytes`
+//!   chunks are retained without copying.
+//! * `encoding`: Enables the [encoding] module for transcoding UTF-16 input to UTF-8 before it is
+//!   parsed. Implies `std`.
+//! * `export`: Enables the [export] module for converting records into other formats, such as
+//!   CSV-rows via [CsvWriter](export::csv::CsvWriter). Implies `std`.
+//! * `fuzz`: Enables the [fuzz] module with harnesses checking invariants of the parser, such as
+//!   independence of chunk boundaries, for use in fuzz targets and property tests.
+//! * `http-body`: Enables parsing any `Body` from the [http_body] crate, such as hyper requests
+//!   and responses, via [from_http_body] and [HttpBodySource](source::HttpBodySource), and
+//!   implements `Body` for [NdjsonBody](writer::NdjsonBody), so NDJSON-responses can be served by
+//!   hyper and frameworks built on it. Implies `bytes` and `stream`.
+//! * `iter` (default): Enables the [Iterator]-style interface ([from_iter] family) and the [join],
+//!   [merge], and [process] modules. Implies `std`.
+//! * `memmap2`: Offers an implementation of [AsBytes](as_bytes::AsBytes) on
+//!   [Mmap](memmap2::Mmap) and [MmapMut](memmap2::MmapMut) from the [memmap2] crate, so
+//!   memory-mapped files can be parsed as a single data block. Implies `std`.
+//! * `reqwest`: Enables the [pagination] module for consuming paginated NDJSON-over-HTTP APIs
+//!   via the [reqwest] crate. Together with `tokio`, also enables the [range] module for resuming
+//!   interrupted downloads with range requests. Implies `bytes` and `stream`.
+//! * `smallvec`: Offers an implementation of [AsBytes](as_bytes::AsBytes) on
+//!   [SmallVec](smallvec::SmallVec)s of bytes from the [smallvec] crate.
+//! * `sse`: Enables the [sse] module for parsing the events of a Server-Sent Events stream as
+//!   NDJSON-records. Implies `std`.
+//! * `std` (default): Enables the [driver], [pipeline], and [source] modules and support for the
+//!   standard library in dependencies. Without this feature, the crate is `no_std` and requires
+//!   only `alloc`, which still provides the [engine], [splitter], and [config] modules.
 //! * `stream`: Enables the [Stream](futures::Stream)-style interface from the `futures` crate
-//! ([from_stream] family).
+//!   ([from_stream] family), the `Send + 'static` [OwnedNdjsonStream](driver::OwnedNdjsonStream),
+//!   the serializing [NdjsonBody](writer::NdjsonBody), and the batching
+//!   [BatchSink](batch::BatchSink). Implies `std`.
+//! * `test_support`: Enables the [test_support] module with helpers asserting that integrations
+//!   of this parser produce the same output independent of chunk boundaries. Implies `fuzz`.
+//! * `tokio`: Enables Tokio-based timers for streams, such as the idle timeout of
+//!   [IdleTimeoutStream](driver::IdleTimeoutStream), the maximum delay of
+//!   [BatchSink](batch::BatchSink), and the input retries of
+//!   [RetryingNdjsonStream](driver::RetryingNdjsonStream), concurrent deserialization via
+//!   [from_stream_buffered], parsing tasks spawned via [spawn_parser] and [spawn_named_parser],
+//!   blocking iteration over streams via [BlockingNdjsonIter](driver::BlockingNdjsonIter), and the
+//!   [AsyncNdjsonWriter](writer::AsyncNdjsonWriter) for Tokio's `AsyncWrite`. Implies `stream`.
+//! * `tracing`: Instruments the tasks spawned via [spawn_named_parser] with spans and events from
+//!   the [tracing] crate and enables Tokio's `tracing` feature, such that these tasks show up with
+//!   their names in tokio-console if compiled with `--cfg tokio_unstable`. Implies `tokio`.
+
+#![forbid(unsafe_code)]
 
 #![warn(missing_docs)]
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
 #![cfg_attr(doc_cfg, feature(doc_cfg))]
 
+extern crate alloc;
+
+mod array;
 pub mod as_bytes;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub mod batch;
+pub mod checksum;
+pub mod compare;
 pub mod config;
+pub mod count;
+pub mod dedup;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
 pub mod driver;
+
+#[cfg(feature = "encoding")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "encoding")))]
+pub mod encoding;
 pub mod engine;
+pub mod entry;
+pub mod error;
+pub mod event;
+
+#[cfg(feature = "export")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "export")))]
+pub mod export;
 pub mod fallible;
 
+#[cfg(feature = "fuzz")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "fuzz")))]
+pub mod fuzz;
+pub mod histogram;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub mod index;
+mod key_case;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub mod join;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub mod merge;
+pub mod middleware;
+pub mod multi;
+
+#[cfg(feature = "reqwest")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "reqwest")))]
+pub mod pagination;
+pub mod parser;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub mod process;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub mod pipeline;
+pub mod projection;
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[cfg_attr(doc_cfg, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+pub mod range;
+pub mod raw;
+mod redact;
+
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub mod source;
+pub mod splitter;
+
+#[cfg(feature = "sse")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "sse")))]
+pub mod sse;
+
+#[cfg(feature = "test_support")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "test_support")))]
+pub mod test_support;
+pub mod writer;
+
+pub use crate::count::count_records;
+
+pub use crate::count::count_records_checked;
+
+pub use crate::parser::Ndjson;
+
 #[cfg(feature = "iter")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
 pub use crate::driver::iter::from_iter;
@@ -144,6 +276,54 @@ pub use crate::driver::iter::from_fallible_iter;
 #[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
 pub use crate::driver::iter::from_fallible_iter_with_config;
 
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::iter::from_iter_multi;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::iter::from_iter_map;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::iter::from_chunk_source;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::iter::from_chunk_source_with_config;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::iter::validate_iter;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::iter::validate_iter_with_config;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::slice::from_slice;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::slice::from_slice_with_config;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::lines::from_lines;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::lines::from_lines_with_config;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::lines::from_fallible_lines;
+
+#[cfg(feature = "iter")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+pub use crate::driver::lines::from_fallible_lines_with_config;
+
 #[cfg(feature = "stream")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
 pub use crate::driver::stream::from_stream;
@@ -160,6 +340,70 @@ pub use crate::driver::stream::from_fallible_stream;
 #[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
 pub use crate::driver::stream::from_fallible_stream_with_config;
 
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::stream::from_stream_multi;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::stream::from_async_chunk_source;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::stream::from_async_chunk_source_with_config;
+
+#[cfg(feature = "http-body")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "http-body")))]
+pub use crate::driver::stream::from_http_body;
+
+#[cfg(feature = "http-body")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "http-body")))]
+pub use crate::driver::stream::from_http_body_with_config;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::stream::validate_stream;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::stream::validate_stream_with_config;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::lines::from_line_stream;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::lines::from_line_stream_with_config;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::lines::from_fallible_line_stream;
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub use crate::driver::lines::from_fallible_line_stream_with_config;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::buffered::from_stream_buffered;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::buffered::from_stream_buffered_with_config;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::spawn::spawn_parser;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::spawn::spawn_named_parser;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub use crate::driver::spawn::DEFAULT_PARSER_TASK_NAME;
+
 #[cfg(test)]
 pub(crate) mod test_util {
     use std::borrow::Borrow;
@@ -167,10 +411,10 @@ pub(crate) mod test_util {
 
     use kernal::{AssertThat, AssertThatData, Failure};
 
-    use serde::Deserialize;
+    use serde::{Deserialize, Serialize};
     use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
 
-    #[derive(Debug, Deserialize, Eq, PartialEq)]
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
     pub(crate) struct TestStruct {
         pub(crate) key: u64,
         pub(crate) value: u64
@@ -188,6 +432,7 @@ pub(crate) mod test_util {
         }
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub(crate) trait FallibleNdjsonResultAssertions<V, E> {
 
         fn is_json_error(self) -> Self;
@@ -0,0 +1,252 @@
+//! This module offers [merge_sorted], which merges several NDJSON-files that are each sorted by
+//! some key into a single sorted sequence of records, as is common when compacting time-partitioned
+//! log files. The merge is a k-way merge using a heap, so only one record per input is held in
+//! memory at any time, in addition to the read buffer of each file.
+//!
+//! If an input is not sorted by the key, the output is not sorted either, but still contains every
+//! record exactly once.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::merge::MergeSorted;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     timestamp: u64
+//! }
+//!
+//! let first = ndjson_stream::from_fallible_iter::<Event, _>(
+//!     vec![Ok::<_, ()>("{\"timestamp\":1}\n{\"timestamp\":4}\n")]);
+//! let second = ndjson_stream::from_fallible_iter::<Event, _>(
+//!     vec![Ok::<_, ()>("{\"timestamp\":2}\n{\"timestamp\":3}\n")]);
+//! let timestamps = MergeSorted::new([first, second], |event: &Event| event.timestamp)
+//!     .map(|event| event.unwrap().timestamp)
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(timestamps, vec![1, 2, 3, 4]);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io;
+use std::iter::FusedIterator;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config::NdjsonConfig;
+use crate::driver::FallibleNdjsonIter;
+use crate::fallible::FallibleNdjsonResult;
+use crate::source::{ChunkSource, Chunks, ReaderSource};
+
+/// The records of one input file of [merge_sorted].
+pub type FileRecords<T> = FallibleNdjsonIter<T, Chunks<ReaderSource<File>>>;
+
+/// An [Iterator] which merges the records of several inputs, each sorted by a key, into a single
+/// sequence sorted by that key. Records with equal keys are returned in the order of their inputs.
+/// Errors of an input are returned as soon as they are encountered, i.e. they are not ordered. See
+/// [merge_sorted] for merging files.
+pub struct MergeSorted<T, I, K, F> {
+    inputs: Vec<I>,
+
+    /// The next record of every input which is in the heap.
+    heads: Vec<Option<T>>,
+    heap: BinaryHeap<Reverse<(K, usize)>>,
+
+    /// The inputs whose next record still has to be fetched into the heap.
+    to_refill: Vec<usize>,
+    key_fn: F
+}
+
+impl<T, E, I, K, F> MergeSorted<T, I, K, F>
+where
+    I: Iterator<Item = FallibleNdjsonResult<T, E>>,
+    K: Ord,
+    F: FnMut(&T) -> K
+{
+
+    /// Creates a new merging iterator over the given `inputs`, which must each be sorted by the key
+    /// computed by `key_fn`.
+    pub fn new(inputs: impl IntoIterator<Item = I>, key_fn: F) -> MergeSorted<T, I, K, F> {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        let heads = inputs.iter().map(|_| None).collect();
+        let to_refill = (0..inputs.len()).rev().collect();
+
+        MergeSorted {
+            inputs,
+            heads,
+            heap: BinaryHeap::new(),
+            to_refill,
+            key_fn
+        }
+    }
+
+    /// Gets a reference to the merged inputs, in the order in which they were given.
+    pub fn inputs(&self) -> &[I] {
+        &self.inputs
+    }
+}
+
+impl<T, E, I, K, F> Iterator for MergeSorted<T, I, K, F>
+where
+    I: Iterator<Item = FallibleNdjsonResult<T, E>>,
+    K: Ord,
+    F: FnMut(&T) -> K
+{
+    type Item = FallibleNdjsonResult<T, E>;
+
+    fn next(&mut self) -> Option<FallibleNdjsonResult<T, E>> {
+        while let Some(&input) = self.to_refill.last() {
+            match self.inputs[input].next() {
+                Some(Ok(record)) => {
+                    self.heap.push(Reverse(((self.key_fn)(&record), input)));
+                    self.heads[input] = Some(record);
+                },
+                // The input stays in `to_refill`, since its next record is still missing.
+                Some(Err(error)) => return Some(Err(error)),
+                None => { }
+            }
+
+            self.to_refill.pop();
+        }
+
+        let Reverse((_, input)) = self.heap.pop()?;
+
+        self.to_refill.push(input);
+        self.heads[input].take().map(Ok)
+    }
+}
+
+impl<T, E, I, K, F> FusedIterator for MergeSorted<T, I, K, F>
+where
+    I: FusedIterator<Item = FallibleNdjsonResult<T, E>>,
+    K: Ord,
+    F: FnMut(&T) -> K
+{ }
+
+/// Opens the NDJSON-files at the given `paths`, which must each be sorted by the key computed by
+/// `key_fn`, and returns an [Iterator] over the records of all files sorted by that key. The files
+/// are parsed with the default [NdjsonConfig]. See [merge_sorted_with_config] for more details.
+pub fn merge_sorted<T, K, F>(paths: impl IntoIterator<Item = impl AsRef<Path>>, key_fn: F)
+    -> io::Result<MergeSorted<T, FileRecords<T>, K, F>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    K: Ord,
+    F: FnMut(&T) -> K
+{
+    merge_sorted_with_config(paths, NdjsonConfig::default(), key_fn)
+}
+
+/// Opens the NDJSON-files at the given `paths`, which must each be sorted by the key computed by
+/// `key_fn`, and returns an [Iterator] over the records of all files sorted by that key. Each file
+/// is parsed with the given [NdjsonConfig]. Records with equal keys are returned in the order of
+/// the paths.
+///
+/// Read errors are reported via
+/// [FallibleNdjsonError::InputError](crate::fallible::FallibleNdjsonError::InputError) and parse
+/// errors via [FallibleNdjsonError::JsonError](crate::fallible::FallibleNdjsonError::JsonError).
+/// Both are returned as soon as they are encountered and do not end the merge.
+///
+/// # Errors
+///
+/// If any of the files cannot be opened.
+pub fn merge_sorted_with_config<T, K, F>(paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    config: NdjsonConfig, key_fn: F) -> io::Result<MergeSorted<T, FileRecords<T>, K, F>>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    K: Ord,
+    F: FnMut(&T) -> K
+{
+    let inputs = paths.into_iter()
+        .map(|path| {
+            let source = ReaderSource::open(path)?;
+
+            Ok(FallibleNdjsonIter::with_config(source.into_chunks(), config.clone()))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(MergeSorted::new(inputs, key_fn))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::fallible::FallibleNdjsonError;
+    use crate::from_fallible_iter;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, data: &str) -> TempFile {
+            let path = std::env::temp_dir()
+                .join(format!("ndjson-stream-merge-{}-{}.ndjson", name, std::process::id()));
+            std::fs::write(&path, data).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn records(pairs: &[(u64, u64)]) -> String {
+        pairs.iter()
+            .map(|(key, value)| format!("{{\"key\":{},\"value\":{}}}\n", key, value))
+            .collect()
+    }
+
+    #[test]
+    fn files_are_merged_by_key_with_ties_in_path_order() {
+        let first = TempFile::new("first", &records(&[(1, 1), (3, 1), (3, 2), (8, 1)]));
+        let second = TempFile::new("second", "");
+        let third = TempFile::new("third", &records(&[(0, 3), (3, 3), (9, 3)]));
+
+        let merged = merge_sorted::<TestStruct, _, _>([&first.0, &second.0, &third.0],
+                |record| record.key)
+            .unwrap()
+            .map(|record| record.map(|record| (record.key, record.value)).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_that!(merged).contains_exactly_in_given_order(
+            [(0, 3), (1, 1), (3, 1), (3, 2), (3, 3), (8, 1), (9, 3)]);
+    }
+
+    #[test]
+    fn missing_file_fails_merge() {
+        let existing = TempFile::new("existing", &records(&[(1, 1)]));
+        let missing = std::env::temp_dir().join("ndjson-stream-merge-missing.ndjson");
+
+        let result = merge_sorted::<TestStruct, _, _>([existing.0.clone(), missing],
+            |record| record.key);
+
+        assert_that!(result.is_err()).is_true();
+    }
+
+    #[test]
+    fn errors_are_returned_when_encountered_and_merge_continues() {
+        let first = from_fallible_iter::<u32, _>(vec![Ok("1\ninvalid\n5\n"), Err("read error")]);
+        let second = from_fallible_iter::<u32, _>(vec![Ok("2\n3\n4\n")]);
+        let mut merged = MergeSorted::new([first, second], |&record| record);
+
+        assert_that!(merged.next()).to_value().contains_value(1);
+        assert_that!(matches!(merged.next(), Some(Err(FallibleNdjsonError::JsonError(_)))))
+            .is_true();
+        assert_that!(merged.next()).to_value().contains_value(2);
+        assert_that!(merged.next()).to_value().contains_value(3);
+        assert_that!(merged.next()).to_value().contains_value(4);
+        assert_that!(merged.next()).to_value().contains_value(5);
+        assert_that!(matches!(merged.next(), Some(Err(FallibleNdjsonError::InputError(_)))))
+            .is_true();
+        assert_that!(merged.next()).is_none();
+    }
+}
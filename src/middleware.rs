@@ -0,0 +1,305 @@
+//! This module defines the [LineMiddleware] trait for transformations which are applied to every
+//! line between the splitting into lines and the deserialization, such as decoding, decompression,
+//! or decryption of individual records. In contrast to a
+//! [LinePreprocessor](crate::config::LinePreprocessor), a middleware may fail, in which case the
+//! line results in an error instead of a record.
+//!
+//! Middlewares are configured via [NdjsonConfig::with_line_middleware] and can be composed via
+//! [LineMiddleware::then] or by configuring several of them. Custom middlewares can be implemented
+//! directly or created from a function via [from_fn]. Lines with base64-encoded records can be
+//! decoded via the provided [Base64Decoder].
+//!
+//! # Example
+//!
+//! ```
+//! use std::borrow::Cow;
+//!
+//! use ndjson_stream::config::NdjsonConfig;
+//! use ndjson_stream::engine::NdjsonEngine;
+//! use ndjson_stream::middleware::{self, Base64Decoder, LineMiddleware};
+//!
+//! let strip_prefix = middleware::from_fn(|line| Ok(Cow::Borrowed(line.strip_prefix(b"v1:")
+//!     .unwrap_or(line))));
+//! let config = NdjsonConfig::default()
+//!     .with_line_middleware(strip_prefix.then(Base64Decoder::new()));
+//! let mut engine = NdjsonEngine::<Vec<u32>>::with_config(config);
+//!
+//! // "WzEsMl0=" is the base64-encoding of "[1,2]"
+//! engine.input("v1:WzEsMl0=\nv1:WzEsM\n");
+//!
+//! assert_eq!(engine.pop().unwrap().unwrap(), vec![1, 2]);
+//! assert!(engine.pop().unwrap().is_err());
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
+
+use crate::error::{NdjsonError, NdjsonResult};
+
+#[cfg(doc)]
+use crate::config::NdjsonConfig;
+
+/// A transformation which is applied to the raw bytes of every line before it is deserialized. See
+/// the [module documentation](self) for details.
+pub trait LineMiddleware: Send + Sync {
+
+    /// Transforms the given line, which is given without its line ending.
+    ///
+    /// # Returns
+    ///
+    /// The bytes which are to be deserialized instead of the given line, borrowing from the line if
+    /// no modification is necessary, or an error which is reported instead of a record.
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>>;
+
+    /// Creates a middleware which applies this middleware and then the given `next` one to the
+    /// result. If this middleware fails, `next` is not applied.
+    fn then<M>(self, next: M) -> Chain<Self, M>
+    where
+        Self: Sized,
+        M: LineMiddleware
+    {
+        Chain {
+            first: self,
+            second: next
+        }
+    }
+}
+
+impl<M> LineMiddleware for Arc<M>
+where
+    M: LineMiddleware + ?Sized
+{
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>> {
+        self.as_ref().transform(line)
+    }
+}
+
+impl<M> LineMiddleware for Box<M>
+where
+    M: LineMiddleware + ?Sized
+{
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>> {
+        self.as_ref().transform(line)
+    }
+}
+
+/// A [LineMiddleware] which applies two middlewares one after the other. Created by
+/// [LineMiddleware::then].
+#[derive(Clone, Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B
+}
+
+impl<A, B> LineMiddleware for Chain<A, B>
+where
+    A: LineMiddleware,
+    B: LineMiddleware
+{
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>> {
+        match self.first.transform(line)? {
+            Cow::Borrowed(line) => self.second.transform(line),
+            Cow::Owned(line) => Ok(Cow::Owned(self.second.transform(&line)?.into_owned()))
+        }
+    }
+}
+
+/// A [LineMiddleware] which applies a function to every line. Created by [from_fn].
+#[derive(Clone)]
+pub struct FnMiddleware<F> {
+    transform: F
+}
+
+impl<F> Debug for FnMiddleware<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("FnMiddleware { .. }")
+    }
+}
+
+impl<F> LineMiddleware for FnMiddleware<F>
+where
+    F: Fn(&[u8]) -> NdjsonResult<Cow<'_, [u8]>> + Send + Sync
+{
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>> {
+        (self.transform)(line)
+    }
+}
+
+/// Creates a [LineMiddleware] which applies the given function to every line. It receives the line
+/// without the line ending and returns the bytes which are to be deserialized instead, borrowing
+/// from the line if no modification is necessary. Errors can be reported via
+/// [NdjsonError::Custom].
+pub fn from_fn<F>(transform: F) -> FnMiddleware<F>
+where
+    F: Fn(&[u8]) -> NdjsonResult<Cow<'_, [u8]>> + Send + Sync
+{
+    FnMiddleware {
+        transform
+    }
+}
+
+/// The error reported by a [Base64Decoder] for lines which are not valid base64.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InvalidBase64 {
+
+    /// The offset of the first invalid byte within the line, or the length of the line if it ends
+    /// in the middle of an encoded byte.
+    pub offset: usize
+}
+
+impl Display for InvalidBase64 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid base64 at offset {}", self.offset)
+    }
+}
+
+impl Error for InvalidBase64 { }
+
+/// A [LineMiddleware] which decodes every line from base64 with the standard alphabet of
+/// [RFC 4648](https://www.rfc-editor.org/rfc/rfc4648), i.e. with `+` and `/` as the last
+/// characters. Padding with `=` is optional and whitespace at the start and end of a line is
+/// ignored. Lines which are not valid base64 result in an [NdjsonError::Custom] holding an
+/// [InvalidBase64].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Base64Decoder {
+    url_safe: bool
+}
+
+impl Base64Decoder {
+
+    /// Creates a new decoder for the standard base64 alphabet.
+    pub fn new() -> Base64Decoder {
+        Base64Decoder::default()
+    }
+
+    /// Creates a new decoder for the URL-safe base64 alphabet, which uses `-` and `_` instead of
+    /// `+` and `/`.
+    pub fn url_safe() -> Base64Decoder {
+        Base64Decoder {
+            url_safe: true
+        }
+    }
+
+    fn sextet(&self, byte: u8) -> Option<u8> {
+        match (byte, self.url_safe) {
+            (b'A'..=b'Z', _) => Some(byte - b'A'),
+            (b'a'..=b'z', _) => Some(byte - b'a' + 26),
+            (b'0'..=b'9', _) => Some(byte - b'0' + 52),
+            (b'+', false) | (b'-', true) => Some(62),
+            (b'/', false) | (b'_', true) => Some(63),
+            _ => None
+        }
+    }
+
+    fn decode(&self, line: &[u8]) -> Result<Vec<u8>, InvalidBase64> {
+        let start = line.len() - line.trim_ascii_start().len();
+        let encoded = line.trim_ascii();
+        let padding_len = encoded.iter().rev().take(2).take_while(|&&byte| byte == b'=').count();
+        let encoded = &encoded[..(encoded.len() - padding_len)];
+        let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3 + 2);
+        let mut buffer = 0u32;
+
+        for (index, &byte) in encoded.iter().enumerate() {
+            let sextet = self.sextet(byte).ok_or(InvalidBase64 { offset: start + index })?;
+
+            buffer = buffer << 6 | sextet as u32;
+
+            if index % 4 == 3 {
+                decoded.extend_from_slice(&buffer.to_be_bytes()[1..]);
+                buffer = 0;
+            }
+        }
+
+        match encoded.len() % 4 {
+            0 => { },
+            2 => decoded.push((buffer >> 4) as u8),
+            3 => decoded.extend_from_slice(&((buffer >> 2) as u16).to_be_bytes()),
+            _ => return Err(InvalidBase64 { offset: start + encoded.len() })
+        }
+
+        Ok(decoded)
+    }
+}
+
+impl LineMiddleware for Base64Decoder {
+    fn transform<'line>(&self, line: &'line [u8]) -> NdjsonResult<Cow<'line, [u8]>> {
+        self.decode(line)
+            .map(Cow::Owned)
+            .map_err(|error| NdjsonError::Custom(Box::new(error)))
+    }
+}
+
+/// The middleware stored in an [NdjsonConfig], which implements [Debug] regardless of the
+/// middleware.
+#[derive(Clone)]
+pub(crate) struct SharedMiddleware(pub(crate) Arc<dyn LineMiddleware>);
+
+impl Debug for SharedMiddleware {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("LineMiddleware { .. }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::string::String;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn decode(decoder: Base64Decoder, line: &str) -> Result<String, InvalidBase64> {
+        decoder.decode(line.as_bytes()).map(|decoded| String::from_utf8(decoded).unwrap())
+    }
+
+    #[test]
+    fn base64_is_decoded_with_and_without_padding() {
+        let cases = [
+            ("", ""),
+            ("eyJhIjoxfQ==", "{\"a\":1}"),
+            ("eyJhIjoxfQ", "{\"a\":1}"),
+            (" WzEsMl0=\r", "[1,2]"),
+            ("WzEsMiwzXQ", "[1,2,3]"),
+            ("WzEsMiwzMF0", "[1,2,30]")
+        ];
+
+        for (encoded, expected) in cases {
+            assert_that!(decode(Base64Decoder::new(), encoded)).contains_value(String::from(expected));
+        }
+    }
+
+    #[test]
+    fn url_safe_alphabet_is_decoded() {
+        assert_that!(decode(Base64Decoder::url_safe(), "Pz8_Pj4-"))
+            .contains_value(String::from("???>>>"));
+        assert_that!(decode(Base64Decoder::new(), "Pz8_Pj4-"))
+            .contains_error(InvalidBase64 { offset: 3 });
+    }
+
+    #[test]
+    fn invalid_base64_is_reported_with_offset() {
+        assert_that!(decode(Base64Decoder::new(), " eyJh=IjoxfQ"))
+            .contains_error(InvalidBase64 { offset: 5 });
+        assert_that!(decode(Base64Decoder::new(), "eyJhI"))
+            .contains_error(InvalidBase64 { offset: 5 });
+    }
+
+    #[test]
+    fn chain_applies_both_middlewares_in_order() {
+        let strip_prefix = from_fn(|line| match line.strip_prefix(b">") {
+            Some(stripped) => Ok(Cow::Borrowed(stripped)),
+            None => Err(NdjsonError::Custom("missing prefix".into()))
+        });
+        let middleware = strip_prefix.then(Base64Decoder::new()).then(from_fn(|line|
+            Ok(Cow::Owned([line, b"!"].concat()))));
+
+        assert_that!(middleware.transform(b">WzFd").unwrap().into_owned()).is_equal_to(b"[1]!".to_vec());
+        assert_that!(middleware.transform(b"WzFd")).is_err();
+    }
+}
@@ -0,0 +1,154 @@
+//! This module offers deserialization of NDJSON-records which may match one of several schemas,
+//! such as different versions of an API. Each line is tried against several types in order and the
+//! first one which deserializes successfully wins. See [SchemaChain] for more details.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::multi::OneOf2;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct PersonV2 {
+//!     first_name: String,
+//!     last_name: String
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct PersonV1 {
+//!     name: String
+//! }
+//!
+//! let data_blocks = vec![
+//!     "{\"first_name\":\"Alice\",\"last_name\":\"Smith\"}\n",
+//!     "{\"name\":\"Bob Jones\"}\n"
+//! ];
+//!
+//! let mut ndjson_iter =
+//!     ndjson_stream::from_iter_multi::<(PersonV2, PersonV1), _>(data_blocks);
+//!
+//! assert!(matches!(ndjson_iter.next(), Some(Ok(OneOf2::First(PersonV2 { .. })))));
+//! assert!(matches!(ndjson_iter.next(), Some(Ok(OneOf2::Second(PersonV1 { .. })))));
+//! assert!(ndjson_iter.next().is_none());
+//! ```
+
+use alloc::format;
+
+use core::fmt::Write;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+
+use serde_json::Value;
+
+/// A tuple of types implementing [Deserialize], which are tried in order when deserializing a
+/// record. The [SchemaChain::Output] is an enum with one variant per type, such as [OneOf2] for
+/// tuples of two types. Chains of two to six types are supported.
+///
+/// The record is first deserialized into a [Value], which is then deserialized into each type of
+/// the chain in turn. Therefore, all types must implement [DeserializeOwned]. If no type matches,
+/// the error contains the errors of all types.
+pub trait SchemaChain {
+
+    /// The enum of all types in this chain, which is produced as the record.
+    type Output: DeserializeOwned;
+}
+
+fn no_schema_matched<E>(errors: &[serde_json::Error]) -> E
+where
+    E: serde::de::Error
+{
+    let mut message = format!("record matches none of the {} schemas", errors.len());
+
+    for (index, error) in errors.iter().enumerate() {
+        // Writing into a string cannot fail.
+        let _ = write!(message, "; schema {}: {}", index + 1, error);
+    }
+
+    E::custom(message)
+}
+
+macro_rules! one_of {
+    ($name:ident, $len:literal, $(($param:ident, $variant:ident)),+) => {
+        #[doc = concat!("The record of a [SchemaChain] of ", $len, " types, which holds the value ",
+            "of the first type that matched.")]
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        pub enum $name<$($param),+> {
+            $(
+                #[doc = concat!("The record was deserialized as the type `", stringify!($param),
+                    "`.")]
+                $variant($param)
+            ),+
+        }
+
+        impl<'de, $($param: DeserializeOwned),+> Deserialize<'de> for $name<$($param),+> {
+            fn deserialize<De>(deserializer: De) -> Result<Self, De::Error>
+            where
+                De: Deserializer<'de>
+            {
+                let value = Value::deserialize(deserializer)?;
+                let errors = [$(
+                    match $param::deserialize(&value) {
+                        Ok(record) => return Ok($name::$variant(record)),
+                        Err(error) => error
+                    }
+                ),+];
+
+                Err(no_schema_matched(&errors))
+            }
+        }
+
+        impl<$($param: DeserializeOwned),+> SchemaChain for ($($param,)+) {
+            type Output = $name<$($param),+>;
+        }
+    };
+}
+
+one_of!(OneOf2, 2, (A, First), (B, Second));
+one_of!(OneOf3, 3, (A, First), (B, Second), (C, Third));
+one_of!(OneOf4, 4, (A, First), (B, Second), (C, Third), (D, Fourth));
+one_of!(OneOf5, 5, (A, First), (B, Second), (C, Third), (D, Fourth), (E, Fifth));
+one_of!(OneOf6, 6, (A, First), (B, Second), (C, Third), (D, Fourth), (E, Fifth), (F, Sixth));
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde::Deserialize;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Eq, PartialEq)]
+    struct Named {
+        name: String
+    }
+
+    type Chain = <(TestStruct, Named, u64) as SchemaChain>::Output;
+
+    #[test]
+    fn first_matching_schema_wins() {
+        let record: Chain = serde_json::from_str("{\"key\":1,\"value\":2,\"name\":\"x\"}").unwrap();
+
+        assert_that!(record).is_equal_to(OneOf3::First(TestStruct { key: 1, value: 2 }));
+    }
+
+    #[test]
+    fn later_schemas_are_tried_if_earlier_ones_fail() {
+        let named: Chain = serde_json::from_str("{\"name\":\"x\"}").unwrap();
+        let number: Chain = serde_json::from_str("42").unwrap();
+
+        assert_that!(named).is_equal_to(OneOf3::Second(Named { name: "x".to_owned() }));
+        assert_that!(number).is_equal_to(OneOf3::Third(42));
+    }
+
+    #[test]
+    fn error_contains_errors_of_all_schemas() {
+        let error = serde_json::from_str::<Chain>("\"text\"").unwrap_err();
+
+        assert_that!(error.to_string()).contains("none of the 3 schemas");
+        assert_that!(error.to_string()).contains("schema 3");
+    }
+}
@@ -0,0 +1,312 @@
+//! This module offers [paginate], which consumes a paginated NDJSON-over-HTTP API as one
+//! continuous [Stream] of records using a [reqwest] [Client]. Every page is requested once all
+//! records of the previous page have been returned. The URL of the next page is determined by a
+//! closure from the [PageInfo] of the previous page, i.e. its URL, its response headers, and its
+//! last record, which covers cursor fields in the records as well as `Link` headers.
+//!
+//! Note that this crate enables no TLS backend of [reqwest]. To request `https` URLs, enable one
+//! via the features of `reqwest` in the dependent crate, such as `rustls-tls`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use reqwest::{Client, Url};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     id: u64,
+//!     next_cursor: Option<String>
+//! }
+//!
+//! # async fn run() {
+//! let first_page = Url::parse("http://localhost:8080/events").unwrap();
+//! let mut events = ndjson_stream::pagination::paginate::<Event, _>(Client::new(), first_page,
+//!     |page| {
+//!         let cursor = page.last_record?.next_cursor.as_ref()?;
+//!         let mut next_page = page.url.clone();
+//!
+//!         next_page.query_pairs_mut().clear().append_pair("cursor", cursor);
+//!         Some(next_page)
+//!     });
+//!
+//! while let Some(event) = events.next().await {
+//!     println!("{}", event.unwrap().id);
+//! }
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Url};
+
+use serde::Deserialize;
+
+use crate::config::NdjsonConfig;
+use crate::driver::FallibleNdjsonStream;
+use crate::fallible::{FallibleNdjsonError, FallibleNdjsonResult};
+
+/// The information about a completely received page which is given to the closure determining the
+/// URL of the next page. See [paginate].
+#[derive(Debug)]
+pub struct PageInfo<'page, T> {
+
+    /// The URL from which the page was received, after following redirects.
+    pub url: &'page Url,
+
+    /// The headers of the response containing the page.
+    pub headers: &'page HeaderMap,
+
+    /// The last record which was successfully parsed from the page, or `None` if the page contained
+    /// no valid record.
+    pub last_record: Option<&'page T>
+}
+
+type PageRecords<T> = Pin<Box<FallibleNdjsonStream<T, BoxStream<'static, reqwest::Result<Bytes>>>>>;
+
+struct Page<T> {
+    url: Url,
+    headers: HeaderMap,
+    records: PageRecords<T>
+}
+
+struct PaginationState<T, F> {
+    client: Client,
+    config: NdjsonConfig,
+    next_page: F,
+    next_url: Option<Url>,
+    page: Option<Page<T>>,
+
+    /// The most recently parsed record of the current page, followed by all errors after it. These
+    /// are held back until the next record is parsed, such that the record can be given to
+    /// `next_page` without cloning if it is the last record of its page.
+    held: VecDeque<FallibleNdjsonResult<T, reqwest::Error>>,
+
+    /// Items which are ready to be returned.
+    ready: VecDeque<FallibleNdjsonResult<T, reqwest::Error>>
+}
+
+impl<T, F> PaginationState<T, F>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    F: FnMut(PageInfo<'_, T>) -> Option<Url>
+{
+    async fn next_item(&mut self) -> Option<FallibleNdjsonResult<T, reqwest::Error>> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+
+            if let Some(page) = &mut self.page {
+                match page.records.next().await {
+                    Some(Ok(record)) => {
+                        self.ready.extend(self.held.drain(..));
+                        self.held.push_back(Ok(record));
+                    },
+                    Some(Err(error)) if self.held.is_empty() => return Some(Err(error)),
+                    Some(Err(error)) => self.held.push_back(Err(error)),
+                    None => {
+                        self.next_url = (self.next_page)(PageInfo {
+                            url: &page.url,
+                            headers: &page.headers,
+                            last_record: self.held.front().and_then(|item| item.as_ref().ok())
+                        });
+                        self.page = None;
+                        self.ready.extend(self.held.drain(..));
+                    }
+                }
+
+                continue;
+            }
+
+            let url = self.next_url.take()?;
+            let response = self.client.get(url).send().await
+                .and_then(reqwest::Response::error_for_status);
+
+            match response {
+                Ok(response) => {
+                    let url = response.url().clone();
+                    let headers = response.headers().clone();
+                    let records = FallibleNdjsonStream::with_config(
+                        response.bytes_stream().boxed(), self.config.clone());
+
+                    self.page = Some(Page {
+                        url,
+                        headers,
+                        records: Box::pin(records)
+                    });
+                },
+                Err(error) => return Some(Err(FallibleNdjsonError::InputError(error)))
+            }
+        }
+    }
+}
+
+/// A [Stream] over the parsed records of all pages of a paginated NDJSON-over-HTTP API. See
+/// [paginate] for more details.
+pub struct PaginatedNdjsonStream<T> {
+    inner: BoxStream<'static, FallibleNdjsonResult<T, reqwest::Error>>
+}
+
+impl<T> Stream for PaginatedNdjsonStream<T> {
+    type Item = FallibleNdjsonResult<T, reqwest::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Requests the page at `first_url` via the given `client` and returns a stream over the parsed
+/// records of it and all following pages, with the default [NdjsonConfig]. See
+/// [paginate_with_config] for more details.
+pub fn paginate<T, F>(client: Client, first_url: Url, next_page: F) -> PaginatedNdjsonStream<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static,
+    F: FnMut(PageInfo<'_, T>) -> Option<Url> + Send + 'static
+{
+    paginate_with_config(client, first_url, NdjsonConfig::default(), next_page)
+}
+
+/// Requests the page at `first_url` via the given `client` and returns a stream over the parsed
+/// records of it and all following pages, each parsed with the given [NdjsonConfig]. After all
+/// records of a page have been returned, `next_page` is called with the [PageInfo] of that page.
+/// If it returns a URL, the next page is requested from it, otherwise the stream ends.
+///
+/// Failed requests, including responses with an error status, and errors while receiving a page
+/// are reported via [FallibleNdjsonError::InputError]. A failed request ends the stream, since no
+/// URL of a next page is known. Parse errors are reported via [FallibleNdjsonError::JsonError] and
+/// do not end the stream.
+pub fn paginate_with_config<T, F>(client: Client, first_url: Url, config: NdjsonConfig,
+    next_page: F) -> PaginatedNdjsonStream<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static,
+    F: FnMut(PageInfo<'_, T>) -> Option<Url> + Send + 'static
+{
+    let state = PaginationState {
+        client,
+        config,
+        next_page,
+        next_url: Some(first_url),
+        page: None,
+        held: VecDeque::new(),
+        ready: VecDeque::new()
+    };
+    let inner = stream::unfold(state, |mut state| async move {
+        let item = state.next_item().await?;
+
+        Some((item, state))
+    });
+
+    PaginatedNdjsonStream {
+        inner: inner.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use kernal::prelude::*;
+
+    use tokio::runtime::Builder;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    /// Serves the given responses, consisting of a status line and a body, to consecutive requests
+    /// and returns the requested paths.
+    fn serve(responses: Vec<(&'static str, &'static str)>) -> (Url, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/page/0", listener.local_addr().unwrap())).unwrap();
+        let server = thread::spawn(move || {
+            let mut paths = Vec::new();
+
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                reader.read_line(&mut request_line).unwrap();
+                paths.push(request_line.split(' ').nth(1).unwrap().to_owned());
+
+                for line in reader.lines() {
+                    if line.unwrap().is_empty() {
+                        break;
+                    }
+                }
+
+                write!(stream, "HTTP/1.1 {status}\r\nContent-Length: {}\r\nX-Next-Page: {}\r\n\
+                    Connection: close\r\n\r\n{body}", body.len(), paths.len()).unwrap();
+            }
+
+            paths
+        });
+
+        (url, server)
+    }
+
+    fn collect(url: Url, last_page: usize) -> Vec<FallibleNdjsonResult<TestStruct, reqwest::Error>> {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+
+        runtime.block_on(paginate::<TestStruct, _>(Client::new(), url, move |page| {
+            let next_page = page.headers["X-Next-Page"].to_str().unwrap().parse::<usize>().unwrap();
+
+            (next_page <= last_page && page.last_record.is_some_and(|record| record.key != 0))
+                .then(|| page.url.join(&next_page.to_string()).unwrap())
+        }).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn records_of_all_pages_are_streamed_continuously() {
+        let (url, server) = serve(vec![
+            ("200 OK", "{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n"),
+            ("200 OK", "{\"key\":5,\"value\":6}\ninvalid\n"),
+            ("200 OK", "{\"key\":7,\"value\":8}\n{\"key\":0,\"value\":0}\n")
+        ]);
+
+        let records = collect(url, 5);
+
+        assert_that!(server.join().unwrap())
+            .contains_exactly_in_given_order(["/page/0", "/page/1", "/page/2"].map(String::from));
+        assert_that!(records).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 }),
+            |it| assert_that!(matches!(it, Err(FallibleNdjsonError::JsonError(_)))).is_true(),
+            |it| assert_that!(it).contains_value(TestStruct { key: 7, value: 8 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 0, value: 0 })
+        ));
+    }
+
+    #[test]
+    fn error_status_ends_stream_with_input_error() {
+        let (url, server) = serve(vec![
+            ("200 OK", "{\"key\":1,\"value\":2}\n"),
+            ("503 Service Unavailable", "")
+        ]);
+
+        let records = collect(url, 5);
+
+        server.join().unwrap();
+
+        assert_that!(&records).has_length(2);
+        assert_that!(&records[0]).contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(matches!(&records[1], Err(FallibleNdjsonError::InputError(error))
+            if error.status().is_some_and(|status| status.as_u16() == 503))).is_true();
+    }
+}
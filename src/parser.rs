@@ -0,0 +1,240 @@
+//! This module offers [Ndjson], a reusable parser object bound to an [NdjsonConfig]. It provides
+//! the same drivers as the free functions of this crate, such as
+//! [from_iter_with_config](crate::from_iter_with_config), but without having to pass and clone the
+//! config at every call site.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+//! use ndjson_stream::Ndjson;
+//!
+//! let ndjson = Ndjson::new(NdjsonConfig::default()
+//!     .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty));
+//!
+//! let first = ndjson.parse_iter::<u32, _>(["1\n", "\n2\n"])
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! let second = ndjson.parse_read::<u32, _>("3\n\n4\n".as_bytes())
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//!
+//! assert_eq!(first, vec![1, 2]);
+//! assert_eq!(second, vec![3, 4]);
+//! ```
+
+#[cfg(feature = "iter")]
+use std::io::Read;
+
+use crate::config::NdjsonConfig;
+use crate::engine::NdjsonEngine;
+
+#[cfg(feature = "iter")]
+use crate::driver::{FallibleNdjsonIter, NdjsonIter, SliceNdjsonIter};
+
+#[cfg(feature = "stream")]
+use crate::driver::{FallibleNdjsonStream, NdjsonStream};
+
+#[cfg(feature = "iter")]
+use crate::source::{ChunkSource, Chunks, ReaderSource};
+
+/// A parser bound to an [NdjsonConfig], which creates drivers for many sources with the same
+/// config. Cloning the config for every driver is cheap, see [NdjsonConfig]. See the
+/// [parser](crate::parser) module for an example.
+#[derive(Clone, Debug, Default)]
+pub struct Ndjson {
+    config: NdjsonConfig
+}
+
+impl Ndjson {
+
+    /// Creates a new parser which configures all created drivers with the given [NdjsonConfig].
+    pub fn new(config: NdjsonConfig) -> Ndjson {
+        Ndjson {
+            config
+        }
+    }
+
+    /// Gets the [NdjsonConfig] with which this parser configures all created drivers.
+    pub fn config(&self) -> &NdjsonConfig {
+        &self.config
+    }
+
+    /// Creates a new [NdjsonEngine] with the config of this parser.
+    pub fn engine<T>(&self) -> NdjsonEngine<T> {
+        NdjsonEngine::with_config(self.config.clone())
+    }
+
+    /// Parses the data blocks of the given iterator with the config of this parser. See
+    /// [from_iter_with_config](crate::from_iter_with_config) for more details.
+    #[cfg(feature = "iter")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+    pub fn parse_iter<T, I>(&self, into_iter: I) -> NdjsonIter<T, I::IntoIter>
+    where
+        I: IntoIterator
+    {
+        NdjsonIter::with_config(into_iter.into_iter(), self.config.clone())
+    }
+
+    /// Parses the [Result]s of data blocks of the given iterator with the config of this parser,
+    /// forwarding errors. See [from_fallible_iter_with_config](crate::from_fallible_iter_with_config)
+    /// for more details.
+    #[cfg(feature = "iter")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+    pub fn parse_fallible_iter<T, I>(&self, into_iter: I) -> FallibleNdjsonIter<T, I::IntoIter>
+    where
+        I: IntoIterator
+    {
+        FallibleNdjsonIter::with_config(into_iter.into_iter(), self.config.clone())
+    }
+
+    /// Parses the given contiguous data with the config of this parser. See
+    /// [from_slice_with_config](crate::from_slice_with_config) for more details.
+    ///
+    /// # Panics
+    ///
+    /// If the config of this parser contains an option which is not supported by
+    /// [SliceNdjsonIter].
+    #[cfg(feature = "iter")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+    pub fn parse_slice<'data, T, D>(&self, data: &'data D) -> SliceNdjsonIter<'data, T>
+    where
+        D: AsRef<[u8]> + ?Sized
+    {
+        crate::from_slice_with_config(data, self.config.clone())
+    }
+
+    /// Parses the data read from the given reader with the config of this parser, wrapping it in a
+    /// [ReaderSource]. Read errors are forwarded via
+    /// [FallibleNdjsonError::InputError](crate::fallible::FallibleNdjsonError::InputError).
+    #[cfg(feature = "iter")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "iter")))]
+    pub fn parse_read<T, R>(&self, reader: R) -> FallibleNdjsonIter<T, Chunks<ReaderSource<R>>>
+    where
+        R: Read
+    {
+        FallibleNdjsonIter::with_config(ReaderSource::new(reader).into_chunks(),
+            self.config.clone())
+    }
+
+    /// Parses the data blocks of the given stream with the config of this parser. See
+    /// [from_stream_with_config](crate::from_stream_with_config) for more details.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+    pub fn parse_stream<T, S>(&self, bytes_stream: S) -> NdjsonStream<T, S> {
+        NdjsonStream::with_config(bytes_stream, self.config.clone())
+    }
+
+    /// Parses the [Result]s of data blocks of the given stream with the config of this parser,
+    /// forwarding errors. See
+    /// [from_fallible_stream_with_config](crate::from_fallible_stream_with_config) for more
+    /// details.
+    #[cfg(feature = "stream")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+    pub fn parse_fallible_stream<T, S>(&self, bytes_stream: S) -> FallibleNdjsonStream<T, S> {
+        FallibleNdjsonStream::with_config(bytes_stream, self.config.clone())
+    }
+}
+
+impl From<NdjsonConfig> for Ndjson {
+    fn from(config: NdjsonConfig) -> Ndjson {
+        Ndjson::new(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::config::EmptyLineHandling;
+
+    #[cfg(feature = "iter")]
+    use crate::fallible::FallibleNdjsonError;
+
+    #[cfg(feature = "iter")]
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    fn ignoring_empty_lines() -> Ndjson {
+        Ndjson::new(NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty))
+    }
+
+    #[test]
+    fn engine_uses_config() {
+        let mut engine = ignoring_empty_lines().engine::<u32>();
+
+        engine.input("\n1\n");
+
+        assert_that!(engine.pop()).to_value().contains_value(1);
+        assert_that!(engine.pop()).is_none();
+    }
+
+    #[cfg(feature = "iter")]
+    #[test]
+    fn parser_is_reused_for_several_sources() {
+        let ndjson = ignoring_empty_lines();
+        let from_iter = ndjson.parse_iter::<TestStruct, _>(["{\"key\":1,\"value\":2}\n\n"])
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        let from_fallible_iter = ndjson.parse_fallible_iter::<u32, _>([Ok("\n3\n"), Err(())])
+            .collect::<Vec<_>>();
+        let from_slice = ndjson.parse_slice::<u32, _>("\n\n4\n")
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(from_iter).contains_exactly_in_given_order([TestStruct { key: 1, value: 2 }]);
+        assert_that!(from_fallible_iter).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(3),
+            |it| assert_that!(matches!(it, Err(FallibleNdjsonError::InputError(()))))
+                .is_true()
+        ));
+        assert_that!(from_slice).contains_exactly_in_given_order([4]);
+    }
+
+    #[cfg(feature = "iter")]
+    #[test]
+    fn read_errors_are_forwarded() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("read error"))
+            }
+        }
+
+        let mut ndjson_iter = ignoring_empty_lines().parse_read::<u32, _>(FailingReader);
+
+        assert_that!(matches!(ndjson_iter.next(), Some(Err(FallibleNdjsonError::InputError(_)))))
+            .is_true();
+    }
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod stream_tests {
+
+    use futures::stream::{self, StreamExt};
+
+    use kernal::prelude::*;
+
+    use tokio_test::block_on;
+
+    use super::*;
+
+    #[test]
+    fn streams_use_config() {
+        let ndjson = Ndjson::new(NdjsonConfig::default().with_parse_rest(true));
+        let values = block_on(ndjson.parse_stream::<u32, _>(stream::iter(["1\n", "2"]))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>());
+        let fallible_values =
+            block_on(ndjson.parse_fallible_stream::<u32, _>(stream::iter([Ok::<_, ()>("3")]))
+                .map(Result::unwrap)
+                .collect::<Vec<_>>());
+
+        assert_that!(values).contains_exactly_in_given_order([1, 2]);
+        assert_that!(fallible_values).contains_exactly_in_given_order([3]);
+    }
+}
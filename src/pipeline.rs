@@ -0,0 +1,450 @@
+//! This module offers end-to-end transformation of NDJSON-data, i.e. reading records from a
+//! reader, mapping or filtering them, and writing the results as NDJSON to a writer. This covers
+//! the common job of filtering or enriching an NDJSON-file without assembling a driver and a
+//! writer manually. See [Pipeline] for more details.
+//!
+//! # Example
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! struct Person {
+//!     name: String,
+//!     age: u16
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Adult {
+//!     name: String
+//! }
+//!
+//! let input = "{\"name\":\"Alice\",\"age\":25}\n{\"name\":\"Bob\",\"age\":12}\n";
+//! let mut output = Vec::new();
+//!
+//! let summary = ndjson_stream::pipeline::transform(input.as_bytes(), &mut output,
+//!     |person: Person| (person.age >= 18).then(|| Adult { name: person.name })).unwrap();
+//!
+//! assert_eq!(output, b"{\"name\":\"Alice\"}\n");
+//! assert_eq!(summary.records_written, 1);
+//! assert_eq!(summary.records_filtered, 1);
+//! ```
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, ErrorKind, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use serde_json::Error as JsonError;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config::NdjsonConfig;
+use crate::engine::NdjsonEngine;
+use crate::error::ValidationError;
+use crate::event::NdjsonEvent;
+use crate::source::DEFAULT_CHUNK_SIZE;
+use crate::writer::serialize_line;
+
+/// Controls how a [Pipeline] treats lines which cannot be parsed and records which cannot be
+/// serialized.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ErrorPolicy {
+
+    /// Stop the transformation at the first error and return it. Records transformed before the
+    /// error have already been written.
+    #[default]
+    Abort,
+
+    /// Skip the offending record and continue. Skipped records are counted in
+    /// [TransformSummary::errors_skipped].
+    Skip
+}
+
+/// Statistics about a completed transformation, as returned by [Pipeline::transform].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TransformSummary {
+
+    /// The number of records which were parsed successfully and given to the mapping function.
+    pub records_read: u64,
+
+    /// The number of records which were written to the output.
+    pub records_written: u64,
+
+    /// The number of records for which the mapping function returned `None`.
+    pub records_filtered: u64,
+
+    /// The number of records which were skipped due to errors according to [ErrorPolicy::Skip].
+    pub errors_skipped: u64
+}
+
+/// The errors which can abort a transformation by a [Pipeline].
+#[derive(Debug)]
+pub enum TransformError {
+
+    /// Reading the input or writing the output failed. The [io::Error] is wrapped in this variant.
+    /// Such errors abort the transformation regardless of the [ErrorPolicy].
+    Io(io::Error),
+
+    /// A line of the input could not be parsed. The [ValidationError] holding the line number is
+    /// wrapped in this variant.
+    Parse(ValidationError),
+
+    /// A mapped record could not be serialized. The [serde_json::Error] is wrapped in this
+    /// variant.
+    Serialize(JsonError)
+}
+
+impl Display for TransformError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformError::Io(error) => write!(f, "I/O error: {error}"),
+            TransformError::Parse(error) => write!(f, "error parsing input: {error}"),
+            TransformError::Serialize(error) => write!(f, "error serializing output: {error}")
+        }
+    }
+}
+
+impl Error for TransformError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TransformError::Io(error) => Some(error),
+            TransformError::Parse(error) => Some(error),
+            TransformError::Serialize(error) => Some(error)
+        }
+    }
+}
+
+impl From<io::Error> for TransformError {
+    fn from(error: io::Error) -> TransformError {
+        TransformError::Io(error)
+    }
+}
+
+/// Reads NDJSON-records from a reader, maps them with a function, and writes the results as
+/// NDJSON to a writer. Records for which the function returns `None` are filtered out. Data is
+/// processed in chunks, so the input is never held in memory entirely. See the
+/// [pipeline](crate::pipeline) module for an example.
+///
+/// The input is parsed according to an [NdjsonConfig] and errors are handled according to an
+/// [ErrorPolicy]. Both can be set via the `with_*` methods.
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline {
+    config: NdjsonConfig,
+    error_policy: ErrorPolicy
+}
+
+impl Pipeline {
+
+    /// Creates a new pipeline with the default [NdjsonConfig] and [ErrorPolicy::Abort].
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Sets the [NdjsonConfig] used to parse the input.
+    ///
+    /// # Returns
+    ///
+    /// A new pipeline with all the same values as this one, except the config.
+    pub fn with_config(self, config: NdjsonConfig) -> Pipeline {
+        Pipeline {
+            config,
+            ..self
+        }
+    }
+
+    /// Sets the [ErrorPolicy] which controls how parse and serialization errors are treated.
+    ///
+    /// # Returns
+    ///
+    /// A new pipeline with all the same values as this one, except the error policy.
+    pub fn with_error_policy(self, error_policy: ErrorPolicy) -> Pipeline {
+        Pipeline {
+            error_policy,
+            ..self
+        }
+    }
+
+    /// Transforms all records of the given `reader` with the function `f` and writes the results
+    /// to the given `writer`, which is flushed at the end. Interrupted reads are retried.
+    ///
+    /// # Errors
+    ///
+    /// If reading or writing fails, or according to the [ErrorPolicy] if a line cannot be parsed
+    /// or a record cannot be serialized.
+    pub fn transform<In, Out, R, W, F>(self, mut reader: R, mut writer: W, f: F)
+        -> Result<TransformSummary, TransformError>
+    where
+        In: DeserializeOwned,
+        Out: Serialize,
+        R: Read,
+        W: Write,
+        F: FnMut(In) -> Option<Out>
+    {
+        let mut transformer = Transformer::new(self, f);
+        let mut chunk = vec![0; DEFAULT_CHUNK_SIZE];
+
+        loop {
+            let length = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(length) => length,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into())
+            };
+
+            transformer.engine.input(&chunk[..length]);
+
+            let result = transformer.process();
+
+            writer.write_all(&transformer.buffer)?;
+            transformer.buffer.clear();
+            result?;
+
+            if transformer.engine.is_finished() {
+                break;
+            }
+        }
+
+        transformer.engine.finalize();
+
+        let result = transformer.process();
+
+        writer.write_all(&transformer.buffer)?;
+        writer.flush()?;
+        result?;
+
+        Ok(transformer.summary)
+    }
+
+    /// Transforms all records of the given asynchronous `reader` with the function `f` and writes
+    /// the results to the given asynchronous `writer`, which is flushed at the end, just like
+    /// [Pipeline::transform].
+    ///
+    /// # Errors
+    ///
+    /// If reading or writing fails, or according to the [ErrorPolicy] if a line cannot be parsed
+    /// or a record cannot be serialized.
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub async fn transform_async<In, Out, R, W, F>(self, mut reader: R, mut writer: W, f: F)
+        -> Result<TransformSummary, TransformError>
+    where
+        In: DeserializeOwned,
+        Out: Serialize,
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+        F: FnMut(In) -> Option<Out>
+    {
+        let mut transformer = Transformer::new(self, f);
+        let mut chunk = vec![0; DEFAULT_CHUNK_SIZE];
+
+        loop {
+            let length = match reader.read(&mut chunk).await {
+                Ok(0) => break,
+                Ok(length) => length,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into())
+            };
+
+            transformer.engine.input(&chunk[..length]);
+
+            let result = transformer.process();
+
+            writer.write_all(&transformer.buffer).await?;
+            transformer.buffer.clear();
+            result?;
+
+            if transformer.engine.is_finished() {
+                break;
+            }
+        }
+
+        transformer.engine.finalize();
+
+        let result = transformer.process();
+
+        writer.write_all(&transformer.buffer).await?;
+        writer.flush().await?;
+        result?;
+
+        Ok(transformer.summary)
+    }
+}
+
+/// The state of a transformation which is shared between the synchronous and asynchronous
+/// variants. Output is collected in a buffer, which is written by the caller after every chunk.
+struct Transformer<In, F> {
+    engine: NdjsonEngine<In>,
+    f: F,
+    error_policy: ErrorPolicy,
+    summary: TransformSummary,
+    line_count: u64,
+    buffer: Vec<u8>
+}
+
+impl<In, F> Transformer<In, F> {
+    fn new(pipeline: Pipeline, f: F) -> Transformer<In, F> {
+        Transformer {
+            engine: NdjsonEngine::with_events(pipeline.config),
+            f,
+            error_policy: pipeline.error_policy,
+            summary: TransformSummary::default(),
+            line_count: 0,
+            buffer: Vec::new()
+        }
+    }
+
+    fn handle_error(&mut self, error: TransformError) -> Result<(), TransformError> {
+        match self.error_policy {
+            ErrorPolicy::Abort => Err(error),
+            ErrorPolicy::Skip => {
+                self.summary.errors_skipped += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Maps all records available from the engine and serializes the results into the buffer.
+    fn process<Out>(&mut self) -> Result<(), TransformError>
+    where
+        In: DeserializeOwned,
+        Out: Serialize,
+        F: FnMut(In) -> Option<Out>
+    {
+        while let Some(event) = self.engine.pop_event() {
+            self.line_count += 1;
+
+            let record = match event {
+                NdjsonEvent::Record(record) | NdjsonEvent::RestParsed(record) => record,
+                NdjsonEvent::ParseError { error, .. } => {
+                    self.handle_error(TransformError::Parse(ValidationError {
+                        line_number: self.line_count,
+                        error
+                    }))?;
+                    continue;
+                },
+                NdjsonEvent::EmptyLine | NdjsonEvent::BlankLine | NdjsonEvent::CommentLine =>
+                    continue
+            };
+
+            self.summary.records_read += 1;
+
+            match (self.f)(record) {
+                Some(output) => match serialize_line(&output, &mut self.buffer) {
+                    Ok(()) => self.summary.records_written += 1,
+                    Err(error) => self.handle_error(TransformError::Serialize(error))?
+                },
+                None => self.summary.records_filtered += 1
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Transforms all records of the given `reader` with the function `f` and writes the results to
+/// the given `writer` using a default [Pipeline], i.e. with the default [NdjsonConfig] and
+/// [ErrorPolicy::Abort]. See the [pipeline](crate::pipeline) module for an example.
+///
+/// # Errors
+///
+/// If reading or writing fails, or if a line cannot be parsed or a record cannot be serialized.
+pub fn transform<In, Out, R, W, F>(reader: R, writer: W, f: F)
+    -> Result<TransformSummary, TransformError>
+where
+    In: DeserializeOwned,
+    Out: Serialize,
+    R: Read,
+    W: Write,
+    F: FnMut(In) -> Option<Out>
+{
+    Pipeline::new().transform(reader, writer, f)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::config::EmptyLineHandling;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    const INPUT: &str = "{\"key\":1,\"value\":2}\n\ninvalid\n{\"key\":3,\"value\":4}";
+
+    fn swap(record: TestStruct) -> Option<TestStruct> {
+        Some(TestStruct {
+            key: record.value,
+            value: record.key
+        })
+    }
+
+    #[test]
+    fn abort_policy_stops_at_first_parse_error() {
+        let mut output = Vec::new();
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+        let result = Pipeline::new()
+            .with_config(config)
+            .transform(INPUT.as_bytes(), &mut output, swap);
+
+        assert_that!(matches!(result,
+            Err(TransformError::Parse(ValidationError { line_number: 3, .. })))).is_true();
+    }
+
+    #[test]
+    fn skip_policy_skips_invalid_lines_and_writes_rest() {
+        let mut output = Vec::new();
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_parse_rest(true);
+        let summary = Pipeline::new()
+            .with_config(config)
+            .with_error_policy(ErrorPolicy::Skip)
+            .transform(INPUT.as_bytes(), &mut output, swap)
+            .unwrap();
+
+        assert_that!(String::from_utf8(output).unwrap())
+            .is_equal_to("{\"key\":2,\"value\":1}\n{\"key\":4,\"value\":3}\n".to_owned());
+        assert_that!(summary).is_equal_to(TransformSummary {
+            records_read: 2,
+            records_written: 2,
+            records_filtered: 0,
+            errors_skipped: 1
+        });
+    }
+
+    #[test]
+    fn records_mapped_to_none_are_filtered() {
+        let mut output = Vec::new();
+        let summary = transform("1\n2\n3\n4\n".as_bytes(), &mut output,
+            |value: u32| value.is_multiple_of(2).then_some(value * 10)).unwrap();
+
+        assert_that!(output).is_equal_to(b"20\n40\n".to_vec());
+        assert_that!(summary.records_filtered).is_equal_to(2);
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tokio_tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn transform_async_writes_mapped_records() {
+        let mut output = Vec::new();
+        let summary = tokio_test::block_on(Pipeline::new()
+            .transform_async(b"[1,2]\n[3]\n".as_slice(), &mut output,
+                |values: Vec<u32>| Some(values.iter().sum::<u32>())))
+            .unwrap();
+
+        assert_that!(output).is_equal_to(b"3\n3\n".to_vec());
+        assert_that!(summary.records_written).is_equal_to(2);
+    }
+}
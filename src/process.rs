@@ -0,0 +1,283 @@
+//! This module offers [process], which implements the common structure of small NDJSON-filters in a
+//! single call: records are parsed from a reader, such as standard input, transformed or filtered
+//! by a function, and written as NDJSON to a writer, such as standard output. Lines which cannot be
+//! parsed are reported with their line number to an error handler, which writes them to standard
+//! error by default, while processing continues. A [Processor] allows configuring the parser, the
+//! output format, and the error handler.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::process;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     user: String,
+//!     level: String
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Error {
+//!     user: String
+//! }
+//!
+//! let input = concat!(
+//!     "{\"user\":\"ann\",\"level\":\"info\"}\n",
+//!     "invalid\n",
+//!     "{\"user\":\"bob\",\"level\":\"error\"}\n");
+//! let mut output = Vec::new();
+//! let mut errors = Vec::new();
+//!
+//! // In a command line tool, this would be `process::process(io::stdin(), io::stdout(), ...)`.
+//! let summary = process::Processor::new()
+//!     .with_error_handler(|error| errors.push(error.to_string()))
+//!     .run(input.as_bytes(), &mut output, |event: Event| {
+//!         (event.level == "error").then_some(Error { user: event.user })
+//!     })
+//!     .unwrap();
+//!
+//! assert_eq!(output, b"{\"user\":\"bob\"}\n");
+//! assert_eq!(errors, vec!["line 2: expected value at line 1 column 1"]);
+//! assert_eq!(summary.records_written, 1);
+//! ```
+
+use std::io::{self, BufWriter, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::NdjsonConfig;
+use crate::driver::FallibleNdjsonIter;
+use crate::error::ValidationError;
+use crate::fallible::FallibleNdjsonError;
+use crate::source::{ChunkSource, ReaderSource};
+use crate::writer::{self, NdjsonWriteConfig};
+
+/// Statistics of a single run of [process] or [Processor::run].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ProcessSummary {
+
+    /// The number of records which were parsed successfully and given to the processing function.
+    pub records_read: u64,
+
+    /// The number of records returned by the processing function, which were written to the
+    /// output.
+    pub records_written: u64,
+
+    /// The number of lines which could not be parsed and were reported to the error handler.
+    pub errors: u64
+}
+
+/// A configurable filter of NDJSON-records, which parses records from a reader, applies a function
+/// to them, and writes the results to a writer. See the [module documentation](self) for an
+/// example and [process] for the default configuration.
+pub struct Processor<'handler> {
+    config: NdjsonConfig,
+    write_config: NdjsonWriteConfig,
+    error_handler: Box<dyn FnMut(ValidationError) + 'handler>
+}
+
+impl<'handler> Processor<'handler> {
+
+    /// Creates a new processor with the default [NdjsonConfig] and [NdjsonWriteConfig], which
+    /// writes errors to standard error.
+    pub fn new() -> Processor<'handler> {
+        Processor {
+            config: NdjsonConfig::default(),
+            write_config: NdjsonWriteConfig::default(),
+            error_handler: Box::new(|error| eprintln!("{error}"))
+        }
+    }
+
+    /// Sets the [NdjsonConfig] with which the input is parsed.
+    ///
+    /// # Returns
+    ///
+    /// This processor with the given config.
+    pub fn with_config(self, config: NdjsonConfig) -> Processor<'handler> {
+        Processor {
+            config,
+            ..self
+        }
+    }
+
+    /// Sets the [NdjsonWriteConfig] with which the output is written.
+    ///
+    /// # Returns
+    ///
+    /// This processor with the given write config.
+    pub fn with_write_config(self, write_config: NdjsonWriteConfig) -> Processor<'handler> {
+        Processor {
+            write_config,
+            ..self
+        }
+    }
+
+    /// Sets the handler which receives every line that cannot be parsed as a [ValidationError],
+    /// which holds the line number and the error. To report errors on a channel, the handler can
+    /// send them to it. By default, errors are written to standard error.
+    ///
+    /// # Returns
+    ///
+    /// This processor with the given error handler.
+    pub fn with_error_handler(self, error_handler: impl FnMut(ValidationError) + 'handler)
+            -> Processor<'handler> {
+        Processor {
+            error_handler: Box::new(error_handler),
+            ..self
+        }
+    }
+
+    /// Parses records of type `In` from the given `reader`, applies `f` to each of them, and writes
+    /// the returned records of type `Out` as NDJSON to the given `writer`. Records for which `f`
+    /// returns `None` are dropped. Lines which cannot be parsed are reported to the error handler
+    /// and do not end processing. The output is buffered and flushed once the input is exhausted.
+    ///
+    /// # Errors
+    ///
+    /// If reading from the reader or writing to the writer fails, or with
+    /// [ErrorKind::InvalidData](io::ErrorKind::InvalidData) if a returned record cannot be
+    /// serialized. Records processed before are written to the writer.
+    pub fn run<In, Out, R, W, F>(&mut self, reader: R, writer: W, mut f: F)
+        -> io::Result<ProcessSummary>
+    where
+        for<'deserialize> In: Deserialize<'deserialize>,
+        Out: Serialize,
+        R: Read,
+        W: Write,
+        F: FnMut(In) -> Option<Out>
+    {
+        let records =
+            FallibleNdjsonIter::with_config(ReaderSource::new(reader).into_chunks(),
+                self.config.clone());
+        let mut writer = BufWriter::new(writer);
+        let mut buffer = Vec::new();
+        let mut summary = ProcessSummary::default();
+
+        for (line_number, record) in records.with_line_numbers() {
+            let record = match record {
+                Ok(record) => record,
+                Err(FallibleNdjsonError::JsonError(error)) => {
+                    summary.errors += 1;
+                    (self.error_handler)(ValidationError { line_number, error });
+                    continue;
+                },
+                Err(FallibleNdjsonError::InputError(error)) => return Err(error)
+            };
+
+            summary.records_read += 1;
+
+            if let Some(output) = f(record) {
+                buffer.clear();
+                writer::serialize_line_with_config(&output, &mut buffer, &self.write_config)?;
+                writer.write_all(&buffer)?;
+                summary.records_written += 1;
+            }
+        }
+
+        writer.flush()?;
+        Ok(summary)
+    }
+}
+
+impl<'handler> Default for Processor<'handler> {
+    fn default() -> Processor<'handler> {
+        Processor::new()
+    }
+}
+
+/// Parses records of type `In` from the given `reader`, applies `f` to each of them, and writes
+/// the returned records of type `Out` as NDJSON to the given `writer`, as is typical for command
+/// line filters reading from standard input and writing to standard output. Lines which cannot be
+/// parsed are written to standard error together with their line number. See [Processor::run] for
+/// more details and [Processor] for configuring the parser, the output, and the error handling.
+///
+/// # Errors
+///
+/// If reading from the reader or writing to the writer fails, or if a returned record cannot be
+/// serialized.
+pub fn process<In, Out, R, W, F>(reader: R, writer: W, f: F) -> io::Result<ProcessSummary>
+where
+    for<'deserialize> In: Deserialize<'deserialize>,
+    Out: Serialize,
+    R: Read,
+    W: Write,
+    F: FnMut(In) -> Option<Out>
+{
+    Processor::new().run(reader, writer, f)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use crate::config::EmptyLineHandling;
+    use crate::error::NdjsonError;
+    use crate::test_util::TestStruct;
+    use crate::writer::WriteLineEnding;
+
+    use super::*;
+
+    #[test]
+    fn records_are_transformed_and_filtered() {
+        let input = "{\"key\":1,\"value\":2}\n\n{\"key\":3,\"value\":4}\n{\"key\":5,\"value\":6}";
+        let mut output = Vec::new();
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreEmpty)
+            .with_parse_rest(true);
+        let write_config = NdjsonWriteConfig::default().with_line_ending(WriteLineEnding::CrLf);
+
+        let summary = Processor::new()
+            .with_config(config)
+            .with_write_config(write_config)
+            .run(input.as_bytes(), &mut output, |record: TestStruct|
+                (record.key != 3).then_some(record.key + record.value))
+            .unwrap();
+
+        assert_that!(output).contains_exactly_in_given_order(b"3\r\n11\r\n".iter().copied());
+        assert_that!(summary).is_equal_to(ProcessSummary {
+            records_read: 3,
+            records_written: 2,
+            errors: 0
+        });
+    }
+
+    #[test]
+    fn parse_errors_are_reported_with_line_numbers() {
+        let input = "1\nx\n2\n[]\n";
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+
+        let summary = Processor::new()
+            .with_error_handler(|error| errors.push(error))
+            .run(input.as_bytes(), &mut output, |record: u32| Some(record))
+            .unwrap();
+
+        assert_that!(output).contains_exactly_in_given_order(b"1\n2\n".iter().copied());
+        assert_that!(summary.errors).is_equal_to(2);
+        assert_that!(errors).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(matches!(it, ValidationError {
+                line_number: 2,
+                error: NdjsonError::Json(_)
+            })).is_true(),
+            |it| assert_that!(it.line_number).is_equal_to(4)
+        ));
+    }
+
+    #[test]
+    fn read_errors_end_processing() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("read error"))
+            }
+        }
+
+        let result = process(FailingReader, io::sink(), |record: u32| Some(record));
+
+        assert_that!(result.map_err(|error| error.to_string()))
+            .contains_error("read error".to_owned());
+    }
+}
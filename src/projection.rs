@@ -0,0 +1,371 @@
+//! This module offers the [Projection], which extracts only selected fields of NDJSON-records,
+//! given as JSON pointers, without deserializing the remaining fields into values. For wide records
+//! of which only few fields are required, this saves both time and memory.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::projection::Projection;
+//! use serde_json::{json, Value};
+//!
+//! let projection = Projection::new(["/id", "/user/name", "/tags/1"]).unwrap();
+//! let data_blocks = vec![
+//!     "{\"id\":1,\"user\":{\"name\":\"Alice\",\"age\":25},\"tags\":[\"a\",\"b\"]}\n",
+//!     "{\"id\":2,\"payload\":[1,2,3]}\n"
+//! ];
+//!
+//! let mut ndjson_iter = ndjson_stream::from_iter::<Vec<Option<Value>>, _>(data_blocks)
+//!     .with_deserializer(move |line| projection.project(line));
+//!
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(),
+//!     vec![Some(json!(1)), Some(json!("Alice")), Some(json!("b"))]);
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), vec![Some(json!(2)), None, None]);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use serde_json::error::Result as JsonResult;
+use serde_json::Value;
+
+/// The error returned by [Projection::new] if one of the given paths is not a valid JSON pointer
+/// according to [RFC 6901](https://www.rfc-editor.org/rfc/rfc6901).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidPointerError {
+
+    /// The invalid pointer.
+    pub pointer: String
+}
+
+impl Display for InvalidPointerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSON pointer `{}`", self.pointer)
+    }
+}
+
+impl Error for InvalidPointerError { }
+
+/// A node in the tree of projected paths. The path of a node is given by the keys leading to it
+/// from the root.
+#[derive(Clone, Debug, Default)]
+struct Node {
+
+    /// The indices in the output of all pointers which refer to the value at this node.
+    indices: Vec<usize>,
+
+    /// The nodes of object keys or array indices below this node.
+    children: BTreeMap<String, Node>
+}
+
+impl Node {
+
+    /// Fills the output for this node and all nodes below it from the fully deserialized `value`.
+    fn fill_from_value(&self, value: &Value, output: &mut [Option<Value>]) {
+        for (key, child) in &self.children {
+            let child_value = match value {
+                Value::Object(map) => map.get(key),
+                Value::Array(array) => parse_index(key).and_then(|index| array.get(index)),
+                _ => None
+            };
+
+            if let Some(child_value) = child_value {
+                child.fill_from_value(child_value, output);
+            }
+        }
+
+        for &index in &self.indices {
+            output[index] = Some(value.clone());
+        }
+    }
+}
+
+/// Parses an array index of a JSON pointer, which must not have leading zeros.
+fn parse_index(key: &str) -> Option<usize> {
+    if key.len() > 1 && key.starts_with('0') {
+        return None;
+    }
+
+    key.parse().ok()
+}
+
+/// The [DeserializeSeed] which visits the value at a [Node], deserializing only projected values
+/// into the output and skipping everything else.
+struct NodeSeed<'projection, 'output> {
+    node: &'projection Node,
+    output: &'output mut [Option<Value>]
+}
+
+impl<'de> DeserializeSeed<'de> for NodeSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        if !self.node.indices.is_empty() {
+            let value = Value::deserialize(deserializer)?;
+            self.node.fill_from_value(&value, self.output);
+            return Ok(());
+        }
+
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>
+    {
+        let mut index = 0usize;
+
+        loop {
+            let child = self.node.children.get(&index.to_string());
+            let element = match child {
+                Some(child) => seq.next_element_seed(NodeSeed {
+                    node: child,
+                    output: self.output
+                })?,
+                None => seq.next_element::<IgnoredAny>()?.map(|_| ())
+            };
+
+            if element.is_none() {
+                return Ok(());
+            }
+
+            index += 1;
+        }
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>
+    {
+        while let Some(key) = map.next_key::<String>()? {
+            match self.node.children.get(&key) {
+                Some(child) => map.next_value_seed(NodeSeed {
+                    node: child,
+                    output: self.output
+                })?,
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Unescapes a reference token of a JSON pointer, i.e. replaces `~1` by `/` and `~0` by `~`.
+///
+/// # Returns
+///
+/// The unescaped token, or `None` if it contains an invalid escape sequence.
+fn unescape_token(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+
+    while let Some(char) = chars.next() {
+        if char == '~' {
+            match chars.next()? {
+                '0' => unescaped.push('~'),
+                '1' => unescaped.push('/'),
+                _ => return None
+            }
+        }
+        else {
+            unescaped.push(char);
+        }
+    }
+
+    Some(unescaped)
+}
+
+/// Extracts the values at a fixed list of JSON pointers from every record, see the
+/// [projection](crate::projection) module for an example. Values which are not projected are
+/// skipped without being deserialized, while the projected values are returned as [Value]s in the
+/// order of the pointers, with `None` for pointers which do not match any value of the record.
+///
+/// Since records are not deserialized with [Deserialize], a projection is used as the custom
+/// deserializer of an engine or driver, such as via
+/// [NdjsonEngine::with_deserializer](crate::engine::NdjsonEngine::with_deserializer).
+#[derive(Clone, Debug)]
+pub struct Projection {
+    root: Node,
+    len: usize
+}
+
+impl Projection {
+
+    /// Creates a new projection extracting the values at the given JSON `pointers`, such as
+    /// `/user/name` or `/tags/0`. The empty pointer refers to the entire record.
+    ///
+    /// # Errors
+    ///
+    /// An [InvalidPointerError] if one of the given pointers is neither empty nor starts with `/`
+    /// or contains an invalid escape sequence.
+    pub fn new<I>(pointers: I) -> Result<Projection, InvalidPointerError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>
+    {
+        let mut root = Node::default();
+        let mut len = 0;
+
+        for pointer in pointers {
+            let pointer = pointer.as_ref();
+            let invalid = || InvalidPointerError {
+                pointer: pointer.to_string()
+            };
+            let mut node = &mut root;
+
+            if !pointer.is_empty() {
+                let tokens = pointer.strip_prefix('/').ok_or_else(invalid)?;
+
+                for token in tokens.split('/') {
+                    let key = unescape_token(token).ok_or_else(invalid)?;
+                    node = node.children.entry(key).or_default();
+                }
+            }
+
+            node.indices.push(len);
+            len += 1;
+        }
+
+        Ok(Projection {
+            root,
+            len
+        })
+    }
+
+    /// Gets the number of pointers of this projection, which is the length of every projected
+    /// record.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Indicates whether this projection has no pointers and thus only validates records.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Projects the given NDJSON-`line` onto the pointers of this projection.
+    ///
+    /// # Errors
+    ///
+    /// If the line is not valid JSON.
+    pub fn project(&self, line: &[u8]) -> JsonResult<Vec<Option<Value>>> {
+        let mut output = vec![None; self.len];
+        let mut deserializer = serde_json::Deserializer::from_slice(line);
+
+        NodeSeed {
+            node: &self.root,
+            output: &mut output
+        }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn project(pointers: &[&str], line: &str) -> Vec<Option<Value>> {
+        Projection::new(pointers).unwrap().project(line.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn invalid_pointers_are_rejected() {
+        assert_that!(Projection::new(["a"]).err())
+            .contains(InvalidPointerError { pointer: "a".to_owned() });
+        assert_that!(Projection::new(["/a~2"]).err())
+            .contains(InvalidPointerError { pointer: "/a~2".to_owned() });
+    }
+
+    #[test]
+    fn nested_fields_and_array_elements_are_projected() {
+        let line = r#"{"a":{"b":[1,{"c":true}]},"d":"x","e/f":null,"g~":2}"#;
+
+        assert_that!(project(&["/a/b/1/c", "/d", "/e~1f", "/g~0", "/a/b/2", "/a/b/01"], line))
+            .contains_exactly_in_given_order([
+                Some(json!(true)),
+                Some(json!("x")),
+                Some(Value::Null),
+                Some(json!(2)),
+                None,
+                None
+            ]);
+    }
+
+    #[test]
+    fn pointer_below_projected_pointer_is_filled_from_value() {
+        let line = r#"{"a":{"b":[1,2]},"c":3}"#;
+
+        assert_that!(project(&["/a/b/0", "/a", "", "/a"], line))
+            .contains_exactly_in_given_order([
+                Some(json!(1)),
+                Some(json!({ "b": [1, 2] })),
+                Some(json!({ "a": { "b": [1, 2] }, "c": 3 })),
+                Some(json!({ "b": [1, 2] }))
+            ]);
+    }
+
+    #[test]
+    fn pointer_into_scalar_is_not_matched() {
+        assert_that!(project(&["/a/b"], r#"{"a":1}"#)).contains_exactly_in_given_order([None]);
+    }
+
+    #[test]
+    fn invalid_json_is_rejected_even_in_skipped_values() {
+        let projection = Projection::new(["/a"]).unwrap();
+
+        assert_that!(projection.project(br#"{"a":1,"b":[1,}"#)).is_err();
+        assert_that!(projection.project(br#"{"a":1} x"#)).is_err();
+    }
+}
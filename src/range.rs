@@ -0,0 +1,279 @@
+//! This module offers [get_resumable], which downloads NDJSON over HTTP using a [reqwest] [Client]
+//! and transparently resumes the download if the connection fails. The absolute number of bytes
+//! received so far is remembered, and on an error while receiving the body, the request is reissued
+//! with a `Range` header, such that the server only sends the data which has not been received yet.
+//! The incomplete line received before the error is retained by the parser and joined with the
+//! beginning of the new response, so no record is lost or duplicated, even if the connection failed
+//! in the middle of a line. Retries are performed according to a [RetryPolicy], see
+//! [FallibleNdjsonStream::with_retry].
+//!
+//! Servers which do not support range requests respond with the entire body instead of only the
+//! requested range. In this case, the bytes which were already received are skipped. A response
+//! with status `416 Range Not Satisfiable` to a resumed request is treated as the end of the body,
+//! since it indicates that the connection failed after the last byte had been received.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use ndjson_stream::driver::RetryPolicy;
+//! use reqwest::{Client, Url};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     id: u64
+//! }
+//!
+//! # async fn run() {
+//! let url = Url::parse("http://localhost:8080/events.ndjson").unwrap();
+//! let mut events =
+//!     ndjson_stream::range::get_resumable::<Event>(Client::new(), url, RetryPolicy::new(5));
+//!
+//! while let Some(event) = events.next().await {
+//!     println!("{}", event.unwrap().id);
+//! }
+//! # }
+//! ```
+
+use std::future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt, TryStreamExt};
+
+use reqwest::header::RANGE;
+use reqwest::{Client, Response, StatusCode, Url};
+
+use serde::Deserialize;
+
+use crate::config::NdjsonConfig;
+use crate::driver::{FallibleNdjsonStream, RetryPolicy};
+use crate::fallible::FallibleNdjsonResult;
+
+#[cfg(doc)]
+use crate::fallible::FallibleNdjsonError;
+
+type BodyStream = BoxStream<'static, reqwest::Result<Bytes>>;
+
+/// Removes the first `skipped` bytes from the given stream of data blocks.
+fn skip_bytes(bytes_stream: impl Stream<Item = reqwest::Result<Bytes>> + Send + 'static,
+        skipped: u64) -> BodyStream {
+    bytes_stream
+        .scan(skipped, |remaining, chunk| {
+            let chunk = chunk.map(|mut chunk| {
+                let skipped_len = (*remaining).min(chunk.len() as u64);
+
+                *remaining -= skipped_len;
+                chunk.split_off(skipped_len as usize)
+            });
+
+            future::ready(Some(chunk))
+        })
+        .boxed()
+}
+
+/// Converts the response to a request for the body starting at `offset` into a stream over the
+/// data from that offset, accounting for servers which ignore the `Range` header.
+fn body_from_offset(response: Response, offset: u64) -> reqwest::Result<BodyStream> {
+    if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(stream::empty().boxed());
+    }
+
+    let response = response.error_for_status()?;
+    let skipped = match response.status() {
+        StatusCode::PARTIAL_CONTENT => 0,
+        _ => offset
+    };
+
+    Ok(skip_bytes(response.bytes_stream(), skipped))
+}
+
+/// Creates a stream which requests the body at the given `url` starting at `offset` once it is
+/// polled and then yields its data blocks.
+fn request_from(client: &Client, url: &Url, offset: u64) -> BodyStream {
+    let mut request = client.get(url.clone());
+
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+
+    stream::once(request.send())
+        .map(move |response| body_from_offset(response?, offset))
+        .try_flatten()
+        .boxed()
+}
+
+/// A [Stream] over the parsed records of an NDJSON-body received via HTTP, which is resumed with
+/// range requests if receiving it fails. See [get_resumable] for more details.
+pub struct ResumableNdjsonStream<T> {
+    inner: BoxStream<'static, FallibleNdjsonResult<T, reqwest::Error>>
+}
+
+impl<T> Stream for ResumableNdjsonStream<T> {
+    type Item = FallibleNdjsonResult<T, reqwest::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Requests the NDJSON-body at `url` via the given `client` and returns a stream over its parsed
+/// records, with the default [NdjsonConfig]. See [get_resumable_with_config] for more details.
+pub fn get_resumable<T>(client: Client, url: Url, policy: RetryPolicy)
+    -> ResumableNdjsonStream<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static
+{
+    get_resumable_with_config(client, url, NdjsonConfig::default(), policy)
+}
+
+/// Requests the NDJSON-body at `url` via the given `client` and returns a stream over its records,
+/// parsed with the given [NdjsonConfig]. If the request or receiving the body fails, the request is
+/// reissued according to the given [RetryPolicy], with a `Range` header starting at the number of
+/// bytes received so far. See the [module documentation](self) for more details.
+///
+/// Failed requests, including responses with an error status, and errors while receiving the body
+/// are reported via [FallibleNdjsonError::InputError] once the maximum number of attempts is
+/// exceeded, after which polling the stream starts a new series of attempts. Parse errors are
+/// reported via [FallibleNdjsonError::JsonError] and do not end the stream.
+///
+/// # Panics
+///
+/// Polling the returned stream panics if an error occurs outside a Tokio runtime with the time
+/// driver enabled.
+pub fn get_resumable_with_config<T>(client: Client, url: Url, config: NdjsonConfig,
+    policy: RetryPolicy) -> ResumableNdjsonStream<T>
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Send + 'static
+{
+    let first_request = request_from(&client, &url, 0);
+    let inner = FallibleNdjsonStream::with_config(first_request, config)
+        .with_retry(policy, move |offset| request_from(&client, &url, offset));
+
+    ResumableNdjsonStream {
+        inner: inner.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    use kernal::prelude::*;
+
+    use tokio::runtime::Builder;
+
+    use crate::fallible::FallibleNdjsonError;
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    /// Serves the given responses, consisting of a status line, the announced length of the body,
+    /// and the actually sent body, to consecutive requests and returns the requested ranges.
+    fn serve(responses: Vec<(&'static str, usize, &'static str)>)
+            -> (Url, JoinHandle<Vec<Option<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/data", listener.local_addr().unwrap())).unwrap();
+        let server = thread::spawn(move || {
+            let mut ranges = Vec::new();
+
+            for (status, content_length, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let reader = BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+
+                for line in reader.lines() {
+                    let line = line.unwrap();
+
+                    if line.is_empty() {
+                        break;
+                    }
+
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: ") {
+                        range = Some(value.to_owned());
+                    }
+                }
+
+                ranges.push(range);
+                write!(stream, "HTTP/1.1 {status}\r\nContent-Length: {content_length}\r\n\
+                    Connection: close\r\n\r\n{body}").unwrap();
+            }
+
+            ranges
+        });
+
+        (url, server)
+    }
+
+    /// Collects at most `limit` items, since the stream retries forever if errors keep occurring.
+    fn collect(url: Url, limit: usize) -> Vec<FallibleNdjsonResult<TestStruct, reqwest::Error>> {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let policy = RetryPolicy::new(3).with_initial_backoff(Duration::from_millis(1));
+
+        runtime.block_on(get_resumable::<TestStruct>(Client::new(), url, policy)
+            .take(limit)
+            .collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn interrupted_body_is_resumed_with_range_request() {
+        let (url, server) = serve(vec![
+            ("200 OK", 40, "{\"key\":1,\"value\":2}\n{\"key\":3,"),
+            ("206 Partial Content", 11, "\"value\":4}\n")
+        ]);
+
+        let records = collect(url, 3);
+
+        assert_that!(server.join().unwrap())
+            .contains_exactly_in_given_order([None, Some("bytes=29-".to_owned())]);
+        assert_that!(records).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn received_bytes_are_skipped_if_server_ignores_range() {
+        let body = "{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n";
+        let (url, server) = serve(vec![
+            ("200 OK", body.len(), &body[..25]),
+            ("200 OK", body.len(), body)
+        ]);
+
+        let records = collect(url, 3);
+
+        assert_that!(server.join().unwrap())
+            .contains_exactly_in_given_order([None, Some("bytes=25-".to_owned())]);
+        assert_that!(records).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 1, value: 2 }),
+            |it| assert_that!(it).contains_value(TestStruct { key: 3, value: 4 })
+        ));
+    }
+
+    #[test]
+    fn error_is_reported_once_attempts_are_exceeded() {
+        let (url, server) = serve(vec![
+            ("200 OK", 100, "{\"key\":1,\"value\":2}\n"),
+            ("503 Service Unavailable", 0, ""),
+            ("503 Service Unavailable", 0, ""),
+            ("503 Service Unavailable", 0, "")
+        ]);
+
+        let records = collect(url, 2);
+
+        assert_that!(server.join().unwrap()).has_length(4);
+        assert_that!(&records[0]).contains_value(TestStruct { key: 1, value: 2 });
+        assert_that!(matches!(&records[1], Err(FallibleNdjsonError::InputError(error))
+            if error.status().is_some_and(|status| status.as_u16() == 503))).is_true();
+    }
+}
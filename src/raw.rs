@@ -0,0 +1,137 @@
+//! This module offers the [WithRaw] wrapper, which yields every parsed record together with the
+//! original JSON text it was parsed from. This allows archiving the exact input of a record, for
+//! example for auditing or replaying, without serializing the typed value again.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::raw::WithRaw;
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize, Eq, PartialEq)]
+//! struct Person {
+//!     name: String
+//! }
+//!
+//! let data_blocks = vec!["{ \"name\": \"Alice\", \"unknown\": 1 }\n"];
+//! let mut ndjson_iter = ndjson_stream::from_iter::<WithRaw<Person>, _>(data_blocks);
+//! let record = ndjson_iter.next().unwrap().unwrap();
+//!
+//! assert_eq!(record.value, Person { name: "Alice".into() });
+//! assert_eq!(record.raw, b"{ \"name\": \"Alice\", \"unknown\": 1 }");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer};
+
+use serde_json::value::RawValue;
+
+/// A record of type `T` together with the raw JSON text it was deserialized from, see the
+/// [raw](crate::raw) module for an example.
+///
+/// The raw text is captured exactly as it occurs in the line, including insignificant whitespace
+/// and fields which `T` ignores, but excluding whitespace surrounding the value and the line
+/// ending. If a [LinePreprocessor](crate::config::LinePreprocessor) is configured, the raw text is
+/// taken from the preprocessed line.
+///
+/// The record is first captured as a [RawValue], which is then deserialized into `T`. Therefore,
+/// `WithRaw` requires the [serde_json] deserializer and cannot be used with a custom deserializer
+/// such as [NdjsonEngine::with_deserializer](crate::engine::NdjsonEngine::with_deserializer).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct WithRaw<T> {
+
+    /// The deserialized record.
+    pub value: T,
+
+    /// The raw JSON text of the record.
+    pub raw: Vec<u8>
+}
+
+impl<T> WithRaw<T> {
+
+    /// Converts this record into the deserialized value, discarding the raw text.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Gets the raw JSON text of this record as a string slice. Since the raw text was validated
+    /// as JSON, it is always valid UTF-8.
+    pub fn raw_str(&self) -> &str {
+        // The raw text originates from a `RawValue`, which is always valid UTF-8.
+        core::str::from_utf8(&self.raw).unwrap_or_default()
+    }
+}
+
+impl<'de, T> Deserialize<'de> for WithRaw<T>
+where
+    T: DeserializeOwned
+{
+    fn deserialize<D>(deserializer: D) -> Result<WithRaw<T>, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        let value = serde_json::from_str(raw.get()).map_err(D::Error::custom)?;
+        let raw: Box<str> = raw.into();
+
+        Ok(WithRaw {
+            value,
+            raw: String::from(raw).into_bytes()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde_json::Value;
+
+    use crate::config::{NdjsonConfig, TrailingDataHandling};
+    use crate::engine::NdjsonEngine;
+
+    use super::*;
+
+    #[test]
+    fn raw_text_is_captured_verbatim() {
+        let mut engine = NdjsonEngine::<WithRaw<Value>>::new();
+
+        engine.input(" {\"b\" : [1, 2],\"a\":null}\r\n");
+
+        let record = engine.pop().unwrap().unwrap();
+
+        assert_that!(record.raw_str()).is_equal_to("{\"b\" : [1, 2],\"a\":null}");
+        assert_that!(record.into_value())
+            .is_equal_to(serde_json::json!({ "a": null, "b": [1, 2] }));
+    }
+
+    #[test]
+    fn schema_mismatch_is_reported_as_error() {
+        let mut engine = NdjsonEngine::<WithRaw<u32>>::new();
+
+        engine.input("\"x\"\n{\n");
+
+        assert_that!(engine.pop()).to_value().is_err();
+        assert_that!(engine.pop()).to_value().is_err();
+        assert_that!(engine.pop()).is_none();
+    }
+
+    #[test]
+    fn trailing_data_handling_still_applies() {
+        let config =
+            NdjsonConfig::default().with_trailing_data_handling(TrailingDataHandling::Ignore);
+        let mut engine = NdjsonEngine::<WithRaw<u32>>::with_config(config);
+
+        engine.input("1 2\n");
+
+        let record = engine.pop().unwrap().unwrap();
+
+        assert_that!(record.value).is_equal_to(1);
+        assert_that!(record.raw_str()).is_equal_to("1");
+    }
+}
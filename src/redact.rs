@@ -0,0 +1,206 @@
+//! This module contains the byte-level scanner behind
+//! [LinePreprocessor::redacting_keys](crate::config::LinePreprocessor::redacting_keys), which masks
+//! the values of top-level keys without fully parsing the line.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+pub(crate) fn skip_whitespace(line: &[u8], mut index: usize) -> usize {
+    while line.get(index).is_some_and(u8::is_ascii_whitespace) {
+        index += 1;
+    }
+
+    index
+}
+
+/// Finds the end of the string starting with the quote at `start`, i.e. the index after its
+/// closing quote, or `None` if the string is not terminated.
+pub(crate) fn string_end(line: &[u8], start: usize) -> Option<usize> {
+    let mut index = start + 1;
+
+    loop {
+        match line.get(index)? {
+            b'"' => return Some(index + 1),
+            b'\\' => index += 2,
+            _ => index += 1
+        }
+    }
+}
+
+/// Finds the end of the JSON value starting at `start`, or `None` if the value is not terminated.
+/// Values are not validated, only their extent is determined.
+fn value_end(line: &[u8], start: usize) -> Option<usize> {
+    match line.get(start)? {
+        b'"' => string_end(line, start),
+        b'{' | b'[' => {
+            let mut index = start;
+            let mut depth = 0usize;
+
+            loop {
+                match line.get(index)? {
+                    b'"' => {
+                        index = string_end(line, index)?;
+                        continue;
+                    },
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+
+                        if depth == 0 {
+                            return Some(index + 1);
+                        }
+                    },
+                    _ => { }
+                }
+
+                index += 1;
+            }
+        },
+        _ => {
+            let mut index = start;
+
+            while line.get(index).is_some_and(|&byte|
+                    !matches!(byte, b',' | b'}') && !byte.is_ascii_whitespace()) {
+                index += 1;
+            }
+
+            Some(index)
+        }
+    }
+}
+
+/// Replaces the values of all members of the top-level object in `line` whose (raw, unescaped) key
+/// is contained in `keys` by `mask`. Scanning is best-effort: it stops at the first structural
+/// error, leaving the rest of the line unchanged, except if the error occurs within the value of a
+/// redacted key, in which case the rest of the line is masked as well.
+pub(crate) fn redact_top_level_keys<'line>(line: &'line [u8], keys: &BTreeSet<Vec<u8>>,
+        mask: &[u8]) -> Cow<'line, [u8]> {
+    let mut redacted = Vec::new();
+    let mut copied_until = 0;
+    let mut index = skip_whitespace(line, 0);
+
+    if line.get(index) != Some(&b'{') {
+        return Cow::Borrowed(line);
+    }
+
+    index += 1;
+
+    loop {
+        index = skip_whitespace(line, index);
+
+        if line.get(index) != Some(&b'"') {
+            break;
+        }
+
+        let Some(key_end) = string_end(line, index)
+        else {
+            break;
+        };
+        let key = &line[index + 1..key_end - 1];
+
+        index = skip_whitespace(line, key_end);
+
+        if line.get(index) != Some(&b':') {
+            break;
+        }
+
+        let value_start = skip_whitespace(line, index + 1);
+        let value_end = value_end(line, value_start);
+
+        if keys.contains(key) {
+            redacted.extend_from_slice(&line[copied_until..value_start]);
+            redacted.extend_from_slice(mask);
+            copied_until = value_end.unwrap_or(line.len());
+        }
+
+        let Some(value_end) = value_end
+        else {
+            break;
+        };
+
+        index = skip_whitespace(line, value_end);
+
+        if line.get(index) != Some(&b',') {
+            break;
+        }
+
+        index += 1;
+    }
+
+    if copied_until == 0 {
+        return Cow::Borrowed(line);
+    }
+
+    redacted.extend_from_slice(&line[copied_until..]);
+    Cow::Owned(redacted)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn redact(line: &str, keys: &[&str]) -> String {
+        let keys = keys.iter().map(|key| key.as_bytes().to_vec()).collect();
+
+        String::from_utf8(redact_top_level_keys(line.as_bytes(), &keys, b"\"***\"").into_owned())
+            .unwrap()
+    }
+
+    #[test]
+    fn line_without_redacted_keys_is_borrowed() {
+        let keys = [b"password".to_vec()].into_iter().collect();
+        let redacted = redact_top_level_keys(b"{\"user\":\"name\"}", &keys, b"\"***\"");
+
+        assert_that!(matches!(redacted, Cow::Borrowed(_))).is_true();
+    }
+
+    #[test]
+    fn values_of_all_types_are_redacted() {
+        let line = r#"{"a":"secret","b":123,"c":{"d":[1,"}"]},"e":[true],"f":null}"#;
+
+        assert_that!(redact(line, &["a", "b", "c", "e"]))
+            .is_equal_to(r#"{"a":"***","b":"***","c":"***","e":"***","f":null}"#.to_owned());
+    }
+
+    #[test]
+    fn whitespace_is_preserved() {
+        let line = r#" { "token" : "secret" , "user" : "name" } "#;
+
+        assert_that!(redact(line, &["token"]))
+            .is_equal_to(r#" { "token" : "***" , "user" : "name" } "#.to_owned());
+    }
+
+    #[test]
+    fn nested_keys_are_not_redacted() {
+        let line = r#"{"user":{"password":"secret"}}"#;
+
+        assert_that!(redact(line, &["password"])).is_equal_to(line.to_owned());
+    }
+
+    #[test]
+    fn escaped_quotes_in_strings_are_handled() {
+        let line = r#"{"a":"x\"y","password":"se\"cret"}"#;
+
+        assert_that!(redact(line, &["password"]))
+            .is_equal_to(r#"{"a":"x\"y","password":"***"}"#.to_owned());
+    }
+
+    #[test]
+    fn unterminated_redacted_value_masks_rest_of_line() {
+        let line = r#"{"user":"name","password":"secret"#;
+
+        assert_that!(redact(line, &["password"]))
+            .is_equal_to(r#"{"user":"name","password":"***""#.to_owned());
+    }
+
+    #[test]
+    fn line_which_is_not_an_object_is_unchanged() {
+        let line = r#"["password","secret"]"#;
+
+        assert_that!(redact(line, &["password"])).is_equal_to(line.to_owned());
+    }
+}
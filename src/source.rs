@@ -0,0 +1,1112 @@
+//! This module defines the [ChunkSource] and [AsyncChunkSource] traits, which abstract over the
+//! origin of the data blocks given to the drivers of this crate. Implementing one of these traits
+//! allows plugging in arbitrary inputs, such as message queue consumers or object storage readers,
+//! without writing a new driver.
+//!
+//! Implementations are provided for all iterators and streams of [Result]s of data blocks, for
+//! readers and files via [ReaderSource], and for channels via [ChannelSource]. With the `tokio`
+//! feature, Tokio's `AsyncRead` is supported via [AsyncReaderSource], and with the `http-body`
+//! feature, bodies of HTTP requests and responses are supported via [HttpBodySource].
+//!
+//! The raw lines of a [ChunkSource] together with their byte ranges can be obtained via
+//! [ChunkSource::lines], which allows building an index of record offsets without parsing. Such an
+//! offset can later be passed to [ReaderSource::seek_to_record] to resume reading at that record.
+//!
+//! Files of unknown origin can be opened via [ReaderSource::open_detecting_encoding], which strips
+//! a UTF-8 byte order mark and rejects compressed data, see [ContentEncoding].
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::Cursor;
+//!
+//! use ndjson_stream::source::ReaderSource;
+//!
+//! let reader = Cursor::new("123\n456\n");
+//! let mut ndjson_iter =
+//!     ndjson_stream::from_chunk_source::<u32, _>(ReaderSource::with_chunk_size(reader, 3));
+//!
+//! assert!(matches!(ndjson_iter.next(), Some(Ok(123))));
+//! assert!(matches!(ndjson_iter.next(), Some(Ok(456))));
+//! assert!(ndjson_iter.next().is_none());
+//! ```
+//!
+//! # Message queues
+//!
+//! Consumers of message queues such as Kafka or NATS usually offer a stream of [Result]s of
+//! messages, which can be adapted by mapping every message to its payload. Since many producers
+//! send one record per message without a trailing line ending, it is advisable to terminate every
+//! payload with one, such that records of consecutive messages are not joined. Payloads which
+//! already end with a line ending then merely produce an additional empty line, which can be
+//! ignored via [EmptyLineHandling](crate::config::EmptyLineHandling).
+//!
+//! ```
+//! # #[cfg(feature = "stream")] {
+//! use futures::stream::{self, StreamExt};
+//! use ndjson_stream::config::{EmptyLineHandling, NdjsonConfig};
+//!
+//! // stand-in for the message type of a consumer library
+//! struct Message {
+//!     payload: Option<Vec<u8>>
+//! }
+//!
+//! let consumer = stream::iter(vec![
+//!     Ok::<_, String>(Message { payload: Some(b"1".to_vec()) }),
+//!     Ok(Message { payload: None }),
+//!     Ok(Message { payload: Some(b"2\n3\n".to_vec()) })
+//! ]);
+//! let chunks = consumer.map(|message| message.map(|message| {
+//!     let mut payload = message.payload.unwrap_or_default();
+//!     payload.push(b'\n');
+//!     payload
+//! }));
+//! let config = NdjsonConfig::default().with_empty_line_handling(EmptyLineHandling::IgnoreEmpty);
+//! let records = ndjson_stream::from_async_chunk_source_with_config::<u32, _>(chunks, config)
+//!     .map(Result::unwrap)
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(tokio_test::block_on(records), vec![1, 2, 3]);
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures::Stream;
+
+#[cfg(feature = "stream")]
+use pin_project_lite::pin_project;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, ReadBuf};
+
+#[cfg(feature = "http-body")]
+use futures::ready;
+
+#[cfg(feature = "http-body")]
+use http_body::Body;
+
+use crate::as_bytes::AsBytes;
+use crate::splitter::NdjsonLineSplitter;
+
+/// The default number of bytes read at once by a [ReaderSource] or [AsyncReaderSource].
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// The number of lines which an adaptively sized chunk should contain on average.
+const ADAPTIVE_LINES_PER_CHUNK: usize = 64;
+
+/// Determines the size of the chunks read by a [ReaderSource] or [AsyncReaderSource]. If the
+/// minimum and maximum size differ, the size is adapted to the average line length of the data read
+/// so far, such that a chunk holds about [ADAPTIVE_LINES_PER_CHUNK] lines. The statistics are
+/// halved regularly, such that the size follows changes in the line length.
+#[derive(Clone, Copy, Debug)]
+struct ChunkSizer {
+    current: usize,
+    min: usize,
+    max: usize,
+    bytes_seen: u64,
+    lines_seen: u64
+}
+
+impl ChunkSizer {
+
+    fn fixed(chunk_size: usize) -> ChunkSizer {
+        ChunkSizer::adaptive(chunk_size, chunk_size)
+    }
+
+    fn adaptive(min: usize, max: usize) -> ChunkSizer {
+        let min = min.max(1);
+        let max = max.max(min);
+
+        ChunkSizer {
+            current: DEFAULT_CHUNK_SIZE.clamp(min, max),
+            min,
+            max,
+            bytes_seen: 0,
+            lines_seen: 0
+        }
+    }
+
+    fn observe(&mut self, chunk: &[u8]) {
+        if self.min == self.max {
+            return;
+        }
+
+        self.bytes_seen += chunk.len() as u64;
+        self.lines_seen += chunk.iter().filter(|&&byte| byte == b'\n').count() as u64;
+
+        let target = match self.bytes_seen.checked_div(self.lines_seen) {
+            Some(average_line_length) => usize::try_from(average_line_length)
+                .unwrap_or(usize::MAX)
+                .saturating_mul(ADAPTIVE_LINES_PER_CHUNK),
+            None => self.current.saturating_mul(2)
+        };
+
+        self.current = target.clamp(self.min, self.max);
+
+        if self.lines_seen > 4 * ADAPTIVE_LINES_PER_CHUNK as u64
+                || self.bytes_seen > 4 * self.max as u64 {
+            self.bytes_seen /= 2;
+            self.lines_seen /= 2;
+        }
+    }
+}
+
+/// A synchronous source of data blocks, which can be parsed by the iterator-based drivers via
+/// [from_chunk_source](crate::from_chunk_source). This is implemented for all iterators over
+/// [Result]s of data blocks.
+pub trait ChunkSource {
+
+    /// The type of data blocks provided by this source.
+    type Chunk: AsBytes;
+
+    /// The type of errors which may occur while obtaining data blocks.
+    type Error;
+
+    /// Obtains the next data block from this source.
+    ///
+    /// # Returns
+    ///
+    /// `Some(Ok(chunk))` with the next data block, `Some(Err(error))` if obtaining the next data
+    /// block failed, or `None` if the source is exhausted.
+    fn next_chunk(&mut self) -> Option<Result<Self::Chunk, Self::Error>>;
+
+    /// Converts this source into an [Iterator] over its data blocks.
+    fn into_chunks(self) -> Chunks<Self>
+    where
+        Self: Sized
+    {
+        Chunks {
+            source: self
+        }
+    }
+
+    /// Converts this source into an [Iterator] over its raw lines, as split by an
+    /// [NdjsonLineSplitter], without deserializing them. See [LineSpans] for details.
+    fn lines(self) -> LineSpans<Self>
+    where
+        Self: Sized
+    {
+        LineSpans {
+            source: self,
+            splitter: NdjsonLineSplitter::new(),
+            lines: VecDeque::new(),
+            exhausted: false
+        }
+    }
+}
+
+impl<I, B, E> ChunkSource for I
+where
+    I: Iterator<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Chunk = B;
+    type Error = E;
+
+    fn next_chunk(&mut self) -> Option<Result<B, E>> {
+        self.next()
+    }
+}
+
+/// An [Iterator] over the data blocks of a [ChunkSource]. See [ChunkSource::into_chunks].
+pub struct Chunks<S> {
+    source: S
+}
+
+impl<S> Chunks<S> {
+
+    /// Gets a reference to the wrapped source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Gets a mutable reference to the wrapped source.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Unwraps this iterator, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S> Iterator for Chunks<S>
+where
+    S: ChunkSource
+{
+    type Item = Result<S::Chunk, S::Error>;
+
+    fn next(&mut self) -> Option<Result<S::Chunk, S::Error>> {
+        self.source.next_chunk()
+    }
+}
+
+/// An [Iterator] over the raw lines of a [ChunkSource], created by [ChunkSource::lines]. Every line
+/// is reported together with the range of bytes it occupies in the entire data of the source,
+/// excluding the line ending. Errors of the source are forwarded, after which iteration continues
+/// with the next data block.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use ndjson_stream::source::{ChunkSource, ReaderSource};
+///
+/// let lines = ReaderSource::with_chunk_size(Cursor::new("{}\n[1, 2]\n"), 4)
+///     .lines()
+///     .map(Result::unwrap)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(lines, vec![(0..2, b"{}".to_vec()), (3..9, b"[1, 2]".to_vec())]);
+/// ```
+pub struct LineSpans<S> {
+    source: S,
+    splitter: NdjsonLineSplitter,
+    lines: VecDeque<(Range<u64>, Vec<u8>)>,
+    exhausted: bool
+}
+
+impl<S> Iterator for LineSpans<S>
+where
+    S: ChunkSource
+{
+    type Item = Result<(Range<u64>, Vec<u8>), S::Error>;
+
+    fn next(&mut self) -> Option<Result<(Range<u64>, Vec<u8>), S::Error>> {
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Some(Ok(line));
+            }
+
+            if self.exhausted {
+                return None;
+            }
+
+            match self.source.next_chunk() {
+                Some(Ok(chunk)) => self.splitter.split_spans(chunk, |range, line|
+                    self.lines.push_back((range, line.to_vec()))),
+                Some(Err(error)) => return Some(Err(error)),
+                None => {
+                    self.exhausted = true;
+                    self.splitter.finalize_spans(|range, rest|
+                        self.lines.push_back((range, rest.to_vec())));
+                }
+            }
+        }
+    }
+}
+
+/// The UTF-8 byte order mark, which is stripped by [ReaderSource::open_detecting_encoding].
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// The maximum number of bytes required to detect a [ContentEncoding].
+const MAX_MAGIC_LEN: usize = 6;
+
+/// The encoding of the content of a file, as detected from its first bytes ("magic bytes") by
+/// [ContentEncoding::detect].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ContentEncoding {
+
+    /// Uncompressed data, which may start with a UTF-8 byte order mark.
+    Plain {
+
+        /// Indicates whether the data starts with a UTF-8 byte order mark.
+        has_bom: bool
+    },
+
+    /// Data compressed with gzip. Such a file may consist of several concatenated gzip members,
+    /// for example if it was produced by compressing the parts of a split file individually and
+    /// joining the results. A decoder which stops after the first member silently drops all
+    /// records after it, so decoders must be member-aware, such as `MultiGzDecoder` from the
+    /// `flate2` crate.
+    Gzip,
+
+    /// Data compressed with Zstandard.
+    Zstd,
+
+    /// Data compressed with xz.
+    Xz
+}
+
+impl ContentEncoding {
+
+    /// Detects the encoding of data starting with the given `prefix`. At most the first six bytes
+    /// are considered. Data which does not start with the magic bytes of a supported compression
+    /// format is considered [ContentEncoding::Plain].
+    pub fn detect(prefix: &[u8]) -> ContentEncoding {
+        if prefix.starts_with(b"\x1f\x8b") {
+            ContentEncoding::Gzip
+        }
+        else if prefix.starts_with(b"\x28\xb5\x2f\xfd") {
+            ContentEncoding::Zstd
+        }
+        else if prefix.starts_with(b"\xfd7zXZ\x00") {
+            ContentEncoding::Xz
+        }
+        else {
+            ContentEncoding::Plain {
+                has_bom: prefix.starts_with(UTF8_BOM)
+            }
+        }
+    }
+}
+
+/// Reads from the given `reader` until `buf` is filled or the reader is exhausted.
+///
+/// # Returns
+///
+/// The number of bytes read.
+fn read_prefix(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut length = 0;
+
+    while length < buf.len() {
+        match reader.read(&mut buf[length..]) {
+            Ok(0) => break,
+            Ok(read) => length += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => { },
+            Err(error) => return Err(error)
+        }
+    }
+
+    Ok(length)
+}
+
+/// A [ChunkSource] reading data blocks of a fixed maximum size from a wrapped [Read]. Interrupted
+/// reads are retried, while all other errors are forwarded. The source is exhausted once the
+/// reader reports the end of its data.
+pub struct ReaderSource<R> {
+    reader: R,
+    chunk_sizer: ChunkSizer,
+    exhausted: bool
+}
+
+impl<R> ReaderSource<R> {
+
+    /// Creates a new source reading up to [DEFAULT_CHUNK_SIZE] bytes at once from the given
+    /// `reader`.
+    pub fn new(reader: R) -> ReaderSource<R> {
+        ReaderSource::with_chunk_size(reader, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new source reading up to `chunk_size` bytes at once from the given `reader`. A
+    /// `chunk_size` of 0 is treated as 1.
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> ReaderSource<R> {
+        ReaderSource {
+            reader,
+            chunk_sizer: ChunkSizer::fixed(chunk_size),
+            exhausted: false
+        }
+    }
+
+    /// Creates a new source reading from the given `reader` in chunks whose size adapts to the
+    /// average length of the lines read so far, while staying between `min_chunk_size` and
+    /// `max_chunk_size`. This avoids many small reads for large records and large buffers for small
+    /// ones. A `min_chunk_size` of 0 is treated as 1 and a `max_chunk_size` below the minimum as
+    /// the minimum.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::source::ReaderSource;
+    /// use std::io::Cursor;
+    ///
+    /// let data = format!("{{\"text\":\"{}\"}}\n", "a".repeat(1000)).repeat(100);
+    /// let source = ReaderSource::with_adaptive_chunk_size(Cursor::new(data), 1024, 1024 * 1024);
+    /// let records = ndjson_stream::from_chunk_source::<serde_json::Value, _>(source)
+    ///     .map(Result::unwrap)
+    ///     .count();
+    ///
+    /// assert_eq!(records, 100);
+    /// ```
+    pub fn with_adaptive_chunk_size(reader: R, min_chunk_size: usize, max_chunk_size: usize)
+            -> ReaderSource<R> {
+        ReaderSource {
+            reader,
+            chunk_sizer: ChunkSizer::adaptive(min_chunk_size, max_chunk_size),
+            exhausted: false
+        }
+    }
+
+    /// Gets the maximum number of bytes which will be read for the next chunk.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_sizer.current
+    }
+
+    /// Gets a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the wrapped reader. Note that data read from it directly is not
+    /// seen by this source.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this source, returning the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> ReaderSource<R>
+where
+    R: Seek
+{
+
+    /// Moves the wrapped reader to the given absolute byte `offset`, such that the next data block
+    /// starts there. This is intended to be used with the start of a range reported by
+    /// [ChunkSource::lines], in order to resume parsing at the corresponding record. A source which
+    /// was already exhausted can be read again afterwards.
+    ///
+    /// Note that any driver consuming this source must be created anew after seeking, since it
+    /// may still hold the incomplete rest of a line from before.
+    ///
+    /// # Errors
+    ///
+    /// If seeking the wrapped reader fails.
+    pub fn seek_to_record(&mut self, offset: u64) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.exhausted = false;
+        Ok(())
+    }
+}
+
+impl ReaderSource<File> {
+
+    /// Opens the file at the given `path` and creates a new source reading up to
+    /// [DEFAULT_CHUNK_SIZE] bytes at once from it.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<ReaderSource<File>> {
+        Ok(ReaderSource::new(File::open(path)?))
+    }
+
+    /// Opens the file at the given `path` like [ReaderSource::open], but first detects its
+    /// [ContentEncoding] from its first bytes. A UTF-8 byte order mark at the start of the file is
+    /// skipped, such that it does not end up in the first line. Offsets reported by
+    /// [ChunkSource::lines] are then relative to the first byte after it.
+    ///
+    /// This crate does not depend on any decompression library, so compressed files are rejected
+    /// with an error instead of being parsed as garbage. Such files can be wrapped in a decoder by
+    /// the caller and supplied via [ReaderSource::new]. For gzip, the decoder must support
+    /// concatenated members, see [ContentEncoding::Gzip]. Since the decoded data of consecutive
+    /// members forms a single stream, a line which spans a member boundary is joined as usual.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or read, or with [ErrorKind::InvalidData] if it is compressed.
+    pub fn open_detecting_encoding(path: impl AsRef<Path>) -> io::Result<ReaderSource<File>> {
+        let mut file = File::open(path)?;
+        let mut prefix = [0; MAX_MAGIC_LEN];
+        let prefix_len = read_prefix(&mut file, &mut prefix)?;
+        let start = match ContentEncoding::detect(&prefix[..prefix_len]) {
+            ContentEncoding::Plain { has_bom: false } => 0,
+            ContentEncoding::Plain { has_bom: true } => UTF8_BOM.len() as u64,
+            encoding => return Err(io::Error::new(ErrorKind::InvalidData,
+                format!("unsupported compressed content: {encoding:?}")))
+        };
+
+        file.seek(SeekFrom::Start(start))?;
+        Ok(ReaderSource::new(file))
+    }
+}
+
+impl<R> ChunkSource for ReaderSource<R>
+where
+    R: Read
+{
+    type Chunk = Vec<u8>;
+    type Error = io::Error;
+
+    fn next_chunk(&mut self) -> Option<io::Result<Vec<u8>>> {
+        if self.exhausted {
+            return None;
+        }
+
+        let mut chunk = vec![0; self.chunk_sizer.current];
+
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.exhausted = true;
+                    return None;
+                },
+                Ok(length) => {
+                    chunk.truncate(length);
+                    self.chunk_sizer.observe(&chunk);
+                    return Some(Ok(chunk));
+                },
+                Err(error) if error.kind() == ErrorKind::Interrupted => { },
+                Err(error) => return Some(Err(error))
+            }
+        }
+    }
+}
+
+/// A [ChunkSource] receiving data blocks from a [Receiver]. The source is exhausted once all
+/// senders have been dropped.
+pub struct ChannelSource<B> {
+    receiver: Receiver<B>
+}
+
+impl<B> ChannelSource<B> {
+
+    /// Creates a new source receiving data blocks from the given `receiver`.
+    pub fn new(receiver: Receiver<B>) -> ChannelSource<B> {
+        ChannelSource {
+            receiver
+        }
+    }
+}
+
+impl<B> ChunkSource for ChannelSource<B>
+where
+    B: AsBytes
+{
+    type Chunk = B;
+    type Error = Infallible;
+
+    fn next_chunk(&mut self) -> Option<Result<B, Infallible>> {
+        self.receiver.recv().ok().map(Ok)
+    }
+}
+
+/// An asynchronous source of data blocks, which can be parsed by the stream-based drivers via
+/// [from_async_chunk_source](crate::from_async_chunk_source). This is implemented for all streams
+/// over [Result]s of data blocks.
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+pub trait AsyncChunkSource {
+
+    /// The type of data blocks provided by this source.
+    type Chunk: AsBytes;
+
+    /// The type of errors which may occur while obtaining data blocks.
+    type Error;
+
+    /// Attempts to obtain the next data block from this source, registering the current task for
+    /// wakeup if none is available yet.
+    ///
+    /// # Returns
+    ///
+    /// `Poll::Pending` if no data block is available yet, and otherwise the same values as
+    /// [ChunkSource::next_chunk].
+    fn poll_next_chunk(self: Pin<&mut Self>, cx: &mut Context<'_>)
+        -> Poll<Option<Result<Self::Chunk, Self::Error>>>;
+
+    /// Converts this source into a [Stream] over its data blocks.
+    fn into_chunk_stream(self) -> ChunkStream<Self>
+    where
+        Self: Sized
+    {
+        ChunkStream {
+            source: self
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S, B, E> AsyncChunkSource for S
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsBytes
+{
+    type Chunk = B;
+    type Error = E;
+
+    fn poll_next_chunk(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<Result<B, E>>> {
+        self.poll_next(cx)
+    }
+}
+
+#[cfg(feature = "stream")]
+pin_project! {
+    /// A [Stream] over the data blocks of an [AsyncChunkSource]. See
+    /// [AsyncChunkSource::into_chunk_stream].
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+    pub struct ChunkStream<S> {
+        #[pin]
+        source: S
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> ChunkStream<S> {
+
+    /// Gets a reference to the wrapped source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Gets a mutable reference to the wrapped source.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Unwraps this stream, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> Stream for ChunkStream<S>
+where
+    S: AsyncChunkSource
+{
+    type Item = Result<S::Chunk, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().source.poll_next_chunk(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+pin_project! {
+    /// An [AsyncChunkSource] reading data blocks of a fixed maximum size from a wrapped Tokio
+    /// [AsyncRead], such as a file or socket. Interrupted reads are retried, while all other errors
+    /// are forwarded. The source is exhausted once the reader reports the end of its data.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+    pub struct AsyncReaderSource<R> {
+        #[pin]
+        reader: R,
+        chunk: Vec<u8>,
+        chunk_sizer: ChunkSizer,
+        exhausted: bool
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> AsyncReaderSource<R> {
+
+    /// Creates a new source reading up to [DEFAULT_CHUNK_SIZE] bytes at once from the given
+    /// `reader`.
+    pub fn new(reader: R) -> AsyncReaderSource<R> {
+        AsyncReaderSource::with_chunk_size(reader, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new source reading up to `chunk_size` bytes at once from the given `reader`. A
+    /// `chunk_size` of 0 is treated as 1.
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> AsyncReaderSource<R> {
+        AsyncReaderSource {
+            reader,
+            chunk: Vec::new(),
+            chunk_sizer: ChunkSizer::fixed(chunk_size),
+            exhausted: false
+        }
+    }
+
+    /// Creates a new source reading from the given `reader` in chunks whose size adapts to the
+    /// average length of the lines read so far, while staying between `min_chunk_size` and
+    /// `max_chunk_size`. See [ReaderSource::with_adaptive_chunk_size] for more details.
+    pub fn with_adaptive_chunk_size(reader: R, min_chunk_size: usize, max_chunk_size: usize)
+            -> AsyncReaderSource<R> {
+        AsyncReaderSource {
+            reader,
+            chunk: Vec::new(),
+            chunk_sizer: ChunkSizer::adaptive(min_chunk_size, max_chunk_size),
+            exhausted: false
+        }
+    }
+
+    /// Gets the maximum number of bytes which will be read for the next chunk.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_sizer.current
+    }
+
+    /// Gets a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps this source, returning the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> AsyncChunkSource for AsyncReaderSource<R>
+where
+    R: AsyncRead
+{
+    type Chunk = Vec<u8>;
+    type Error = io::Error;
+
+    fn poll_next_chunk(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<io::Result<Vec<u8>>>> {
+        let mut this = self.project();
+
+        if *this.exhausted {
+            return Poll::Ready(None);
+        }
+
+        this.chunk.resize(this.chunk_sizer.current, 0);
+
+        loop {
+            let mut read_buf = ReadBuf::new(&mut this.chunk[..]);
+
+            match this.reader.as_mut().poll_read(cx, &mut read_buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(())) if read_buf.filled().is_empty() => {
+                    *this.exhausted = true;
+                    return Poll::Ready(None);
+                },
+                Poll::Ready(Ok(())) => {
+                    let chunk = read_buf.filled().to_vec();
+                    this.chunk_sizer.observe(&chunk);
+                    return Poll::Ready(Some(Ok(chunk)));
+                },
+                Poll::Ready(Err(error)) if error.kind() == ErrorKind::Interrupted => { },
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error)))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "http-body")]
+pin_project! {
+    /// An [AsyncChunkSource] providing the data frames of a wrapped `http_body::Body`, such as the
+    /// body of a hyper request or response. Trailers are skipped, while errors of the body are
+    /// forwarded. See [from_http_body](crate::from_http_body) for parsing a body directly.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "http-body")))]
+    pub struct HttpBodySource<B> {
+        #[pin]
+        body: B
+    }
+}
+
+#[cfg(feature = "http-body")]
+impl<B> HttpBodySource<B> {
+
+    /// Creates a new source providing the data frames of the given `body`.
+    pub fn new(body: B) -> HttpBodySource<B> {
+        HttpBodySource {
+            body
+        }
+    }
+
+    /// Gets a reference to the wrapped body.
+    pub fn get_ref(&self) -> &B {
+        &self.body
+    }
+
+    /// Unwraps this source, returning the wrapped body.
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+#[cfg(feature = "http-body")]
+impl<B> AsyncChunkSource for HttpBodySource<B>
+where
+    B: Body,
+    B::Data: AsBytes
+{
+    type Chunk = B::Data;
+    type Error = B::Error;
+
+    fn poll_next_chunk(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<Result<B::Data, B::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            match ready!(this.body.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => {
+                    if let Ok(data) = frame.into_data() {
+                        return Poll::Ready(Some(Ok(data)));
+                    }
+                },
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => return Poll::Ready(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use std::io::Cursor;
+    use std::sync::mpsc;
+    use std::thread;
+
+    use super::*;
+
+    struct InterruptingReader {
+        interrupted: bool,
+        data: Cursor<&'static str>
+    }
+
+    impl Read for InterruptingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if !self.interrupted {
+                self.interrupted = true;
+                return Err(io::Error::from(ErrorKind::Interrupted));
+            }
+
+            self.data.read(buf)
+        }
+    }
+
+    #[test]
+    fn iterator_is_chunk_source() {
+        let chunks = vec![Ok::<_, &str>("a"), Err("error"), Ok("b")].into_iter()
+            .into_chunks()
+            .collect::<Vec<_>>();
+
+        assert_that!(chunks).contains_exactly_in_given_order([Ok("a"), Err("error"), Ok("b")]);
+    }
+
+    #[test]
+    fn reader_source_reads_chunks_of_given_size() {
+        let chunks = ReaderSource::with_chunk_size(Cursor::new("abcde"), 2)
+            .into_chunks()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(chunks)
+            .contains_exactly_in_given_order([b"ab".to_vec(), b"cd".to_vec(), b"e".to_vec()]);
+    }
+
+    #[test]
+    fn reader_source_retries_interrupted_reads() {
+        let reader = InterruptingReader {
+            interrupted: false,
+            data: Cursor::new("abc")
+        };
+        let chunks = ReaderSource::new(reader)
+            .into_chunks()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(chunks).contains_exactly_in_given_order([b"abc".to_vec()]);
+    }
+
+    #[test]
+    fn channel_source_is_exhausted_once_senders_are_dropped() {
+        let (sender, receiver) = mpsc::channel();
+        let producer = thread::spawn(move || {
+            sender.send("a".to_owned()).unwrap();
+            sender.send("b".to_owned()).unwrap();
+        });
+        let chunks = ChannelSource::new(receiver)
+            .into_chunks()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        producer.join().unwrap();
+
+        assert_that!(chunks).contains_exactly_in_given_order(["a".to_owned(), "b".to_owned()]);
+    }
+
+    #[test]
+    fn lines_reports_ranges_across_chunks_and_forwards_errors() {
+        let lines = vec![Ok("a\nb"), Err("error"), Ok("c\n\nd")].into_iter()
+            .lines()
+            .collect::<Vec<_>>();
+
+        assert_that!(lines).contains_exactly_in_given_order([
+            Ok((0..1, b"a".to_vec())),
+            Err("error"),
+            Ok((2..4, b"bc".to_vec())),
+            Ok((5..5, Vec::new())),
+            Ok((6..7, b"d".to_vec()))
+        ]);
+    }
+
+    #[cfg(feature = "iter")]
+    #[test]
+    fn seek_to_record_resumes_reading_at_indexed_line() {
+        use crate::test_util::TestStruct;
+
+        let data = "{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n";
+        let offsets = ReaderSource::with_chunk_size(Cursor::new(data), 5)
+            .lines()
+            .map(|line| line.unwrap().0.start)
+            .collect::<Vec<_>>();
+        let mut source = ReaderSource::new(Cursor::new(data));
+
+        source.next_chunk();
+        source.next_chunk();
+        source.seek_to_record(offsets[1]).unwrap();
+
+        let records = crate::from_chunk_source::<TestStruct, _>(source)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(records).contains_exactly_in_given_order([TestStruct { key: 3, value: 4 }]);
+    }
+
+    #[test]
+    fn content_encoding_is_detected_from_magic_bytes() {
+        assert_that!(ContentEncoding::detect(b"\x1f\x8b\x08\x00"))
+            .is_equal_to(ContentEncoding::Gzip);
+        assert_that!(ContentEncoding::detect(b"\x28\xb5\x2f\xfd\x00"))
+            .is_equal_to(ContentEncoding::Zstd);
+        assert_that!(ContentEncoding::detect(b"\xfd7zXZ\x00"))
+            .is_equal_to(ContentEncoding::Xz);
+        assert_that!(ContentEncoding::detect(b"\xef\xbb\xbf{}"))
+            .is_equal_to(ContentEncoding::Plain { has_bom: true });
+        assert_that!(ContentEncoding::detect(b"{}"))
+            .is_equal_to(ContentEncoding::Plain { has_bom: false });
+        assert_that!(ContentEncoding::detect(b""))
+            .is_equal_to(ContentEncoding::Plain { has_bom: false });
+    }
+
+    fn temp_file(name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("ndjson-stream-{}-{name}", std::process::id()));
+
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn open_detecting_encoding_strips_bom() {
+        let path = temp_file("bom.ndjson", b"\xef\xbb\xbf1\n2\n");
+        let chunks = ReaderSource::open_detecting_encoding(&path).unwrap()
+            .into_chunks()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_that!(chunks).contains_exactly_in_given_order([b"1\n2\n".to_vec()]);
+    }
+
+    #[test]
+    fn open_detecting_encoding_rejects_compressed_file() {
+        let path = temp_file("data.ndjson.gz", b"\x1f\x8b\x08\x00\x00\x00");
+        let result = ReaderSource::open_detecting_encoding(&path);
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_that!(result.err().map(|error| error.kind())).contains(ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn adaptive_chunk_size_grows_for_long_lines_and_shrinks_for_short_ones() {
+        let data = format!("{}\n", "a".repeat(999)).repeat(16) + &"a\n".repeat(10000);
+        let mut source = ReaderSource::with_adaptive_chunk_size(Cursor::new(data), 16, 32 * 1024);
+        let initial_chunk_size = source.chunk_size();
+
+        source.next_chunk().unwrap().unwrap();
+
+        assert_that!(source.chunk_size()).is_greater_than(initial_chunk_size);
+
+        while source.next_chunk().is_some() { }
+
+        assert_that!(source.chunk_size()).is_less_than(initial_chunk_size);
+    }
+
+    #[test]
+    fn adaptive_chunk_size_stays_within_limits() {
+        let mut source = ReaderSource::with_adaptive_chunk_size(
+            Cursor::new("a".repeat(100_000)), 0, 20_000);
+
+        while source.next_chunk().is_some() { }
+
+        assert_that!(source.chunk_size()).is_equal_to(20_000);
+
+        let mut source =
+            ReaderSource::with_adaptive_chunk_size(Cursor::new("\n".repeat(100)), 50, 10);
+
+        source.next_chunk().unwrap().unwrap();
+
+        assert_that!(source.chunk_size()).is_equal_to(50);
+    }
+}
+
+#[cfg(all(test, feature = "http-body"))]
+mod http_body_tests {
+
+    use std::collections::VecDeque;
+
+    use bytes::Bytes;
+
+    use futures::stream::StreamExt;
+
+    use http_body::Frame;
+
+    use kernal::prelude::*;
+
+    use crate::fallible::FallibleNdjsonError;
+
+    use super::*;
+
+    struct FrameBody(VecDeque<Result<Frame<Bytes>, &'static str>>);
+
+    impl Body for FrameBody {
+        type Data = Bytes;
+        type Error = &'static str;
+
+        fn poll_frame(mut self: Pin<&mut Self>, _: &mut Context<'_>)
+                -> Poll<Option<Result<Frame<Bytes>, &'static str>>> {
+            Poll::Ready(self.0.pop_front())
+        }
+    }
+
+    #[test]
+    fn body_data_is_parsed_and_trailers_are_skipped() {
+        let body = FrameBody(VecDeque::from([
+            Ok(Frame::data(Bytes::from_static(b"1\n2"))),
+            Err("error"),
+            Ok(Frame::data(Bytes::from_static(b"3\n4"))),
+            Ok(Frame::trailers(http::HeaderMap::new()))
+        ]));
+        let results =
+            tokio_test::block_on(crate::from_http_body::<u32, _>(body).collect::<Vec<_>>());
+
+        assert_that!(results).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(1),
+            |it| assert_that!(matches!(it, Err(FallibleNdjsonError::InputError("error"))))
+                .is_true(),
+            |it| assert_that!(it).contains_value(23),
+            |it| assert_that!(it).contains_value(4)
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tokio_tests {
+
+    use futures::StreamExt;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn async_reader_source_reads_chunks_of_given_size() {
+        let chunks = tokio_test::block_on(
+            AsyncReaderSource::with_chunk_size(b"abcde".as_slice(), 2)
+                .into_chunk_stream()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>());
+
+        assert_that!(chunks)
+            .contains_exactly_in_given_order([b"ab".to_vec(), b"cd".to_vec(), b"e".to_vec()]);
+    }
+}
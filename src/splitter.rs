@@ -0,0 +1,953 @@
+//! This module contains the [NdjsonLineSplitter], which splits data given in chunks into lines
+//! without parsing them. It is the same logic used by the
+//! [NdjsonEngine](crate::engine::NdjsonEngine) and can be used to process NDJSON-data with a
+//! different JSON library.
+
+use alloc::vec::Vec;
+
+use core::fmt::{self, Debug, Formatter};
+#[cfg(feature = "bytes")]
+use core::mem;
+use core::ops::{Deref, Range};
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+use memchr::{memchr, memchr2, memchr_iter};
+
+use crate::as_bytes::AsBytes;
+use crate::config::LineEnding;
+
+const NEW_LINE: u8 = b'\n';
+const CARRIAGE_RETURN: u8 = b'\r';
+
+/// Gets the length of the first segment of `data` which ends with a byte that may terminate a line
+/// for some [LineEnding], i.e. `\n` or `\r`, or the length of `data` if there is no such byte.
+/// Giving the segments one by one to a splitter completes every line with the last segment it
+/// spans, whichever line ending is used.
+#[cfg(any(feature = "iter", feature = "stream"))]
+pub(crate) fn first_segment_len(data: &[u8]) -> usize {
+    memchr2(NEW_LINE, CARRIAGE_RETURN, data).map_or(data.len(), |index| index + 1)
+}
+
+/// The number of bytes of an incomplete line which a [RestBuffer] stores without allocating.
+const INLINE_REST_CAPACITY: usize = 256;
+
+/// The buffer for the incomplete line at the end of a chunk. Since most lines which straddle chunk
+/// boundaries are short, the first [INLINE_REST_CAPACITY] bytes are stored inline. Longer lines
+/// spill to the heap, whose allocation is then reused for all subsequent lines.
+#[derive(Clone)]
+struct RestBuffer {
+    inline: [u8; INLINE_REST_CAPACITY],
+    inline_len: usize,
+
+    /// The spilled data. As long as this has a non-zero capacity, it is used instead of `inline`.
+    heap: Vec<u8>
+}
+
+impl RestBuffer {
+
+    fn is_spilled(&self) -> bool {
+        self.heap.capacity() > 0
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        if self.is_spilled() {
+            self.heap.extend_from_slice(data);
+        }
+        else if self.inline_len + data.len() <= INLINE_REST_CAPACITY {
+            self.inline[self.inline_len..(self.inline_len + data.len())].copy_from_slice(data);
+            self.inline_len += data.len();
+        }
+        else {
+            self.heap = Vec::with_capacity(self.inline_len + data.len());
+            self.heap.extend_from_slice(&self.inline[..self.inline_len]);
+            self.heap.extend_from_slice(data);
+            self.inline_len = 0;
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if self.is_spilled() {
+            self.heap.truncate(len);
+        }
+        else {
+            self.inline_len = self.inline_len.min(len);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    fn pop(&mut self) {
+        self.truncate(self.len().saturating_sub(1));
+    }
+
+    /// Moves the content of this buffer into a vector, leaving this buffer empty. If the buffer is
+    /// spilled, its allocation is moved, otherwise the inline bytes are copied.
+    #[cfg(feature = "bytes")]
+    fn take_vec(&mut self) -> Vec<u8> {
+        if self.is_spilled() {
+            mem::take(&mut self.heap)
+        }
+        else {
+            let vec = self.inline[..self.inline_len].to_vec();
+            self.inline_len = 0;
+            vec
+        }
+    }
+}
+
+impl Default for RestBuffer {
+    fn default() -> RestBuffer {
+        RestBuffer {
+            inline: [0; INLINE_REST_CAPACITY],
+            inline_len: 0,
+            heap: Vec::new()
+        }
+    }
+}
+
+impl Deref for RestBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.is_spilled() {
+            &self.heap
+        }
+        else {
+            &self.inline[..self.inline_len]
+        }
+    }
+}
+
+impl Debug for RestBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The location of the end of a line within a chunk of data.
+struct LineEnd {
+
+    /// The number of bytes in the chunk which belong to the line.
+    line_len: usize,
+
+    /// The number of bytes in the chunk which belong to the separator after the line.
+    separator_len: usize,
+
+    /// Whether the last byte of the buffered rest belongs to the separator rather than the line.
+    /// This happens if a `\r\n` separator is split between two chunks.
+    separator_in_rest: bool
+}
+
+impl LineEnd {
+    fn new(line_len: usize, separator_len: usize) -> LineEnd {
+        LineEnd {
+            line_len,
+            separator_len,
+            separator_in_rest: false
+        }
+    }
+}
+
+/// A line completed by a chunk of data, as reported by [NdjsonLineSplitter::split_with].
+enum Line<'data> {
+
+    /// The line lies entirely within the chunk, at the given range.
+    InChunk(Range<usize>),
+
+    /// The line started in an earlier chunk and was joined in the buffered rest, which is cleared
+    /// afterwards.
+    Joined(&'data mut RestBuffer),
+
+    /// The line exceeds the maximum line length. Only the first bytes of the line are retained.
+    Oversized {
+        prefix: &'data [u8],
+        length: usize
+    }
+}
+
+/// A line reported to the [NdjsonEngine](crate::engine::NdjsonEngine) by
+/// [NdjsonLineSplitter::split_lines].
+pub(crate) enum SplitLine<'data> {
+
+    /// A line within the maximum line length, without the line ending.
+    Complete(&'data [u8]),
+
+    /// A line exceeding the maximum line length, of which only the first bytes are retained.
+    Oversized {
+        prefix: &'data [u8],
+        length: usize
+    }
+}
+
+impl<'data> SplitLine<'data> {
+    fn resolve(data: &'data [u8], line: Line<'data>) -> SplitLine<'data> {
+        match line {
+            Line::InChunk(range) => SplitLine::Complete(&data[range]),
+            Line::Joined(rest) => SplitLine::Complete(rest),
+            Line::Oversized { prefix, length } => SplitLine::Oversized { prefix, length }
+        }
+    }
+}
+
+/// The state of a line which exceeds the maximum line length and whose remainder is discarded.
+#[derive(Clone, Debug)]
+struct OversizedLine {
+
+    /// The number of bytes in the line so far, including a potential `\r` at the end which may
+    /// still turn out to belong to a `\r\n` separator.
+    length: usize,
+
+    /// Whether the last discarded byte is a `\r`.
+    ends_with_carriage_return: bool
+}
+
+/// Splits data supplied in chunks into lines, independent of the JSON parser. By default, lines are
+/// segments between `\n` characters, but other line endings can be configured via
+/// [NdjsonLineSplitter::with_line_ending]. Lines may span several chunks, in which case the partial
+/// line at the end of a chunk is stored in an internal buffer until its remainder arrives.
+///
+/// Lines are reported without the terminating line ending. With the default [LineEnding::Lf], any
+/// `\r` character preceding the `\n` is kept as part of the line.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::splitter::NdjsonLineSplitter;
+///
+/// let mut splitter = NdjsonLineSplitter::new();
+///
+/// assert_eq!(splitter.split_owned("123\n4"), vec![b"123".to_vec()]);
+/// assert_eq!(splitter.split_owned("56\n789\n"), vec![b"456".to_vec(), b"789".to_vec()]);
+/// assert_eq!(splitter.rest(), b"");
+/// ```
+///
+/// With the `bytes` feature, [NdjsonLineSplitter::split_bytes] offers zero-copy splitting of
+/// [Bytes] chunks.
+#[derive(Clone, Debug, Default)]
+pub struct NdjsonLineSplitter {
+    rest: RestBuffer,
+
+    /// The rest of a [Bytes] chunk, which is retained without copying as long as no further data
+    /// arrives. At most one of this and `rest` is non-empty.
+    #[cfg(feature = "bytes")]
+    shared_rest: Bytes,
+    line_ending: LineEnding,
+    skip_new_line: bool,
+    max_line_length: Option<usize>,
+    oversized_line_prefix_len: usize,
+
+    /// If the current line exceeds the maximum line length, its state. In that case, `rest` holds
+    /// only the first bytes of the line.
+    oversized_line: Option<OversizedLine>,
+
+    /// The number of bytes given to this splitter before the current chunk.
+    consumed: u64,
+
+    /// The offset of the first byte of the current line in all data given to this splitter.
+    line_start: u64
+}
+
+impl NdjsonLineSplitter {
+
+    /// Creates a new line splitter with an empty buffer which splits lines at `\n` characters.
+    pub fn new() -> NdjsonLineSplitter {
+        NdjsonLineSplitter::default()
+    }
+
+    /// Creates a new line splitter with an empty buffer which splits lines at the given
+    /// [LineEnding].
+    pub fn with_line_ending(line_ending: LineEnding) -> NdjsonLineSplitter {
+        NdjsonLineSplitter {
+            line_ending,
+            ..NdjsonLineSplitter::default()
+        }
+    }
+
+    /// Limits the length of lines to `max_line_length` bytes. Longer lines are not buffered
+    /// beyond their first `prefix_len` bytes and are reported as [SplitLine::Oversized].
+    pub(crate) fn set_max_line_length(&mut self, max_line_length: usize, prefix_len: usize) {
+        self.max_line_length = Some(max_line_length);
+        self.oversized_line_prefix_len = prefix_len.min(max_line_length);
+    }
+
+    fn exceeds_max_line_length(&self, length: usize) -> bool {
+        self.max_line_length.is_some_and(|max_line_length| length > max_line_length)
+    }
+
+    fn rest_ends_with_carriage_return(&self) -> bool {
+        match &self.oversized_line {
+            Some(oversized_line) => oversized_line.ends_with_carriage_return,
+            None => self.rest.last() == Some(&CARRIAGE_RETURN)
+        }
+    }
+
+    fn find_line_end(&self, data: &[u8]) -> Option<LineEnd> {
+        match self.line_ending {
+            LineEnding::Lf => memchr(NEW_LINE, data).map(|index| LineEnd::new(index, 1)),
+            LineEnding::Cr => memchr(CARRIAGE_RETURN, data).map(|index| LineEnd::new(index, 1)),
+            LineEnding::CrLf => {
+                let mut search_start = 0;
+
+                loop {
+                    let index = search_start + memchr(NEW_LINE, &data[search_start..])?;
+
+                    if index > 0 && data[index - 1] == CARRIAGE_RETURN {
+                        return Some(LineEnd::new(index - 1, 2));
+                    }
+
+                    if index == 0 && self.rest_ends_with_carriage_return() {
+                        return Some(LineEnd {
+                            line_len: 0,
+                            separator_len: 1,
+                            separator_in_rest: true
+                        });
+                    }
+
+                    search_start = index + 1;
+                }
+            },
+            LineEnding::Any => {
+                let index = memchr2(NEW_LINE, CARRIAGE_RETURN, data)?;
+
+                if data[index] == CARRIAGE_RETURN && data.get(index + 1) == Some(&NEW_LINE) {
+                    Some(LineEnd::new(index, 2))
+                }
+                else {
+                    Some(LineEnd::new(index, 1))
+                }
+            }
+        }
+    }
+
+    /// Returns the separator byte if every line ending is a single, fixed byte and no partial line
+    /// is buffered, in which case [NdjsonLineSplitter::split_in_chunk] can be used.
+    fn fast_path_separator(&self) -> Option<u8> {
+        if !self.rest.is_empty() || self.oversized_line.is_some() {
+            return None;
+        }
+
+        match self.line_ending {
+            LineEnding::Lf => Some(NEW_LINE),
+            LineEnding::Cr => Some(CARRIAGE_RETURN),
+            LineEnding::CrLf | LineEnding::Any => None
+        }
+    }
+
+    /// Reports all lines in `data` after `offset` which end with the given `separator`, without
+    /// consulting the rest buffer. This iterates over all separators in a single pass.
+    ///
+    /// # Returns
+    ///
+    /// The index in `data` at which the incomplete rest starts.
+    fn split_in_chunk(&mut self, data: &[u8], mut offset: usize, separator: u8,
+            on_line: &mut impl FnMut(Line<'_>, u64)) -> usize {
+        let search_start = offset;
+
+        for index in memchr_iter(separator, &data[search_start..]) {
+            let line_range = offset..(search_start + index);
+
+            if self.exceeds_max_line_length(line_range.len()) {
+                let prefix_len = self.oversized_line_prefix_len;
+                let prefix = &data[line_range.start..(line_range.start + prefix_len)];
+
+                on_line(Line::Oversized { prefix, length: line_range.len() }, self.line_start);
+            }
+            else {
+                on_line(Line::InChunk(line_range.clone()), self.line_start);
+            }
+
+            offset = line_range.end + 1;
+            self.line_start = self.consumed + offset as u64;
+        }
+
+        offset
+    }
+
+    /// Moves a retained rest of a [Bytes] chunk into the owned buffer.
+    fn unshare_rest(&mut self) {
+        #[cfg(feature = "bytes")]
+        if !self.shared_rest.is_empty() {
+            self.rest.extend_from_slice(&mem::take(&mut self.shared_rest));
+        }
+    }
+
+    /// Splits the given data into lines and calls `on_line` for every completed line together
+    /// with the offset of its first byte, in order.
+    ///
+    /// # Returns
+    ///
+    /// The index in `data` at which the incomplete rest starts, which the caller must store via
+    /// [NdjsonLineSplitter::store_rest].
+    fn split_with(&mut self, data: &[u8], mut on_line: impl FnMut(Line<'_>, u64)) -> usize {
+        self.unshare_rest();
+
+        let mut offset = 0;
+
+        if self.skip_new_line && !data.is_empty() {
+            // The previous chunk ended with a `\r`, which was already treated as a line ending. If
+            // it is part of a `\r\n` sequence, the `\n` must not end another line.
+            self.skip_new_line = false;
+
+            if data[0] == NEW_LINE {
+                offset = 1;
+                self.line_start += 1;
+            }
+        }
+
+        loop {
+            if let Some(separator) = self.fast_path_separator() {
+                // Only the first line of a chunk can continue the rest, so once it is empty, all
+                // remaining lines lie within the chunk.
+                offset = self.split_in_chunk(data, offset, separator, &mut on_line);
+                break;
+            }
+
+            let Some(line_end) = self.find_line_end(&data[offset..])
+            else {
+                break;
+            };
+            let line_range = offset..(offset + line_end.line_len);
+            let line_start = self.line_start;
+
+            if line_end.separator_in_rest {
+                match &mut self.oversized_line {
+                    Some(oversized_line) => oversized_line.length -= 1,
+                    None => self.rest.pop()
+                }
+            }
+
+            if let Some(oversized_line) = self.oversized_line.take() {
+                let length = oversized_line.length + line_range.len();
+
+                on_line(Line::Oversized { prefix: &self.rest, length }, line_start);
+                self.rest.clear();
+            }
+            else if self.rest.is_empty() {
+                if self.exceeds_max_line_length(line_range.len()) {
+                    let prefix_len = self.oversized_line_prefix_len;
+                    let prefix = &data[line_range.start..(line_range.start + prefix_len)];
+
+                    on_line(Line::Oversized { prefix, length: line_range.len() }, line_start);
+                }
+                else {
+                    on_line(Line::InChunk(line_range.clone()), line_start);
+                }
+            }
+            else {
+                let length = self.rest.len() + line_range.len();
+
+                if self.exceeds_max_line_length(length) {
+                    let prefix_len = self.oversized_line_prefix_len;
+                    let missing_prefix_len = prefix_len.saturating_sub(self.rest.len());
+
+                    self.rest.truncate(prefix_len);
+                    self.rest.extend_from_slice(&data[line_range.clone()][..missing_prefix_len]);
+                    on_line(Line::Oversized { prefix: &self.rest, length }, line_start);
+                }
+                else {
+                    self.rest.extend_from_slice(&data[line_range.clone()]);
+                    on_line(Line::Joined(&mut self.rest), line_start);
+                }
+
+                self.rest.clear();
+            }
+
+            let ends_with_carriage_return = data.get(line_range.end) == Some(&CARRIAGE_RETURN)
+                && line_end.separator_len == 1;
+
+            offset = line_range.end + line_end.separator_len;
+            self.line_start = self.consumed + offset as u64;
+
+            if self.line_ending == LineEnding::Any && ends_with_carriage_return
+                    && offset == data.len() {
+                self.skip_new_line = true;
+            }
+        }
+
+        self.consumed += data.len() as u64;
+        offset
+    }
+
+    /// Stores the given incomplete rest of a chunk in the internal buffer. If the current line
+    /// thereby exceeds the maximum line length, only its first bytes are retained.
+    fn store_rest(&mut self, rest: &[u8]) {
+        if rest.is_empty() {
+            return;
+        }
+
+        let ends_with_carriage_return = rest.last() == Some(&CARRIAGE_RETURN);
+
+        if let Some(oversized_line) = &mut self.oversized_line {
+            oversized_line.length += rest.len();
+            oversized_line.ends_with_carriage_return = ends_with_carriage_return;
+            return;
+        }
+
+        self.rest.extend_from_slice(rest);
+
+        // With `\r\n` line endings, a trailing `\r` may belong to the separator.
+        let potential_separator_len =
+            (self.line_ending == LineEnding::CrLf && ends_with_carriage_return) as usize;
+
+        if self.exceeds_max_line_length(self.rest.len() - potential_separator_len) {
+            self.oversized_line = Some(OversizedLine {
+                length: self.rest.len(),
+                ends_with_carriage_return
+            });
+            self.rest.truncate(self.oversized_line_prefix_len);
+        }
+    }
+
+    /// Splits the given data into lines just like [NdjsonLineSplitter::split], but also reports
+    /// lines exceeding the maximum line length and the offset of the first byte of every line.
+    pub(crate) fn split_lines(&mut self, data: impl AsBytes,
+            mut on_line: impl FnMut(SplitLine<'_>, u64)) {
+        #[cfg(feature = "bytes")]
+        if let Some(bytes) = data.to_shared_bytes() {
+            let rest_start = self.split_with(&bytes, |line, offset|
+                on_line(SplitLine::resolve(&bytes, line), offset));
+
+            self.store_shared_rest(&bytes, rest_start);
+            return;
+        }
+
+        let data = data.as_bytes();
+        let rest_start = self.split_with(data, |line, offset|
+            on_line(SplitLine::resolve(data, line), offset));
+
+        self.store_rest(&data[rest_start..]);
+    }
+
+    /// Splits the given data into lines and calls `on_line` for every line which is completed by
+    /// it, in order. In case the end does not match up with a line ending, the rest is stored in an
+    /// internal buffer. Consequently, the rest from a previous call to this method is prepended to
+    /// the first line completed by the given data.
+    ///
+    /// With the `bytes` feature, data stored in a shared `Bytes` buffer (see
+    /// [AsBytes::to_shared_bytes]) is handled by [NdjsonLineSplitter::split_shared], i.e. its rest
+    /// is retained without copying.
+    pub fn split(&mut self, data: impl AsBytes, mut on_line: impl FnMut(&[u8])) {
+        self.split_lines(data, |line, _| {
+            // Splitters created via the public interface have no maximum line length, so there are
+            // no oversized lines.
+            if let SplitLine::Complete(line) = line {
+                on_line(line);
+            }
+        });
+    }
+
+    /// Splits the given data into lines just like [NdjsonLineSplitter::split], but also passes the
+    /// range of bytes occupied by every line in all data given to this splitter to `on_line`. The
+    /// range excludes the line ending. This allows building indexes over NDJSON data, such as the
+    /// offset of every record, without parsing it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ndjson_stream::splitter::NdjsonLineSplitter;
+    ///
+    /// let mut splitter = NdjsonLineSplitter::new();
+    /// let mut spans = Vec::new();
+    ///
+    /// splitter.split_spans("12\n3", |range, line| spans.push((range, line.to_vec())));
+    /// splitter.split_spans("4\n", |range, line| spans.push((range, line.to_vec())));
+    ///
+    /// assert_eq!(spans, vec![(0..2, b"12".to_vec()), (3..5, b"34".to_vec())]);
+    /// ```
+    pub fn split_spans(&mut self, data: impl AsBytes, mut on_line: impl FnMut(Range<u64>, &[u8])) {
+        self.split_lines(data, |line, offset| {
+            if let SplitLine::Complete(line) = line {
+                on_line(offset..(offset + line.len() as u64), line);
+            }
+        });
+    }
+
+    #[cfg(feature = "bytes")]
+    fn store_shared_rest(&mut self, data: &Bytes, rest_start: usize) {
+        if self.rest.is_empty() && self.oversized_line.is_none()
+                && !self.exceeds_max_line_length(data.len() - rest_start) {
+            self.shared_rest = data.slice(rest_start..);
+        }
+        else {
+            self.store_rest(&data[rest_start..]);
+        }
+    }
+
+    /// Splits the given [Bytes] chunk into lines just like [NdjsonLineSplitter::split]. However,
+    /// an incomplete line at the end of the chunk is retained as a reference-counted slice of the
+    /// chunk instead of being copied into the internal buffer. It is only copied once more data
+    /// arrives, which is necessary to join it with the remainder of the line. Lines which lie
+    /// entirely within the chunk are passed to `on_line` without copying, just like with
+    /// [NdjsonLineSplitter::split].
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "bytes")))]
+    pub fn split_shared(&mut self, data: Bytes, on_line: impl FnMut(&[u8])) {
+        self.split(data, on_line);
+    }
+
+    /// Splits the given [Bytes] chunk into lines and calls `on_line` with every completed line as
+    /// an owned [Bytes] instance. Lines which lie entirely within the chunk are reference-counted
+    /// slices of it, while lines spanning several chunks are moved out of the internal buffer, so
+    /// no line is copied more often than necessary to make it contiguous. As with
+    /// [NdjsonLineSplitter::split_shared], the rest is retained without copying.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use ndjson_stream::splitter::NdjsonLineSplitter;
+    ///
+    /// let mut splitter = NdjsonLineSplitter::new();
+    /// let mut lines = Vec::new();
+    ///
+    /// splitter.split_bytes(Bytes::from_static(b"123\n4"), |line| lines.push(line));
+    /// splitter.split_bytes(Bytes::from_static(b"56\n"), |line| lines.push(line));
+    ///
+    /// assert_eq!(lines, vec![Bytes::from_static(b"123"), Bytes::from_static(b"456")]);
+    /// ```
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "bytes")))]
+    pub fn split_bytes(&mut self, data: Bytes, mut on_line: impl FnMut(Bytes)) {
+        let rest_start = self.split_with(&data, |line, _| match line {
+            Line::InChunk(range) => on_line(data.slice(range)),
+            Line::Joined(rest) => on_line(Bytes::from(rest.take_vec())),
+            Line::Oversized { .. } => { }
+        });
+
+        self.store_shared_rest(&data, rest_start);
+    }
+
+    /// Splits the given data into lines just like [NdjsonLineSplitter::split], but collects the
+    /// completed lines into owned buffers instead of lending them to a callback.
+    ///
+    /// # Returns
+    ///
+    /// A vector of all lines completed by the given data, in order.
+    pub fn split_owned(&mut self, data: impl AsBytes) -> Vec<Vec<u8>> {
+        let mut lines = Vec::new();
+        self.split(data, |line| lines.push(line.to_vec()));
+        lines
+    }
+
+    /// Gets the rest leftover from previous calls to [NdjsonLineSplitter::split], i.e. the data
+    /// after the last given newline character, which does not yet form a complete line.
+    pub fn rest(&self) -> &[u8] {
+        #[cfg(feature = "bytes")]
+        if !self.shared_rest.is_empty() {
+            return &self.shared_rest;
+        }
+
+        &self.rest
+    }
+
+    /// Gets the length of the incomplete line leftover from previous calls to
+    /// [NdjsonLineSplitter::split_lines], including the bytes of oversized lines which were not
+    /// retained.
+    pub(crate) fn pending_len(&self) -> usize {
+        match &self.oversized_line {
+            Some(oversized_line) => oversized_line.length,
+            None => self.rest().len()
+        }
+    }
+
+    /// Calls `on_rest` with the rest leftover from previous calls to
+    /// [NdjsonLineSplitter::split_lines] and the offset of its first byte, if it is non-empty. In
+    /// any case, the rest is discarded from the internal buffer.
+    pub(crate) fn finalize_lines(&mut self, on_rest: impl FnOnce(SplitLine<'_>, u64)) {
+        self.unshare_rest();
+
+        if let Some(oversized_line) = self.oversized_line.take() {
+            on_rest(SplitLine::Oversized {
+                prefix: &self.rest,
+                length: oversized_line.length
+            }, self.line_start);
+        }
+        else if !self.rest.is_empty() {
+            on_rest(SplitLine::Complete(&self.rest), self.line_start);
+        }
+
+        self.rest.clear();
+        self.line_start = self.consumed;
+    }
+
+    /// Calls `on_rest` with the rest leftover from previous calls to [NdjsonLineSplitter::split],
+    /// i.e. the data after the last given newline character, if it is non-empty. In any case, the
+    /// rest is discarded from the internal buffer. Therefore, this function is idempotent.
+    pub fn finalize(&mut self, on_rest: impl FnOnce(&[u8])) {
+        self.finalize_lines(|rest, _| {
+            if let SplitLine::Complete(rest) = rest {
+                on_rest(rest);
+            }
+        });
+    }
+
+    /// Calls `on_rest` with the range of bytes occupied by the rest leftover from previous calls to
+    /// [NdjsonLineSplitter::split_spans] and the rest itself, just like
+    /// [NdjsonLineSplitter::finalize].
+    pub fn finalize_spans(&mut self, on_rest: impl FnOnce(Range<u64>, &[u8])) {
+        self.finalize_lines(|rest, offset| {
+            if let SplitLine::Complete(rest) = rest {
+                on_rest(offset..(offset + rest.len() as u64), rest);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn no_lines_in_chunk_without_newline() {
+        let mut splitter = NdjsonLineSplitter::new();
+
+        assert_that!(splitter.split_owned("abc")).is_empty();
+        assert_that!(splitter.rest()).contains_exactly_in_given_order(b"abc".iter());
+    }
+
+    #[test]
+    fn line_spanning_multiple_chunks_is_joined() {
+        let mut splitter = NdjsonLineSplitter::new();
+
+        splitter.split_owned("ab");
+        splitter.split_owned("c");
+
+        assert_that!(splitter.split_owned("d\r\nef\n\ng"))
+            .contains_exactly_in_given_order([b"abcd\r".to_vec(), b"ef".to_vec(), Vec::new()]);
+        assert_that!(splitter.rest()).contains_exactly_in_given_order(b"g".iter());
+    }
+
+    #[test]
+    fn rest_exceeding_inline_capacity_is_joined() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let first = "a".repeat(INLINE_REST_CAPACITY - 1);
+        let second = "b".repeat(INLINE_REST_CAPACITY);
+
+        assert_that!(splitter.split_owned(&first)).is_empty();
+        assert_that!(splitter.split_owned(&second)).is_empty();
+        assert_that!(splitter.split_owned("c\nd\n")).contains_exactly_in_given_order([
+            format!("{first}{second}c").into_bytes(),
+            b"d".to_vec()
+        ]);
+    }
+
+    #[test]
+    fn rest_buffer_reuses_spilled_allocation() {
+        let mut buffer = RestBuffer::default();
+
+        buffer.extend_from_slice(b"ab");
+        buffer.pop();
+        assert_that!(buffer.is_spilled()).is_false();
+        assert_that!(&*buffer).contains_exactly_in_given_order(b"a".iter());
+
+        buffer.extend_from_slice(&[b'x'; INLINE_REST_CAPACITY]);
+        buffer.clear();
+        buffer.extend_from_slice(b"yz");
+
+        assert_that!(buffer.is_spilled()).is_true();
+        assert_that!(&*buffer).contains_exactly_in_given_order(b"yz".iter());
+    }
+
+    #[test]
+    fn old_data_is_discarded() {
+        let mut splitter = NdjsonLineSplitter::new();
+
+        splitter.split("abc", |_| { });
+        splitter.split("def\nghi\n", |_| { });
+
+        assert_that!(splitter.rest()).is_empty();
+    }
+
+    #[test]
+    fn finalize_reports_non_empty_rest_once() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let mut rests = Vec::new();
+
+        splitter.split("abc\ndef", |_| { });
+        splitter.finalize(|rest| rests.push(rest.to_vec()));
+        splitter.finalize(|rest| rests.push(rest.to_vec()));
+
+        assert_that!(rests).contains_exactly_in_given_order([b"def".to_vec()]);
+    }
+
+    #[test]
+    fn finalize_does_not_report_empty_rest() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let mut called = false;
+
+        splitter.split("abc\n", |_| { });
+        splitter.finalize(|_| called = true);
+
+        assert_that!(called).is_false();
+    }
+
+    #[test]
+    fn split_spans_reports_absolute_ranges_without_line_ending() {
+        let mut splitter = NdjsonLineSplitter::with_line_ending(LineEnding::CrLf);
+        let mut spans = Vec::new();
+
+        splitter.split_spans("ab\r\nc", |range, line| spans.push((range, line.to_vec())));
+        splitter.split_spans("d\r", |range, line| spans.push((range, line.to_vec())));
+        splitter.split_spans("\n\r\nef", |range, line| spans.push((range, line.to_vec())));
+        splitter.finalize_spans(|range, rest| spans.push((range, rest.to_vec())));
+
+        assert_that!(spans).contains_exactly_in_given_order([
+            (0..2, b"ab".to_vec()),
+            (4..6, b"cd".to_vec()),
+            (8..8, Vec::new()),
+            (10..12, b"ef".to_vec())
+        ]);
+    }
+
+    fn split_all<'data>(line_ending: LineEnding, chunks: impl IntoIterator<Item = &'data str>)
+            -> Vec<Vec<u8>> {
+        let mut splitter = NdjsonLineSplitter::with_line_ending(line_ending);
+
+        chunks.into_iter().flat_map(|chunk| splitter.split_owned(chunk)).collect()
+    }
+
+    #[test]
+    fn lf_line_ending_keeps_carriage_return() {
+        assert_that!(split_all(LineEnding::Lf, ["a\r\nb\rc\n"]))
+            .contains_exactly_in_given_order([b"a\r".to_vec(), b"b\rc".to_vec()]);
+    }
+
+    #[test]
+    fn cr_line_ending_splits_only_at_carriage_return() {
+        assert_that!(split_all(LineEnding::Cr, ["a\rb\nc\r", "d\r"]))
+            .contains_exactly_in_given_order([b"a".to_vec(), b"b\nc".to_vec(), b"d".to_vec()]);
+    }
+
+    #[test]
+    fn cr_lf_line_ending_splits_only_at_cr_lf_sequence() {
+        assert_that!(split_all(LineEnding::CrLf, ["a\r\nb\nc\rd\r\n"]))
+            .contains_exactly_in_given_order([b"a".to_vec(), b"b\nc\rd".to_vec()]);
+    }
+
+    #[test]
+    fn cr_lf_line_ending_handles_sequence_split_between_chunks() {
+        assert_that!(split_all(LineEnding::CrLf, ["a\r", "\nb\r", "\r", "\n"]))
+            .contains_exactly_in_given_order([b"a".to_vec(), b"b\r".to_vec()]);
+    }
+
+    #[test]
+    fn any_line_ending_splits_at_all_line_endings() {
+        assert_that!(split_all(LineEnding::Any, ["a\nb\r\nc\rd\r\re\n"]))
+            .contains_exactly_in_given_order([
+                b"a".to_vec(),
+                b"b".to_vec(),
+                b"c".to_vec(),
+                b"d".to_vec(),
+                Vec::new(),
+                b"e".to_vec()
+            ]);
+    }
+
+    #[test]
+    fn lines_after_joined_line_report_offsets_and_oversized_lengths() {
+        let mut splitter = NdjsonLineSplitter::with_line_ending(LineEnding::Cr);
+        let mut lines = Vec::new();
+
+        splitter.set_max_line_length(3, 2);
+        splitter.split_lines("a", |_, _| { });
+        splitter.split_lines("b\rcdef\r\rxyz\rg", |line, offset| lines.push(match line {
+            SplitLine::Complete(line) => (line.to_vec(), None, offset),
+            SplitLine::Oversized { prefix, length } => (prefix.to_vec(), Some(length), offset)
+        }));
+
+        assert_that!(lines).contains_exactly_in_given_order([
+            (b"ab".to_vec(), None, 0),
+            (b"cd".to_vec(), Some(4), 3),
+            (Vec::new(), None, 8),
+            (b"xyz".to_vec(), None, 9)
+        ]);
+        assert_that!(splitter.rest()).contains_exactly_in_given_order(b"g".iter());
+    }
+
+    #[test]
+    fn any_line_ending_handles_cr_lf_sequence_split_between_chunks() {
+        assert_that!(split_all(LineEnding::Any, ["a\r", "", "\nb\r", "c\r", "\r\n"]))
+            .contains_exactly_in_given_order([
+                b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), Vec::new()
+            ]);
+    }
+}
+
+#[cfg(all(test, feature = "bytes"))]
+mod bytes_tests {
+
+    use bytes::Bytes;
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn rest_of_bytes_chunk_is_not_copied() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let data = Bytes::from_static(b"abc\ndef");
+
+        splitter.split(data.clone(), |_| { });
+
+        assert_that!(splitter.rest()).contains_exactly_in_given_order(b"def".iter());
+        assert_that!(splitter.rest().as_ptr()).is_equal_to(data[4..].as_ptr());
+    }
+
+    #[test]
+    fn shared_rest_is_joined_with_next_chunk() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let mut lines = Vec::new();
+
+        splitter.split(Bytes::from_static(b"ab"), |line| lines.push(line.to_vec()));
+        splitter.split("c\nd", |line| lines.push(line.to_vec()));
+        splitter.split(Bytes::from_static(b"e\n"), |line| lines.push(line.to_vec()));
+
+        assert_that!(lines).contains_exactly_in_given_order([b"abc".to_vec(), b"de".to_vec()]);
+    }
+
+    #[test]
+    fn split_bytes_slices_lines_within_chunk() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let data = Bytes::from_static(b"abc\ndef\n");
+        let mut lines = Vec::new();
+
+        splitter.split_bytes(data.clone(), |line| lines.push(line));
+
+        assert_that!(lines.iter().map(|line| line.as_ptr()).collect::<Vec<_>>())
+            .contains_exactly_in_given_order([data.as_ptr(), data[4..].as_ptr()]);
+    }
+
+    #[test]
+    fn split_bytes_joins_lines_spanning_chunks() {
+        let mut splitter = NdjsonLineSplitter::with_line_ending(LineEnding::CrLf);
+        let mut lines = Vec::new();
+
+        splitter.split_bytes(Bytes::from_static(b"a\r\nb\r"), |line| lines.push(line));
+        splitter.split_bytes(Bytes::from_static(b"\nc"), |line| lines.push(line));
+
+        assert_that!(lines).contains_exactly_in_given_order([
+            Bytes::from_static(b"a"),
+            Bytes::from_static(b"b")
+        ]);
+        assert_that!(splitter.rest()).contains_exactly_in_given_order(b"c".iter());
+    }
+
+    #[test]
+    fn finalize_reports_shared_rest() {
+        let mut splitter = NdjsonLineSplitter::new();
+        let mut rests = Vec::new();
+
+        splitter.split(Bytes::from_static(b"abc\ndef"), |_| { });
+        splitter.finalize(|rest| rests.push(rest.to_vec()));
+
+        assert_that!(rests).contains_exactly_in_given_order([b"def".to_vec()]);
+        assert_that!(splitter.rest()).is_empty();
+    }
+}
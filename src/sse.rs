@@ -0,0 +1,312 @@
+//! This module offers the [SseSource], which extracts the `data` payloads of a Server-Sent Events
+//! (SSE) stream, as used by the web `EventSource` API, such that every event is parsed as one
+//! NDJSON-record. Many HTTP APIs which stream JSON documents actually send SSE events with one JSON
+//! document per event, which would otherwise produce parse errors for the `data:` prefixes and the
+//! empty lines separating events.
+//!
+//! Lines are split at `\n`, `\r`, and `\r\n` and interpreted as specified by the
+//! [HTML standard](https://html.spec.whatwg.org/multipage/server-sent-events.html). Comments and
+//! fields other than `data`, such as `event`, `id`, or `retry`, are ignored. Every event with at
+//! least one `data` field is turned into one line of output. If an event has several `data`
+//! fields, their values are joined with a space rather than a line feed, which is equivalent for
+//! JSON, such that documents spread across several `data` fields are still parsed as a single
+//! record. An incomplete event at the end of the stream is discarded.
+//!
+//! The decoding is also available without a [ChunkSource] via [SseDecoder].
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::sse::SseSource;
+//!
+//! let events = "event: update\ndata: {\"id\":1}\n\n: keep-alive\n\ndata: {\"id\":\ndata: 2}\n\n";
+//! let source = SseSource::new(vec![Ok::<_, std::io::Error>(events)].into_iter());
+//! let mut ndjson_iter = ndjson_stream::from_chunk_source::<serde_json::Value, _>(source);
+//!
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), serde_json::json!({ "id": 1 }));
+//! assert_eq!(ndjson_iter.next().unwrap().unwrap(), serde_json::json!({ "id": 2 }));
+//! assert!(ndjson_iter.next().is_none());
+//! ```
+
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+
+#[cfg(feature = "stream")]
+use std::task::{ready, Context, Poll};
+
+use crate::as_bytes::AsBytes;
+use crate::config::LineEnding;
+#[cfg(feature = "stream")]
+use crate::source::AsyncChunkSource;
+use crate::source::ChunkSource;
+use crate::splitter::NdjsonLineSplitter;
+
+const BYTE_ORDER_MARK: &[u8] = b"\xef\xbb\xbf";
+
+/// The `data` field of the event which is currently being received.
+#[derive(Clone, Debug, Default)]
+struct EventData {
+    data: Vec<u8>,
+
+    /// Whether the event has a `data` field, which may be empty.
+    has_data: bool,
+
+    /// Whether no line has been processed yet, in which case a byte order mark is removed.
+    at_start: bool
+}
+
+impl EventData {
+
+    fn handle_line(&mut self, mut line: &[u8], output: &mut Vec<u8>) {
+        if self.at_start {
+            self.at_start = false;
+            line = line.strip_prefix(BYTE_ORDER_MARK).unwrap_or(line);
+        }
+
+        if line.is_empty() {
+            if self.has_data {
+                output.append(&mut self.data);
+                output.push(b'\n');
+                self.has_data = false;
+            }
+
+            return;
+        }
+
+        let (name, value) = match line.iter().position(|&byte| byte == b':') {
+            Some(index) => {
+                let value = &line[(index + 1)..];
+
+                (&line[..index], value.strip_prefix(b" ").unwrap_or(value))
+            },
+            None => (line, &b""[..])
+        };
+
+        if name == b"data" {
+            if self.has_data {
+                self.data.push(b' ');
+            }
+
+            self.data.extend_from_slice(value);
+            self.has_data = true;
+        }
+    }
+}
+
+/// Converts a Server-Sent Events stream given in chunks into NDJSON-data, with one line for the
+/// `data` fields of each event. See the [module documentation](self) for details. The output can
+/// be given to an [NdjsonEngine](crate::engine::NdjsonEngine) or any other consumer of NDJSON.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::sse::SseDecoder;
+///
+/// let mut decoder = SseDecoder::new();
+///
+/// assert_eq!(decoder.decode("data: 1\n\ndata: [2,"), b"1\n");
+/// assert_eq!(decoder.decode("\r\ndata: 3]\r\n\r\n"), b"[2, 3]\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SseDecoder {
+    splitter: NdjsonLineSplitter,
+    event: EventData
+}
+
+impl SseDecoder {
+
+    /// Creates a new decoder at the start of a Server-Sent Events stream.
+    pub fn new() -> SseDecoder {
+        SseDecoder {
+            splitter: NdjsonLineSplitter::with_line_ending(LineEnding::Any),
+            event: EventData {
+                at_start: true,
+                ..EventData::default()
+            }
+        }
+    }
+
+    /// Decodes the given chunk of the Server-Sent Events stream. Lines and events may span several
+    /// chunks, in which case the incomplete part is stored until its remainder arrives.
+    ///
+    /// # Returns
+    ///
+    /// The NDJSON-lines of all events completed by the given chunk, each terminated with `\n`. This
+    /// is empty if no event was completed.
+    pub fn decode(&mut self, chunk: impl AsBytes) -> Vec<u8> {
+        let mut output = Vec::new();
+        let event = &mut self.event;
+
+        self.splitter.split(chunk, |line| event.handle_line(line, &mut output));
+        output
+    }
+
+    /// Indicates that the Server-Sent Events stream has ended. An incomplete event is discarded,
+    /// as specified for the `EventSource` API, and the decoder is reset to the start of a stream.
+    pub fn finish(&mut self) {
+        *self = SseDecoder::new();
+    }
+}
+
+impl Default for SseDecoder {
+    fn default() -> SseDecoder {
+        SseDecoder::new()
+    }
+}
+
+/// A [ChunkSource] which wraps another one providing a Server-Sent Events stream and converts its
+/// data blocks into NDJSON-data using an [SseDecoder], such that every event with a `data` field
+/// is parsed as one record by the drivers of this crate. Errors of the wrapped source are forwarded
+/// unchanged.
+///
+/// With the `stream` feature, this is also an [AsyncChunkSource] if the wrapped source is one and
+/// implements [Unpin].
+pub struct SseSource<S> {
+    source: S,
+    decoder: SseDecoder
+}
+
+impl<S> SseSource<S> {
+
+    /// Creates a new source extracting the events of the given Server-Sent Events `source`.
+    pub fn new(source: S) -> SseSource<S> {
+        SseSource {
+            source,
+            decoder: SseDecoder::new()
+        }
+    }
+
+    /// Gets a reference to the wrapped source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Unwraps this source, returning the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Decodes the given chunk, returning `None` if no event was completed by it.
+    fn decode(&mut self, chunk: impl AsBytes) -> Option<Vec<u8>> {
+        Some(self.decoder.decode(chunk)).filter(|output| !output.is_empty())
+    }
+}
+
+impl<S> ChunkSource for SseSource<S>
+where
+    S: ChunkSource
+{
+    type Chunk = Vec<u8>;
+    type Error = S::Error;
+
+    fn next_chunk(&mut self) -> Option<Result<Vec<u8>, S::Error>> {
+        loop {
+            match self.source.next_chunk() {
+                Some(Ok(chunk)) => if let Some(output) = self.decode(chunk) {
+                    return Some(Ok(output));
+                },
+                Some(Err(error)) => return Some(Err(error)),
+                None => {
+                    self.decoder.finish();
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+impl<S> AsyncChunkSource for SseSource<S>
+where
+    S: AsyncChunkSource + Unpin
+{
+    type Chunk = Vec<u8>;
+    type Error = S::Error;
+
+    fn poll_next_chunk(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<Result<Vec<u8>, S::Error>>> {
+        let this = self.get_mut();
+
+        loop {
+            match ready!(Pin::new(&mut this.source).poll_next_chunk(cx)) {
+                Some(Ok(chunk)) => if let Some(output) = this.decode(chunk) {
+                    return Poll::Ready(Some(Ok(output)));
+                },
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => {
+                    this.decoder.finish();
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use super::*;
+
+    fn decode(data: &str, chunk_size: usize) -> String {
+        let mut decoder = SseDecoder::new();
+        let output = data.as_bytes()
+            .chunks(chunk_size)
+            .flat_map(|chunk| decoder.decode(chunk))
+            .collect::<Vec<_>>();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn data_fields_are_extracted_independent_of_chunk_size() {
+        let data = "\u{feff}: comment\r\nevent: a\r\nid: 1\r\ndata:{\"a\":1}\r\n\r\n\
+            retry: 10\rdata: [1,\rdata\rdata:  2]\r\rid: 2\n\ndata: 3\n\ndata: 4";
+
+        for chunk_size in 1..=data.len() {
+            assert_that!(decode(data, chunk_size))
+                .is_equal_to("{\"a\":1}\n[1,   2]\n3\n".to_owned());
+        }
+    }
+
+    #[test]
+    fn empty_data_field_produces_empty_line() {
+        assert_that!(decode("data\n\ndata:\n\n", 4)).is_equal_to("\n\n".to_owned());
+    }
+
+    #[test]
+    fn finish_discards_incomplete_event() {
+        let mut decoder = SseDecoder::new();
+
+        assert_that!(decoder.decode("data: 1\n")).is_empty();
+        decoder.finish();
+        assert_that!(decoder.decode("data: 2\n\n")).is_equal_to(b"2\n".to_vec());
+    }
+
+    #[test]
+    fn input_errors_are_forwarded() {
+        let chunks = vec![Ok("data: 1\n"), Err("test"), Ok("\ndata: 2"), Ok("\n\n")];
+        let results = SseSource::new(chunks.into_iter()).into_chunks().collect::<Vec<_>>();
+
+        assert_that!(results).contains_exactly_in_given_order([
+            Err("test"),
+            Ok(b"1\n".to_vec()),
+            Ok(b"2\n".to_vec())
+        ]);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn async_source_is_decoded() {
+        use futures::stream::{self, StreamExt};
+
+        let chunks = stream::iter(vec![Ok::<_, &str>("data: 1\n\nda"), Ok("ta: 2\n"), Ok("\n")]);
+        let records = crate::from_async_chunk_source::<u32, _>(SseSource::new(chunks))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        assert_that!(tokio_test::block_on(records)).contains_exactly_in_given_order([1, 2]);
+    }
+}
@@ -0,0 +1,269 @@
+//! This module offers helpers for the tests of crates which embed this parser in their own drivers,
+//! for example to wrap a custom transport. The main invariant such an integration must preserve is
+//! that its output does not depend on how the input is split into chunks.
+//!
+//! [ChunkInvarianceCheck] runs a given byte corpus through a driver under test once as a single
+//! chunk and then under many pseudo-random chunkings, asserting that the output is identical every
+//! time. The chunkings are derived from a seed, so failures are reproducible. For checking the
+//! [NdjsonEngine](crate::engine::NdjsonEngine) itself, [assert_chunk_invariance] offers a shortcut.
+//!
+//! The chunkings are built from pseudo-random chunk lengths via
+//! [chunk_by_lengths](crate::fuzz::chunk_by_lengths), and the engine is checked via
+//! [assert_chunking_invariant](crate::fuzz::assert_chunking_invariant), so this module shares its
+//! harness with the [fuzz] module.
+//!
+//! # Example
+//!
+//! ```
+//! use ndjson_stream::engine::NdjsonEngine;
+//! use ndjson_stream::test_support::ChunkInvarianceCheck;
+//!
+//! let corpus = b"{\"a\":1}\n[1,\n2]\n\n\"x\"";
+//!
+//! ChunkInvarianceCheck::new()
+//!     .with_max_chunk_length(4)
+//!     .assert_driver(corpus, |chunks| {
+//!         let mut engine = NdjsonEngine::<serde_json::Value>::new();
+//!         let mut results = Vec::new();
+//!
+//!         for chunk in chunks {
+//!             engine.input(chunk);
+//!             engine.drain_into(&mut results);
+//!         }
+//!
+//!         engine.finalize();
+//!         engine.drain_into(&mut results);
+//!         results.into_iter()
+//!             .map(|result| result.map_err(|error| error.to_string()))
+//!             .collect::<Vec<_>>()
+//!     });
+//! ```
+
+use alloc::vec::Vec;
+
+use core::fmt::Debug;
+
+use serde::Deserialize;
+
+use crate::config::NdjsonConfig;
+use crate::fuzz;
+
+/// The number of pseudo-random chunkings tried by a [ChunkInvarianceCheck] unless configured
+/// otherwise.
+pub const DEFAULT_CHUNKINGS: usize = 64;
+
+/// The maximum length of the chunks generated by a [ChunkInvarianceCheck] unless configured
+/// otherwise.
+pub const DEFAULT_MAX_CHUNK_LENGTH: usize = 16;
+
+/// The seed of a [ChunkInvarianceCheck] unless configured otherwise.
+pub const DEFAULT_SEED: u64 = 0x6e64_6a73_6f6e;
+
+/// A small pseudo-random number generator (SplitMix64), which is sufficient for choosing chunk
+/// lengths and avoids a dependency.
+struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+}
+
+/// Checks that a driver produces the same output for a corpus independent of how it is split into
+/// chunks. The check is configured in builder-style and run via
+/// [assert_driver](ChunkInvarianceCheck::assert_driver) or
+/// [assert_engine](ChunkInvarianceCheck::assert_engine).
+///
+/// The first chunking gives every byte as a separate chunk. All further chunkings consist of chunks
+/// of pseudo-random lengths between 0 and the configured maximum chunk length, so empty chunks are
+/// tested as well.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ChunkInvarianceCheck {
+    chunkings: usize,
+    max_chunk_length: usize,
+    seed: u64
+}
+
+impl ChunkInvarianceCheck {
+
+    /// Creates a new check with [DEFAULT_CHUNKINGS] chunkings, a maximum chunk length of
+    /// [DEFAULT_MAX_CHUNK_LENGTH], and the [DEFAULT_SEED].
+    pub fn new() -> ChunkInvarianceCheck {
+        ChunkInvarianceCheck {
+            chunkings: DEFAULT_CHUNKINGS,
+            max_chunk_length: DEFAULT_MAX_CHUNK_LENGTH,
+            seed: DEFAULT_SEED
+        }
+    }
+
+    /// Creates a new check from this one with the given number of `chunkings` to try.
+    pub fn with_chunkings(mut self, chunkings: usize) -> ChunkInvarianceCheck {
+        self.chunkings = chunkings;
+        self
+    }
+
+    /// Creates a new check from this one with the given maximum length of generated chunks. A
+    /// `max_chunk_length` of 0 is treated as 1.
+    pub fn with_max_chunk_length(mut self, max_chunk_length: usize) -> ChunkInvarianceCheck {
+        self.max_chunk_length = max_chunk_length.max(1);
+        self
+    }
+
+    /// Creates a new check from this one with the given `seed` from which the chunkings are
+    /// derived. The same seed always produces the same chunkings.
+    pub fn with_seed(mut self, seed: u64) -> ChunkInvarianceCheck {
+        self.seed = seed;
+        self
+    }
+
+    /// Generates the chunk lengths of the chunkings which this check tries for a corpus of the
+    /// given length, in the format of [chunk_by_lengths](fuzz::chunk_by_lengths).
+    fn chunk_lengths(&self, corpus_len: usize) -> Vec<Vec<usize>> {
+        let mut random = SplitMix64 {
+            state: self.seed
+        };
+        let mut chunk_lengths = Vec::with_capacity(self.chunkings);
+
+        if self.chunkings > 0 {
+            chunk_lengths.push(Vec::from([1]));
+        }
+
+        for _ in 1..self.chunkings {
+            let mut lengths = Vec::new();
+            let mut covered = 0;
+
+            while covered < corpus_len {
+                let length = (random.next() % (self.max_chunk_length as u64 + 1)) as usize;
+
+                lengths.push(length);
+                covered += length;
+            }
+
+            chunk_lengths.push(lengths);
+        }
+
+        chunk_lengths
+    }
+
+    /// Generates the chunkings of the given `corpus` which this check tries, as lists of chunks.
+    pub fn chunkings<'corpus>(&self, corpus: &'corpus [u8]) -> Vec<Vec<&'corpus [u8]>> {
+        self.chunk_lengths(corpus.len()).iter()
+            .map(|lengths| fuzz::chunk_by_lengths(corpus, lengths))
+            .collect()
+    }
+
+    /// Asserts that the given `driver` produces the same output for all chunkings of the given
+    /// `corpus` as for the entire corpus given as a single chunk. The driver receives the chunks in
+    /// order and returns a comparable representation of its entire output.
+    ///
+    /// # Panics
+    ///
+    /// If the output for any chunking differs. The panic message contains the lengths of the chunks
+    /// of the failing chunking.
+    pub fn assert_driver<O, F>(&self, corpus: &[u8], mut driver: F)
+    where
+        F: FnMut(Vec<&[u8]>) -> O,
+        O: Debug + PartialEq
+    {
+        let expected = driver(Vec::from([corpus]));
+
+        for chunks in self.chunkings(corpus) {
+            let chunk_lengths = chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>();
+            let actual = driver(chunks);
+
+            assert_eq!(actual, expected, "output differs for chunk lengths {chunk_lengths:?}");
+        }
+    }
+
+    /// Asserts that an [NdjsonEngine](crate::engine::NdjsonEngine) with the given [NdjsonConfig]
+    /// produces the same records and errors for all chunkings of the given `corpus` as for the
+    /// entire corpus given as a single chunk, as checked by
+    /// [assert_chunking_invariant](fuzz::assert_chunking_invariant). Errors are compared by their
+    /// message.
+    ///
+    /// # Panics
+    ///
+    /// If the output for any chunking differs.
+    pub fn assert_engine<T>(&self, corpus: &[u8], config: NdjsonConfig)
+    where
+        for<'deserialize> T: Deserialize<'deserialize>,
+        T: Debug + PartialEq
+    {
+        for lengths in self.chunk_lengths(corpus.len()) {
+            fuzz::assert_chunking_invariant::<T>(corpus, &lengths, config.clone());
+        }
+    }
+}
+
+impl Default for ChunkInvarianceCheck {
+    fn default() -> ChunkInvarianceCheck {
+        ChunkInvarianceCheck::new()
+    }
+}
+
+/// Asserts that an [NdjsonEngine](crate::engine::NdjsonEngine) with the given [NdjsonConfig]
+/// produces the same output for the given `corpus` under many chunkings, as checked by the default
+/// [ChunkInvarianceCheck].
+///
+/// # Panics
+///
+/// If the output for any chunking differs.
+pub fn assert_chunk_invariance<T>(corpus: &[u8], config: NdjsonConfig)
+where
+    for<'deserialize> T: Deserialize<'deserialize>,
+    T: Debug + PartialEq
+{
+    ChunkInvarianceCheck::new().assert_engine::<T>(corpus, config);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde_json::Value;
+
+    use crate::config::EmptyLineHandling;
+
+    use super::*;
+
+    #[test]
+    fn chunkings_cover_corpus_and_are_reproducible() {
+        let corpus = b"{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n";
+        let check = ChunkInvarianceCheck::new().with_chunkings(10).with_max_chunk_length(5);
+        let chunkings = check.chunkings(corpus);
+
+        assert_that!(&chunkings).has_length(10);
+        assert_that!(chunkings[0].len()).is_equal_to(corpus.len());
+
+        for chunks in &chunkings {
+            assert_that!(chunks.concat()).is_equal_to(corpus.to_vec());
+            assert_that!(chunks.iter().all(|chunk| chunk.len() <= 5)).is_true();
+        }
+
+        assert_that!(check.chunkings(corpus)).is_equal_to(chunkings.clone());
+        assert_that!(check.with_seed(1).chunkings(corpus)).is_not_equal_to(chunkings);
+    }
+
+    #[test]
+    fn engine_passes_check() {
+        let config = NdjsonConfig::default()
+            .with_empty_line_handling(EmptyLineHandling::IgnoreBlank)
+            .with_parse_rest(true);
+
+        assert_chunk_invariance::<Value>(b"{\"a\":[1,2]}\r\n\n  x\n\"\xff\"\n[1,\n2]", config);
+    }
+
+    #[test]
+    #[should_panic(expected = "output differs for chunk lengths")]
+    fn chunking_dependent_driver_fails_check() {
+        ChunkInvarianceCheck::new().assert_driver(b"abc", |chunks| chunks.len());
+    }
+}
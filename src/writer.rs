@@ -0,0 +1,1120 @@
+//! This module contains the write side of this crate, i.e. utilities which serialize values into
+//! NDJSON-data. Values are serialized using [serde_json], hence they must implement [Serialize].
+
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures::{ready, Stream};
+
+#[cfg(feature = "stream")]
+use pin_project_lite::pin_project;
+
+#[cfg(feature = "std")]
+use std::collections::hash_map::{Entry, HashMap};
+
+#[cfg(feature = "std")]
+use std::hash::Hash;
+
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+#[cfg(feature = "std")]
+use std::marker::PhantomData;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+#[cfg(feature = "http-body")]
+use bytes::Bytes;
+
+#[cfg(feature = "http-body")]
+use http_body::{Body, Frame};
+
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+#[cfg(feature = "std")]
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+
+use serde_json::error::Result as JsonResult;
+
+#[cfg(feature = "std")]
+use serde_json::ser::{CompactFormatter, Formatter};
+
+#[cfg(feature = "std")]
+use serde_json::Value;
+
+/// Serializes the given value as a single NDJSON-line and appends it, including the terminating
+/// newline character, to the given buffer. If serialization fails, the buffer is left unchanged.
+///
+/// # Example
+///
+/// ```
+/// let mut buffer = Vec::new();
+///
+/// ndjson_stream::writer::serialize_line(&[1, 2, 3], &mut buffer).unwrap();
+/// ndjson_stream::writer::serialize_line("text", &mut buffer).unwrap();
+///
+/// assert_eq!(buffer, b"[1,2,3]\n\"text\"\n");
+/// ```
+pub fn serialize_line<T>(value: &T, buffer: &mut Vec<u8>) -> JsonResult<()>
+where
+    T: Serialize + ?Sized
+{
+    #[cfg(feature = "std")]
+    {
+        let original_len = buffer.len();
+
+        if let Err(error) = serde_json::to_writer(&mut *buffer, value) {
+            buffer.truncate(original_len);
+            return Err(error);
+        }
+    }
+
+    // Without `std`, serde_json can only serialize into a new vector.
+    #[cfg(not(feature = "std"))]
+    buffer.extend_from_slice(&serde_json::to_vec(value)?);
+
+    buffer.push(b'\n');
+    Ok(())
+}
+
+/// Controls which character sequence terminates the lines written according to an
+/// [NdjsonWriteConfig].
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum WriteLineEnding {
+
+    /// Every line is terminated by a `\n` character.
+    #[default]
+    Lf,
+
+    /// Every line is terminated by a `\r\n` sequence, as expected by some Windows tools.
+    CrLf
+}
+
+/// Controls how floating-point numbers are formatted when written according to an
+/// [NdjsonWriteConfig]. Non-finite numbers are always written as `null`.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FloatFormat {
+
+    /// Floats are written with the shortest representation which parses back to the same value,
+    /// such as `0.1` or `1e100`.
+    #[default]
+    Shortest,
+
+    /// Floats are written in decimal notation with the given number of digits after the decimal
+    /// point, such as `0.10` for 2 digits. Values are rounded as by Rust's formatting machinery.
+    Fixed(usize)
+}
+
+/// Controls how values are serialized into NDJSON-lines by [serialize_line_with_config] and the
+/// writers of this module. The default config produces the same compact output as
+/// [serialize_line].
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::writer::{NdjsonWriteConfig, WriteLineEnding};
+/// use serde_json::json;
+///
+/// let config = NdjsonWriteConfig::default()
+///     .with_escape_non_ascii(true)
+///     .with_sort_keys(true)
+///     .with_line_ending(WriteLineEnding::CrLf);
+/// let mut buffer = Vec::new();
+///
+/// ndjson_stream::writer::serialize_line_with_config(&json!({ "b": "ä", "a": 1 }), &mut buffer,
+///     &config).unwrap();
+///
+/// assert_eq!(buffer, b"{\"a\":1,\"b\":\"\\u00e4\"}\r\n");
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct NdjsonWriteConfig {
+    pub(crate) escape_non_ascii: bool,
+    pub(crate) sort_keys: bool,
+    pub(crate) line_ending: WriteLineEnding,
+    pub(crate) float_format: FloatFormat
+}
+
+#[cfg(feature = "std")]
+impl NdjsonWriteConfig {
+
+    /// Creates a new config from this config which has the given configuration on whether to
+    /// escape all non-ASCII characters in strings and keys as `\uXXXX`-sequences, such that the
+    /// output consists only of ASCII characters. Characters outside the basic multilingual plane
+    /// are escaped as surrogate pairs. By default, this is set to `false`.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the escaping of non-ASCII
+    /// characters.
+    pub fn with_escape_non_ascii(self, escape_non_ascii: bool) -> NdjsonWriteConfig {
+        NdjsonWriteConfig {
+            escape_non_ascii,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has the given configuration on whether to
+    /// write the keys of all objects, including nested ones, in lexicographic order, such that
+    /// the output is stable and diffable irrespective of the iteration order of maps. Values are
+    /// converted into a [Value] first, which costs an additional allocation per record. By
+    /// default, this is set to `false`, i.e. keys are written in the order given by [Serialize].
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the key sorting.
+    pub fn with_sort_keys(self, sort_keys: bool) -> NdjsonWriteConfig {
+        NdjsonWriteConfig {
+            sort_keys,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has a different character sequence terminating
+    /// every line. See [WriteLineEnding] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the line ending.
+    pub fn with_line_ending(self, line_ending: WriteLineEnding) -> NdjsonWriteConfig {
+        NdjsonWriteConfig {
+            line_ending,
+            ..self
+        }
+    }
+
+    /// Creates a new config from this config which has a different formatting of floating-point
+    /// numbers. See [FloatFormat] for more details.
+    ///
+    /// # Returns
+    ///
+    /// A new config with all the same values as this one, except the float format.
+    pub fn with_float_format(self, float_format: FloatFormat) -> NdjsonWriteConfig {
+        NdjsonWriteConfig {
+            float_format,
+            ..self
+        }
+    }
+}
+
+/// The [Formatter] applying the options of an [NdjsonWriteConfig] on top of the compact output.
+#[cfg(feature = "std")]
+struct ConfiguredFormatter<'config> {
+    config: &'config NdjsonWriteConfig
+}
+
+#[cfg(feature = "std")]
+impl Formatter for ConfiguredFormatter<'_> {
+    fn write_f32<W>(&mut self, writer: &mut W, value: f32) -> io::Result<()>
+    where
+        W: Write + ?Sized
+    {
+        match self.config.float_format {
+            FloatFormat::Shortest => CompactFormatter.write_f32(writer, value),
+            FloatFormat::Fixed(decimals) => write!(writer, "{value:.decimals$}")
+        }
+    }
+
+    fn write_f64<W>(&mut self, writer: &mut W, value: f64) -> io::Result<()>
+    where
+        W: Write + ?Sized
+    {
+        match self.config.float_format {
+            FloatFormat::Shortest => CompactFormatter.write_f64(writer, value),
+            FloatFormat::Fixed(decimals) => write!(writer, "{value:.decimals$}")
+        }
+    }
+
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: Write + ?Sized
+    {
+        if !self.config.escape_non_ascii {
+            return writer.write_all(fragment.as_bytes());
+        }
+
+        let mut ascii_start = 0;
+
+        for (index, char) in fragment.char_indices() {
+            if char.is_ascii() {
+                continue;
+            }
+
+            writer.write_all(&fragment.as_bytes()[ascii_start..index])?;
+
+            for unit in char.encode_utf16(&mut [0; 2]) {
+                write!(writer, "\\u{unit:04x}")?;
+            }
+
+            ascii_start = index + char.len_utf8();
+        }
+
+        writer.write_all(&fragment.as_bytes()[ascii_start..])
+    }
+}
+
+/// Serializes a [Value] with the keys of all objects in lexicographic order, independent of the
+/// order of the map implementation used by [serde_json].
+#[cfg(feature = "std")]
+struct SortedKeys<'value>(&'value Value);
+
+#[cfg(feature = "std")]
+impl Serialize for SortedKeys<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        match self.0 {
+            Value::Array(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+
+                for element in elements {
+                    seq.serialize_element(&SortedKeys(element))?;
+                }
+
+                seq.end()
+            },
+            Value::Object(entries) => {
+                let mut entries = entries.iter().collect::<Vec<_>>();
+                entries.sort_unstable_by_key(|&(key, _)| key);
+
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+
+                for (key, value) in entries {
+                    map.serialize_entry(key, &SortedKeys(value))?;
+                }
+
+                map.end()
+            },
+            value => value.serialize(serializer)
+        }
+    }
+}
+
+/// Serializes the given value as a single NDJSON-line according to the given [NdjsonWriteConfig]
+/// and appends it, including the configured line ending, to the given buffer. If serialization
+/// fails, the buffer is left unchanged. See [NdjsonWriteConfig] for an example.
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub fn serialize_line_with_config<T>(value: &T, buffer: &mut Vec<u8>, config: &NdjsonWriteConfig)
+    -> JsonResult<()>
+where
+    T: Serialize + ?Sized
+{
+    let original_len = buffer.len();
+    let mut serializer =
+        serde_json::Serializer::with_formatter(&mut *buffer, ConfiguredFormatter { config });
+    let result = if config.sort_keys {
+        serde_json::to_value(value)
+            .and_then(|value| SortedKeys(&value).serialize(&mut serializer))
+    }
+    else {
+        value.serialize(&mut serializer)
+    };
+
+    if let Err(error) = result {
+        buffer.truncate(original_len);
+        return Err(error);
+    }
+
+    match config.line_ending {
+        WriteLineEnding::Lf => buffer.push(b'\n'),
+        WriteLineEnding::CrLf => buffer.extend_from_slice(b"\r\n")
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "stream")]
+pin_project! {
+    /// Wraps a [Stream] of values implementing [Serialize] and offers a [Stream] of NDJSON-lines,
+    /// each containing one value including the terminating newline character. This can be used as
+    /// the body of a streaming HTTP response, for example by passing it to
+    /// `axum::body::Body::from_stream`, since [Vec<u8>] converts into `Bytes`. With the
+    /// `http-body` feature, it also implements `http_body::Body` directly, emitting one data frame
+    /// per line, so it can be used with hyper and any framework built on it. See
+    /// [NdjsonBody::new] for an example.
+    #[cfg_attr(doc_cfg, doc(cfg(feature = "stream")))]
+    pub struct NdjsonBody<S> {
+        #[pin]
+        values: S,
+        config: NdjsonWriteConfig
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> NdjsonBody<S> {
+
+    /// Creates a new NDJSON-body wrapping the given stream of values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::stream::{self, StreamExt};
+    /// use ndjson_stream::writer::NdjsonBody;
+    ///
+    /// let body = NdjsonBody::new(stream::iter(vec![1, 2, 3]));
+    /// let lines = tokio_test::block_on(body.collect::<Vec<_>>());
+    ///
+    /// assert_eq!(lines.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+    ///     vec![b"1\n".to_vec(), b"2\n".to_vec(), b"3\n".to_vec()]);
+    /// ```
+    pub fn new(values: S) -> NdjsonBody<S> {
+        NdjsonBody {
+            values,
+            config: NdjsonWriteConfig::default()
+        }
+    }
+
+    /// Sets the [NdjsonWriteConfig] according to which the lines of this body are serialized.
+    ///
+    /// # Returns
+    ///
+    /// A new body with all the same values as this one, except the write config.
+    pub fn with_write_config(self, config: NdjsonWriteConfig) -> NdjsonBody<S> {
+        NdjsonBody {
+            config,
+            ..self
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S> Stream for NdjsonBody<S>
+where
+    S: Stream,
+    S::Item: Serialize
+{
+    type Item = JsonResult<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<JsonResult<Vec<u8>>>> {
+        let this = self.project();
+        let value = ready!(this.values.poll_next(cx));
+
+        Poll::Ready(value.map(|value| {
+            let mut line = Vec::new();
+            serialize_line_with_config(&value, &mut line, this.config)?;
+            Ok(line)
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+#[cfg(feature = "http-body")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "http-body")))]
+impl<S> Body for NdjsonBody<S>
+where
+    S: Stream,
+    S::Item: Serialize
+{
+    type Data = Bytes;
+    type Error = serde_json::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>)
+            -> Poll<Option<JsonResult<Frame<Bytes>>>> {
+        Stream::poll_next(self, cx)
+            .map(|line| line.map(|line| line.map(|line| Frame::data(Bytes::from(line)))))
+    }
+}
+
+/// The default number of buffered bytes after which an [AsyncNdjsonWriter] writes its buffer to
+/// the wrapped writer.
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub const DEFAULT_ASYNC_WRITER_CAPACITY: usize = 8 * 1024;
+
+/// Serializes values of type `T` as NDJSON-lines into a wrapped [AsyncWrite]. Lines are collected
+/// in an internal buffer, which is written to the wrapped writer once it exceeds its capacity or
+/// [AsyncNdjsonWriter::flush] is called. For emitters which are sensitive to latency, flushing
+/// after every record can be enabled via [AsyncNdjsonWriter::with_flush_each_record].
+///
+/// Buffered data which has not been flushed is lost when the writer is dropped, so
+/// [AsyncNdjsonWriter::flush] should be called once all values have been written.
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::writer::AsyncNdjsonWriter;
+///
+/// let mut output = Vec::new();
+///
+/// tokio_test::block_on(async {
+///     let mut writer = AsyncNdjsonWriter::new(&mut output);
+///
+///     writer.write(&vec![1, 2]).await.unwrap();
+///     writer.write(&vec![3]).await.unwrap();
+///     writer.flush().await.unwrap();
+/// });
+///
+/// assert_eq!(output, b"[1,2]\n[3]\n");
+/// ```
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub struct AsyncNdjsonWriter<T: ?Sized, W> {
+    writer: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+    flush_each_record: bool,
+    config: NdjsonWriteConfig,
+    value_type: PhantomData<fn(&T)>
+}
+
+#[cfg(feature = "tokio")]
+impl<T, W> AsyncNdjsonWriter<T, W>
+where
+    T: Serialize + ?Sized,
+    W: AsyncWrite + Unpin
+{
+
+    /// Creates a new NDJSON-writer wrapping the given `writer`, which buffers up to
+    /// [DEFAULT_ASYNC_WRITER_CAPACITY] bytes.
+    pub fn new(writer: W) -> AsyncNdjsonWriter<T, W> {
+        AsyncNdjsonWriter::with_capacity(writer, DEFAULT_ASYNC_WRITER_CAPACITY)
+    }
+
+    /// Creates a new NDJSON-writer wrapping the given `writer`, which buffers up to `capacity`
+    /// bytes before writing them to the wrapped writer.
+    pub fn with_capacity(writer: W, capacity: usize) -> AsyncNdjsonWriter<T, W> {
+        AsyncNdjsonWriter {
+            writer,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            flush_each_record: false,
+            config: NdjsonWriteConfig::default(),
+            value_type: PhantomData
+        }
+    }
+
+    /// Sets whether this writer flushes the wrapped writer after every record written via
+    /// [AsyncNdjsonWriter::write], such that each record is delivered as soon as possible. By
+    /// default, this is disabled.
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the flushing behavior.
+    pub fn with_flush_each_record(self, flush_each_record: bool) -> AsyncNdjsonWriter<T, W> {
+        AsyncNdjsonWriter {
+            flush_each_record,
+            ..self
+        }
+    }
+
+    /// Sets the [NdjsonWriteConfig] according to which values are serialized by this writer.
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the write config.
+    pub fn with_write_config(self, config: NdjsonWriteConfig) -> AsyncNdjsonWriter<T, W> {
+        AsyncNdjsonWriter {
+            config,
+            ..self
+        }
+    }
+
+    /// Serializes the given value as a single NDJSON-line and appends it to the buffer of this
+    /// writer. The buffer is written to the wrapped writer if it exceeds the capacity of this
+    /// writer or flushing after every record is enabled.
+    ///
+    /// # Errors
+    ///
+    /// If serialization fails, in which case nothing is written, or writing to the wrapped writer
+    /// fails. Serialization errors are converted into an [io::Error] of kind
+    /// [io::ErrorKind::InvalidData].
+    pub async fn write(&mut self, value: &T) -> io::Result<()> {
+        serialize_line_with_config(value, &mut self.buffer, &self.config)?;
+
+        if self.flush_each_record {
+            self.flush().await
+        }
+        else if self.buffer.len() >= self.capacity {
+            self.write_buffer().await
+        }
+        else {
+            Ok(())
+        }
+    }
+
+    /// Writes all buffered data to the wrapped writer and flushes it.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer().await?;
+        self.writer.flush().await
+    }
+
+    async fn write_buffer(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer).await?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Gets a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+}
+
+/// The state of the outputs of a [DemuxWriter] for a single key.
+#[cfg(feature = "std")]
+struct DemuxSink<W> {
+
+    /// The currently open output, or `None` if it was closed or could not be opened.
+    writer: Option<W>,
+
+    /// The part number with which the next output is opened.
+    next_part: u64,
+
+    /// The number of bytes written to the currently open output.
+    written: u64
+}
+
+/// Serializes values of type `T` as NDJSON-lines and routes each line to one of several outputs
+/// depending on a key computed from the value, such as a date or a tenant. Outputs are opened
+/// lazily by a closure when the first value with a new key is written. The closure receives the key
+/// and a part number, which starts at 0 and is incremented whenever the output of a key is rotated
+/// according to [DemuxWriter::with_max_sink_size].
+///
+/// Lines are written to the outputs directly, so files should be wrapped in a
+/// [BufWriter](std::io::BufWriter) by the closure. Outputs are flushed when they are rotated, when
+/// they are closed via [DemuxWriter::close], and by [DemuxWriter::flush].
+///
+/// # Example
+///
+/// ```
+/// use ndjson_stream::writer::DemuxWriter;
+/// use serde_json::{json, Value};
+///
+/// let mut outputs = Vec::new();
+///
+/// {
+///     let mut writer = DemuxWriter::new(
+///         |record: &Value| record["tenant"].as_str().unwrap_or("unknown").to_owned(),
+///         |tenant: &String, part| {
+///             outputs.push(format!("{}-{}.ndjson", tenant, part));
+///             Ok(Vec::new())
+///         });
+///
+///     writer.write(&json!({ "tenant": "a", "id": 1 })).unwrap();
+///     writer.write(&json!({ "tenant": "b", "id": 2 })).unwrap();
+///     writer.write(&json!({ "tenant": "a", "id": 3 })).unwrap();
+///
+///     assert_eq!(writer.sink(&"a".to_owned()).unwrap(),
+///         b"{\"id\":1,\"tenant\":\"a\"}\n{\"id\":3,\"tenant\":\"a\"}\n");
+/// }
+///
+/// assert_eq!(outputs, vec!["a-0.ndjson", "b-0.ndjson"]);
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "std")))]
+pub struct DemuxWriter<T: ?Sized, K, W, F, O> {
+    key_fn: F,
+    open_sink: O,
+    sinks: HashMap<K, DemuxSink<W>>,
+    max_sink_size: Option<u64>,
+    config: NdjsonWriteConfig,
+    buffer: Vec<u8>,
+    value_type: PhantomData<fn(&T)>
+}
+
+#[cfg(feature = "std")]
+impl<T, K, W, F, O> DemuxWriter<T, K, W, F, O>
+where
+    T: Serialize + ?Sized,
+    K: Eq + Hash,
+    W: Write,
+    F: FnMut(&T) -> K,
+    O: FnMut(&K, u64) -> io::Result<W>
+{
+
+    /// Creates a new demultiplexing writer which computes the key of every value with `key_fn` and
+    /// opens the output for a key and part number with `open_sink`.
+    pub fn new(key_fn: F, open_sink: O) -> DemuxWriter<T, K, W, F, O> {
+        DemuxWriter {
+            key_fn,
+            open_sink,
+            sinks: HashMap::new(),
+            max_sink_size: None,
+            config: NdjsonWriteConfig::default(),
+            buffer: Vec::new(),
+            value_type: PhantomData
+        }
+    }
+
+    /// Sets the maximum number of bytes written to a single output. If writing a line would exceed
+    /// it, the output is flushed and dropped, and a new one is opened with the next part number. A
+    /// line which exceeds the maximum by itself is still written to a fresh output. By default,
+    /// outputs are never rotated.
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the maximum output size.
+    pub fn with_max_sink_size(self, max_sink_size: u64) -> DemuxWriter<T, K, W, F, O> {
+        DemuxWriter {
+            max_sink_size: Some(max_sink_size),
+            ..self
+        }
+    }
+
+    /// Sets the [NdjsonWriteConfig] according to which values are serialized by this writer.
+    ///
+    /// # Returns
+    ///
+    /// A new writer with all the same values as this one, except the write config.
+    pub fn with_write_config(self, config: NdjsonWriteConfig) -> DemuxWriter<T, K, W, F, O> {
+        DemuxWriter {
+            config,
+            ..self
+        }
+    }
+
+    /// Serializes the given value as a single NDJSON-line and writes it to the output for its key,
+    /// opening or rotating that output if necessary.
+    ///
+    /// # Errors
+    ///
+    /// If serialization fails, in which case nothing is written, or opening, flushing, or writing
+    /// to an output fails. Serialization errors are converted into an [io::Error] of kind
+    /// [io::ErrorKind::InvalidData].
+    pub fn write(&mut self, value: &T) -> io::Result<()> {
+        self.buffer.clear();
+        serialize_line_with_config(value, &mut self.buffer, &self.config)?;
+
+        let line_len = self.buffer.len() as u64;
+        let mut entry = match self.sinks.entry((self.key_fn)(value)) {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(entry) => entry.insert_entry(DemuxSink {
+                writer: None,
+                next_part: 0,
+                written: 0
+            })
+        };
+        let sink = entry.get_mut();
+        let exceeds_max_sink_size = self.max_sink_size.is_some_and(|max_sink_size|
+            sink.written > 0 && sink.written + line_len > max_sink_size);
+
+        if exceeds_max_sink_size {
+            if let Some(mut writer) = sink.writer.take() {
+                writer.flush()?;
+            }
+        }
+
+        if entry.get().writer.is_none() {
+            let writer = (self.open_sink)(entry.key(), entry.get().next_part)?;
+            let sink = entry.get_mut();
+
+            sink.writer = Some(writer);
+            sink.next_part += 1;
+            sink.written = 0;
+        }
+
+        let sink = entry.into_mut();
+
+        if let Some(writer) = &mut sink.writer {
+            writer.write_all(&self.buffer)?;
+            sink.written += line_len;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes and drops the output for the given key, if any. A later value with this key opens a
+    /// new output with the next part number.
+    pub fn close(&mut self, key: &K) -> io::Result<()> {
+        match self.sinks.get_mut(key).and_then(|sink| sink.writer.take()) {
+            Some(mut writer) => writer.flush(),
+            None => Ok(())
+        }
+    }
+
+    /// Flushes all currently open outputs.
+    pub fn flush(&mut self) -> io::Result<()> {
+        for writer in self.sinks.values_mut().filter_map(|sink| sink.writer.as_mut()) {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets a reference to the currently open output for the given key, if any.
+    pub fn sink(&self, key: &K) -> Option<&W> {
+        self.sinks.get(key).and_then(|sink| sink.writer.as_ref())
+    }
+
+    /// Gets the number of currently open outputs.
+    pub fn sink_count(&self) -> usize {
+        self.sinks.values().filter(|sink| sink.writer.is_some()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use kernal::prelude::*;
+
+    use serde::Serializer;
+
+    use super::*;
+
+    struct Unserializable;
+
+    #[cfg(feature = "std")]
+    #[derive(Serialize)]
+    struct Unsorted {
+        b: [InnerUnsorted; 1],
+        a: ()
+    }
+
+    #[cfg(feature = "std")]
+    #[derive(Serialize)]
+    struct InnerUnsorted {
+        d: u32,
+        c: u32
+    }
+
+    impl Serialize for Unserializable {
+        fn serialize<S: Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("test message"))
+        }
+    }
+
+    #[test]
+    fn serialize_line_appends_compact_json_and_newline() {
+        let mut buffer = b"prefix\n".to_vec();
+
+        serialize_line(&serde_json::json!({ "key": 1, "value": [2, 3] }), &mut buffer).unwrap();
+
+        assert_that!(buffer).is_equal_to(b"prefix\n{\"key\":1,\"value\":[2,3]}\n".to_vec());
+    }
+
+    #[test]
+    fn serialize_line_leaves_buffer_unchanged_on_error() {
+        let mut buffer = b"prefix\n".to_vec();
+
+        assert_that!(serialize_line(&[Unserializable], &mut buffer)).is_err();
+        assert_that!(buffer).is_equal_to(b"prefix\n".to_vec());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn default_write_config_matches_serialize_line() {
+        let value = serde_json::json!({ "b": [1.5, "ä"], "a": null });
+        let mut expected = Vec::new();
+        let mut actual = Vec::new();
+
+        serialize_line(&value, &mut expected).unwrap();
+        serialize_line_with_config(&value, &mut actual, &NdjsonWriteConfig::default()).unwrap();
+
+        assert_that!(actual).is_equal_to(expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn non_ascii_characters_are_escaped() {
+        let config = NdjsonWriteConfig::default().with_escape_non_ascii(true);
+        let mut buffer = Vec::new();
+
+        serialize_line_with_config(&[("ä\n", "a😀b")], &mut buffer, &config).unwrap();
+
+        assert_that!(buffer)
+            .is_equal_to(b"[[\"\\u00e4\\n\",\"a\\ud83d\\ude00b\"]]\n".to_vec());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn keys_are_sorted_recursively() {
+        let config = NdjsonWriteConfig::default().with_sort_keys(true);
+        let value = Unsorted {
+            b: [InnerUnsorted { d: 1, c: 2 }],
+            a: ()
+        };
+        let mut buffer = Vec::new();
+
+        serialize_line_with_config(&value, &mut buffer, &config).unwrap();
+
+        assert_that!(buffer).is_equal_to(b"{\"a\":null,\"b\":[{\"c\":2,\"d\":1}]}\n".to_vec());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn floats_are_formatted_and_line_ending_is_applied() {
+        let config = NdjsonWriteConfig::default()
+            .with_float_format(FloatFormat::Fixed(2))
+            .with_line_ending(WriteLineEnding::CrLf);
+        let mut buffer = Vec::new();
+
+        serialize_line_with_config(&(0.129f64, 1.0f32, 3, f64::NAN), &mut buffer, &config)
+            .unwrap();
+
+        assert_that!(buffer).is_equal_to(b"[0.13,1.00,3,null]\r\n".to_vec());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn failed_serialization_with_config_leaves_buffer_unchanged() {
+        let config = NdjsonWriteConfig::default().with_sort_keys(true);
+        let mut buffer = b"prefix\n".to_vec();
+
+        assert_that!(serialize_line_with_config(&[Unserializable], &mut buffer, &config)).is_err();
+        assert_that!(buffer).is_equal_to(b"prefix\n".to_vec());
+    }
+
+    /// The outputs opened by a [DemuxWriter] in a test, as pairs of the name derived from key and
+    /// part number and the written data.
+    #[cfg(feature = "std")]
+    type DemuxOutputs = std::rc::Rc<std::cell::RefCell<Vec<(String, Vec<u8>)>>>;
+
+    #[cfg(feature = "std")]
+    struct DemuxOutput {
+        outputs: DemuxOutputs,
+        index: usize
+    }
+
+    #[cfg(feature = "std")]
+    impl Write for DemuxOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.outputs.borrow_mut()[self.index].1.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn parity(value: &u32) -> u32 {
+        value % 2
+    }
+
+    #[cfg(feature = "std")]
+    fn open_demux_output(outputs: &DemuxOutputs)
+            -> impl FnMut(&u32, u64) -> io::Result<DemuxOutput> {
+        let outputs = outputs.clone();
+
+        move |key, part| {
+            let mut opened = outputs.borrow_mut();
+
+            opened.push((format!("{}-{}", key, part), Vec::new()));
+
+            Ok(DemuxOutput {
+                outputs: outputs.clone(),
+                index: opened.len() - 1
+            })
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn demux_output_strings(outputs: &DemuxOutputs) -> Vec<(String, String)> {
+        outputs.borrow().iter()
+            .map(|(name, data)| (name.clone(), String::from_utf8(data.clone()).unwrap()))
+            .collect()
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn demux_writer_routes_values_by_key_and_rotates_by_size() {
+        let outputs = DemuxOutputs::default();
+        let mut writer =
+            DemuxWriter::new(parity, open_demux_output(&outputs)).with_max_sink_size(4);
+
+        for value in [1, 2, 3, 4, 5, 12345, 7] {
+            writer.write(&value).unwrap();
+        }
+
+        assert_that!(writer.sink_count()).is_equal_to(2);
+        assert_that!(demux_output_strings(&outputs)).contains_exactly_in_given_order([
+            ("1-0".to_owned(), "1\n3\n".to_owned()),
+            ("0-0".to_owned(), "2\n4\n".to_owned()),
+            ("1-1".to_owned(), "5\n".to_owned()),
+            ("1-2".to_owned(), "12345\n".to_owned()),
+            ("1-3".to_owned(), "7\n".to_owned())
+        ]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn demux_writer_opens_next_part_after_close() {
+        let outputs = DemuxOutputs::default();
+        let mut writer = DemuxWriter::new(parity, open_demux_output(&outputs));
+
+        writer.write(&1).unwrap();
+        writer.write(&2).unwrap();
+        writer.close(&1).unwrap();
+
+        assert_that!(writer.sink(&1).is_none()).is_true();
+        assert_that!(writer.sink_count()).is_equal_to(1);
+
+        writer.write(&3).unwrap();
+
+        assert_that!(demux_output_strings(&outputs)).contains_exactly_in_given_order([
+            ("1-0".to_owned(), "1\n".to_owned()),
+            ("0-0".to_owned(), "2\n".to_owned()),
+            ("1-1".to_owned(), "3\n".to_owned())
+        ]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn demux_writer_does_not_open_output_for_unserializable_value() {
+        let mut opened = 0;
+        let mut writer = DemuxWriter::new(|_: &[Unserializable]| (), |_: &(), _| {
+            opened += 1;
+            Ok(Vec::new())
+        });
+        let result = writer.write(&[Unserializable]);
+
+        assert_that!(result.map_err(|error| error.kind()))
+            .contains_error(io::ErrorKind::InvalidData);
+        assert_that!(writer.sink_count()).is_equal_to(0);
+        drop(writer);
+        assert_that!(opened).is_equal_to(0);
+    }
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod stream_tests {
+
+    use futures::stream::{self, StreamExt};
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[test]
+    fn body_emits_one_line_per_value() {
+        let values = stream::iter(vec![
+            TestStruct { key: 1, value: 2 },
+            TestStruct { key: 3, value: 4 }
+        ]);
+        let lines = tokio_test::block_on(NdjsonBody::new(values).collect::<Vec<_>>());
+
+        assert_that!(lines).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(b"{\"key\":1,\"value\":2}\n".to_vec()),
+            |it| assert_that!(it).contains_value(b"{\"key\":3,\"value\":4}\n".to_vec())
+        ));
+    }
+
+    #[test]
+    fn body_output_can_be_parsed_again() {
+        let values = stream::iter(vec![TestStruct { key: 5, value: 6 }]);
+        let lines = NdjsonBody::new(values).map(Result::unwrap);
+        let parsed = tokio_test::block_on(crate::from_stream(lines).collect::<Vec<_>>());
+
+        assert_that!(parsed).satisfies_exactly_in_given_order(dyn_assertions!(
+            |it| assert_that!(it).contains_value(TestStruct { key: 5, value: 6 })
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "http-body"))]
+mod http_body_tests {
+
+    use std::pin::pin;
+
+    use futures::future;
+    use futures::stream;
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[test]
+    fn body_emits_one_data_frame_per_value() {
+        let values = stream::iter(vec![
+            TestStruct { key: 1, value: 2 },
+            TestStruct { key: 3, value: 4 }
+        ]);
+        let mut body = pin!(NdjsonBody::new(values));
+        let mut frames = Vec::new();
+
+        tokio_test::block_on(async {
+            while let Some(frame) = future::poll_fn(|cx| body.as_mut().poll_frame(cx)).await {
+                frames.push(frame.unwrap().into_data().unwrap());
+            }
+        });
+
+        assert_that!(frames).contains_exactly_in_given_order([
+            Bytes::from_static(b"{\"key\":1,\"value\":2}\n"),
+            Bytes::from_static(b"{\"key\":3,\"value\":4}\n")
+        ]);
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tokio_tests {
+
+    use kernal::prelude::*;
+
+    use crate::test_util::TestStruct;
+
+    use super::*;
+
+    #[test]
+    fn async_writer_buffers_until_capacity_is_exceeded() {
+        tokio_test::block_on(async {
+            let mut writer = AsyncNdjsonWriter::with_capacity(Vec::new(), 30);
+
+            writer.write(&TestStruct { key: 1, value: 2 }).await.unwrap();
+
+            assert_that!(writer.get_ref()).is_empty();
+
+            writer.write(&TestStruct { key: 3, value: 4 }).await.unwrap();
+
+            assert_that!(writer.get_ref().as_slice())
+                .is_equal_to(b"{\"key\":1,\"value\":2}\n{\"key\":3,\"value\":4}\n".as_slice());
+        });
+    }
+
+    #[test]
+    fn async_writer_writes_buffer_on_flush() {
+        tokio_test::block_on(async {
+            let mut writer = AsyncNdjsonWriter::new(Vec::new());
+
+            writer.write("text").await.unwrap();
+            writer.flush().await.unwrap();
+
+            assert_that!(writer.get_ref().as_slice()).is_equal_to(b"\"text\"\n".as_slice());
+        });
+    }
+
+    #[test]
+    fn async_writer_flushing_each_record_writes_immediately() {
+        tokio_test::block_on(async {
+            let mut writer = AsyncNdjsonWriter::new(Vec::new()).with_flush_each_record(true);
+
+            writer.write(&1).await.unwrap();
+
+            assert_that!(writer.get_ref().as_slice()).is_equal_to(b"1\n".as_slice());
+        });
+    }
+
+    #[test]
+    fn async_writer_reports_serialization_error_as_invalid_data() {
+        tokio_test::block_on(async {
+            let mut writer = AsyncNdjsonWriter::new(Vec::new());
+            let map = [(vec![1], 2)].into_iter().collect::<std::collections::HashMap<_, _>>();
+
+            let error = writer.write(&map).await.unwrap_err();
+
+            assert_that!(error.kind()).is_equal_to(io::ErrorKind::InvalidData);
+            assert_that!(writer.get_ref()).is_empty();
+        });
+    }
+}